@@ -0,0 +1,266 @@
+use crate::errors::ServiceError;
+use crate::services::account_lockout::AccountLockout;
+use crate::services::mailer::mail_limit_from_env;
+use crate::services::rate_limiter::RateLimitConfig;
+use crate::utils::body_limit::BodyLimitConfig;
+use crate::utils::cookie::CookieConfig;
+use crate::utils::jwt::{JwtConfig, JwtKeys, list_env};
+use crate::utils::password::Argon2Config;
+use once_cell::sync::OnceCell;
+use std::sync::Arc;
+use std::{env, fmt, str::FromStr};
+
+pub const DEFAULT_PORT: u16 = 7878;
+pub const DEFAULT_MONGODB_URI: &str = "mongodb://localhost:27017";
+pub const DEFAULT_MONGODB_DATABASE: &str = "qtoky";
+/// Lama menunggu request yang sedang berjalan saat shutdown sebelum worker dipaksa berhenti
+pub const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 30;
+/// Panjang minimal `SECRET`, dipakai untuk sign JWT HS256 dan CSRF token
+pub const MIN_SECRET_LEN: usize = 32;
+
+/// Seluruh konfigurasi aplikasi, dibaca dan divalidasi sekali saat startup
+#[derive(Clone)]
+pub struct Config {
+    pub port: u16,
+    pub mongodb_uri: String,
+    pub mongodb_database: String,
+    pub secret: String,
+    // Secret lama dari `JWT_PREVIOUS_SECRETS` (dipisah koma, terbaru dulu) untuk rotasi
+    pub previous_secrets: Vec<String>,
+    pub jwt: JwtConfig,
+    pub jwt_keys: Arc<JwtKeys>,
+    pub argon2: Argon2Config,
+    pub cookie: CookieConfig,
+    pub body_limit: BodyLimitConfig,
+    pub login_limit: RateLimitConfig,
+    pub mail_limit: RateLimitConfig,
+    pub lockout: AccountLockout,
+    pub shutdown_timeout_secs: u64,
+}
+
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Config")
+            .field("port", &self.port)
+            .field("mongodb_uri", &self.mongodb_uri)
+            .field("mongodb_database", &self.mongodb_database)
+            .field("secret", &"***")
+            .field("previous_secrets", &self.previous_secrets.len())
+            .field("jwt", &self.jwt)
+            .field("jwt_algorithm", &self.jwt_keys.algorithm())
+            .field("argon2", &self.argon2)
+            .field("cookie", &self.cookie)
+            .field("body_limit", &self.body_limit)
+            .field("login_limit", &self.login_limit)
+            .field("mail_limit", &self.mail_limit)
+            .field("lockout", &self.lockout)
+            .field("shutdown_timeout_secs", &self.shutdown_timeout_secs)
+            .finish()
+    }
+}
+
+static CONFIG: OnceCell<Config> = OnceCell::new();
+
+impl Config {
+    /// Baca `PORT`, `MONGODB_URI`, `MONGODB_DATABASE`, `SECRET`, `SHUTDOWN_TIMEOUT_SECS`
+    /// beserta konfigurasi JWT (termasuk key), Argon2, cookie, batas ukuran body, rate limit
+    /// login dan email, serta lockout akun. Error menyebutkan nama variabel yang kosong
+    /// atau tidak valid.
+    pub fn from_env() -> Result<Config, ServiceError> {
+        let secret = required_env("SECRET")?;
+        if secret.len() < MIN_SECRET_LEN {
+            return Err(config_error(format!(
+                "SECRET minimal {} karakter",
+                MIN_SECRET_LEN
+            )));
+        }
+
+        let mongodb_uri =
+            optional_env("MONGODB_URI").unwrap_or_else(|| DEFAULT_MONGODB_URI.to_string());
+        if !mongodb_uri.starts_with("mongodb://") && !mongodb_uri.starts_with("mongodb+srv://") {
+            return Err(config_error(
+                "MONGODB_URI harus diawali mongodb:// atau mongodb+srv://",
+            ));
+        }
+
+        let previous_secrets = list_env("JWT_PREVIOUS_SECRETS");
+        let jwt_keys = Arc::new(JwtKeys::from_env(&secret, &previous_secrets)?);
+
+        Ok(Config {
+            port: parse_env("PORT", DEFAULT_PORT)?,
+            mongodb_uri,
+            mongodb_database: optional_env("MONGODB_DATABASE")
+                .unwrap_or_else(|| DEFAULT_MONGODB_DATABASE.to_string()),
+            secret,
+            previous_secrets,
+            jwt: JwtConfig::from_env()?,
+            jwt_keys,
+            argon2: Argon2Config::from_env()?,
+            cookie: CookieConfig::from_env()?,
+            body_limit: BodyLimitConfig::from_env()?,
+            login_limit: RateLimitConfig::login_from_env()?,
+            mail_limit: mail_limit_from_env()?,
+            lockout: AccountLockout::from_env()?,
+            shutdown_timeout_secs: parse_env(
+                "SHUTDOWN_TIMEOUT_SECS",
+                DEFAULT_SHUTDOWN_TIMEOUT_SECS,
+            )?,
+        })
+    }
+
+    /// Simpan config yang sudah divalidasi di `main` agar dipakai seluruh aplikasi
+    pub fn install(config: Config) -> &'static Config {
+        CONFIG.get_or_init(|| config)
+    }
+
+    /// Config aktif. Jika belum di-`install`, dibaca dari environment dan panic
+    /// kalau tidak valid.
+    pub fn global() -> &'static Config {
+        CONFIG.get_or_init(|| Config::from_env().unwrap_or_else(|e| panic!("{}", e)))
+    }
+}
+
+pub(crate) fn config_error(message: impl Into<String>) -> ServiceError {
+    ServiceError::Unexpected(format!("Konfigurasi tidak valid: {}", message.into()))
+}
+
+/// Nilai env yang kosong dianggap tidak di-set
+pub(crate) fn optional_env(key: &str) -> Option<String> {
+    env::var(key)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+pub(crate) fn required_env(key: &str) -> Result<String, ServiceError> {
+    optional_env(key).ok_or_else(|| config_error(format!("{} wajib di-set", key)))
+}
+
+/// Parse env ke tipe `T`, pakai `default` jika tidak di-set
+pub(crate) fn parse_env<T: FromStr>(key: &str, default: T) -> Result<T, ServiceError> {
+    match optional_env(key) {
+        Some(raw) => raw.parse().map_err(|_| {
+            config_error(format!("{} bernilai '{}' bukan angka yang valid", key, raw))
+        }),
+        None => Ok(default),
+    }
+}
+
+/// Parse env boolean (`true`/`false`, `1`/`0`, `yes`/`no`), nilai lain ditolak
+pub(crate) fn parse_bool_env(key: &str, default: bool) -> Result<bool, ServiceError> {
+    match optional_env(key) {
+        Some(raw) => match raw.to_lowercase().as_str() {
+            "true" | "1" | "yes" => Ok(true),
+            "false" | "0" | "no" => Ok(false),
+            _ => Err(config_error(format!(
+                "{} bernilai '{}' bukan boolean yang valid",
+                key, raw
+            ))),
+        },
+        None => Ok(default),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    // Env proses dipakai bersama semua test, jadi test di sini dijalankan bergantian
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    const VALID_SECRET: &str = "rahasia-test-yang-panjangnya-32-karakter";
+    const KEYS: [&str; 5] = [
+        "SECRET",
+        "MONGODB_URI",
+        "MONGODB_DATABASE",
+        "PORT",
+        "SHUTDOWN_TIMEOUT_SECS",
+    ];
+
+    /// Jalankan `Config::from_env` dengan hanya `vars` yang di-set dari `KEYS`
+    fn from_vars(vars: &[(&str, &str)]) -> Result<Config, ServiceError> {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        // SAFETY: semua test yang mengubah env memegang `ENV_LOCK`
+        unsafe {
+            for key in KEYS {
+                env::remove_var(key);
+            }
+            for (key, value) in vars {
+                env::set_var(key, value);
+            }
+        }
+        let result = Config::from_env();
+        unsafe {
+            for key in KEYS {
+                env::remove_var(key);
+            }
+        }
+        result
+    }
+
+    fn error_message(result: Result<Config, ServiceError>) -> String {
+        match result {
+            Err(ServiceError::Unexpected(msg)) => msg,
+            other => panic!("hasil tidak terduga: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn complete_environment_loads() {
+        let config = from_vars(&[
+            ("SECRET", VALID_SECRET),
+            ("MONGODB_URI", "mongodb+srv://cluster.example.net"),
+            ("MONGODB_DATABASE", "qtoky_staging"),
+            ("PORT", "8080"),
+            ("SHUTDOWN_TIMEOUT_SECS", "5"),
+        ])
+        .unwrap();
+
+        assert_eq!(config.port, 8080);
+        assert_eq!(config.mongodb_uri, "mongodb+srv://cluster.example.net");
+        assert_eq!(config.mongodb_database, "qtoky_staging");
+        assert_eq!(config.secret, VALID_SECRET);
+        assert_eq!(config.shutdown_timeout_secs, 5);
+        assert!(!format!("{:?}", config).contains(VALID_SECRET));
+    }
+
+    #[test]
+    fn optional_keys_fall_back_to_defaults() {
+        let config = from_vars(&[("SECRET", VALID_SECRET), ("PORT", "  ")]).unwrap();
+
+        assert_eq!(config.port, DEFAULT_PORT);
+        assert_eq!(config.mongodb_uri, DEFAULT_MONGODB_URI);
+        assert_eq!(config.mongodb_database, DEFAULT_MONGODB_DATABASE);
+        assert_eq!(config.shutdown_timeout_secs, DEFAULT_SHUTDOWN_TIMEOUT_SECS);
+    }
+
+    #[test]
+    fn missing_or_short_secret_is_named() {
+        assert!(error_message(from_vars(&[])).contains("SECRET wajib di-set"));
+        assert!(error_message(from_vars(&[("SECRET", "pendek")])).contains("SECRET minimal 32"));
+    }
+
+    #[test]
+    fn invalid_values_name_their_key() {
+        let cases = [
+            (
+                "MONGODB_URI",
+                "localhost:27017",
+                "MONGODB_URI harus diawali",
+            ),
+            ("PORT", "abc", "PORT bernilai 'abc'"),
+            ("PORT", "70000", "PORT bernilai '70000'"),
+            (
+                "SHUTDOWN_TIMEOUT_SECS",
+                "-1",
+                "SHUTDOWN_TIMEOUT_SECS bernilai '-1'",
+            ),
+        ];
+
+        for (key, value, expected) in cases {
+            let message = error_message(from_vars(&[("SECRET", VALID_SECRET), (key, value)]));
+            assert!(message.contains(expected), "{}: {}", key, message);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use crate::db::deserialize::deserialize_document;
+use crate::db::timeout::{DB_OPERATION_TIMEOUT, server_max_time, with_timeout};
+use crate::errors::ServiceError;
+use crate::utils::map_mongo_error;
+use futures::stream::TryStreamExt;
+use mongodb::{
+    Collection,
+    bson::{Bson, Document, doc},
+};
+use serde::de::DeserializeOwned;
+
+/// Jalankan pipeline yang menghasilkan paling banyak satu dokumen (contoh diakhiri `$group`
+/// dengan `_id: null`), lalu deserialize ke `R`. Hasil kosong dikembalikan sebagai `None`.
+/// Dibatasi `DB_OPERATION_TIMEOUT`, baik di client maupun lewat `maxTimeMS` di server.
+pub async fn aggregate_one<T, R>(
+    collection: &Collection<T>,
+    pipeline: Vec<Document>,
+) -> Result<Option<R>, ServiceError>
+where
+    T: Send + Sync,
+    R: DeserializeOwned,
+{
+    let limit = DB_OPERATION_TIMEOUT;
+    let first = with_timeout(limit, async {
+        let mut cursor = collection
+            .aggregate(pipeline)
+            .max_time(server_max_time(limit))
+            .await
+            .map_err(map_mongo_error)?;
+        cursor.try_next().await.map_err(map_mongo_error)
+    })
+    .await?;
+
+    let Some(first) = first else {
+        return Ok(None);
+    };
+
+    deserialize_document(first).map(Some)
+}
+
+/// Total `field` dari dokumen yang cocok dengan `match_filter`, dihitung di database lewat
+/// `$match` + `$group`. Tidak ada dokumen yang cocok berarti total 0, bukan error.
+/// Contoh: `aggregate_sum(&sales, doc! { "user_id": id }, "final_amount")`
+pub async fn aggregate_sum<T>(
+    collection: &Collection<T>,
+    match_filter: Document,
+    field: &str,
+) -> Result<f64, ServiceError>
+where
+    T: Send + Sync,
+{
+    let pipeline = vec![
+        doc! { "$match": match_filter },
+        doc! { "$group": { "_id": null, "total": { "$sum": format!("${}", field) } } },
+    ];
+
+    let result: Option<Document> = aggregate_one(collection, pipeline).await?;
+    // `$sum` mengikuti tipe field (int32/int64/double), samakan ke f64
+    let total = match result.as_ref().and_then(|d| d.get("total")) {
+        Some(Bson::Double(v)) => *v,
+        Some(Bson::Int32(v)) => *v as f64,
+        Some(Bson::Int64(v)) => *v as f64,
+        _ => 0.0,
+    };
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_database;
+    use bson::oid::ObjectId;
+    use serde::Deserialize;
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn sum_counts_only_matching_documents() {
+        let sales = test_database().await.collection::<Document>("sales");
+        let owner = ObjectId::new();
+        sales
+            .insert_many([
+                doc! { "user_id": owner, "final_amount": 15000 },
+                doc! { "user_id": owner, "final_amount": 2500.5 },
+                doc! { "user_id": owner, "final_amount": 10_000_000_000i64 },
+                doc! { "user_id": ObjectId::new(), "final_amount": 99999 },
+            ])
+            .await
+            .unwrap();
+
+        let total = aggregate_sum(&sales, doc! { "user_id": owner }, "final_amount")
+            .await
+            .unwrap();
+
+        assert_eq!(total, 10_000_017_500.5);
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn empty_match_sums_to_zero() {
+        let sales = test_database().await.collection::<Document>("sales");
+
+        let empty = aggregate_sum(&sales, doc! {}, "final_amount")
+            .await
+            .unwrap();
+        sales
+            .insert_one(doc! { "user_id": ObjectId::new(), "final_amount": 5000 })
+            .await
+            .unwrap();
+        let no_match = aggregate_sum(&sales, doc! { "user_id": ObjectId::new() }, "final_amount")
+            .await
+            .unwrap();
+
+        assert_eq!(empty, 0.0);
+        assert_eq!(no_match, 0.0);
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn aggregate_one_deserializes_single_result() {
+        #[derive(Debug, Deserialize)]
+        struct StockSummary {
+            products: i32,
+            stock: i32,
+        }
+
+        let products = test_database().await.collection::<Document>("products");
+        products
+            .insert_many([doc! { "stock": 3 }, doc! { "stock": 4 }])
+            .await
+            .unwrap();
+        let pipeline = |filter: Document| {
+            vec![
+                doc! { "$match": filter },
+                doc! { "$group": { "_id": null, "products": { "$sum": 1 }, "stock": { "$sum": "$stock" } } },
+            ]
+        };
+
+        let summary: Option<StockSummary> =
+            aggregate_one(&products, pipeline(doc! {})).await.unwrap();
+        let none: Option<StockSummary> =
+            aggregate_one(&products, pipeline(doc! { "stock": { "$gt": 100 } }))
+                .await
+                .unwrap();
+
+        let summary = summary.unwrap();
+        assert_eq!((summary.products, summary.stock), (2, 7));
+        assert!(none.is_none());
+    }
+}
@@ -0,0 +1,309 @@
+use crate::db::retry::backoff_delay;
+use futures::StreamExt;
+use futures::stream::{self, BoxStream};
+use mongodb::{
+    Collection,
+    bson::{Bson, Document},
+    change_stream::{
+        ChangeStream,
+        event::{ChangeStreamEvent, OperationType, ResumeToken},
+    },
+    options::FullDocumentType,
+};
+use serde::de::DeserializeOwned;
+use std::sync::{Arc, Mutex};
+
+/// Perubahan dokumen dari change stream, `id` adalah `_id` dokumen yang berubah
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangeEvent<T> {
+    Insert(T),
+    // `document` adalah isi terbaru (update lookup), `None` jika sudah dihapus lagi
+    Update { id: Bson, document: Option<T> },
+    Delete { id: Bson },
+}
+
+/// Resume token event terakhir yang sudah diteruskan ke caller. Bisa diisi token yang
+/// disimpan sebelumnya agar watcher yang restart melanjutkan dari event berikutnya.
+#[derive(Debug, Clone, Default)]
+pub struct ResumeTokenSlot(Arc<Mutex<Option<ResumeToken>>>);
+
+impl ResumeTokenSlot {
+    pub fn new(token: Option<ResumeToken>) -> Self {
+        ResumeTokenSlot(Arc::new(Mutex::new(token)))
+    }
+
+    pub fn get(&self) -> Option<ResumeToken> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn set(&self, token: ResumeToken) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Some(token);
+    }
+}
+
+/// Ubah event mentah menjadi `ChangeEvent`. Replace dianggap update; drop, rename dan
+/// event lain yang tidak membawa dokumen menjadi `None`.
+pub fn map_change_event<T>(event: ChangeStreamEvent<T>) -> Option<ChangeEvent<T>> {
+    let id = event
+        .document_key
+        .as_ref()
+        .and_then(|key| key.get("_id"))
+        .cloned();
+
+    match event.operation_type {
+        OperationType::Insert => event.full_document.map(ChangeEvent::Insert),
+        OperationType::Update | OperationType::Replace => Some(ChangeEvent::Update {
+            id: id?,
+            document: event.full_document,
+        }),
+        OperationType::Delete => Some(ChangeEvent::Delete { id: id? }),
+        _ => None,
+    }
+}
+
+/// Stream perubahan `collection`, butuh MongoDB replica set. `pipeline` untuk menyaring
+/// event (contoh `$match` pada `operationType`) dan tidak boleh membuang field `_id`.
+pub fn watch_collection<T>(
+    collection: Collection<T>,
+    pipeline: Vec<Document>,
+) -> BoxStream<'static, ChangeEvent<T>>
+where
+    T: DeserializeOwned + Unpin + Send + Sync + 'static,
+{
+    watch_collection_with(collection, pipeline, ResumeTokenSlot::default())
+}
+
+struct WatchState<T>
+where
+    T: DeserializeOwned + Unpin + Send + Sync,
+{
+    collection: Collection<T>,
+    pipeline: Vec<Document>,
+    slot: ResumeTokenSlot,
+    stream: Option<ChangeStream<ChangeStreamEvent<T>>>,
+    attempt: u32,
+}
+
+/// Seperti `watch_collection`, mulai dari token di `slot` dan memperbaruinya setiap event.
+/// Error dicatat ke log lalu stream dibuka ulang dengan token terakhir sehingga tidak ada
+/// event yang terlewat. Stream berakhir jika server mengirim event invalidate (koleksi di-drop).
+pub fn watch_collection_with<T>(
+    collection: Collection<T>,
+    pipeline: Vec<Document>,
+    slot: ResumeTokenSlot,
+) -> BoxStream<'static, ChangeEvent<T>>
+where
+    T: DeserializeOwned + Unpin + Send + Sync + 'static,
+{
+    let state = WatchState {
+        collection,
+        pipeline,
+        slot,
+        stream: None,
+        attempt: 0,
+    };
+
+    stream::unfold(state, |mut state| async move {
+        loop {
+            let Some(stream) = state.stream.as_mut() else {
+                let opened = state
+                    .collection
+                    .watch()
+                    .pipeline(state.pipeline.clone())
+                    .full_document(FullDocumentType::UpdateLookup)
+                    .resume_after(state.slot.get())
+                    .await;
+                match opened {
+                    Ok(stream) => state.stream = Some(stream),
+                    Err(e) => {
+                        log::error!("Gagal membuka change stream: {}", e);
+                        actix_web::rt::time::sleep(backoff_delay(state.attempt)).await;
+                        state.attempt += 1;
+                    }
+                }
+                continue;
+            };
+
+            match stream.next().await {
+                Some(Ok(event)) => {
+                    state.attempt = 0;
+                    let is_invalidate = event.operation_type == OperationType::Invalidate;
+                    state.slot.set(event.id.clone());
+                    if is_invalidate {
+                        log::warn!("Change stream di-invalidate, watcher berhenti");
+                        return None;
+                    }
+                    if let Some(change) = map_change_event(event) {
+                        return Some((change, state));
+                    }
+                }
+                Some(Err(e)) => {
+                    log::error!("Change stream error, mencoba resume: {}", e);
+                    state.stream = None;
+                    actix_web::rt::time::sleep(backoff_delay(state.attempt)).await;
+                    state.attempt += 1;
+                }
+                None => {
+                    log::warn!("Change stream ditutup server, mencoba resume");
+                    state.stream = None;
+                    actix_web::rt::time::sleep(backoff_delay(state.attempt)).await;
+                    state.attempt += 1;
+                }
+            }
+        }
+    })
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::{doc, oid::ObjectId};
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    struct Stock {
+        sku: String,
+        qty: i64,
+    }
+
+    // Bentuk event mentah seperti dikirim server, `_id` adalah resume token
+    fn raw_event(operation: &str, id: ObjectId, full_document: Option<Document>) -> Document {
+        let mut event = doc! {
+            "_id": { "_data": "826500000000000000" },
+            "operationType": operation,
+            "ns": { "db": "qtoky_test", "coll": "stocks" },
+            "documentKey": { "_id": id },
+        };
+        if let Some(full_document) = full_document {
+            event.insert("fullDocument", full_document);
+        }
+        event
+    }
+
+    fn mapped(event: Document) -> Option<ChangeEvent<Stock>> {
+        map_change_event(mongodb::bson::from_document::<ChangeStreamEvent<Stock>>(event).unwrap())
+    }
+
+    #[test]
+    fn insert_carries_document() {
+        let id = ObjectId::new();
+        let event = raw_event(
+            "insert",
+            id,
+            Some(doc! { "_id": id, "sku": "KOPI", "qty": 10_i64 }),
+        );
+
+        assert_eq!(
+            mapped(event),
+            Some(ChangeEvent::Insert(Stock {
+                sku: "KOPI".into(),
+                qty: 10
+            }))
+        );
+    }
+
+    #[test]
+    fn update_and_replace_carry_id_and_latest_document() {
+        let id = ObjectId::new();
+        let latest = doc! { "_id": id, "sku": "KOPI", "qty": 7_i64 };
+
+        for operation in ["update", "replace"] {
+            assert_eq!(
+                mapped(raw_event(operation, id, Some(latest.clone()))),
+                Some(ChangeEvent::Update {
+                    id: Bson::ObjectId(id),
+                    document: Some(Stock {
+                        sku: "KOPI".into(),
+                        qty: 7
+                    }),
+                })
+            );
+        }
+        assert_eq!(
+            mapped(raw_event("update", id, None)),
+            Some(ChangeEvent::Update {
+                id: Bson::ObjectId(id),
+                document: None
+            })
+        );
+    }
+
+    #[test]
+    fn delete_carries_id_and_other_events_are_skipped() {
+        let id = ObjectId::new();
+
+        assert_eq!(
+            mapped(raw_event("delete", id, None)),
+            Some(ChangeEvent::Delete {
+                id: Bson::ObjectId(id)
+            })
+        );
+        assert_eq!(mapped(raw_event("drop", id, None)), None);
+    }
+
+    #[test]
+    fn resume_slot_keeps_latest_token() {
+        let token = |data: &str| -> ResumeToken {
+            mongodb::bson::from_bson(Bson::Document(doc! { "_data": data })).unwrap()
+        };
+        let slot = ResumeTokenSlot::default();
+        let shared = slot.clone();
+        assert!(slot.get().is_none());
+
+        shared.set(token("01"));
+        shared.set(token("02"));
+
+        assert_eq!(
+            mongodb::bson::to_bson(&slot.get().unwrap()).unwrap(),
+            Bson::Document(doc! { "_data": "02" })
+        );
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB replica set"]
+    async fn watch_reports_insert_then_update() {
+        let collection = crate::testing::test_database()
+            .await
+            .collection::<Stock>("stocks");
+        let raw = collection.clone_with_type::<Document>();
+        let slot = ResumeTokenSlot::default();
+        let mut events = watch_collection_with(collection, vec![], slot.clone());
+        let writer = async {
+            // Beri waktu stream terbuka sebelum menulis
+            actix_web::rt::time::sleep(std::time::Duration::from_millis(500)).await;
+            let id = raw
+                .insert_one(doc! { "sku": "KOPI", "qty": 10_i64 })
+                .await
+                .unwrap()
+                .inserted_id;
+            raw.update_one(doc! { "_id": &id }, doc! { "$set": { "qty": 7_i64 } })
+                .await
+                .unwrap();
+            id
+        };
+
+        // Stream baru dibuka saat pertama kali di-poll, jadi ditunggu bersamaan dengan writer
+        let (id, (first, second)) =
+            futures::join!(writer, async { (events.next().await, events.next().await) });
+
+        assert_eq!(
+            first,
+            Some(ChangeEvent::Insert(Stock {
+                sku: "KOPI".into(),
+                qty: 10
+            }))
+        );
+        assert_eq!(
+            second,
+            Some(ChangeEvent::Update {
+                id,
+                document: Some(Stock {
+                    sku: "KOPI".into(),
+                    qty: 7
+                }),
+            })
+        );
+        assert!(slot.get().is_some());
+    }
+}
@@ -0,0 +1,120 @@
+use crate::errors::ServiceError;
+use crate::utils::map_mongo_error;
+use mongodb::Cursor;
+use serde::de::DeserializeOwned;
+
+/// Dokumen yang dilewati `collect_lenient` karena tidak sesuai model
+#[derive(Debug, Clone)]
+pub struct DeserializeError {
+    // `_id` dalam bentuk hex/string jika masih bisa dibaca dari dokumen mentah
+    pub id: Option<String>,
+    pub message: String,
+}
+
+/// Kumpulkan semua dokumen dari cursor. Dokumen yang gagal di-deserialize tidak
+/// menggagalkan seluruh hasil, melainkan dikembalikan terpisah di `Vec<DeserializeError>`.
+/// Error koneksi/query tetap dikembalikan sebagai `Err`.
+pub async fn collect_lenient<T>(
+    mut cursor: Cursor<T>,
+) -> Result<(Vec<T>, Vec<DeserializeError>), ServiceError>
+where
+    T: DeserializeOwned,
+{
+    let mut items = Vec::new();
+    let mut errors = Vec::new();
+
+    while cursor.advance().await.map_err(map_mongo_error)? {
+        match cursor.deserialize_current() {
+            Ok(item) => items.push(item),
+            Err(e) => errors.push(DeserializeError {
+                id: current_id(&cursor),
+                message: e.to_string(),
+            }),
+        }
+    }
+
+    Ok((items, errors))
+}
+
+/// Kumpulkan semua dokumen dari cursor, berhenti di dokumen pertama yang gagal
+/// di-deserialize. Pakai untuk endpoint yang butuh data lengkap.
+pub async fn collect_all<T>(mut cursor: Cursor<T>) -> Result<Vec<T>, ServiceError>
+where
+    T: DeserializeOwned,
+{
+    let mut items = Vec::new();
+
+    while cursor.advance().await.map_err(map_mongo_error)? {
+        items.push(cursor.deserialize_current().map_err(map_mongo_error)?);
+    }
+
+    Ok(items)
+}
+
+fn current_id<T>(cursor: &Cursor<T>) -> Option<String> {
+    let id = cursor.current().get("_id").ok()??;
+    match id.as_object_id() {
+        Some(oid) => Some(oid.to_hex()),
+        None => id.as_str().map(str::to_string),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_database;
+    use bson::{Document, doc, oid::ObjectId};
+    use mongodb::Collection;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct Item {
+        name: String,
+        price: f64,
+    }
+
+    // Dua dokumen valid dan satu baris import yang `price`-nya berupa teks
+    async fn mixed_items() -> (Collection<Item>, ObjectId) {
+        let collection = test_database().await.collection::<Document>("items");
+        let bad_id = ObjectId::new();
+        collection
+            .insert_many([
+                doc! { "name": "Kopi", "price": 10.0 },
+                doc! { "_id": bad_id, "name": "Teh", "price": "sepuluh" },
+                doc! { "name": "Gula", "price": 5.0 },
+            ])
+            .await
+            .unwrap();
+        (collection.clone_with_type(), bad_id)
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn lenient_collect_keeps_good_documents() {
+        let (items, bad_id) = mixed_items().await;
+
+        let cursor = items
+            .find(doc! {})
+            .sort(doc! { "price": -1 })
+            .await
+            .unwrap();
+        let (good, errors) = collect_lenient(cursor).await.unwrap();
+
+        let names: Vec<_> = good.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(&"Kopi") && names.contains(&"Gula"));
+        assert!(good.iter().all(|item| item.price > 0.0));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].id, Some(bad_id.to_hex()));
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn strict_collect_fails_on_bad_document() {
+        let (items, _) = mixed_items().await;
+
+        let cursor = items.find(doc! {}).await.unwrap();
+
+        assert!(collect_all(cursor).await.is_err());
+    }
+}
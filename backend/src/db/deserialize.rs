@@ -0,0 +1,230 @@
+use crate::errors::ServiceError;
+use crate::utils::request_context::log_with_context;
+use log::Level;
+use mongodb::bson::{self, Bson, Document};
+use serde::de::DeserializeOwned;
+
+/// `bson::from_document` dengan error yang menyebut field penyebabnya, contoh
+/// `Dokumen tidak sesuai model di field "items.0.price"`. Dokumen yang gagal beserta
+/// `_id`-nya dicatat ke log agar data hasil migrasi yang rusak mudah dicari.
+pub fn deserialize_document<T: DeserializeOwned>(doc: Document) -> Result<T, ServiceError> {
+    // Dokumen asli dibutuhkan lagi untuk mencari field yang gagal
+    let err = match bson::from_document::<T>(doc.clone()) {
+        Ok(value) => return Ok(value),
+        Err(err) => err,
+    };
+
+    let message = err.to_string();
+    let path = failing_path::<T>(&doc, &message);
+    let id = document_id(&doc).unwrap_or_else(|| "-".into());
+    log_with_context(
+        Level::Error,
+        &format!(
+            "Dokumen _id={} tidak sesuai model {} di field {}: {}",
+            id,
+            std::any::type_name::<T>(),
+            path.as_deref().unwrap_or("?"),
+            message
+        ),
+    );
+
+    let context = match path {
+        Some(path) => format!("Dokumen tidak sesuai model di field \"{}\"", path),
+        None => "Dokumen tidak sesuai model".to_string(),
+    };
+    Err(ServiceError::internal(context, err))
+}
+
+fn document_id(doc: &Document) -> Option<String> {
+    match doc.get("_id")? {
+        Bson::ObjectId(id) => Some(id.to_hex()),
+        Bson::String(id) => Some(id.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+fn format_path(path: &[Segment]) -> String {
+    path.iter()
+        .map(|segment| match segment {
+            Segment::Key(key) => key.clone(),
+            Segment::Index(index) => index.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Error serde tidak membawa path field. Field yang hilang atau tidak dikenal disebut di
+/// pesannya; untuk error lain setiap field dicoba dibuang satu per satu, field yang
+/// membuat pesan error berubah adalah penyebabnya. Ditelusuri sampai field terdalam.
+fn failing_path<T: DeserializeOwned>(doc: &Document, message: &str) -> Option<String> {
+    for prefix in ["missing field `", "unknown field `"] {
+        if let Some(rest) = message.split(prefix).nth(1) {
+            return rest.split('`').next().map(str::to_string);
+        }
+    }
+
+    let root = Bson::Document(doc.clone());
+    let mut path = Vec::new();
+    probe::<T>(&root, &root, &mut path, message).then(|| format_path(&path))
+}
+
+// `true` jika penyebab ditemukan di bawah `current`, `path` berisi path terdalamnya
+fn probe<T: DeserializeOwned>(
+    root: &Bson,
+    current: &Bson,
+    path: &mut Vec<Segment>,
+    message: &str,
+) -> bool {
+    let children: Vec<(Segment, &Bson)> = match current {
+        Bson::Document(doc) => doc
+            .iter()
+            .map(|(key, value)| (Segment::Key(key.clone()), value))
+            .collect(),
+        Bson::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (Segment::Index(index), value))
+            .collect(),
+        _ => return false,
+    };
+
+    for (segment, value) in children {
+        path.push(segment);
+        let Some(Bson::Document(candidate)) = without_path(root, path) else {
+            path.pop();
+            continue;
+        };
+        let changed = match bson::from_document::<T>(candidate) {
+            Ok(_) => true,
+            Err(err) => err.to_string() != message,
+        };
+        if changed {
+            probe::<T>(root, value, path, message);
+            return true;
+        }
+        path.pop();
+    }
+    false
+}
+
+fn without_path(value: &Bson, path: &[Segment]) -> Option<Bson> {
+    let (first, rest) = path.split_first()?;
+
+    match (value, first) {
+        (Bson::Document(doc), Segment::Key(key)) => {
+            let mut doc = doc.clone();
+            if rest.is_empty() {
+                doc.remove(key);
+            } else {
+                let child = without_path(doc.get(key)?, rest)?;
+                doc.insert(key.clone(), child);
+            }
+            Some(Bson::Document(doc))
+        }
+        (Bson::Array(items), Segment::Index(index)) => {
+            let mut items = items.clone();
+            if rest.is_empty() {
+                items.remove(*index);
+            } else {
+                let child = without_path(items.get(*index)?, rest)?;
+                items[*index] = child;
+            }
+            Some(Bson::Array(items))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::{doc, oid::ObjectId};
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Item {
+        sku: String,
+        price: f64,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Sale {
+        #[serde(rename = "_id")]
+        id: ObjectId,
+        customer: String,
+        items: Vec<Item>,
+        note: Option<String>,
+    }
+
+    fn context(result: Result<Sale, ServiceError>) -> String {
+        match result {
+            Err(ServiceError::Internal { context, .. }) => context,
+            other => panic!("hasil tidak terduga: {:?}", other),
+        }
+    }
+
+    fn sale(items: Bson) -> Document {
+        doc! { "_id": ObjectId::new(), "customer": "Budi", "items": items }
+    }
+
+    #[test]
+    fn valid_document_deserializes() {
+        let sale: Sale =
+            deserialize_document(sale(bson::bson!([{ "sku": "KOPI", "price": 10.0 }]))).unwrap();
+
+        assert_eq!(sale.items[0].sku, "KOPI");
+        assert!(sale.note.is_none());
+    }
+
+    #[test]
+    fn wrong_typed_field_is_named() {
+        let mut doc = sale(bson::bson!([]));
+        doc.insert("customer", 42);
+
+        assert_eq!(
+            context(deserialize_document(doc)),
+            "Dokumen tidak sesuai model di field \"customer\""
+        );
+    }
+
+    #[test]
+    fn nested_field_path_is_named() {
+        let doc = sale(bson::bson!([
+            { "sku": "KOPI", "price": 10.0 },
+            { "sku": "TEH", "price": "sepuluh" },
+        ]));
+
+        assert_eq!(
+            context(deserialize_document(doc)),
+            "Dokumen tidak sesuai model di field \"items.1.price\""
+        );
+    }
+
+    #[test]
+    fn missing_field_is_named() {
+        let mut doc = sale(bson::bson!([]));
+        doc.remove("customer");
+
+        assert_eq!(
+            context(deserialize_document(doc)),
+            "Dokumen tidak sesuai model di field \"customer\""
+        );
+    }
+
+    #[test]
+    fn document_id_is_readable() {
+        let id = ObjectId::new();
+
+        assert_eq!(document_id(&doc! { "_id": id }), Some(id.to_hex()));
+        assert_eq!(document_id(&doc! { "_id": "SKU-1" }), Some("SKU-1".into()));
+        assert_eq!(document_id(&doc! {}), None);
+    }
+}
@@ -0,0 +1,242 @@
+use crate::errors::ServiceError;
+use bson::{Bson, DateTime as BsonDateTime, Document, doc, oid::ObjectId};
+
+/// Nama field soft-delete. Di model pakai
+/// `#[serde(default, skip_serializing_if = "Option::is_none")] deleted_at: Option<bson::DateTime>`
+pub const DELETED_AT_FIELD: &str = "deleted_at";
+
+/// Filter data yang belum dihapus. `null` juga cocok dengan dokumen yang tidak punya field-nya
+pub fn not_deleted_filter() -> Document {
+    doc! { DELETED_AT_FIELD: null }
+}
+
+/// Update document untuk soft-delete, dipakai dengan `update_one`/`update_many`
+pub fn mark_deleted() -> Document {
+    doc! { "$set": { DELETED_AT_FIELD: BsonDateTime::now() } }
+}
+
+/// Gabungkan dua filter dengan `$and`, filter kosong diabaikan.
+/// Contoh: `merge_filters(not_deleted_filter(), doc! { "user_id": id })`
+pub fn merge_filters(base: Document, extra: Document) -> Document {
+    if base.is_empty() {
+        return extra;
+    }
+    if extra.is_empty() {
+        return base;
+    }
+
+    doc! { "$and": [base, extra] }
+}
+
+/// Panjang maksimal kata kunci pencarian (dalam karakter)
+pub const MAX_SEARCH_TERM_LEN: usize = 64;
+
+/// Cara kata kunci dicocokkan dengan isi field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchMatch {
+    // `^term`, bisa memakai index biasa jika collation-nya cocok
+    Prefix,
+    #[default]
+    Substring,
+}
+
+/// Filter `$regex` case-insensitive dari input user. Karakter khusus regex di-escape
+/// sehingga `.*(` dicocokkan apa adanya, dan kata kunci lebih dari `MAX_SEARCH_TERM_LEN`
+/// ditolak dengan `BadRequest`.
+pub fn build_search_filter(field: &str, term: &str) -> Result<Document, ServiceError> {
+    build_search_filter_with(field, term, SearchMatch::default(), MAX_SEARCH_TERM_LEN)
+}
+
+pub fn build_search_filter_with(
+    field: &str,
+    term: &str,
+    mode: SearchMatch,
+    max_len: usize,
+) -> Result<Document, ServiceError> {
+    let term = term.trim();
+    if term.chars().count() > max_len {
+        return Err(ServiceError::BadRequest(format!(
+            "Kata kunci pencarian maksimal {} karakter",
+            max_len
+        )));
+    }
+
+    let escaped = regex::escape(term);
+    let pattern = match mode {
+        SearchMatch::Prefix => format!("^{}", escaped),
+        SearchMatch::Substring => escaped,
+    };
+
+    Ok(doc! { field: { "$regex": pattern, "$options": "i" } })
+}
+
+/// Batas jumlah nilai dalam satu query param CSV
+pub const MAX_CSV_VALUES: usize = 100;
+
+/// Field id (`_id` atau berakhiran `_id`) disimpan sebagai `ObjectId`
+fn is_id_field(field: &str) -> bool {
+    field == "_id" || field.ends_with("_id")
+}
+
+/// Filter `$in` dari query param seperti `?category=a,b,c`. Nilai di-trim dan nilai kosong
+/// dibuang, input kosong menghasilkan document kosong (tanpa filter). Untuk field id,
+/// nilai di-parse menjadi `ObjectId` dan id yang rusak ditolak dengan `BadRequest`.
+pub fn csv_in_filter(field: &str, raw: &str) -> Result<Document, ServiceError> {
+    let values: Vec<&str> = raw
+        .split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .collect();
+
+    if values.is_empty() {
+        return Ok(Document::new());
+    }
+    if values.len() > MAX_CSV_VALUES {
+        return Err(ServiceError::BadRequest(format!(
+            "Filter {} maksimal {} nilai",
+            field, MAX_CSV_VALUES
+        )));
+    }
+
+    let values: Vec<Bson> = if is_id_field(field) {
+        values
+            .into_iter()
+            .map(|v| {
+                ObjectId::parse_str(v).map(Bson::ObjectId).map_err(|_| {
+                    ServiceError::BadRequest(format!(
+                        "ID '{}' pada filter {} tidak valid",
+                        v, field
+                    ))
+                })
+            })
+            .collect::<Result<_, _>>()?
+    } else {
+        values
+            .into_iter()
+            .map(|v| Bson::String(v.to_string()))
+            .collect()
+    };
+
+    Ok(doc! { field: { "$in": values } })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn not_deleted_filter_matches_null() {
+        assert_eq!(not_deleted_filter(), doc! { "deleted_at": null });
+    }
+
+    #[test]
+    fn mark_deleted_sets_timestamp() {
+        let update = mark_deleted();
+
+        let set = update.get_document("$set").unwrap();
+        assert_eq!(set.len(), 1);
+        assert!(set.get_datetime(DELETED_AT_FIELD).is_ok());
+    }
+
+    #[test]
+    fn merge_filters_keeps_both_conditions() {
+        let user_id = ObjectId::new();
+
+        let merged = merge_filters(not_deleted_filter(), doc! { "user_id": user_id });
+
+        assert_eq!(
+            merged,
+            doc! { "$and": [{ "deleted_at": null }, { "user_id": user_id }] }
+        );
+    }
+
+    #[test]
+    fn merge_filters_skips_empty_side() {
+        assert_eq!(
+            merge_filters(Document::new(), not_deleted_filter()),
+            not_deleted_filter()
+        );
+        assert_eq!(
+            merge_filters(not_deleted_filter(), Document::new()),
+            not_deleted_filter()
+        );
+    }
+
+    fn regex_pattern(filter: &Document, field: &str) -> String {
+        let condition = filter.get_document(field).unwrap();
+        assert_eq!(condition.get_str("$options").unwrap(), "i");
+        condition.get_str("$regex").unwrap().to_string()
+    }
+
+    #[test]
+    fn search_term_is_matched_literally() {
+        let filter = build_search_filter("name", "  kopi.*(  ").unwrap();
+
+        let pattern = regex_pattern(&filter, "name");
+        assert_eq!(pattern, r"kopi\.\*\(");
+        let regex = regex::RegexBuilder::new(&pattern)
+            .case_insensitive(true)
+            .build()
+            .unwrap();
+        assert!(regex.is_match("Es KOPI.*( susu"));
+        assert!(!regex.is_match("kopi susu"));
+    }
+
+    #[test]
+    fn prefix_mode_is_anchored() {
+        let filter = build_search_filter_with("sku", "QT-1", SearchMatch::Prefix, 8).unwrap();
+
+        assert_eq!(regex_pattern(&filter, "sku"), "^QT\\-1");
+    }
+
+    #[test]
+    fn over_long_term_is_rejected() {
+        let term = "a".repeat(MAX_SEARCH_TERM_LEN + 1);
+
+        assert!(matches!(
+            build_search_filter("name", &term),
+            Err(ServiceError::BadRequest(msg)) if msg.contains("maksimal 64")
+        ));
+        // Spasi di pinggir tidak ikut dihitung
+        let padded = format!("  {}  ", "a".repeat(MAX_SEARCH_TERM_LEN));
+        assert!(build_search_filter("name", &padded).is_ok());
+    }
+
+    #[test]
+    fn csv_values_become_in_filter() {
+        assert_eq!(
+            csv_in_filter("category", " kopi, teh ,,susu ").unwrap(),
+            doc! { "category": { "$in": ["kopi", "teh", "susu"] } }
+        );
+    }
+
+    #[test]
+    fn empty_csv_is_no_op() {
+        for raw in ["", "  ", ",, ,"] {
+            assert!(csv_in_filter("category", raw).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn id_fields_are_parsed_to_object_ids() {
+        let (a, b) = (ObjectId::new(), ObjectId::new());
+
+        let filter = csv_in_filter("category_id", &format!("{}, {}", a, b)).unwrap();
+
+        assert_eq!(filter, doc! { "category_id": { "$in": [a, b] } });
+        assert!(matches!(
+            csv_in_filter("_id", &format!("{},abc", a)),
+            Err(ServiceError::BadRequest(msg)) if msg.contains("'abc'")
+        ));
+    }
+
+    #[test]
+    fn too_many_csv_values_are_rejected() {
+        let raw = vec!["x"; MAX_CSV_VALUES + 1].join(",");
+
+        assert!(matches!(
+            csv_in_filter("category", &raw),
+            Err(ServiceError::BadRequest(_))
+        ));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::errors::ServiceError;
+use actix_web::{FromRequest, HttpRequest, dev::Payload, web::Data};
+use futures::future::{Ready, ready};
+use mongodb::{Collection, Database};
+use std::ops::Deref;
+
+/// Handle database yang di-inject lewat `web::Data<Db>`, bukan static global, sehingga
+/// test bisa memasang `Db` yang menunjuk ke database sementara
+#[derive(Debug, Clone)]
+pub struct Db {
+    database: Database,
+}
+
+impl Db {
+    pub fn new(database: Database) -> Self {
+        Db { database }
+    }
+
+    /// Collection bertipe, contoh: `db.collection::<Product>("products")`
+    pub fn collection<T: Send + Sync>(&self, name: &str) -> Collection<T> {
+        self.database.collection(name)
+    }
+
+    pub fn database(&self) -> &Database {
+        &self.database
+    }
+
+    /// Ambil dari app data, `Data<Database>` tetap diterima untuk app yang belum memakai `Db`
+    pub fn from_app_data(req: &HttpRequest) -> Option<Db> {
+        req.app_data::<Data<Db>>()
+            .map(|db| db.get_ref().clone())
+            .or_else(|| {
+                req.app_data::<Data<Database>>()
+                    .map(|database| Db::new(database.get_ref().clone()))
+            })
+    }
+}
+
+impl From<Database> for Db {
+    fn from(database: Database) -> Self {
+        Db::new(database)
+    }
+}
+
+// Service yang menerima `&Database` tetap bisa dipanggil dengan `&db`
+impl Deref for Db {
+    type Target = Database;
+
+    fn deref(&self) -> &Database {
+        &self.database
+    }
+}
+
+impl FromRequest for Db {
+    type Error = ServiceError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            Db::from_app_data(req)
+                .ok_or_else(|| ServiceError::Unexpected("Database tidak tersedia".into())),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_database;
+    use actix_web::http::StatusCode;
+    use actix_web::{App, HttpResponse, test, web};
+    use bson::{Document, doc};
+
+    async fn database_name(db: Db) -> HttpResponse {
+        HttpResponse::Ok().body(db.name().to_string())
+    }
+
+    async fn offline_database() -> Database {
+        mongodb::Client::with_uri_str("mongodb://127.0.0.1:1/")
+            .await
+            .unwrap()
+            .database("qtoky_test")
+    }
+
+    #[actix_web::test]
+    async fn missing_db_is_an_error() {
+        let app = test::init_service(App::new().route("/", web::get().to(database_name))).await;
+
+        let res = test::call_service(&app, test::TestRequest::get().to_request()).await;
+
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[actix_web::test]
+    async fn plain_database_data_is_still_accepted() {
+        let database = offline_database().await;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(database))
+                .route("/", web::get().to(database_name)),
+        )
+        .await;
+
+        let body = test::call_and_read_body(&app, test::TestRequest::get().to_request()).await;
+
+        assert_eq!(body, "qtoky_test");
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn injected_db_round_trips_insert_and_find() {
+        async fn create(db: Data<Db>) -> HttpResponse {
+            db.collection::<Document>("products")
+                .insert_one(doc! { "name": "Kopi" })
+                .await
+                .unwrap();
+            HttpResponse::Created().finish()
+        }
+
+        async fn show(db: Data<Db>) -> HttpResponse {
+            let found = db
+                .collection::<Document>("products")
+                .find_one(doc! { "name": "Kopi" })
+                .await
+                .unwrap()
+                .unwrap();
+            HttpResponse::Ok().body(found.get_str("name").unwrap().to_string())
+        }
+
+        let db = Db::new(test_database().await);
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db.clone()))
+                .route("/products", web::post().to(create))
+                .route("/products", web::get().to(show)),
+        )
+        .await;
+
+        let req = test::TestRequest::post().uri("/products").to_request();
+        assert_eq!(
+            test::call_service(&app, req).await.status(),
+            StatusCode::CREATED
+        );
+        let req = test::TestRequest::get().uri("/products").to_request();
+        assert_eq!(test::call_and_read_body(&app, req).await, "Kopi");
+
+        // Data tersimpan di database test yang di-inject, bukan database aplikasi
+        let stored = db
+            .collection::<Document>("products")
+            .count_documents(doc! {})
+            .await
+            .unwrap();
+        assert_eq!(stored, 1);
+    }
+}
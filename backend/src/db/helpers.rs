@@ -0,0 +1,955 @@
+use crate::db::projection::validate_projection;
+use crate::db::scope::ORG_FIELD;
+use crate::errors::ServiceError;
+use crate::utils::i18n::{Message, t};
+use crate::utils::{
+    is_duplicate_key_error, map_indexed_write_error, map_mongo_error, parse_object_id_param,
+};
+use mongodb::{
+    Collection,
+    bson::{Bson, Document, doc, oid::ObjectId},
+    error::{ErrorKind, InsertManyError},
+    options::{Collation, CollationStrength, FindOneOptions, ReturnDocument},
+};
+use serde::{Serialize, de::DeserializeOwned};
+use std::collections::HashSet;
+
+/// Collation case-insensitive (strength 2 mengabaikan huruf besar/kecil, tidak mengabaikan aksen).
+/// Buat index dengan collation yang sama agar query tidak melakukan collection scan.
+pub fn case_insensitive_collation() -> Collation {
+    Collation::builder()
+        .locale("en")
+        .strength(CollationStrength::Secondary)
+        .build()
+}
+
+/// Cari satu dokumen dengan `field == value` tanpa membedakan huruf besar/kecil,
+/// contoh: `Budi` cocok dengan `budi`. Untuk email, lebih baik simpan field turunan
+/// seperti `email_lc` yang diisi `normalize_email` lalu query biasa ke field itu.
+pub async fn find_one_ci<T>(
+    collection: &Collection<T>,
+    field: &str,
+    value: &str,
+) -> Result<Option<T>, ServiceError>
+where
+    T: DeserializeOwned + Send + Sync,
+{
+    collection
+        .find_one(doc! { field: value })
+        .collation(case_insensitive_collation())
+        .await
+        .map_err(map_mongo_error)
+}
+
+/// Cari satu dokumen, `NotFound("<entity_name> tidak ditemukan")` jika tidak ada.
+/// Contoh: `find_one_or_not_found(&products, filter, "Produk")`
+pub async fn find_one_or_not_found<T>(
+    collection: &Collection<T>,
+    filter: Document,
+    entity_name: &str,
+) -> Result<T, ServiceError>
+where
+    T: DeserializeOwned + Send + Sync,
+{
+    find_one_projected_or_not_found(collection, filter, None, entity_name).await
+}
+
+/// Seperti `find_one_or_not_found` dengan projection opsional dari `db::projection`.
+/// Field yang disembunyikan harus `Option`/`#[serde(default)]` di `T`, atau pakai
+/// `collection.clone_with_type::<Document>()`.
+pub async fn find_one_projected_or_not_found<T>(
+    collection: &Collection<T>,
+    filter: Document,
+    projection: Option<Document>,
+    entity_name: &str,
+) -> Result<T, ServiceError>
+where
+    T: DeserializeOwned + Send + Sync,
+{
+    if let Some(projection) = &projection {
+        validate_projection(projection)?;
+    }
+
+    collection
+        .find_one(filter)
+        .with_options(FindOneOptions::builder().projection(projection).build())
+        .await
+        .map_err(map_mongo_error)?
+        .ok_or_else(|| ServiceError::NotFound(format!("{} tidak ditemukan", entity_name)))
+}
+
+/// Seperti `find_one_or_not_found` dengan filter `_id`, id yang rusak menjadi `BadRequest`
+pub async fn find_by_id_or_not_found<T>(
+    collection: &Collection<T>,
+    id: &str,
+    entity_name: &str,
+) -> Result<T, ServiceError>
+where
+    T: DeserializeOwned + Send + Sync,
+{
+    let object_id = parse_object_id_param(id)?;
+    find_one_or_not_found(collection, doc! { "_id": object_id }, entity_name).await
+}
+
+/// Parse daftar id string sekaligus. Id yang valid dan yang rusak dikembalikan terpisah
+/// agar semua id rusak bisa dilaporkan dalam satu response.
+pub fn parse_object_ids<S: AsRef<str>>(ids: &[S]) -> (Vec<ObjectId>, Vec<String>) {
+    let mut parsed = Vec::new();
+    let mut malformed = Vec::new();
+
+    for id in ids {
+        match ObjectId::parse_str(id.as_ref().trim()) {
+            Ok(oid) => parsed.push(oid),
+            Err(_) => malformed.push(id.as_ref().to_string()),
+        }
+    }
+
+    (parsed, malformed)
+}
+
+/// Cek keberadaan banyak `_id` dengan satu query `$in`, mengembalikan id yang tidak ada.
+/// Urutan hasil mengikuti urutan `ids`.
+pub async fn exists_all<T>(
+    collection: &Collection<T>,
+    ids: &[ObjectId],
+) -> Result<Vec<ObjectId>, ServiceError>
+where
+    T: Send + Sync,
+{
+    exists_all_matching(collection, ids, Document::new()).await
+}
+
+/// Seperti `exists_all`, dengan filter tambahan. Contoh: `doc! { "user_id": user_id }`
+/// agar produk milik user lain ikut dianggap tidak ada.
+pub async fn exists_all_matching<T>(
+    collection: &Collection<T>,
+    ids: &[ObjectId],
+    filter: Document,
+) -> Result<Vec<ObjectId>, ServiceError>
+where
+    T: Send + Sync,
+{
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut unique = ids.to_vec();
+    unique.sort();
+    unique.dedup();
+
+    let mut query = filter;
+    query.insert("_id", doc! { "$in": &unique });
+
+    let found: HashSet<ObjectId> = collection
+        .distinct("_id", query)
+        .await
+        .map_err(map_mongo_error)?
+        .into_iter()
+        .filter_map(|id| id.as_object_id())
+        .collect();
+
+    let mut missing = Vec::new();
+    for id in ids {
+        if !found.contains(id) && !missing.contains(id) {
+            missing.push(*id);
+        }
+    }
+    Ok(missing)
+}
+
+// Field pemilik/tenant dan `_id` (pengecualian dokumen yang sedang di-update) tidak
+// disebut di pesan conflict, user hanya peduli nilai yang diinput
+const OWNER_FIELDS: [&str; 3] = ["_id", "user_id", ORG_FIELD];
+
+fn describe_key(filter: &Document) -> String {
+    let describe = |(field, value): (&String, &Bson)| match value {
+        Bson::String(v) => format!("{} '{}'", field, v),
+        other => format!("{} {}", field, other),
+    };
+    let business: Vec<String> = filter
+        .iter()
+        .filter(|(field, _)| !OWNER_FIELDS.contains(&field.as_str()))
+        .map(describe)
+        .collect();
+
+    if business.is_empty() {
+        filter.iter().map(describe).collect::<Vec<_>>().join(", ")
+    } else {
+        business.join(", ")
+    }
+}
+
+/// Pre-check sebelum insert agar conflict bisa dilaporkan tanpa write yang gagal, contoh
+/// `ensure_unique(&products, doc! { "user_id": id, "sku": sku })` menjadi
+/// `Conflict("sku 'A1' sudah digunakan")`. Untuk update, kecualikan dokumen itu sendiri
+/// dengan `"_id": { "$ne": id }`. Unique index tetap penjaga utama karena insert
+/// bersamaan masih bisa lolos pre-check ini.
+pub async fn ensure_unique<T>(
+    collection: &Collection<T>,
+    filter: Document,
+) -> Result<(), ServiceError>
+where
+    T: Send + Sync,
+{
+    let existing = collection
+        .count_documents(filter.clone())
+        .limit(1)
+        .await
+        .map_err(map_mongo_error)?;
+
+    if existing > 0 {
+        return Err(ServiceError::Conflict(format!(
+            "{} {}",
+            describe_key(&filter),
+            t(Message::AlreadyUsed)
+        )));
+    }
+    Ok(())
+}
+
+/// Field yang tidak boleh berubah lewat update apa pun
+pub const DEFAULT_IMMUTABLE_FIELDS: [&str; 2] = ["_id", "created_at"];
+/// Tambahan untuk data milik user/tenant, ownership tidak boleh dipindah lewat update
+pub const OWNED_IMMUTABLE_FIELDS: [&str; 4] = ["_id", "created_at", "user_id", ORG_FIELD];
+
+/// `key` adalah salah satu `fields` atau sub-field-nya, contoh `created_at.$date`
+pub(crate) fn is_protected(key: &str, fields: &[&str]) -> bool {
+    fields.iter().any(|field| {
+        key == *field
+            || key
+                .strip_prefix(field)
+                .is_some_and(|rest| rest.starts_with('.'))
+    })
+}
+
+// Update bisa berupa field langsung atau operator seperti `{ "$set": { ... } }`
+fn immutable_keys(update: &Document, fields: &[&str]) -> Vec<String> {
+    let mut found = Vec::new();
+    for (key, value) in update {
+        if key.starts_with('$') {
+            if let Bson::Document(inner) = value {
+                found.extend(
+                    inner
+                        .keys()
+                        .filter(|k| is_protected(k, fields))
+                        .map(|k| k.to_string()),
+                );
+            }
+        } else if is_protected(key, fields) {
+            found.push(key.to_string());
+        }
+    }
+    found
+}
+
+/// Buang field immutable dari update document (termasuk di dalam `$set`, `$unset`, dst),
+/// mengembalikan nama field yang dibuang
+pub fn strip_immutable(update: &mut Document, fields: &[&str]) -> Vec<String> {
+    let removed = immutable_keys(update, fields);
+    if removed.is_empty() {
+        return removed;
+    }
+
+    let mut kept = Document::new();
+    for (key, value) in std::mem::take(update) {
+        match value {
+            Bson::Document(inner) if key.starts_with('$') => {
+                let inner: Document = inner
+                    .into_iter()
+                    .filter(|(k, _)| !is_protected(k, fields))
+                    .collect();
+                if !inner.is_empty() {
+                    kept.insert(key, inner);
+                }
+            }
+            value if !is_protected(&key, fields) => {
+                kept.insert(key, value);
+            }
+            _ => {}
+        }
+    }
+    *update = kept;
+    removed
+}
+
+/// Seperti `strip_immutable`, tapi menolak update dengan `BadRequest` yang menyebut field-nya
+pub fn reject_immutable(update: &Document, fields: &[&str]) -> Result<(), ServiceError> {
+    let found = immutable_keys(update, fields);
+    if !found.is_empty() {
+        return Err(ServiceError::BadRequest(format!(
+            "Field {} tidak boleh diubah",
+            found.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Hasil `update_one_checked` untuk dokumen yang ditemukan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateOutcome {
+    Modified,
+    // Dokumen ditemukan tapi nilainya sudah sama dengan update
+    Unchanged,
+}
+
+/// `update_one` yang mengembalikan `NotFound(not_found_message)` jika filter tidak cocok
+/// dengan dokumen apa pun. Error lain diterjemahkan lewat `map_mongo_error`.
+pub async fn update_one_checked<T>(
+    collection: &Collection<T>,
+    filter: Document,
+    update: Document,
+    not_found_message: &str,
+) -> Result<UpdateOutcome, ServiceError>
+where
+    T: Send + Sync,
+{
+    let result = collection
+        .update_one(filter, update)
+        .await
+        .map_err(map_mongo_error)?;
+
+    update_outcome(
+        result.matched_count,
+        result.modified_count,
+        not_found_message,
+    )
+}
+
+// Bagian `update_one_checked` setelah update dijalankan
+fn update_outcome(
+    matched_count: u64,
+    modified_count: u64,
+    not_found_message: &str,
+) -> Result<UpdateOutcome, ServiceError> {
+    match (matched_count, modified_count) {
+        (0, _) => Err(ServiceError::NotFound(not_found_message.to_string())),
+        (_, 0) => Ok(UpdateOutcome::Unchanged),
+        _ => Ok(UpdateOutcome::Modified),
+    }
+}
+
+/// `delete_one` yang mengembalikan `NotFound(not_found_message)` jika tidak ada yang terhapus
+pub async fn delete_one_checked<T>(
+    collection: &Collection<T>,
+    filter: Document,
+    not_found_message: &str,
+) -> Result<(), ServiceError>
+where
+    T: Send + Sync,
+{
+    let result = collection
+        .delete_one(filter)
+        .await
+        .map_err(map_mongo_error)?;
+
+    if result.deleted_count == 0 {
+        return Err(ServiceError::NotFound(not_found_message.to_string()));
+    }
+
+    Ok(())
+}
+
+/// Hasil `upsert_one`/`upsert_one_returning_previous`
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpsertOutcome<T> {
+    // `_id` dokumen yang baru dibuat
+    Inserted(Bson),
+    // Dokumen sebelum di-update, hanya diisi oleh `upsert_one_returning_previous`
+    Updated(Option<T>),
+}
+
+// Dua upsert bersamaan dengan filter di unique index bisa sama-sama mencoba insert,
+// yang kalah mendapat 11000 dan cukup diulang sekali untuk menjadi update
+const UPSERT_ATTEMPTS: usize = 2;
+
+/// Update dokumen yang cocok dengan `filter`, atau insert jika belum ada, dalam satu
+/// operasi atomik. Menghindari race check-then-insert, contoh upsert produk by SKU.
+pub async fn upsert_one<T>(
+    collection: &Collection<T>,
+    filter: Document,
+    update: Document,
+) -> Result<UpsertOutcome<T>, ServiceError>
+where
+    T: Send + Sync,
+{
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        match collection
+            .update_one(filter.clone(), update.clone())
+            .upsert(true)
+            .await
+        {
+            Ok(result) => {
+                return Ok(match result.upserted_id {
+                    Some(id) => UpsertOutcome::Inserted(id),
+                    None => UpsertOutcome::Updated(None),
+                });
+            }
+            Err(err) if is_duplicate_key_error(&err) && attempt < UPSERT_ATTEMPTS => continue,
+            Err(err) => return Err(map_mongo_error(err)),
+        }
+    }
+}
+
+/// Seperti `upsert_one`, tapi untuk branch update ikut mengembalikan dokumen sebelum
+/// di-update (`find_one_and_update` dengan `ReturnDocument::Before`). `_id` untuk branch
+/// insert dibuat di sini lewat `$setOnInsert` kecuali `filter` sudah memuat `_id`.
+pub async fn upsert_one_returning_previous<T>(
+    collection: &Collection<T>,
+    filter: Document,
+    mut update: Document,
+) -> Result<UpsertOutcome<T>, ServiceError>
+where
+    T: DeserializeOwned + Send + Sync,
+{
+    let inserted_id = match filter.get("_id") {
+        Some(id) => id.clone(),
+        None => {
+            let id = Bson::ObjectId(ObjectId::new());
+            match update.get_document_mut("$setOnInsert") {
+                Ok(set_on_insert) => {
+                    set_on_insert.insert("_id", id.clone());
+                }
+                Err(_) => {
+                    update.insert("$setOnInsert", doc! { "_id": id.clone() });
+                }
+            }
+            id
+        }
+    };
+
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        match collection
+            .find_one_and_update(filter.clone(), update.clone())
+            .upsert(true)
+            .return_document(ReturnDocument::Before)
+            .await
+        {
+            Ok(Some(previous)) => return Ok(UpsertOutcome::Updated(Some(previous))),
+            Ok(None) => return Ok(UpsertOutcome::Inserted(inserted_id)),
+            Err(err) if is_duplicate_key_error(&err) && attempt < UPSERT_ATTEMPTS => continue,
+            Err(err) => return Err(map_mongo_error(err)),
+        }
+    }
+}
+
+/// Dokumen yang gagal di-insert oleh `insert_many_lenient`, `index` sesuai urutan input
+#[derive(Debug)]
+pub struct FailedInsert {
+    pub index: usize,
+    pub error: ServiceError,
+}
+
+/// Hasil `insert_many_lenient`
+#[derive(Debug, Default)]
+pub struct InsertManyReport {
+    pub inserted: usize,
+    pub failed: Vec<FailedInsert>,
+}
+
+impl InsertManyReport {
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// `insert_many` unordered untuk import massal: dokumen yang gagal (misal SKU duplikat)
+/// dilaporkan per index tanpa membatalkan dokumen lain. Error yang bukan per dokumen
+/// (koneksi, write concern) tetap dikembalikan sebagai `Err`.
+pub async fn insert_many_lenient<T>(
+    collection: &Collection<T>,
+    docs: impl IntoIterator<Item = T>,
+) -> Result<InsertManyReport, ServiceError>
+where
+    T: Serialize + Send + Sync,
+{
+    let docs: Vec<T> = docs.into_iter().collect();
+    let total = docs.len();
+    if total == 0 {
+        return Ok(InsertManyReport::default());
+    }
+
+    let err = match collection.insert_many(docs).ordered(false).await {
+        Ok(result) => {
+            return Ok(InsertManyReport {
+                inserted: result.inserted_ids.len(),
+                failed: Vec::new(),
+            });
+        }
+        Err(err) => err,
+    };
+
+    let write_errors = match err.kind.as_ref() {
+        ErrorKind::InsertMany(InsertManyError {
+            write_errors: Some(write_errors),
+            write_concern_error: None,
+            ..
+        }) => write_errors,
+        _ => return Err(map_mongo_error(err)),
+    };
+
+    let mut failed: Vec<FailedInsert> = write_errors
+        .iter()
+        .map(|write_error| FailedInsert {
+            index: write_error.index,
+            error: map_indexed_write_error(write_error),
+        })
+        .collect();
+    failed.sort_by_key(|f| f.index);
+
+    Ok(InsertManyReport {
+        inserted: total - failed.len(),
+        failed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_database;
+
+    #[test]
+    fn collation_ignores_case_only() {
+        let collation = case_insensitive_collation();
+
+        assert_eq!(collation.locale, "en");
+        assert!(matches!(
+            collation.strength,
+            Some(CollationStrength::Secondary)
+        ));
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn mixed_case_value_matches_lowercase_record() {
+        let users = test_database().await.collection::<Document>("users");
+        users
+            .insert_one(doc! { "username": "budi", "email": "budi@mail.com" })
+            .await
+            .unwrap();
+
+        let found = find_one_ci(&users, "username", "BuDi").await.unwrap();
+
+        assert_eq!(found.unwrap().get_str("email").unwrap(), "budi@mail.com");
+        assert!(
+            find_one_ci(&users, "username", "budi2")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn ci_lookup_surfaces_deserialization_errors() {
+        #[derive(Debug, serde::Deserialize)]
+        struct User {
+            #[allow(dead_code)]
+            username: i32,
+        }
+        let db = test_database().await;
+        db.collection::<Document>("users")
+            .insert_one(doc! { "username": "budi" })
+            .await
+            .unwrap();
+
+        let result = find_one_ci(&db.collection::<User>("users"), "username", "BUDI").await;
+
+        assert!(matches!(result, Err(ServiceError::Unexpected(_))));
+    }
+
+    #[test]
+    fn update_outcome_distinguishes_each_case() {
+        assert_eq!(
+            update_outcome(1, 1, "Produk tidak ditemukan").unwrap(),
+            UpdateOutcome::Modified
+        );
+        assert_eq!(
+            update_outcome(1, 0, "Produk tidak ditemukan").unwrap(),
+            UpdateOutcome::Unchanged
+        );
+        assert!(matches!(
+            update_outcome(0, 0, "Produk tidak ditemukan"),
+            Err(ServiceError::NotFound(msg)) if msg == "Produk tidak ditemukan"
+        ));
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn checked_update_and_delete_against_database() {
+        let products = test_database().await.collection::<Document>("products");
+        let id = ObjectId::new();
+        products
+            .insert_one(doc! { "_id": id, "name": "Kopi" })
+            .await
+            .unwrap();
+        let rename = |name: &str| doc! { "$set": { "name": name } };
+
+        let modified = update_one_checked(&products, doc! { "_id": id }, rename("Teh"), "x").await;
+        let unchanged = update_one_checked(&products, doc! { "_id": id }, rename("Teh"), "x").await;
+        let missing = update_one_checked(
+            &products,
+            doc! { "_id": ObjectId::new() },
+            rename("Teh"),
+            "x",
+        )
+        .await;
+
+        assert_eq!(modified.unwrap(), UpdateOutcome::Modified);
+        assert_eq!(unchanged.unwrap(), UpdateOutcome::Unchanged);
+        assert!(matches!(missing, Err(ServiceError::NotFound(_))));
+
+        assert!(
+            delete_one_checked(&products, doc! { "_id": id }, "x")
+                .await
+                .is_ok()
+        );
+        assert!(matches!(
+            delete_one_checked(&products, doc! { "_id": id }, "x").await,
+            Err(ServiceError::NotFound(_))
+        ));
+    }
+
+    #[actix_web::test]
+    async fn invalid_id_is_rejected_before_querying() {
+        // Client tanpa server, id rusak harus ditolak sebelum query dikirim
+        let client = mongodb::Client::with_uri_str("mongodb://127.0.0.1:1/")
+            .await
+            .unwrap();
+        let products = client
+            .database("qtoky_test")
+            .collection::<Document>("products");
+
+        let result = find_by_id_or_not_found(&products, "bukan-object-id", "Produk").await;
+
+        assert!(matches!(result, Err(ServiceError::BadRequest(msg)) if msg == "id tidak valid"));
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn found_and_not_found_by_id() {
+        let products = test_database().await.collection::<Document>("products");
+        let id = ObjectId::new();
+        products
+            .insert_one(doc! { "_id": id, "name": "Kopi" })
+            .await
+            .unwrap();
+
+        let found = find_by_id_or_not_found(&products, &format!(" {} ", id.to_hex()), "Produk")
+            .await
+            .unwrap();
+        let missing = find_by_id_or_not_found(&products, &ObjectId::new().to_hex(), "Produk").await;
+        let missing_filter =
+            find_one_or_not_found(&products, doc! { "name": "Teh" }, "Produk").await;
+
+        assert_eq!(found.get_str("name").unwrap(), "Kopi");
+        for result in [missing, missing_filter] {
+            assert!(matches!(
+                result,
+                Err(ServiceError::NotFound(msg)) if msg == "Produk tidak ditemukan"
+            ));
+        }
+    }
+
+    #[test]
+    fn malformed_ids_are_collected_separately() {
+        let valid = ObjectId::new();
+        let ids = [format!(" {} ", valid.to_hex()), "abc".into(), String::new()];
+
+        let (parsed, malformed) = parse_object_ids(&ids);
+
+        assert_eq!(parsed, vec![valid]);
+        assert_eq!(malformed, vec!["abc".to_string(), String::new()]);
+    }
+
+    #[actix_web::test]
+    async fn empty_id_list_skips_query() {
+        let client = mongodb::Client::with_uri_str("mongodb://127.0.0.1:1/")
+            .await
+            .unwrap();
+        let products = client
+            .database("qtoky_test")
+            .collection::<Document>("products");
+
+        assert!(exists_all(&products, &[]).await.unwrap().is_empty());
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn exists_all_reports_missing_ids_in_order() {
+        let products = test_database().await.collection::<Document>("products");
+        let owner = ObjectId::new();
+        let (kopi, teh) = (ObjectId::new(), ObjectId::new());
+        products
+            .insert_many([
+                doc! { "_id": kopi, "user_id": owner },
+                doc! { "_id": teh, "user_id": ObjectId::new() },
+            ])
+            .await
+            .unwrap();
+        let (gone, lost) = (ObjectId::new(), ObjectId::new());
+
+        let all_present = exists_all(&products, &[kopi, teh, kopi]).await.unwrap();
+        let some_missing = exists_all(&products, &[lost, kopi, gone, lost])
+            .await
+            .unwrap();
+        let foreign = exists_all_matching(&products, &[kopi, teh], doc! { "user_id": owner })
+            .await
+            .unwrap();
+
+        assert!(all_present.is_empty());
+        assert_eq!(some_missing, vec![lost, gone]);
+        assert_eq!(foreign, vec![teh]);
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn upsert_inserts_then_updates() {
+        let products = test_database().await.collection::<Document>("products");
+        let update = |stock: i32| doc! { "$set": { "stock": stock } };
+
+        let inserted = upsert_one(&products, doc! { "sku": "QT-1" }, update(5)).await;
+        let updated = upsert_one(&products, doc! { "sku": "QT-1" }, update(7)).await;
+
+        let Ok(UpsertOutcome::Inserted(id)) = inserted else {
+            panic!("hasil tidak terduga: {:?}", inserted);
+        };
+        assert_eq!(updated.unwrap(), UpsertOutcome::Updated(None));
+        let stored = products
+            .find_one(doc! { "_id": id })
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.get_i32("stock").unwrap(), 7);
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn upsert_returning_previous_gives_old_document() {
+        let products = test_database().await.collection::<Document>("products");
+        let update = |stock: i32| {
+            doc! {
+                "$set": { "stock": stock },
+                "$setOnInsert": { "name": "Kopi" },
+            }
+        };
+
+        let inserted =
+            upsert_one_returning_previous(&products, doc! { "sku": "QT-1" }, update(5)).await;
+        let updated =
+            upsert_one_returning_previous(&products, doc! { "sku": "QT-1" }, update(7)).await;
+
+        let Ok(UpsertOutcome::Inserted(id)) = inserted else {
+            panic!("hasil tidak terduga: {:?}", inserted);
+        };
+        let Ok(UpsertOutcome::Updated(Some(previous))) = updated else {
+            panic!("hasil tidak terduga: {:?}", updated);
+        };
+        assert_eq!(previous.get("_id"), Some(&id));
+        assert_eq!(previous.get_i32("stock").unwrap(), 5);
+        assert_eq!(previous.get_str("name").unwrap(), "Kopi");
+    }
+
+    #[actix_web::test]
+    async fn empty_batch_skips_insert() {
+        let client = mongodb::Client::with_uri_str("mongodb://127.0.0.1:1/")
+            .await
+            .unwrap();
+        let products = client
+            .database("qtoky_test")
+            .collection::<Document>("products");
+
+        let report = insert_many_lenient(&products, Vec::new()).await.unwrap();
+
+        assert_eq!(report.inserted, 0);
+        assert!(report.is_complete());
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn duplicate_row_does_not_block_the_rest() {
+        let products = test_database().await.collection::<Document>("products");
+        products
+            .create_index(
+                mongodb::IndexModel::builder()
+                    .keys(doc! { "sku": 1 })
+                    .options(
+                        mongodb::options::IndexOptions::builder()
+                            .unique(true)
+                            .build(),
+                    )
+                    .build(),
+            )
+            .await
+            .unwrap();
+        products.insert_one(doc! { "sku": "KOPI" }).await.unwrap();
+
+        let report = insert_many_lenient(
+            &products,
+            [
+                doc! { "sku": "TEH" },
+                doc! { "sku": "KOPI" },
+                doc! { "sku": "GULA" },
+                doc! { "sku": "SUSU" },
+            ],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.inserted, 3);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].index, 1);
+        assert!(matches!(
+            &report.failed[0].error,
+            ServiceError::DuplicateField { fields } if fields == &vec!["sku".to_string()]
+        ));
+        // Dokumen setelah yang duplikat tetap masuk karena insert unordered
+        assert_eq!(
+            products
+                .count_documents(doc! { "sku": "SUSU" })
+                .await
+                .unwrap(),
+            1
+        );
+    }
+
+    #[actix_web::test]
+    async fn mixed_projection_is_rejected_before_querying() {
+        let client = mongodb::Client::with_uri_str("mongodb://127.0.0.1:1/")
+            .await
+            .unwrap();
+        let products = client
+            .database("qtoky_test")
+            .collection::<Document>("products");
+
+        let result = find_one_projected_or_not_found(
+            &products,
+            doc! {},
+            Some(doc! { "name": 1, "cost_price": 0 }),
+            "Produk",
+        )
+        .await;
+
+        assert!(matches!(result, Err(ServiceError::Unexpected(_))));
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn projection_hides_excluded_fields() {
+        let products = test_database().await.collection::<Document>("products");
+        products
+            .insert_one(doc! { "name": "Kopi", "price": 15000, "cost_price": 9000 })
+            .await
+            .unwrap();
+
+        let public = find_one_projected_or_not_found(
+            &products,
+            doc! { "name": "Kopi" },
+            Some(crate::db::projection::projection_excluding(&["cost_price"])),
+            "Produk",
+        )
+        .await
+        .unwrap();
+
+        assert!(public.get("cost_price").is_none());
+        assert_eq!(public.get_i32("price").unwrap(), 15000);
+    }
+
+    #[test]
+    fn conflict_message_names_the_business_key() {
+        let owner = ObjectId::new();
+
+        assert_eq!(
+            describe_key(&doc! { ORG_FIELD: owner, "sku": "A1" }),
+            "sku 'A1'"
+        );
+        assert_eq!(
+            describe_key(
+                &doc! { "user_id": owner, "sku": "A1", "batch": 3, "_id": { "$ne": owner } }
+            ),
+            "sku 'A1', batch 3"
+        );
+        assert_eq!(describe_key(&doc! { "user_id": "u-1" }), "user_id 'u-1'");
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn ensure_unique_reports_existing_compound_key() {
+        let products = test_database().await.collection::<Document>("products");
+        let (org, other_org) = (ObjectId::new(), ObjectId::new());
+        let existing = ObjectId::new();
+        products
+            .insert_one(doc! { "_id": existing, ORG_FIELD: org, "sku": "A1" })
+            .await
+            .unwrap();
+
+        let conflict = ensure_unique(&products, doc! { ORG_FIELD: org, "sku": "A1" }).await;
+
+        assert!(matches!(
+            conflict,
+            Err(ServiceError::Conflict(msg)) if msg.starts_with("sku 'A1'")
+        ));
+        ensure_unique(&products, doc! { ORG_FIELD: org, "sku": "B2" })
+            .await
+            .unwrap();
+        ensure_unique(&products, doc! { ORG_FIELD: other_org, "sku": "A1" })
+            .await
+            .unwrap();
+        // Update dokumen itu sendiri tidak dianggap conflict
+        ensure_unique(
+            &products,
+            doc! { ORG_FIELD: org, "sku": "A1", "_id": { "$ne": existing } },
+        )
+        .await
+        .unwrap();
+    }
+
+    #[test]
+    fn immutable_fields_are_stripped_from_plain_and_operator_updates() {
+        let mut update = doc! {
+            "_id": "palsu",
+            "name": "Kopi",
+            "$set": { "price": 12000, "created_at": "kemarin", "user_id": "u-2" },
+            "$unset": { "created_at.$date": "" },
+        };
+
+        let removed = strip_immutable(&mut update, &OWNED_IMMUTABLE_FIELDS);
+
+        assert_eq!(
+            removed,
+            vec!["_id", "created_at", "user_id", "created_at.$date"]
+        );
+        assert_eq!(update, doc! { "name": "Kopi", "$set": { "price": 12000 } });
+    }
+
+    #[test]
+    fn update_without_immutable_fields_is_untouched() {
+        let original = doc! { "$set": { "name": "Teh", "created_by": "u-1", "ids": [1] } };
+        let mut update = original.clone();
+
+        assert!(strip_immutable(&mut update, &DEFAULT_IMMUTABLE_FIELDS).is_empty());
+        assert_eq!(update, original);
+        assert!(reject_immutable(&original, &DEFAULT_IMMUTABLE_FIELDS).is_ok());
+    }
+
+    #[test]
+    fn reject_names_every_immutable_field() {
+        let update = doc! { "$set": { "_id": 1, "org_id": "o-2", "name": "Kopi" } };
+
+        assert!(matches!(
+            reject_immutable(&update, &OWNED_IMMUTABLE_FIELDS),
+            Err(ServiceError::BadRequest(msg)) if msg == "Field _id, org_id tidak boleh diubah"
+        ));
+        // `org_id` hanya dilindungi untuk data milik tenant
+        assert!(
+            reject_immutable(
+                &doc! { "$set": { "org_id": "o-2" } },
+                &DEFAULT_IMMUTABLE_FIELDS
+            )
+            .is_ok()
+        );
+    }
+}
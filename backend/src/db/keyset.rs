@@ -0,0 +1,314 @@
+use crate::db::deserialize::deserialize_document;
+use crate::db::filters::merge_filters;
+use crate::db::pagination::MAX_PER_PAGE;
+use crate::errors::ServiceError;
+use crate::utils::map_mongo_error;
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use futures::stream::TryStreamExt;
+use mongodb::{
+    Collection,
+    bson::{Bson, Document, doc, oid::ObjectId},
+};
+use serde::{Serialize, de::DeserializeOwned};
+
+/// Batas panjang cursor dari query string, cursor normal jauh di bawah ini
+pub const MAX_CURSOR_LEN: usize = 512;
+
+/// Posisi terakhir halaman sebelumnya, `_id` dipakai sebagai tie-breaker agar urutannya stabil
+#[derive(Debug, Clone, PartialEq)]
+pub struct CursorState {
+    pub last_id: ObjectId,
+    pub last_value: Bson,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl SortDirection {
+    fn operator(self) -> &'static str {
+        match self {
+            SortDirection::Asc => "$gt",
+            SortDirection::Desc => "$lt",
+        }
+    }
+
+    /// Nilai untuk dokumen sort Mongo, `1` atau `-1`
+    pub fn order(self) -> i32 {
+        match self {
+            SortDirection::Asc => 1,
+            SortDirection::Desc => -1,
+        }
+    }
+}
+
+/// Cursor opaque (BSON kecil lalu base64 URL-safe) untuk query halaman berikutnya
+pub fn encode_cursor(last_id: &ObjectId, last_sort_value: impl Into<Bson>) -> String {
+    let state = doc! { "i": last_id, "v": last_sort_value.into() };
+    let bytes = bson::to_vec(&state).expect("Document selalu bisa di-serialize ke BSON");
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Cursor yang rusak atau diubah di client menjadi `BadRequest`
+pub fn decode_cursor(cursor: &str) -> Result<CursorState, ServiceError> {
+    let invalid = || ServiceError::BadRequest("Cursor tidak valid".into());
+    if cursor.len() > MAX_CURSOR_LEN {
+        return Err(invalid());
+    }
+
+    let bytes = URL_SAFE_NO_PAD
+        .decode(cursor.trim())
+        .map_err(|_| invalid())?;
+    let state: Document = bson::from_slice(&bytes).map_err(|_| invalid())?;
+    if state.len() != 2 {
+        return Err(invalid());
+    }
+
+    let last_id = state.get_object_id("i").map_err(|_| invalid())?;
+    let last_value = state.get("v").cloned().ok_or_else(invalid)?;
+    Ok(CursorState {
+        last_id,
+        last_value,
+    })
+}
+
+/// Filter lanjutan setelah `state`, contoh untuk `price` ascending:
+/// `{ $or: [{ price: { $gt: v } }, { price: v, _id: { $gt: id } }] }`
+pub fn keyset_filter(sort_field: &str, direction: SortDirection, state: &CursorState) -> Document {
+    let op = direction.operator();
+    if sort_field == "_id" {
+        return doc! { "_id": { op: state.last_id } };
+    }
+
+    doc! {
+        "$or": [
+            { sort_field: { op: state.last_value.clone() } },
+            { sort_field: state.last_value.clone(), "_id": { op: state.last_id } },
+        ]
+    }
+}
+
+/// Sort yang cocok dengan `keyset_filter`, buat compound index dengan urutan yang sama
+pub fn keyset_sort(sort_field: &str, direction: SortDirection) -> Document {
+    let order = direction.order();
+    if sort_field == "_id" {
+        return doc! { "_id": order };
+    }
+    doc! { sort_field: order, "_id": order }
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeysetPage<T> {
+    pub items: Vec<T>,
+    // `None` jika sudah halaman terakhir
+    pub next_cursor: Option<String>,
+}
+
+/// Ambil `limit` dokumen setelah `cursor`. Tidak memakai `skip` sehingga tetap cepat di
+/// collection besar, asalkan ada index `{ sort_field, _id }`.
+pub async fn paginate_keyset<T>(
+    collection: &Collection<T>,
+    filter: Document,
+    sort_field: &str,
+    direction: SortDirection,
+    cursor: Option<&str>,
+    limit: u64,
+) -> Result<KeysetPage<T>, ServiceError>
+where
+    T: DeserializeOwned + Send + Sync,
+{
+    let limit = limit.clamp(1, MAX_PER_PAGE);
+    let query = match cursor.filter(|c| !c.trim().is_empty()) {
+        Some(cursor) => {
+            let state = decode_cursor(cursor)?;
+            merge_filters(filter, keyset_filter(sort_field, direction, &state))
+        }
+        None => filter,
+    };
+
+    // Ambil satu dokumen lebih untuk tahu apakah masih ada halaman berikutnya
+    let mut docs: Vec<Document> = collection
+        .clone_with_type::<Document>()
+        .find(query)
+        .sort(keyset_sort(sort_field, direction))
+        .limit(limit as i64 + 1)
+        .await
+        .map_err(map_mongo_error)?
+        .try_collect()
+        .await
+        .map_err(map_mongo_error)?;
+
+    let has_more = docs.len() as u64 > limit;
+    docs.truncate(limit as usize);
+
+    let next_cursor = match docs.last() {
+        Some(last) if has_more => {
+            let last_id = last.get_object_id("_id").map_err(|_| {
+                ServiceError::Unexpected("Keyset pagination butuh _id berupa ObjectId".into())
+            })?;
+            let last_value = last.get(sort_field).cloned().unwrap_or(Bson::Null);
+            Some(encode_cursor(&last_id, last_value))
+        }
+        _ => None,
+    };
+
+    let items = docs
+        .into_iter()
+        .map(deserialize_document)
+        .collect::<Result<Vec<T>, _>>()?;
+
+    Ok(KeysetPage { items, next_cursor })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_database;
+    use bson::DateTime as BsonDateTime;
+
+    fn bad_request(cursor: &str) -> bool {
+        matches!(
+            decode_cursor(cursor),
+            Err(ServiceError::BadRequest(msg)) if msg == "Cursor tidak valid"
+        )
+    }
+
+    #[test]
+    fn cursor_round_trips_sort_values() {
+        let id = ObjectId::new();
+
+        for value in [
+            Bson::Double(15_000.5),
+            Bson::String("Kopi Susu".into()),
+            Bson::DateTime(BsonDateTime::from_millis(1_700_000_000_000)),
+            Bson::Null,
+        ] {
+            let cursor = encode_cursor(&id, value.clone());
+
+            assert!(cursor.len() <= MAX_CURSOR_LEN);
+            assert!(!cursor.contains(['+', '/', '=']));
+            assert_eq!(
+                decode_cursor(&cursor).unwrap(),
+                CursorState {
+                    last_id: id,
+                    last_value: value,
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn corrupted_cursors_are_bad_requests() {
+        let cursor = encode_cursor(&ObjectId::new(), 10);
+        let truncated = &cursor[..cursor.len() - 4];
+        let wrong_id = URL_SAFE_NO_PAD.encode(bson::to_vec(&doc! { "i": "abc", "v": 1 }).unwrap());
+        let extra_field = URL_SAFE_NO_PAD
+            .encode(bson::to_vec(&doc! { "i": ObjectId::new(), "v": 1, "x": 1 }).unwrap());
+
+        for corrupted in [
+            truncated,
+            "bukan base64!",
+            "",
+            wrong_id.as_str(),
+            extra_field.as_str(),
+            &"A".repeat(MAX_CURSOR_LEN + 1),
+        ] {
+            assert!(bad_request(corrupted), "{}", corrupted);
+        }
+    }
+
+    #[test]
+    fn tampered_byte_is_rejected() {
+        let mut bytes = bson::to_vec(&doc! { "i": ObjectId::new(), "v": 10 }).unwrap();
+        // Ubah panjang dokumen di header BSON, isi tidak lagi konsisten
+        bytes[0] ^= 0x7f;
+
+        assert!(bad_request(&URL_SAFE_NO_PAD.encode(bytes)));
+    }
+
+    #[test]
+    fn continuation_filter_uses_id_as_tie_breaker() {
+        let state = CursorState {
+            last_id: ObjectId::new(),
+            last_value: Bson::Int32(100),
+        };
+
+        assert_eq!(
+            keyset_filter("price", SortDirection::Asc, &state),
+            doc! { "$or": [
+                { "price": { "$gt": 100 } },
+                { "price": 100, "_id": { "$gt": state.last_id } },
+            ] }
+        );
+        assert_eq!(
+            keyset_filter("_id", SortDirection::Desc, &state),
+            doc! { "_id": { "$lt": state.last_id } }
+        );
+        assert_eq!(
+            keyset_sort("price", SortDirection::Desc),
+            doc! { "price": -1, "_id": -1 }
+        );
+    }
+
+    #[actix_web::test]
+    async fn bad_cursor_is_rejected_before_querying() {
+        let client = mongodb::Client::with_uri_str("mongodb://127.0.0.1:1/")
+            .await
+            .unwrap();
+        let products = client
+            .database("qtoky_test")
+            .collection::<Document>("products");
+
+        let result = paginate_keyset(
+            &products,
+            doc! {},
+            "price",
+            SortDirection::Asc,
+            Some("rusak"),
+            10,
+        )
+        .await;
+
+        assert!(matches!(result, Err(ServiceError::BadRequest(_))));
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn pages_follow_each_other_without_gaps() {
+        let products = test_database().await.collection::<Document>("products");
+        // Harga kembar memastikan `_id` dipakai sebagai tie-breaker
+        products
+            .insert_many((0..5).map(|i| doc! { "name": format!("P{}", i), "price": i / 2 }))
+            .await
+            .unwrap();
+
+        let mut names = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let page = paginate_keyset(
+                &products,
+                doc! {},
+                "price",
+                SortDirection::Asc,
+                cursor.as_deref(),
+                2,
+            )
+            .await
+            .unwrap();
+            names.extend(
+                page.items
+                    .iter()
+                    .map(|item| item.get_str("name").unwrap().to_string()),
+            );
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        assert_eq!(names, ["P0", "P1", "P2", "P3", "P4"]);
+    }
+}
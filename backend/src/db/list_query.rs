@@ -0,0 +1,183 @@
+use crate::db::filters::build_search_filter;
+use crate::db::pagination::{DEFAULT_PER_PAGE, clamp_page};
+use crate::db::sort::{SortSpec, sort_document};
+use crate::errors::ServiceError;
+use crate::utils::string_enum::StringEnum;
+use bson::{Document, doc};
+use serde::Deserialize;
+
+/// Query string standar untuk endpoint list: `?page=&per_page=&sort=&q=`.
+/// Pakai dengan `Result<Query<ListQuery>, ActixError>` agar input non-angka menjadi 400.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ListQuery {
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+    // Nama field dipisah koma, awali dengan `-` untuk descending. Contoh: `-created_at,name`
+    pub sort: Option<String>,
+    pub q: Option<String>,
+}
+
+/// Hasil `ListQuery::validate`, siap dipakai untuk `paginate`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListParams {
+    pub page: u64,
+    pub per_page: u64,
+    pub sort: Option<Document>,
+    // Kosong jika `q` tidak diisi, gabungkan dengan filter lain lewat `merge_filters`
+    pub filter: Document,
+}
+
+impl ListQuery {
+    /// Clamp page/per_page, cek `sort` terhadap `sort_fields`, dan ubah `q` menjadi
+    /// filter regex case-insensitive di `search_fields` lewat `build_search_filter`.
+    pub fn validate(
+        &self,
+        sort_fields: &[&str],
+        search_fields: &[&str],
+    ) -> Result<ListParams, ServiceError> {
+        let (page, per_page) = clamp_page(
+            self.page.unwrap_or(1),
+            self.per_page.unwrap_or(DEFAULT_PER_PAGE),
+        );
+
+        Ok(ListParams {
+            page,
+            per_page,
+            sort: self.sort_document(sort_fields)?,
+            filter: self.search_filter(search_fields)?,
+        })
+    }
+
+    fn sort_document(&self, sort_fields: &[&str]) -> Result<Option<Document>, ServiceError> {
+        match self.sort.as_deref() {
+            Some(sort) => sort_document(sort, sort_fields),
+            None => Ok(None),
+        }
+    }
+
+    /// `sort` sebagai `SortSpec` dengan field yang dibatasi enum `F`
+    pub fn sort_spec<F: StringEnum>(&self) -> Result<SortSpec<F>, ServiceError> {
+        SortSpec::parse(self.sort.as_deref().unwrap_or_default())
+    }
+
+    fn search_filter(&self, search_fields: &[&str]) -> Result<Document, ServiceError> {
+        let Some(term) = self.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) else {
+            return Ok(Document::new());
+        };
+
+        let mut conditions = search_fields
+            .iter()
+            .map(|field| build_search_filter(field, term))
+            .collect::<Result<Vec<Document>, ServiceError>>()?;
+
+        Ok(match conditions.len() {
+            0 => Document::new(),
+            1 => conditions.remove(0),
+            _ => doc! { "$or": conditions },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::pagination::MAX_PER_PAGE;
+    use actix_web::web::Query;
+
+    const SORT_FIELDS: &[&str] = &["name", "price", "created_at"];
+    const SEARCH_FIELDS: &[&str] = &["name", "sku"];
+
+    fn parse(query: &str) -> ListQuery {
+        Query::<ListQuery>::from_query(query).unwrap().into_inner()
+    }
+
+    #[test]
+    fn empty_query_uses_defaults() {
+        let params = parse("").validate(SORT_FIELDS, SEARCH_FIELDS).unwrap();
+
+        assert_eq!(
+            params,
+            ListParams {
+                page: 1,
+                per_page: DEFAULT_PER_PAGE,
+                sort: None,
+                filter: Document::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn page_and_per_page_are_clamped() {
+        let params = parse("page=0&per_page=5000")
+            .validate(SORT_FIELDS, SEARCH_FIELDS)
+            .unwrap();
+        assert_eq!((params.page, params.per_page), (1, MAX_PER_PAGE));
+
+        let params = parse("page=3&per_page=0")
+            .validate(SORT_FIELDS, SEARCH_FIELDS)
+            .unwrap();
+        assert_eq!((params.page, params.per_page), (3, 1));
+    }
+
+    #[test]
+    fn non_numeric_page_is_rejected() {
+        assert!(Query::<ListQuery>::from_query("page=abc").is_err());
+        assert!(Query::<ListQuery>::from_query("per_page=-1").is_err());
+    }
+
+    #[test]
+    fn sort_is_checked_against_allow_list() {
+        let params = parse("sort=-created_at,name")
+            .validate(SORT_FIELDS, SEARCH_FIELDS)
+            .unwrap();
+        assert_eq!(params.sort, Some(doc! { "created_at": -1, "name": 1 }));
+
+        for sort in ["password", "-price,$where", "name.secret"] {
+            let query = ListQuery {
+                sort: Some(sort.to_string()),
+                ..Default::default()
+            };
+            assert!(
+                matches!(
+                    query.validate(SORT_FIELDS, SEARCH_FIELDS),
+                    Err(ServiceError::BadRequest(_))
+                ),
+                "{}",
+                sort
+            );
+        }
+    }
+
+    #[test]
+    fn search_term_is_escaped() {
+        let query = ListQuery {
+            q: Some("  (a+)+.*  ".to_string()),
+            ..Default::default()
+        };
+
+        let params = query.validate(SORT_FIELDS, SEARCH_FIELDS).unwrap();
+
+        let pattern = r"\(a\+\)\+\.\*";
+        assert_eq!(
+            params.filter,
+            doc! { "$or": [
+                { "name": { "$regex": pattern, "$options": "i" } },
+                { "sku": { "$regex": pattern, "$options": "i" } },
+            ] }
+        );
+    }
+
+    #[test]
+    fn blank_search_term_adds_no_filter() {
+        let params = parse("q=%20%20")
+            .validate(SORT_FIELDS, SEARCH_FIELDS)
+            .unwrap();
+        assert!(params.filter.is_empty());
+
+        let params = parse("q=kopi").validate(SORT_FIELDS, &["name"]).unwrap();
+        assert_eq!(
+            params.filter,
+            doc! { "name": { "$regex": "kopi", "$options": "i" } }
+        );
+    }
+}
@@ -1 +1,18 @@
+pub mod aggregate;
+pub mod change_stream;
+pub mod cursor;
+pub mod deserialize;
+pub mod filters;
+pub mod handle;
+pub mod helpers;
+pub mod keyset;
+pub mod list_query;
 pub mod mongo;
+pub mod pagination;
+pub mod patch;
+pub mod projection;
+pub mod retry;
+pub mod scope;
+pub mod sort;
+pub mod timeout;
+pub mod transaction;
@@ -1,13 +1,121 @@
-use mongodb::{Client, Database, options::ClientOptions};
+use crate::config::Config;
+use crate::errors::ServiceError;
+use actix_web::rt::time::timeout;
+use mongodb::{Client, Database, bson::doc, options::ClientOptions};
+use serde::Serialize;
 use std::error::Error;
+use std::time::{Duration, Instant};
 
-pub async fn init_db() -> Result<Database, Box<dyn Error>> {
+/// Batas waktu ping agar readiness probe tidak menggantung saat MongoDB mati
+pub const PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub async fn init_db(config: &Config) -> Result<Database, Box<dyn Error>> {
     // Parse the MongoDB connection string
-    let client_options = ClientOptions::parse("mongodb://localhost:27017").await?;
+    let client_options = ClientOptions::parse(&config.mongodb_uri).await?;
 
     // Create the MongoDB client
     let client = Client::with_options(client_options)?;
 
     // Get the database
-    Ok(client.database("qtoky"))
+    Ok(client.database(&config.mongodb_database))
+}
+
+/// Tutup client MongoDB saat shutdown. `Client::shutdown` menunggu session dan cursor yang
+/// masih dipakai dilepas, jadi dibatasi `limit` agar proses tidak menggantung.
+pub async fn close_db(db: &Database, limit: Duration) {
+    match timeout(limit, db.client().clone().shutdown()).await {
+        Ok(()) => log::info!("Koneksi MongoDB ditutup"),
+        Err(_) => log::warn!("Menutup koneksi MongoDB melebihi batas waktu, dilewati"),
+    }
+}
+
+/// Kirim `{ ping: 1 }` ke database admin, gagal atau timeout menjadi `ServiceUnavailable`
+pub async fn ping_database(client: &Client) -> Result<(), ServiceError> {
+    ping_database_with_timeout(client, PING_TIMEOUT).await
+}
+
+pub async fn ping_database_with_timeout(
+    client: &Client,
+    limit: Duration,
+) -> Result<(), ServiceError> {
+    let admin = client.database("admin");
+    let ping = admin.run_command(doc! { "ping": 1 });
+
+    match timeout(limit, ping).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(ServiceError::ServiceUnavailable(format!(
+            "Database tidak dapat dihubungi: {}",
+            e
+        ))),
+        Err(_) => Err(ServiceError::ServiceUnavailable(
+            "Database tidak merespons".into(),
+        )),
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct HealthStatus {
+    pub db_ok: bool,
+    pub latency_ms: u128,
+}
+
+/// Status koneksi database beserta latency ping, dipakai endpoint `/health`
+pub async fn health_status(client: &Client) -> HealthStatus {
+    let started = Instant::now();
+    let db_ok = ping_database(client).await.is_ok();
+
+    HealthStatus {
+        db_ok,
+        latency_ms: started.elapsed().as_millis(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Port 1 tidak pernah dipakai MongoDB, koneksi langsung ditolak
+    async fn unreachable_client(server_selection_ms: u64) -> Client {
+        Client::with_uri_str(format!(
+            "mongodb://127.0.0.1:1/?serverSelectionTimeoutMS={}",
+            server_selection_ms
+        ))
+        .await
+        .unwrap()
+    }
+
+    #[actix_web::test]
+    async fn unreachable_database_is_unavailable() {
+        let client = unreachable_client(100).await;
+
+        assert!(matches!(
+            ping_database(&client).await,
+            Err(ServiceError::ServiceUnavailable(msg)) if msg.contains("tidak dapat dihubungi")
+        ));
+        assert!(!health_status(&client).await.db_ok);
+    }
+
+    #[actix_web::test]
+    async fn ping_stops_at_timeout() {
+        let client = unreachable_client(30_000).await;
+        let started = Instant::now();
+
+        let result = ping_database_with_timeout(&client, Duration::from_millis(50)).await;
+
+        assert!(matches!(
+            result,
+            Err(ServiceError::ServiceUnavailable(msg)) if msg.contains("tidak merespons")
+        ));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[actix_web::test]
+    async fn close_db_returns_within_limit() {
+        let client = unreachable_client(100).await;
+        let started = Instant::now();
+
+        close_db(&client.database("qtoky_test"), Duration::from_secs(2)).await;
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
 }
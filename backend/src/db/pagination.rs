@@ -0,0 +1,176 @@
+use crate::errors::ServiceError;
+use crate::utils::map_mongo_error;
+use futures::stream::TryStreamExt;
+use mongodb::{Collection, bson::Document};
+use serde::{Serialize, de::DeserializeOwned};
+
+pub const DEFAULT_PER_PAGE: u64 = 20;
+pub const MAX_PER_PAGE: u64 = 100;
+
+#[derive(Debug, Serialize)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    pub total: u64,
+    pub page: u64,
+    pub per_page: u64,
+    pub total_pages: u64,
+}
+
+impl<T> Paginated<T> {
+    pub fn new(items: Vec<T>, total: u64, page: u64, per_page: u64) -> Self {
+        Paginated {
+            items,
+            total,
+            page,
+            per_page,
+            total_pages: total.div_ceil(per_page.max(1)),
+        }
+    }
+
+    /// Ubah tipe item, contoh: `Paginated<Product>` ke `Paginated<ProductResponse>`
+    pub fn map<U, F>(self, f: F) -> Paginated<U>
+    where
+        F: FnMut(T) -> U,
+    {
+        Paginated {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            page: self.page,
+            per_page: self.per_page,
+            total_pages: self.total_pages,
+        }
+    }
+}
+
+/// Normalisasi page (minimal 1) dan per_page (1..=MAX_PER_PAGE)
+pub fn clamp_page(page: u64, per_page: u64) -> (u64, u64) {
+    (page.max(1), per_page.clamp(1, MAX_PER_PAGE))
+}
+
+/// Jumlah dokumen yang dilewati untuk `page`, page yang sangat besar tidak overflow dan
+/// dibatasi `i64::MAX` (batas `skip` di MongoDB) sehingga hasilnya halaman kosong
+pub fn page_skip(page: u64, per_page: u64) -> u64 {
+    page.saturating_sub(1)
+        .saturating_mul(per_page)
+        .min(i64::MAX as u64)
+}
+
+/// Ambil satu halaman data beserta total dokumen, query find dan count dijalankan paralel
+pub async fn paginate<T>(
+    collection: &Collection<T>,
+    filter: Document,
+    page: u64,
+    per_page: u64,
+    sort: Option<Document>,
+) -> Result<Paginated<T>, ServiceError>
+where
+    T: DeserializeOwned + Send + Sync,
+{
+    let (page, per_page) = clamp_page(page, per_page);
+    let skip = page_skip(page, per_page);
+
+    let find = async {
+        let mut action = collection
+            .find(filter.clone())
+            .skip(skip)
+            .limit(per_page as i64);
+        if let Some(sort) = sort {
+            action = action.sort(sort);
+        }
+
+        // Dokumen yang gagal di-deserialize ikut jadi error, bukan dibuang diam-diam
+        action.await?.try_collect::<Vec<T>>().await
+    };
+    let count = async { collection.count_documents(filter.clone()).await };
+
+    let (items, total) = futures::try_join!(find, count).map_err(map_mongo_error)?;
+
+    Ok(Paginated::new(items, total, page, per_page))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_database;
+    use bson::doc;
+
+    #[test]
+    fn empty_result_has_no_pages() {
+        let page: Paginated<u32> = Paginated::new(Vec::new(), 0, 1, DEFAULT_PER_PAGE);
+
+        assert!(page.items.is_empty());
+        assert_eq!(page.total_pages, 0);
+    }
+
+    #[test]
+    fn exact_page_boundary_does_not_add_a_page() {
+        assert_eq!(Paginated::new(vec![0; 20], 40, 2, 20).total_pages, 2);
+        assert_eq!(Paginated::new(vec![0], 41, 3, 20).total_pages, 3);
+        assert_eq!(page_skip(2, 20), 20);
+        assert_eq!(page_skip(3, 20), 40);
+    }
+
+    #[test]
+    fn page_and_per_page_are_clamped() {
+        assert_eq!(clamp_page(0, 0), (1, 1));
+        assert_eq!(clamp_page(5, MAX_PER_PAGE + 1), (5, MAX_PER_PAGE));
+        assert_eq!(clamp_page(1, u64::MAX), (1, MAX_PER_PAGE));
+    }
+
+    #[test]
+    fn huge_page_does_not_overflow_skip() {
+        assert_eq!(page_skip(u64::MAX, MAX_PER_PAGE), i64::MAX as u64);
+        assert_eq!(page_skip(1, MAX_PER_PAGE), 0);
+        assert_eq!(page_skip(0, MAX_PER_PAGE), 0);
+    }
+
+    #[test]
+    fn map_keeps_pagination_metadata() {
+        let page = Paginated::new(vec![1, 2], 12, 2, 10).map(|n| n.to_string());
+
+        assert_eq!(page.items, vec!["1", "2"]);
+        assert_eq!(
+            (page.total, page.page, page.per_page, page.total_pages),
+            (12, 2, 10, 2)
+        );
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn paginate_reads_pages_and_surfaces_bad_documents() {
+        let db = test_database().await;
+        let raw = db.collection::<Document>("items");
+        raw.insert_many((0..25).map(|n| doc! { "n": n }))
+            .await
+            .unwrap();
+
+        #[derive(serde::Deserialize)]
+        struct Item {
+            n: i32,
+        }
+        let items = db.collection::<Item>("items");
+
+        let last = paginate(&items, doc! {}, 3, 10, Some(doc! { "n": 1 }))
+            .await
+            .unwrap();
+        assert_eq!(last.total, 25);
+        assert_eq!(last.total_pages, 3);
+        assert_eq!(
+            last.items.iter().map(|i| i.n).collect::<Vec<_>>(),
+            (20..25).collect::<Vec<_>>()
+        );
+
+        let beyond = paginate(&items, doc! {}, u64::MAX, 1000, None)
+            .await
+            .unwrap();
+        assert!(beyond.items.is_empty());
+        assert_eq!(beyond.per_page, MAX_PER_PAGE);
+
+        raw.insert_one(doc! { "n": "bukan angka" }).await.unwrap();
+        assert!(
+            paginate(&items, doc! {}, 1, MAX_PER_PAGE, None)
+                .await
+                .is_err()
+        );
+    }
+}
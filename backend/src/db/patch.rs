@@ -0,0 +1,337 @@
+use crate::db::helpers::is_protected;
+use crate::errors::ServiceError;
+use mongodb::bson::{Bson, Document, doc};
+
+/// Perlakuan field yang dikirim dengan nilai `null` pada PATCH
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NullMode {
+    // Field dihapus dari dokumen lewat `$unset`
+    #[default]
+    Unset,
+    // Field tetap ada dengan nilai `null`
+    SetNull,
+}
+
+/// Ubah dokumen parsial dari body PATCH menjadi `{ $set: { ... } }` agar field yang tidak
+/// dikirim tidak ikut tertimpa. Field yang dikirim `null` di-`$unset`, lihat
+/// `build_set_update_with` untuk menyimpannya sebagai `null`.
+pub fn build_set_update(partial: Document) -> Document {
+    build_set_update_with(partial, NullMode::default())
+}
+
+/// Object nested diratakan ke dotted path, contoh `{ address: { city: "Bandung" } }` menjadi
+/// `{ $set: { "address.city": "Bandung" } }` sehingga sub-field lain tidak hilang.
+/// Array dan object kosong di-set apa adanya. Dokumen kosong menghasilkan update kosong.
+pub fn build_set_update_with(partial: Document, null_mode: NullMode) -> Document {
+    let mut set = Document::new();
+    let mut unset = Document::new();
+    flatten_into(partial, "", null_mode, &mut set, &mut unset);
+
+    let mut update = Document::new();
+    if !set.is_empty() {
+        update.insert("$set", set);
+    }
+    if !unset.is_empty() {
+        update.insert("$unset", unset);
+    }
+    update
+}
+
+fn flatten_into(
+    partial: Document,
+    prefix: &str,
+    null_mode: NullMode,
+    set: &mut Document,
+    unset: &mut Document,
+) {
+    for (key, value) in partial {
+        let path = if prefix.is_empty() {
+            key
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+
+        match value {
+            Bson::Document(nested) if !nested.is_empty() => {
+                flatten_into(nested, &path, null_mode, set, unset);
+            }
+            Bson::Null if null_mode == NullMode::Unset => {
+                unset.insert(path, "");
+            }
+            other => {
+                set.insert(path, other);
+            }
+        }
+    }
+}
+
+/// Field yang selalu diisi server dan tidak boleh datang dari input client
+pub const SERVER_CONTROLLED_FIELDS: [&str; 3] = ["_id", "created_at", "updated_at"];
+
+/// Gabungkan update bawaan server (`base`, contoh `updated_at` atau total hasil hitung)
+/// dengan update dari client (`overlay`). Ditolak dengan `BadRequest` jika `overlay`
+/// menyentuh `SERVER_CONTROLLED_FIELDS`, lihat `merge_update_docs_with`.
+pub fn merge_update_docs(base: Document, overlay: Document) -> Result<Document, ServiceError> {
+    merge_update_docs_with(base, overlay, &SERVER_CONTROLLED_FIELDS)
+}
+
+/// Seperti `merge_update_docs` dengan daftar field terlindungi sendiri (sub-field ikut
+/// terlindungi). Operator yang sama digabung per field dan object di `$set` di-merge
+/// rekursif. Untuk field lain yang bentrok, `overlay` menang dan path `base` yang
+/// tumpang tindih (operator lain atau parent/child path) dibuang agar MongoDB tidak
+/// menolak update karena path conflict. Field tanpa operator dianggap `$set`.
+pub fn merge_update_docs_with(
+    base: Document,
+    overlay: Document,
+    protected: &[&str],
+) -> Result<Document, ServiceError> {
+    let mut merged = group_by_operator(base)?;
+    let overlay = group_by_operator(overlay)?;
+
+    let mut conflicts: Vec<&str> = overlay
+        .values()
+        .filter_map(Bson::as_document)
+        .flat_map(|fields| fields.keys())
+        .filter(|path| is_protected(path, protected))
+        .map(String::as_str)
+        .collect();
+    if !conflicts.is_empty() {
+        conflicts.sort_unstable();
+        conflicts.dedup();
+        return Err(ServiceError::BadRequest(format!(
+            "Field {} tidak boleh diubah",
+            conflicts.join(", ")
+        )));
+    }
+
+    for (operator, fields) in overlay {
+        let Bson::Document(fields) = fields else {
+            continue;
+        };
+        for (path, value) in fields {
+            remove_overlapping(&mut merged, &operator, &path);
+
+            if !merged.contains_key(&operator) {
+                merged.insert(operator.clone(), Document::new());
+            }
+            let section = merged
+                .get_document_mut(&operator)
+                .map_err(|e| ServiceError::internal("Update document tidak valid", e))?;
+            match (section.get_mut(&path), value) {
+                (Some(Bson::Document(existing)), Bson::Document(incoming))
+                    if operator == "$set" =>
+                {
+                    deep_merge(existing, incoming);
+                }
+                (_, value) => {
+                    section.insert(path, value);
+                }
+            }
+        }
+    }
+
+    Ok(merged
+        .into_iter()
+        .filter(|(_, fields)| fields.as_document().is_none_or(|f| !f.is_empty()))
+        .collect())
+}
+
+// `{ name: "A", $inc: { stock: 1 } }` menjadi `{ $set: { name: "A" }, $inc: { stock: 1 } }`
+fn group_by_operator(update: Document) -> Result<Document, ServiceError> {
+    let mut grouped = Document::new();
+    for (key, value) in update {
+        let (operator, fields) = if key.starts_with('$') {
+            match value {
+                Bson::Document(fields) => (key, fields),
+                _ => {
+                    return Err(ServiceError::BadRequest(format!(
+                        "Operator update {} harus berupa object",
+                        key
+                    )));
+                }
+            }
+        } else {
+            ("$set".to_string(), doc! { key: value })
+        };
+
+        match grouped.get_mut(&operator) {
+            Some(Bson::Document(existing)) => existing.extend(fields),
+            _ => {
+                grouped.insert(operator, fields);
+            }
+        }
+    }
+    Ok(grouped)
+}
+
+fn paths_overlap(a: &str, b: &str) -> bool {
+    let is_child = |child: &str, parent: &str| {
+        child
+            .strip_prefix(parent)
+            .is_some_and(|rest| rest.starts_with('.'))
+    };
+    a == b || is_child(a, b) || is_child(b, a)
+}
+
+// Path yang sama di operator yang sama tidak dibuang karena akan di-merge/ditimpa
+fn remove_overlapping(merged: &mut Document, operator: &str, path: &str) {
+    for (other_operator, fields) in merged.iter_mut() {
+        let Bson::Document(fields) = fields else {
+            continue;
+        };
+        let overlapping: Vec<String> = fields
+            .keys()
+            .filter(|key| paths_overlap(key, path))
+            .filter(|key| other_operator != operator || key.as_str() != path)
+            .cloned()
+            .collect();
+        for key in overlapping {
+            fields.remove(&key);
+        }
+    }
+}
+
+fn deep_merge(target: &mut Document, incoming: Document) {
+    for (key, value) in incoming {
+        match (target.get_mut(&key), value) {
+            (Some(Bson::Document(existing)), Bson::Document(nested)) => {
+                deep_merge(existing, nested)
+            }
+            (_, value) => {
+                target.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_present_fields_are_set() {
+        let update = build_set_update(doc! { "name": "Kopi Susu", "price": 18000 });
+
+        assert_eq!(
+            update,
+            doc! { "$set": { "name": "Kopi Susu", "price": 18000 } }
+        );
+        assert!(build_set_update(doc! {}).is_empty());
+    }
+
+    #[test]
+    fn null_unsets_or_sets_null_per_mode() {
+        let partial = doc! { "name": "Kopi", "description": null };
+
+        assert_eq!(
+            build_set_update(partial.clone()),
+            doc! { "$set": { "name": "Kopi" }, "$unset": { "description": "" } }
+        );
+        assert_eq!(
+            build_set_update_with(partial, NullMode::SetNull),
+            doc! { "$set": { "name": "Kopi", "description": null } }
+        );
+    }
+
+    #[test]
+    fn nested_objects_become_dotted_paths() {
+        let update = build_set_update(doc! {
+            "supplier": {
+                "address": { "city": "Bandung", "zip": null },
+                "phone": "0812",
+            },
+            "tags": ["kopi", "panas"],
+            "metadata": {},
+        });
+
+        assert_eq!(
+            update,
+            doc! {
+                "$set": {
+                    "supplier.address.city": "Bandung",
+                    "supplier.phone": "0812",
+                    "tags": ["kopi", "panas"],
+                    "metadata": {},
+                },
+                "$unset": { "supplier.address.zip": "" },
+            }
+        );
+    }
+
+    #[test]
+    fn clean_merge_keeps_both_sides() {
+        let base = doc! { "$set": { "status": "draft" }, "$inc": { "version": 1 } };
+        let overlay = doc! { "name": "Kopi", "$set": { "price": 12.5 } };
+
+        assert_eq!(
+            merge_update_docs(base, overlay).unwrap(),
+            doc! {
+                "$set": { "status": "draft", "name": "Kopi", "price": 12.5 },
+                "$inc": { "version": 1 },
+            }
+        );
+    }
+
+    #[test]
+    fn overlapping_keys_take_overlay_and_merge_objects() {
+        let base = doc! {
+            "$set": { "name": "Default", "address": { "city": "Bandung", "zip": "40111" } },
+        };
+        let overlay = doc! { "$set": { "name": "Kopi", "address": { "city": "Jakarta" } } };
+
+        assert_eq!(
+            merge_update_docs(base, overlay).unwrap(),
+            doc! { "$set": { "name": "Kopi", "address": { "city": "Jakarta", "zip": "40111" } } }
+        );
+    }
+
+    #[test]
+    fn overlapping_paths_from_other_operators_are_dropped() {
+        let base = doc! {
+            "$unset": { "discount": "" },
+            "$set": { "address.city": "Bandung", "stock": 3 },
+        };
+        let overlay = doc! { "$set": { "discount": 10, "address": { "city": "Jakarta" } } };
+
+        assert_eq!(
+            merge_update_docs(base, overlay).unwrap(),
+            doc! { "$set": { "stock": 3, "discount": 10, "address": { "city": "Jakarta" } } }
+        );
+    }
+
+    #[test]
+    fn protected_key_is_rejected() {
+        let base = doc! { "$set": { "updated_at": "server" } };
+
+        for overlay in [
+            doc! { "updated_at": "client" },
+            doc! { "$set": { "created_at.day": 1 } },
+            doc! { "$unset": { "_id": "" } },
+        ] {
+            assert!(matches!(
+                merge_update_docs(base.clone(), overlay),
+                Err(ServiceError::BadRequest(msg)) if msg.contains("tidak boleh diubah")
+            ));
+        }
+    }
+
+    #[test]
+    fn protected_set_is_configurable() {
+        let base = doc! { "$set": { "total": 100 } };
+
+        assert!(merge_update_docs(base.clone(), doc! { "total": 1 }).is_ok());
+        assert!(matches!(
+            merge_update_docs_with(base.clone(), doc! { "total": 1 }, &["total"]),
+            Err(ServiceError::BadRequest(msg)) if msg.contains("total")
+        ));
+        assert!(merge_update_docs_with(base, doc! { "updated_at": 1 }, &["total"]).is_ok());
+    }
+
+    #[test]
+    fn non_object_operator_is_rejected() {
+        assert!(matches!(
+            merge_update_docs(doc! {}, doc! { "$set": 1 }),
+            Err(ServiceError::BadRequest(_))
+        ));
+    }
+}
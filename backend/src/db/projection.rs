@@ -0,0 +1,111 @@
+use crate::errors::ServiceError;
+use bson::{Bson, Document};
+
+/// Projection `{ field: 1, ... }`, hanya field di allow-list (dan `_id`) yang dikembalikan.
+/// List kosong menghasilkan `{ _id: 1 }`, bukan seluruh dokumen.
+pub fn projection_from(fields: &[&str]) -> Document {
+    if fields.is_empty() {
+        let mut projection = Document::new();
+        projection.insert("_id", 1);
+        return projection;
+    }
+
+    fields
+        .iter()
+        .map(|field| (field.to_string(), Bson::Int32(1)))
+        .collect()
+}
+
+/// Projection `{ field: 0, ... }` untuk menyembunyikan field sensitif, contoh `cost_price`
+pub fn projection_excluding(fields: &[&str]) -> Document {
+    fields
+        .iter()
+        .map(|field| (field.to_string(), Bson::Int32(0)))
+        .collect()
+}
+
+fn is_included(value: &Bson) -> Option<bool> {
+    match value {
+        Bson::Int32(v) => Some(*v != 0),
+        Bson::Int64(v) => Some(*v != 0),
+        Bson::Double(v) => Some(*v != 0.0),
+        Bson::Boolean(v) => Some(*v),
+        // Ekspresi seperti `$slice` atau `$elemMatch` dihitung sebagai inclusion
+        _ => None,
+    }
+}
+
+/// MongoDB menolak projection yang mencampur inclusion dan exclusion kecuali untuk `_id`
+pub fn validate_projection(projection: &Document) -> Result<(), ServiceError> {
+    let (mut includes, mut excludes) = (false, false);
+    for (field, value) in projection {
+        if field == "_id" {
+            continue;
+        }
+        match is_included(value) {
+            Some(false) => excludes = true,
+            _ => includes = true,
+        }
+    }
+
+    if includes && excludes {
+        return Err(ServiceError::Unexpected(
+            "Projection tidak boleh mencampur inclusion dan exclusion".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Gabungkan dua projection, mis. allow-list endpoint dengan field tambahan
+pub fn merge_projections(base: Document, extra: Document) -> Result<Document, ServiceError> {
+    let mut merged = base;
+    merged.extend(extra);
+    validate_projection(&merged)?;
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::doc;
+
+    #[test]
+    fn allow_list_becomes_inclusion() {
+        assert_eq!(
+            projection_from(&["name", "price"]),
+            doc! { "name": 1, "price": 1 }
+        );
+        assert_eq!(projection_from(&[]), doc! { "_id": 1 });
+    }
+
+    #[test]
+    fn deny_list_becomes_exclusion() {
+        assert_eq!(
+            projection_excluding(&["cost_price", "supplier"]),
+            doc! { "cost_price": 0, "supplier": 0 }
+        );
+    }
+
+    #[test]
+    fn mixing_inclusion_and_exclusion_is_rejected() {
+        assert!(matches!(
+            validate_projection(&doc! { "name": 1, "cost_price": 0 }),
+            Err(ServiceError::Unexpected(msg)) if msg.contains("mencampur")
+        ));
+        assert!(matches!(
+            merge_projections(
+                projection_from(&["name"]),
+                projection_excluding(&["cost_price"])
+            ),
+            Err(ServiceError::Unexpected(_))
+        ));
+        assert!(validate_projection(&doc! { "name": true, "tags": { "$slice": 3 } }).is_ok());
+    }
+
+    #[test]
+    fn id_may_be_excluded_from_an_inclusion() {
+        let merged = merge_projections(projection_from(&["name"]), doc! { "_id": 0 }).unwrap();
+
+        assert_eq!(merged, doc! { "name": 1, "_id": 0 });
+    }
+}
@@ -0,0 +1,144 @@
+use crate::errors::ServiceError;
+use crate::utils::map_mongo_error;
+use mongodb::error::{
+    Error as MongoError, ErrorKind, RETRYABLE_WRITE_ERROR, TRANSIENT_TRANSACTION_ERROR,
+};
+use rand::Rng;
+use std::future::Future;
+use std::time::Duration;
+
+/// Jeda awal sebelum percobaan ulang pertama, berlipat dua di setiap percobaan
+pub const RETRY_BASE_DELAY_MS: u64 = 50;
+pub const RETRY_MAX_DELAY_MS: u64 = 2_000;
+
+/// Error yang kemungkinan berhasil jika diulang: berlabel retryable/transient dari
+/// server, atau gangguan jaringan dan pemilihan server
+pub fn is_transient_error(err: &MongoError) -> bool {
+    if err.contains_label(RETRYABLE_WRITE_ERROR) || err.contains_label(TRANSIENT_TRANSACTION_ERROR)
+    {
+        return true;
+    }
+
+    matches!(
+        err.kind.as_ref(),
+        ErrorKind::Io(_)
+            | ErrorKind::ConnectionPoolCleared { .. }
+            | ErrorKind::ServerSelection { .. }
+    )
+}
+
+/// Jeda exponential backoff untuk percobaan ke-`attempt` (mulai dari 0) dengan full
+/// jitter, agar banyak request yang gagal bersamaan tidak retry di waktu yang sama
+pub fn backoff_delay(attempt: u32) -> Duration {
+    let max = RETRY_BASE_DELAY_MS
+        .saturating_mul(1u64 << attempt.min(16))
+        .min(RETRY_MAX_DELAY_MS);
+    Duration::from_millis(rand::rng().random_range(0..=max))
+}
+
+/// Jalankan operasi MongoDB, ulangi maksimal `max_retries` kali jika error-nya transient.
+/// Error lain langsung dikembalikan tanpa retry. Operasi harus aman diulang (idempotent).
+pub async fn retry_transient<F, Fut, T>(max_retries: u32, mut f: F) -> Result<T, ServiceError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, MongoError>>,
+{
+    let mut attempt = 0;
+    loop {
+        match f().await {
+            Ok(value) => return Ok(value),
+            Err(err) if attempt < max_retries && is_transient_error(&err) => {
+                actix_web::rt::time::sleep(backoff_delay(attempt)).await;
+                attempt += 1;
+            }
+            Err(err) => return Err(map_mongo_error(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::doc;
+    use mongodb::error::CommandError;
+    use std::cell::Cell;
+    use std::sync::Arc;
+
+    fn network_error() -> MongoError {
+        let io = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "reset");
+        MongoError::from(ErrorKind::Io(Arc::new(io)))
+    }
+
+    fn unauthorized_error() -> MongoError {
+        let command_error: CommandError = bson::from_document(doc! {
+            "code": 13,
+            "codeName": "Unauthorized",
+            "errmsg": "not authorized",
+        })
+        .unwrap();
+        MongoError::from(ErrorKind::Command(command_error))
+    }
+
+    #[test]
+    fn network_errors_are_transient() {
+        assert!(is_transient_error(&network_error()));
+        assert!(!is_transient_error(&unauthorized_error()));
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        for attempt in [0, 3, 10, 40] {
+            let cap = (RETRY_BASE_DELAY_MS << attempt.min(16)).min(RETRY_MAX_DELAY_MS);
+            assert!(backoff_delay(attempt) <= Duration::from_millis(cap));
+        }
+    }
+
+    #[actix_web::test]
+    async fn transient_failures_are_retried_until_success() {
+        let calls = Cell::new(0);
+
+        let result = retry_transient(3, || {
+            calls.set(calls.get() + 1);
+            let attempt = calls.get();
+            async move {
+                if attempt <= 2 {
+                    Err(network_error())
+                } else {
+                    Ok("tersimpan")
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), "tersimpan");
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[actix_web::test]
+    async fn retries_stop_at_the_limit() {
+        let calls = Cell::new(0);
+
+        let result: Result<(), _> = retry_transient(2, || {
+            calls.set(calls.get() + 1);
+            async { Err(network_error()) }
+        })
+        .await;
+
+        assert!(matches!(result, Err(ServiceError::ServiceUnavailable(_))));
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[actix_web::test]
+    async fn non_retryable_error_fails_immediately() {
+        let calls = Cell::new(0);
+
+        let result: Result<(), _> = retry_transient(5, || {
+            calls.set(calls.get() + 1);
+            async { Err(unauthorized_error()) }
+        })
+        .await;
+
+        assert!(matches!(result, Err(ServiceError::Internal { .. })));
+        assert_eq!(calls.get(), 1);
+    }
+}
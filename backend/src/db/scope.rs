@@ -0,0 +1,160 @@
+use crate::db::cursor::collect_all;
+use crate::db::helpers::find_one_or_not_found;
+use crate::db::projection::validate_projection;
+use crate::errors::ServiceError;
+use crate::utils::map_mongo_error;
+use mongodb::{
+    Collection,
+    bson::{Bson, Document, doc, oid::ObjectId},
+    options::FindOptions,
+};
+use serde::de::DeserializeOwned;
+
+pub const ORG_FIELD: &str = "org_id";
+
+/// Gabungkan batasan `org_id` ke filter query. Jika `base` sudah memuat `org_id`
+/// yang berbeda, keduanya digabung dengan `$and` sehingga hasilnya tetap kosong
+/// alih-alih menimpa scope.
+pub fn scoped_filter(org_id: &ObjectId, base: Document) -> Document {
+    match base.get(ORG_FIELD) {
+        None => {
+            let mut filter = base;
+            filter.insert(ORG_FIELD, *org_id);
+            filter
+        }
+        Some(Bson::ObjectId(existing)) if existing == org_id => base,
+        Some(_) => doc! { "$and": [base, { ORG_FIELD: *org_id }] },
+    }
+}
+
+/// Filter dianggap ter-scope jika memuat `org_id` di level atas atau di salah satu
+/// elemen `$and`
+pub fn is_org_scoped(filter: &Document) -> bool {
+    if filter.contains_key(ORG_FIELD) {
+        return true;
+    }
+
+    match filter.get_array("$and") {
+        Ok(clauses) => clauses.iter().any(|clause| match clause {
+            Bson::Document(clause) => is_org_scoped(clause),
+            _ => false,
+        }),
+        Err(_) => false,
+    }
+}
+
+/// Panic di build debug jika query data tenant lupa memakai `scoped_filter`
+#[track_caller]
+pub fn debug_assert_org_scoped(filter: &Document) {
+    debug_assert!(
+        is_org_scoped(filter),
+        "query tanpa scope org_id: {}",
+        filter
+    );
+}
+
+/// `find` untuk data tenant, filter wajib sudah melewati `scoped_filter`
+pub async fn find_scoped<T>(
+    collection: &Collection<T>,
+    filter: Document,
+) -> Result<Vec<T>, ServiceError>
+where
+    T: DeserializeOwned + Send + Sync,
+{
+    find_scoped_projected(collection, filter, None).await
+}
+
+/// Seperti `find_scoped` dengan projection opsional dari `db::projection`
+pub async fn find_scoped_projected<T>(
+    collection: &Collection<T>,
+    filter: Document,
+    projection: Option<Document>,
+) -> Result<Vec<T>, ServiceError>
+where
+    T: DeserializeOwned + Send + Sync,
+{
+    debug_assert_org_scoped(&filter);
+    if let Some(projection) = &projection {
+        validate_projection(projection)?;
+    }
+
+    let cursor = collection
+        .find(filter)
+        .with_options(FindOptions::builder().projection(projection).build())
+        .await
+        .map_err(map_mongo_error)?;
+    collect_all(cursor).await
+}
+
+/// `find_one_or_not_found` untuk data tenant, filter wajib sudah melewati `scoped_filter`
+pub async fn find_one_scoped<T>(
+    collection: &Collection<T>,
+    filter: Document,
+    entity_name: &str,
+) -> Result<T, ServiceError>
+where
+    T: DeserializeOwned + Send + Sync,
+{
+    debug_assert_org_scoped(&filter);
+    find_one_or_not_found(collection, filter, entity_name).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn org_is_merged_into_filter() {
+        let org_id = ObjectId::new();
+
+        assert_eq!(
+            scoped_filter(&org_id, doc! { "sku": "QT-1" }),
+            doc! { "sku": "QT-1", "org_id": org_id }
+        );
+        assert_eq!(
+            scoped_filter(&org_id, doc! { "org_id": org_id }),
+            doc! { "org_id": org_id }
+        );
+    }
+
+    #[test]
+    fn other_org_in_base_is_not_overwritten() {
+        let (org_id, other) = (ObjectId::new(), ObjectId::new());
+
+        let filter = scoped_filter(&org_id, doc! { "org_id": other });
+
+        assert_eq!(
+            filter,
+            doc! { "$and": [{ "org_id": other }, { "org_id": org_id }] }
+        );
+    }
+
+    #[test]
+    fn scope_is_detected_at_top_level_or_in_and() {
+        let org_id = ObjectId::new();
+
+        assert!(is_org_scoped(&scoped_filter(&org_id, Document::new())));
+        assert!(is_org_scoped(
+            &doc! { "$and": [{ "deleted_at": null }, { "org_id": org_id }] }
+        ));
+        assert!(!is_org_scoped(&doc! { "sku": "QT-1" }));
+        assert!(!is_org_scoped(
+            &doc! { "$or": [{ "org_id": org_id }, { "public": true }] }
+        ));
+    }
+
+    #[cfg(debug_assertions)]
+    #[actix_web::test]
+    #[should_panic(expected = "query tanpa scope org_id")]
+    async fn unscoped_find_is_caught() {
+        // Assert jalan sebelum query dikirim, jadi server tidak perlu ada
+        let client = mongodb::Client::with_uri_str("mongodb://127.0.0.1:1/")
+            .await
+            .unwrap();
+        let products = client
+            .database("qtoky_test")
+            .collection::<Document>("products");
+
+        let _ = find_scoped(&products, doc! { "sku": "QT-1" }).await;
+    }
+}
@@ -0,0 +1,194 @@
+use crate::db::keyset::SortDirection;
+use crate::errors::ServiceError;
+use crate::utils::string_enum::StringEnum;
+use bson::Document;
+
+/// Batas jumlah key dalam satu query `sort`, sort panjang jarang tercakup index
+pub const MAX_SORT_KEYS: usize = 3;
+
+/// Pecah `sort` seperti `-created_at,name` menjadi pasangan field dan arah. Awalan `-`
+/// berarti descending, `+` atau tanpa awalan ascending. Key kosong di antara koma diabaikan.
+pub fn parse_sort_keys(raw: &str) -> Result<Vec<(&str, SortDirection)>, ServiceError> {
+    let keys: Vec<(&str, SortDirection)> = raw
+        .split(',')
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(|key| match key.strip_prefix('-') {
+            Some(field) => (field.trim(), SortDirection::Desc),
+            None => (
+                key.strip_prefix('+').unwrap_or(key).trim(),
+                SortDirection::Asc,
+            ),
+        })
+        .collect();
+
+    if keys.len() > MAX_SORT_KEYS {
+        return Err(ServiceError::BadRequest(format!(
+            "sort maksimal {} field",
+            MAX_SORT_KEYS
+        )));
+    }
+
+    for (i, (field, _)) in keys.iter().enumerate() {
+        if keys[..i].iter().any(|(seen, _)| seen == field) {
+            return Err(ServiceError::BadRequest(format!(
+                "sort '{}' disebut lebih dari sekali",
+                field
+            )));
+        }
+    }
+
+    Ok(keys)
+}
+
+fn unsupported(field: &str, allowed: &[&str]) -> ServiceError {
+    ServiceError::BadRequest(format!(
+        "sort '{}' tidak didukung, gunakan salah satu dari: {}",
+        field,
+        allowed.join(", ")
+    ))
+}
+
+/// Dokumen sort dari `raw` yang setiap field-nya dicek terhadap `allowed`.
+/// `None` jika `raw` kosong.
+pub fn sort_document(raw: &str, allowed: &[&str]) -> Result<Option<Document>, ServiceError> {
+    let mut sort = Document::new();
+    for (field, direction) in parse_sort_keys(raw)? {
+        if !allowed.contains(&field) {
+            return Err(unsupported(field, allowed));
+        }
+        sort.insert(field, direction.order());
+    }
+
+    Ok((!sort.is_empty()).then_some(sort))
+}
+
+/// Sort yang field-nya dibatasi enum `F` buatan `string_enum!`, sehingga nama field dari
+/// query string tidak pernah diteruskan ke Mongo apa adanya.
+///
+/// ```ignore
+/// string_enum! {
+///     pub enum ProductSort {
+///         Name => "name",
+///         Price => "price",
+///         CreatedAt => "created_at",
+///     }
+/// }
+/// let spec = SortSpec::<ProductSort>::parse("-price,name")?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortSpec<F> {
+    pub keys: Vec<(F, SortDirection)>,
+}
+
+impl<F: StringEnum> SortSpec<F> {
+    pub fn parse(raw: &str) -> Result<Self, ServiceError> {
+        let keys = parse_sort_keys(raw)?
+            .into_iter()
+            .map(|(field, direction)| {
+                F::from_value(field)
+                    .map(|f| (f, direction))
+                    .ok_or_else(|| unsupported(field, &F::values()))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(SortSpec { keys })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Dokumen `{ field: 1/-1 }` sesuai urutan key
+    pub fn to_document(&self) -> Document {
+        let mut sort = Document::new();
+        for (field, direction) in &self.keys {
+            sort.insert(field.as_str(), direction.order());
+        }
+        sort
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::string_enum;
+    use bson::doc;
+
+    string_enum! {
+        enum ProductSort {
+            Name => "name",
+            Price => "price",
+            CreatedAt => "created_at",
+        }
+    }
+
+    #[test]
+    fn single_and_multi_key_sorts() {
+        let single = SortSpec::<ProductSort>::parse("price").unwrap();
+        let multi = SortSpec::<ProductSort>::parse("-created_at, name").unwrap();
+
+        assert_eq!(single.to_document(), doc! { "price": 1 });
+        assert_eq!(
+            multi.keys,
+            vec![
+                (ProductSort::CreatedAt, SortDirection::Desc),
+                (ProductSort::Name, SortDirection::Asc),
+            ]
+        );
+        assert_eq!(multi.to_document(), doc! { "created_at": -1, "name": 1 });
+        assert!(SortSpec::<ProductSort>::parse(" , ").unwrap().is_empty());
+    }
+
+    #[test]
+    fn direction_prefixes() {
+        assert_eq!(
+            parse_sort_keys("+name,-price,created_at").unwrap(),
+            vec![
+                ("name", SortDirection::Asc),
+                ("price", SortDirection::Desc),
+                ("created_at", SortDirection::Asc),
+            ]
+        );
+        assert_eq!(
+            parse_sort_keys("- price").unwrap(),
+            vec![("price", SortDirection::Desc)]
+        );
+    }
+
+    #[test]
+    fn unknown_fields_are_bad_requests() {
+        for raw in ["password_hash", "name,-$where", "-cost_price"] {
+            assert!(matches!(
+                SortSpec::<ProductSort>::parse(raw),
+                Err(ServiceError::BadRequest(msg))
+                    if msg.contains("tidak didukung") && msg.contains("name, price, created_at")
+            ));
+        }
+        assert!(matches!(
+            sort_document("stock", &["name"]),
+            Err(ServiceError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn duplicate_or_too_many_keys_are_bad_requests() {
+        assert!(matches!(
+            parse_sort_keys("name,-name"),
+            Err(ServiceError::BadRequest(msg)) if msg.contains("lebih dari sekali")
+        ));
+        assert!(matches!(
+            parse_sort_keys("a,b,c,d"),
+            Err(ServiceError::BadRequest(msg)) if msg == "sort maksimal 3 field"
+        ));
+    }
+
+    #[test]
+    fn sort_document_uses_allow_list() {
+        assert_eq!(
+            sort_document("-price", &["price", "name"]).unwrap(),
+            Some(doc! { "price": -1 })
+        );
+        assert_eq!(sort_document("", &["price"]).unwrap(), None);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::errors::ServiceError;
+use actix_web::rt::time::timeout;
+use std::future::Future;
+use std::time::Duration;
+
+/// Batas waktu default query berat (aggregation, laporan) agar satu query lambat tidak
+/// menahan worker selamanya
+pub const DB_OPERATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Selisih antara `maxTimeMS` dan batas waktu client, agar server sempat membatalkan
+/// query dan mengirim error-nya sebelum client menyerah
+const SERVER_TIMEOUT_MARGIN: Duration = Duration::from_millis(250);
+
+/// Nilai `max_time` untuk operasi yang dibungkus `with_timeout(limit, ...)`. Server
+/// membatalkan query sedikit lebih awal sehingga query tidak tetap berjalan di database
+/// setelah request ditinggalkan. Contoh:
+/// `with_timeout(limit, collection.aggregate(pipeline).max_time(server_max_time(limit)).into_future())`
+pub fn server_max_time(limit: Duration) -> Duration {
+    limit
+        .checked_sub(SERVER_TIMEOUT_MARGIN)
+        .filter(|max_time| !max_time.is_zero())
+        .unwrap_or(limit)
+}
+
+/// Jalankan `future` paling lama `limit`. Jika waktunya habis future di-drop dan hasilnya
+/// `ServiceUnavailable`; error dari future sendiri (contoh `mongodb::error::Error`)
+/// diterjemahkan lewat `Into<ServiceError>`.
+pub async fn with_timeout<T, E, F>(limit: Duration, future: F) -> Result<T, ServiceError>
+where
+    F: Future<Output = Result<T, E>>,
+    E: Into<ServiceError>,
+{
+    match timeout(limit, future).await {
+        Ok(result) => result.map_err(Into::into),
+        Err(_) => {
+            log::warn!(
+                "Operasi database melebihi batas waktu {} ms, dibatalkan",
+                limit.as_millis()
+            );
+            Err(ServiceError::ServiceUnavailable(
+                "Database terlalu lama merespons, coba lagi nanti".into(),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::{Document, doc};
+    use std::future::IntoFuture;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Instant;
+
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[actix_web::test]
+    async fn pending_future_times_out() {
+        let dropped = Arc::new(AtomicBool::new(false));
+        let guard = DropFlag(dropped.clone());
+        let started = Instant::now();
+
+        let result = with_timeout(Duration::from_millis(50), async move {
+            let _guard = guard;
+            std::future::pending::<Result<(), ServiceError>>().await
+        })
+        .await;
+
+        assert!(matches!(result, Err(ServiceError::ServiceUnavailable(_))));
+        assert!(dropped.load(Ordering::SeqCst));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[actix_web::test]
+    async fn finished_future_keeps_its_result() {
+        let ok = with_timeout(Duration::from_secs(1), async { Ok::<_, ServiceError>(7) }).await;
+        let err = with_timeout(Duration::from_secs(1), async {
+            Err::<(), _>(ServiceError::NotFound("Produk".into()))
+        })
+        .await;
+
+        assert_eq!(ok.unwrap(), 7);
+        assert!(matches!(err, Err(ServiceError::NotFound(_))));
+    }
+
+    #[actix_web::test]
+    async fn unreachable_database_query_is_bounded() {
+        let collection = mongodb::Client::with_uri_str("mongodb://127.0.0.1:1/")
+            .await
+            .unwrap()
+            .database("qtoky_test")
+            .collection::<Document>("sales");
+        let limit = Duration::from_millis(100);
+        let started = Instant::now();
+
+        let result = with_timeout(
+            limit,
+            collection
+                .aggregate(vec![doc! { "$match": {} }])
+                .max_time(server_max_time(limit))
+                .into_future(),
+        )
+        .await;
+
+        assert!(matches!(result, Err(ServiceError::ServiceUnavailable(_))));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn server_max_time_is_shorter_than_limit() {
+        assert_eq!(
+            server_max_time(Duration::from_secs(10)),
+            Duration::from_millis(9750)
+        );
+        assert_eq!(
+            server_max_time(Duration::from_millis(100)),
+            Duration::from_millis(100)
+        );
+        assert_eq!(
+            server_max_time(Duration::from_millis(250)),
+            Duration::from_millis(250)
+        );
+    }
+}
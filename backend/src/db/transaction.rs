@@ -0,0 +1,171 @@
+use crate::errors::ServiceError;
+use crate::utils::map_mongo_error;
+use futures::future::BoxFuture;
+use mongodb::{
+    Client, ClientSession,
+    error::{Error as MongoError, TRANSIENT_TRANSACTION_ERROR, UNKNOWN_TRANSACTION_COMMIT_RESULT},
+};
+
+/// Batas percobaan ulang transaksi/commit sebelum menyerah
+pub const TRANSACTION_MAX_RETRIES: usize = 5;
+
+/// Error dari closure transaksi. Error MongoDB dipisah agar label transient-nya
+/// masih bisa dibaca untuk retry, error bisnis langsung membatalkan transaksi.
+#[derive(Debug)]
+pub enum TransactionError {
+    Mongo(MongoError),
+    Service(ServiceError),
+    // Snapshot transaksi sudah usang (contoh transaksi lain commit data yang sama lebih
+    // dulu), transaksi dibatalkan lalu diulang dengan snapshot baru
+    Retry,
+}
+
+impl From<MongoError> for TransactionError {
+    fn from(err: MongoError) -> Self {
+        TransactionError::Mongo(err)
+    }
+}
+
+impl From<ServiceError> for TransactionError {
+    fn from(err: ServiceError) -> Self {
+        TransactionError::Service(err)
+    }
+}
+
+/// Jalankan `f` di dalam transaksi lalu commit. Transaksi diulang jika error berlabel
+/// `TransientTransactionError` atau `f` mengembalikan `TransactionError::Retry`, commit
+/// diulang jika hasilnya `UnknownTransactionCommitResult`.
+/// Semua operasi di dalam `f` harus memakai `.session(&mut *session)` agar ikut transaksi.
+pub async fn with_transaction<F, T>(client: &Client, mut f: F) -> Result<T, ServiceError>
+where
+    F: for<'a> FnMut(&'a mut ClientSession) -> BoxFuture<'a, Result<T, TransactionError>>,
+{
+    let mut session = client.start_session().await.map_err(map_mongo_error)?;
+
+    for _ in 0..TRANSACTION_MAX_RETRIES {
+        session.start_transaction().await.map_err(map_mongo_error)?;
+
+        let value = match f(&mut session).await {
+            Ok(value) => value,
+            Err(err) => {
+                // Abort gagal tidak masalah, transaksi akan expired sendiri di server
+                let _ = session.abort_transaction().await;
+
+                match err {
+                    TransactionError::Mongo(err)
+                        if err.contains_label(TRANSIENT_TRANSACTION_ERROR) =>
+                    {
+                        continue;
+                    }
+                    TransactionError::Retry => continue,
+                    TransactionError::Mongo(err) => return Err(map_mongo_error(err)),
+                    TransactionError::Service(err) => return Err(err),
+                }
+            }
+        };
+
+        match commit_with_retry(&mut session).await {
+            Ok(()) => return Ok(value),
+            Err(err) if err.contains_label(TRANSIENT_TRANSACTION_ERROR) => continue,
+            Err(err) => return Err(map_mongo_error(err)),
+        }
+    }
+
+    Err(ServiceError::DatabaseError(format!(
+        "Transaksi gagal setelah {} percobaan",
+        TRANSACTION_MAX_RETRIES
+    )))
+}
+
+async fn commit_with_retry(session: &mut ClientSession) -> Result<(), MongoError> {
+    let mut attempt = 1;
+    loop {
+        match session.commit_transaction().await {
+            Err(err)
+                if err.contains_label(UNKNOWN_TRANSACTION_COMMIT_RESULT)
+                    && attempt < TRANSACTION_MAX_RETRIES =>
+            {
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_database;
+    use bson::{Document, doc};
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB replica set"]
+    async fn commits_on_success_and_aborts_on_error() {
+        let db = test_database().await;
+        db.create_collection("orders").await.unwrap();
+        let orders = db.collection::<Document>("orders");
+
+        with_transaction(db.client(), |session| {
+            let orders = orders.clone();
+            Box::pin(async move {
+                orders
+                    .insert_one(doc! { "order": "berhasil" })
+                    .session(&mut *session)
+                    .await?;
+                Ok(())
+            })
+        })
+        .await
+        .unwrap();
+
+        let result: Result<(), _> = with_transaction(db.client(), |session| {
+            let orders = orders.clone();
+            Box::pin(async move {
+                orders
+                    .insert_one(doc! { "order": "batal" })
+                    .session(&mut *session)
+                    .await?;
+                Err(ServiceError::BadRequest("stok tidak cukup".into()).into())
+            })
+        })
+        .await;
+
+        assert!(matches!(result, Err(ServiceError::BadRequest(_))));
+        assert_eq!(
+            orders
+                .count_documents(doc! { "order": "berhasil" })
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            orders
+                .count_documents(doc! { "order": "batal" })
+                .await
+                .unwrap(),
+            0
+        );
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB replica set"]
+    async fn retry_reruns_the_closure() {
+        let db = test_database().await;
+        let mut attempts = 0;
+
+        let value = with_transaction(db.client(), |_| {
+            attempts += 1;
+            let attempt = attempts;
+            Box::pin(async move {
+                if attempt < 3 {
+                    return Err(TransactionError::Retry);
+                }
+                Ok(attempt)
+            })
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(value, 3);
+    }
+}
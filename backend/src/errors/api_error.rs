@@ -1,19 +1,25 @@
 // src/errors/api_error.rs
+use crate::errors::ServiceError;
+use crate::utils::i18n::{Message, t};
+use crate::utils::request_context::{current_request_id, log_with_context};
+use crate::utils::validation::join_field_errors;
 use actix_web::{
     Error as ActixError, HttpResponse, ResponseError,
-    error::{JsonPayloadError, PayloadError, UrlencodedError},
-    http::StatusCode,
+    error::{JsonPayloadError, PathError, PayloadError, QueryPayloadError, UrlencodedError},
+    http::{StatusCode, header::RETRY_AFTER},
 };
+use log::Level;
 use serde::Serialize;
+use std::collections::HashMap;
 use thiserror::Error;
 use validator::ValidationErrors;
 
-#[derive(Debug, Error)]
+#[derive(Debug, Error, Clone)]
 pub enum ApiError {
     #[error("Internal Server Error")]
     InternalError(String),
 
-    #[error("Not Found")]
+    #[error("Not Found: {0}")]
     NotFound(String),
 
     #[error("Bad Request: {0}")]
@@ -22,14 +28,35 @@ pub enum ApiError {
     #[error("Conflict: {0}")]
     Conflict(String),
 
+    #[error("Conflict: {} {}", .0.join(", "), t(Message::AlreadyUsed))]
+    DuplicateField(Vec<String>),
+
     #[error("ValidationError: {0}")]
     ValidationError(String),
 
+    #[error("ValidationError: {}", join_field_errors(.0))]
+    Validation(HashMap<String, Vec<String>>),
+
     #[error("Unauthorized: {0}")]
     Unauthorized(String),
 
     #[error("Forbidden: {0}")]
     Forbidden(String),
+
+    #[error("Gone: {0}")]
+    Gone(String),
+
+    #[error("Payload Too Large: {0}")]
+    PayloadTooLarge(String),
+
+    #[error("Service Unavailable: {0}")]
+    ServiceUnavailable(String),
+
+    #[error("Too Many Requests: {message}")]
+    TooManyRequests {
+        message: String,
+        retry_after_secs: u64,
+    },
 }
 
 #[derive(Debug, Serialize)]
@@ -37,96 +64,196 @@ struct ErrorResponse {
     status: &'static str,
     message: String,
     code: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fields: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    errors: Option<HashMap<String, Vec<String>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
 }
 
 // Response
 impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Conflict(_) | ApiError::DuplicateField(_) => StatusCode::CONFLICT,
+            ApiError::ValidationError(_) | ApiError::Validation(_) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::Gone(_) => StatusCode::GONE,
+            ApiError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+        }
+    }
+
     fn error_response(&self) -> HttpResponse {
-        let (status_code, message) = match self {
-            ApiError::InternalError(_) => (500, self.to_string()),
-            ApiError::NotFound(_) => (404, self.to_string()),
-            ApiError::BadRequest(_) => (400, self.to_string()),
-            ApiError::Conflict(_) => (409, self.to_string()),
-            ApiError::ValidationError(_) => (422, self.to_string()),
-            ApiError::Unauthorized(_) => (401, self.to_string()),
-            ApiError::Forbidden(_) => (403, self.to_string()),
+        let status_code = self.status_code();
+
+        // Detail error internal hanya masuk log, client cukup dapat pesan generik
+        if let ApiError::InternalError(cause) = self {
+            log_with_context(Level::Error, &format!("Internal error: {}", cause));
+        }
+        let message = self.to_string();
+
+        let fields = match self {
+            ApiError::DuplicateField(fields) => Some(fields.clone()),
+            _ => None,
+        };
+        let errors = match self {
+            ApiError::Validation(errors) => Some(errors.clone()),
+            _ => None,
         };
 
         let response = ErrorResponse {
             status: "error",
             message,
-            code: status_code,
+            code: status_code.as_u16(),
+            fields,
+            errors,
+            request_id: current_request_id(),
         };
 
-        HttpResponse::build(StatusCode::from_u16(status_code).unwrap()).json(response)
+        let mut builder = HttpResponse::build(status_code);
+        if let ApiError::TooManyRequests {
+            retry_after_secs, ..
+        } = self
+        {
+            builder.insert_header((RETRY_AFTER, retry_after_secs.to_string()));
+        }
+
+        builder.json(response)
     }
 }
 
 // Validation
 impl From<ValidationErrors> for ApiError {
     fn from(err: ValidationErrors) -> Self {
-        // Ubah ValidationErrors menjadi satu string yang readable
-        let msg = err
-            .field_errors()
-            .iter()
-            .map(|(_field, errs)| {
-                let reasons = errs
-                    .iter()
-                    .map(|e| {
-                        e.message
-                            .as_ref()
-                            .map(|s| s.to_string())
-                            .unwrap_or_else(|| "tidak valid".into())
-                    })
-                    .collect::<Vec<_>>()
-                    .join(", ");
-                format!("{reasons}")
-            })
-            .collect::<Vec<_>>()
-            .join(" | ");
-
-        ApiError::ValidationError(msg)
+        ApiError::from(&ServiceError::from(err))
     }
 }
 
 // Actix
 
-impl From<ActixError> for ApiError {
-    fn from(err: ActixError) -> Self {
-        if let Some(json_err) = err.as_error::<JsonPayloadError>() {
-            match json_err {
-                JsonPayloadError::ContentType => ApiError::BadRequest(
-                    "Konten harus berupa JSON (Content-Type: application/json)".into(),
-                ),
+fn payload_too_large(limit: Option<usize>) -> ApiError {
+    match limit {
+        Some(limit) => {
+            ApiError::PayloadTooLarge(format!("Ukuran body melebihi batas {} byte", limit))
+        }
+        None => ApiError::PayloadTooLarge("Ukuran body terlalu besar".into()),
+    }
+}
+
+impl From<&JsonPayloadError> for ApiError {
+    fn from(err: &JsonPayloadError) -> Self {
+        match err {
+            JsonPayloadError::ContentType => ApiError::BadRequest(
+                "Konten harus berupa JSON (Content-Type: application/json)".into(),
+            ),
 
-                JsonPayloadError::Deserialize(_) => ApiError::BadRequest(
+            JsonPayloadError::Deserialize(e) => match describe_field_error(&e.to_string()) {
+                Some(message) => ApiError::BadRequest(message),
+                None if e.is_data() => ApiError::BadRequest(format!(
+                    "Ada field dengan tipe data yang salah (baris {} kolom {})",
+                    e.line(),
+                    e.column()
+                )),
+                None => ApiError::BadRequest(
                     "Format JSON tidak valid atau ada field yang salah/tidak lengkap".into(),
                 ),
+            },
 
-                JsonPayloadError::Payload(_) => {
-                    ApiError::BadRequest("Ukuran body terlalu besar atau rusak".into())
-                }
+            JsonPayloadError::Overflow { limit }
+            | JsonPayloadError::OverflowKnownLength { limit, .. } => {
+                payload_too_large(Some(*limit))
+            }
 
-                JsonPayloadError::Overflow { limit: _ } => {
-                    ApiError::BadRequest("Nilai melebihi batas yang diperbolehkan".into())
-                }
+            JsonPayloadError::Payload(payload_err) => ApiError::from(payload_err),
 
-                _ => ApiError::BadRequest("Terjadi kesalahan saat memproses JSON".into()),
-            }
-        } else if let Some(payload_err) = err.as_error::<PayloadError>() {
-            match payload_err {
-                PayloadError::Overflow => {
-                    ApiError::BadRequest("Ukuran permintaan terlalu besar".into())
-                }
+            _ => ApiError::BadRequest("Terjadi kesalahan saat memproses JSON".into()),
+        }
+    }
+}
 
-                PayloadError::Incomplete(_) => {
-                    ApiError::BadRequest("Data tidak lengkap atau rusak".into())
-                }
+impl From<&PayloadError> for ApiError {
+    fn from(err: &PayloadError) -> Self {
+        match err {
+            PayloadError::Overflow => payload_too_large(None),
 
-                _ => ApiError::BadRequest("Permintaan tidak dapat dibaca".into()),
+            PayloadError::Incomplete(_) => {
+                ApiError::BadRequest("Data tidak lengkap atau rusak".into())
             }
-        } else if let Some(_form_err) = err.as_error::<UrlencodedError>() {
-            ApiError::BadRequest("Format form tidak valid".into())
+
+            _ => ApiError::BadRequest("Permintaan tidak dapat dibaca".into()),
+        }
+    }
+}
+
+impl From<&UrlencodedError> for ApiError {
+    fn from(err: &UrlencodedError) -> Self {
+        match err {
+            UrlencodedError::Overflow { limit, .. } => payload_too_large(Some(*limit)),
+            UrlencodedError::Payload(payload_err) => ApiError::from(payload_err),
+            _ => ApiError::BadRequest("Format form tidak valid".into()),
+        }
+    }
+}
+
+impl From<&PathError> for ApiError {
+    fn from(err: &PathError) -> Self {
+        let message = match err {
+            PathError::Deserialize(e) => describe_field_error(&e.to_string()),
+            _ => None,
+        };
+        ApiError::BadRequest(message.unwrap_or_else(|| "Parameter URL tidak valid".into()))
+    }
+}
+
+impl From<&QueryPayloadError> for ApiError {
+    fn from(err: &QueryPayloadError) -> Self {
+        let message = match err {
+            QueryPayloadError::Deserialize(e) => describe_field_error(&e.to_string()),
+            _ => None,
+        };
+        ApiError::BadRequest(message.unwrap_or_else(|| "Query parameter tidak valid".into()))
+    }
+}
+
+/// Pesan serde yang menyebut nama field, contoh "missing field `price` at line 1 column 9",
+/// menjadi "Kolom price wajib diisi". `None` jika serde tidak menyebut field-nya.
+fn describe_field_error(detail: &str) -> Option<String> {
+    let field = detail.split('`').nth(1)?;
+    if detail.starts_with("missing field") {
+        Some(format!("Kolom {} wajib diisi", field))
+    } else if detail.starts_with("unknown field") {
+        Some(format!("Kolom {} tidak dikenal", field))
+    } else if detail.starts_with("duplicate field") {
+        Some(format!("Kolom {} dikirim lebih dari sekali", field))
+    } else {
+        None
+    }
+}
+
+impl From<ActixError> for ApiError {
+    fn from(err: ActixError) -> Self {
+        // Error dari `error_handler` extractor sudah berupa ApiError
+        if let Some(api_err) = err.as_error::<ApiError>() {
+            api_err.clone()
+        } else if let Some(json_err) = err.as_error::<JsonPayloadError>() {
+            ApiError::from(json_err)
+        } else if let Some(payload_err) = err.as_error::<PayloadError>() {
+            ApiError::from(payload_err)
+        } else if let Some(form_err) = err.as_error::<UrlencodedError>() {
+            ApiError::from(form_err)
+        } else if let Some(path_err) = err.as_error::<PathError>() {
+            ApiError::from(path_err)
+        } else if let Some(query_err) = err.as_error::<QueryPayloadError>() {
+            ApiError::from(query_err)
         } else {
             ApiError::InternalError("Terjadi kesalahan internal saat memproses permintaan".into())
         }
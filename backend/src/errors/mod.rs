@@ -1,22 +1,351 @@
 mod api_error;
 mod service_error;
 
+use actix_web::{HttpResponse, ResponseError, http::StatusCode};
+use std::collections::HashMap;
+
 pub use api_error::ApiError;
 pub use service_error::ServiceError;
 
-impl From<ServiceError> for ApiError {
-    fn from(error: ServiceError) -> Self {
+impl From<&ServiceError> for ApiError {
+    fn from(error: &ServiceError) -> Self {
         match error {
-            ServiceError::NotFound(msg) => ApiError::NotFound(msg),
+            ServiceError::NotFound(msg) => ApiError::NotFound(msg.clone()),
             ServiceError::BadRequest(msg) | ServiceError::InvalidId(msg) => {
-                ApiError::BadRequest(msg)
+                ApiError::BadRequest(msg.clone())
             }
             ServiceError::HashingError(msg) | ServiceError::DatabaseError(msg) => {
-                ApiError::InternalError(msg)
+                ApiError::InternalError(msg.clone())
+            }
+            ServiceError::Validation(errors) => ApiError::Validation(errors.clone()),
+            ServiceError::Conflict(msg) => ApiError::Conflict(msg.clone()),
+            ServiceError::DuplicateField { fields } => ApiError::DuplicateField(fields.clone()),
+            ServiceError::Unexpected(msg) => ApiError::InternalError(msg.clone()),
+            ServiceError::Internal { .. } => ApiError::InternalError(error.detail()),
+            ServiceError::Unauthorized(msg) => ApiError::Unauthorized(msg.clone()),
+            ServiceError::Forbidden(msg) => ApiError::Forbidden(msg.clone()),
+            ServiceError::Gone(msg) => ApiError::Gone(msg.clone()),
+            ServiceError::ServiceUnavailable(msg) => ApiError::ServiceUnavailable(msg.clone()),
+            ServiceError::TooManyRequests {
+                message,
+                retry_after_secs,
+            } => ApiError::TooManyRequests {
+                message: message.clone(),
+                retry_after_secs: *retry_after_secs,
+            },
+        }
+    }
+}
+
+impl From<ServiceError> for ApiError {
+    fn from(error: ServiceError) -> Self {
+        ApiError::from(&error)
+    }
+}
+
+// ServiceError bisa langsung dikembalikan handler, format response sama dengan ApiError
+impl ResponseError for ServiceError {
+    fn status_code(&self) -> StatusCode {
+        ApiError::from(self).status_code()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        ApiError::from(self).error_response()
+    }
+}
+
+/// Pesan error tanpa prefix jenis error
+fn error_message(error: &ServiceError) -> String {
+    match error {
+        ServiceError::NotFound(msg)
+        | ServiceError::InvalidId(msg)
+        | ServiceError::DatabaseError(msg)
+        | ServiceError::Unexpected(msg)
+        | ServiceError::HashingError(msg)
+        | ServiceError::Conflict(msg)
+        | ServiceError::BadRequest(msg)
+        | ServiceError::Unauthorized(msg)
+        | ServiceError::Forbidden(msg)
+        | ServiceError::Gone(msg)
+        | ServiceError::ServiceUnavailable(msg) => msg.clone(),
+        ServiceError::TooManyRequests { message, .. } => message.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Ganti pesan error dengan `message`, varian tanpa pesan bebas dikembalikan apa adanya
+fn with_message(error: ServiceError, message: String) -> ServiceError {
+    match error {
+        ServiceError::NotFound(_) => ServiceError::NotFound(message),
+        ServiceError::InvalidId(_) => ServiceError::InvalidId(message),
+        ServiceError::DatabaseError(_) => ServiceError::DatabaseError(message),
+        ServiceError::Unexpected(_) => ServiceError::Unexpected(message),
+        ServiceError::HashingError(_) => ServiceError::HashingError(message),
+        ServiceError::Conflict(_) => ServiceError::Conflict(message),
+        ServiceError::BadRequest(_) => ServiceError::BadRequest(message),
+        ServiceError::Unauthorized(_) => ServiceError::Unauthorized(message),
+        ServiceError::Forbidden(_) => ServiceError::Forbidden(message),
+        ServiceError::Gone(_) => ServiceError::Gone(message),
+        ServiceError::ServiceUnavailable(_) => ServiceError::ServiceUnavailable(message),
+        other => other,
+    }
+}
+
+/// Kumpulkan hasil beberapa operasi independen (contoh dari `join_all`) tanpa kehilangan
+/// error selain yang pertama. Jika ada yang gagal, error dengan status paling berat
+/// (500 di atas 4xx) dipakai dan pesannya berisi semua pesan error, dipisah `; `.
+/// Jika semuanya `Validation`, error per field digabung menjadi satu `Validation`.
+pub fn collect_errors<T>(results: Vec<Result<T, ServiceError>>) -> Result<Vec<T>, ServiceError> {
+    let mut values = Vec::with_capacity(results.len());
+    let mut errors = Vec::new();
+    for result in results {
+        match result {
+            Ok(value) => values.push(value),
+            Err(err) => errors.push(err),
+        }
+    }
+
+    if errors.is_empty() {
+        return Ok(values);
+    }
+    if errors.len() == 1 {
+        return Err(errors.remove(0));
+    }
+
+    if errors
+        .iter()
+        .all(|e| matches!(e, ServiceError::Validation(_)))
+    {
+        let mut merged: HashMap<String, Vec<String>> = HashMap::new();
+        for error in errors {
+            if let ServiceError::Validation(fields) = error {
+                for (field, messages) in fields {
+                    merged.entry(field).or_default().extend(messages);
+                }
+            }
+        }
+        return Err(ServiceError::Validation(merged));
+    }
+
+    let message = errors
+        .iter()
+        .map(error_message)
+        .collect::<Vec<_>>()
+        .join("; ");
+    // Stabil, sehingga di antara status yang sama error pertama yang dipakai
+    errors.sort_by_key(|e| std::cmp::Reverse(e.status_code().as_u16()));
+    Err(with_message(errors.remove(0), message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+    use serde_json::Value;
+
+    async fn render(error: ServiceError) -> (StatusCode, Value) {
+        let response = error.error_response();
+        let status = response.status();
+        let body = to_bytes(response.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[actix_web::test]
+    async fn each_variant_maps_to_its_status_and_body() {
+        let cases = [
+            (ServiceError::BadRequest("data salah".into()), 400),
+            (ServiceError::InvalidId("id rusak".into()), 400),
+            (ServiceError::Unauthorized("token kosong".into()), 401),
+            (ServiceError::Forbidden("bukan milik anda".into()), 403),
+            (ServiceError::Conflict("sku dipakai".into()), 409),
+            (ServiceError::Gone("link kedaluwarsa".into()), 410),
+            (ServiceError::ServiceUnavailable("maintenance".into()), 503),
+        ];
+
+        for (error, code) in cases {
+            let message = error_message(&error);
+            let (status, body) = render(error).await;
+
+            assert_eq!(status.as_u16(), code);
+            assert_eq!(body["status"], "error");
+            assert_eq!(body["code"], code);
+            assert!(body["message"].as_str().unwrap().contains(&message));
+        }
+    }
+
+    #[actix_web::test]
+    async fn not_found_names_the_entity() {
+        let (status, body) = render(ServiceError::NotFound("Produk tidak ditemukan".into())).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["message"], "Not Found: Produk tidak ditemukan");
+    }
+
+    #[actix_web::test]
+    async fn duplicate_field_lists_fields() {
+        let (status, body) = render(ServiceError::DuplicateField {
+            fields: vec!["email".into(), "sku".into()],
+        })
+        .await;
+
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["fields"], serde_json::json!(["email", "sku"]));
+    }
+
+    #[actix_web::test]
+    async fn validation_returns_field_errors() {
+        let errors = HashMap::from([("name".to_string(), vec!["wajib diisi".to_string()])]);
+
+        let (status, body) = render(ServiceError::Validation(errors)).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["errors"]["name"][0], "wajib diisi");
+    }
+
+    #[actix_web::test]
+    async fn too_many_requests_sets_retry_after() {
+        let response = ServiceError::TooManyRequests {
+            message: "coba lagi nanti".into(),
+            retry_after_secs: 30,
+        }
+        .error_response();
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers().get("retry-after").unwrap(), "30");
+    }
+
+    #[actix_web::test]
+    async fn internal_errors_hide_their_cause() {
+        let cases = [
+            ServiceError::DatabaseError("koneksi mongodb://admin:rahasia putus".into()),
+            ServiceError::HashingError("salt rusak".into()),
+            ServiceError::Unexpected("panic di worker".into()),
+            ServiceError::internal("Gagal membuat token", "kunci RSA rusak"),
+        ];
+
+        for error in cases {
+            let (status, body) = render(error).await;
+
+            assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+            assert_eq!(body["message"], "Internal Server Error");
+        }
+    }
+
+    #[actix_web::test]
+    async fn validation_body_groups_messages_per_field() {
+        let mut errors = crate::utils::validation::ValidationErrors::new();
+        errors
+            .add("email", "format tidak valid")
+            .add("email", "sudah terdaftar")
+            .add("name", "wajib diisi");
+
+        let (status, body) = render(errors.into_result().unwrap_err()).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["status"], "error");
+        assert_eq!(body["code"], 422);
+        assert_eq!(
+            body["errors"],
+            serde_json::json!({
+                "email": ["format tidak valid", "sudah terdaftar"],
+                "name": ["wajib diisi"],
+            })
+        );
+        assert!(body.get("fields").is_none());
+    }
+
+    #[actix_web::test]
+    async fn bad_request_has_no_field_map() {
+        let (status, body) = render(ServiceError::BadRequest("data salah".into())).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.get("errors").is_none());
+    }
+
+    #[actix_web::test]
+    async fn internal_body_never_contains_source() {
+        let cause = std::io::Error::other("mongodb://admin:rahasia@db putus");
+        let (status, body) = render(ServiceError::internal("Gagal menyimpan sesi", cause)).await;
+
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["status"], "error");
+        assert_eq!(body["message"], "Internal Server Error");
+        let text = body.to_string();
+        assert!(!text.contains("rahasia"));
+        assert!(!text.contains("Gagal menyimpan sesi"));
+    }
+
+    #[test]
+    fn collect_errors_returns_all_values_on_success() {
+        let results: Vec<Result<u32, ServiceError>> = vec![Ok(1), Ok(2), Ok(3)];
+
+        assert_eq!(collect_errors(results).unwrap(), vec![1, 2, 3]);
+        assert!(collect_errors::<u32>(Vec::new()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn single_error_is_returned_unchanged() {
+        let results = vec![
+            Ok(1),
+            Err(ServiceError::NotFound("Produk tidak ditemukan".into())),
+        ];
+
+        assert!(matches!(
+            collect_errors(results),
+            Err(ServiceError::NotFound(msg)) if msg == "Produk tidak ditemukan"
+        ));
+    }
+
+    #[test]
+    fn most_severe_error_carries_every_message() {
+        let results: Vec<Result<(), ServiceError>> = vec![
+            Err(ServiceError::BadRequest("SKU kosong".into())),
+            Ok(()),
+            Err(ServiceError::DatabaseError("stok gagal disimpan".into())),
+            Err(ServiceError::NotFound("Supplier tidak ditemukan".into())),
+        ];
+
+        match collect_errors(results) {
+            Err(ServiceError::DatabaseError(msg)) => assert_eq!(
+                msg,
+                "SKU kosong; stok gagal disimpan; Supplier tidak ditemukan"
+            ),
+            other => panic!("hasil tidak terduga: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn same_status_keeps_first_error_variant() {
+        let results: Vec<Result<(), ServiceError>> = vec![
+            Err(ServiceError::InvalidId("id rusak".into())),
+            Err(ServiceError::BadRequest("harga negatif".into())),
+        ];
+
+        assert!(matches!(
+            collect_errors(results),
+            Err(ServiceError::InvalidId(msg)) if msg == "id rusak; harga negatif"
+        ));
+    }
+
+    #[test]
+    fn validation_errors_are_merged_per_field() {
+        let field = |name: &str, message: &str| {
+            Err::<(), _>(ServiceError::Validation(HashMap::from([(
+                name.to_string(),
+                vec![message.to_string()],
+            )])))
+        };
+
+        match collect_errors(vec![
+            field("name", "wajib diisi"),
+            field("price", "harus lebih dari 0"),
+            field("name", "terlalu panjang"),
+        ]) {
+            Err(ServiceError::Validation(fields)) => {
+                assert_eq!(fields["name"], vec!["wajib diisi", "terlalu panjang"]);
+                assert_eq!(fields["price"], vec!["harus lebih dari 0"]);
             }
-            ServiceError::Conflict(msg) => ApiError::Conflict(msg),
-            ServiceError::Unexpected(msg) => ApiError::InternalError(msg),
-            ServiceError::Unauthorized(msg) => ApiError::Unauthorized(msg),
+            other => panic!("hasil tidak terduga: {:?}", other),
         }
     }
 }
@@ -1,3 +1,7 @@
+use crate::utils::i18n::{Message, t};
+use crate::utils::validation::join_field_errors;
+use std::collections::HashMap;
+use std::error::Error as StdError;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -20,9 +24,129 @@ pub enum ServiceError {
     #[error("Conflict: {0}")]
     Conflict(String),
 
+    #[error("{} {}", .fields.join(", "), t(Message::AlreadyUsed))]
+    DuplicateField { fields: Vec<String> },
+
     #[error("Bad Request: {0}")]
     BadRequest(String),
 
+    // Pesan error per field, dirender sebagai `errors` dengan status 422
+    #[error("Validation Error: {}", join_field_errors(.0))]
+    Validation(HashMap<String, Vec<String>>),
+
     #[error("Unauthorized: {0}")]
     Unauthorized(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    // Resource pernah valid tapi sudah tidak berlaku, contoh link yang kedaluwarsa
+    #[error("Gone: {0}")]
+    Gone(String),
+
+    #[error("Service Unavailable: {0}")]
+    ServiceUnavailable(String),
+
+    #[error("Too Many Requests: {message}")]
+    TooManyRequests {
+        message: String,
+        retry_after_secs: u64,
+    },
+
+    // Error asli disimpan untuk log, client hanya dapat pesan generik + request id
+    #[error("Internal: {context}")]
+    Internal {
+        context: String,
+        #[source]
+        source: Box<dyn StdError + Send + Sync>,
+    },
+}
+
+impl ServiceError {
+    /// Bungkus error lain sebagai `Internal`, contoh:
+    /// `.map_err(|e| ServiceError::internal("Gagal membuat token", e))`
+    pub fn internal(
+        context: impl Into<String>,
+        err: impl Into<Box<dyn StdError + Send + Sync>>,
+    ) -> Self {
+        ServiceError::Internal {
+            context: context.into(),
+            source: err.into(),
+        }
+    }
+
+    /// Pesan lengkap beserta rantai `source`, hanya untuk log
+    pub fn detail(&self) -> String {
+        let mut detail = self.to_string();
+        let mut source = self.source();
+        while let Some(err) = source {
+            detail.push_str(": ");
+            detail.push_str(&err.to_string());
+            source = err.source();
+        }
+        detail
+    }
+}
+
+impl From<mongodb::error::Error> for ServiceError {
+    fn from(err: mongodb::error::Error) -> Self {
+        crate::utils::map_mongo_error(err)
+    }
+}
+
+impl From<validator::ValidationErrors> for ServiceError {
+    fn from(err: validator::ValidationErrors) -> Self {
+        // Pesan per field agar frontend bisa menandai beberapa input sekaligus
+        let errors = err
+            .field_errors()
+            .into_iter()
+            .map(|(field, errs)| {
+                let messages = errs
+                    .iter()
+                    .map(|e| {
+                        e.message
+                            .as_ref()
+                            .map(|s| s.to_string())
+                            .unwrap_or_else(|| "tidak valid".into())
+                    })
+                    .collect();
+                (field.to_string(), messages)
+            })
+            .collect();
+
+        ServiceError::Validation(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    fn wrapped() -> ServiceError {
+        let cause = io::Error::new(io::ErrorKind::InvalidData, "kunci RSA rusak");
+        ServiceError::internal("Gagal membuat token", cause)
+    }
+
+    #[test]
+    fn internal_keeps_source_for_logging() {
+        let error = wrapped();
+
+        assert_eq!(error.to_string(), "Internal: Gagal membuat token");
+        let source = error.source().expect("source harus tersimpan");
+        assert_eq!(source.to_string(), "kunci RSA rusak");
+        assert!(source.downcast_ref::<io::Error>().is_some());
+    }
+
+    #[test]
+    fn detail_walks_the_source_chain() {
+        assert_eq!(
+            wrapped().detail(),
+            "Internal: Gagal membuat token: kunci RSA rusak"
+        );
+        assert_eq!(
+            ServiceError::NotFound("Produk".into()).detail(),
+            ServiceError::NotFound("Produk".into()).to_string()
+        );
+    }
 }
@@ -0,0 +1,35 @@
+use crate::db::handle::Db;
+use crate::errors::ServiceError;
+use crate::models::api_key::ApiKey;
+use crate::services::api_key_service::authenticate_api_key;
+use crate::utils::api_key::API_KEY_HEADER;
+use actix_web::{FromRequest, HttpRequest, dev::Payload};
+use futures::future::LocalBoxFuture;
+
+/// Partner yang terautentikasi lewat header `X-API-Key`, tanpa cookie/JWT
+#[derive(Debug)]
+pub struct ApiKeyAuth(pub ApiKey);
+
+async fn authenticate(req: HttpRequest) -> Result<ApiKeyAuth, ServiceError> {
+    let presented = req
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| ServiceError::Unauthorized("API key tidak ditemukan".into()))?;
+
+    let db = Db::from_app_data(&req)
+        .ok_or_else(|| ServiceError::Unexpected("Database tidak tersedia".into()))?;
+
+    authenticate_api_key(presented, &db).await.map(ApiKeyAuth)
+}
+
+impl FromRequest for ApiKeyAuth {
+    type Error = ServiceError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        Box::pin(authenticate(req.clone()))
+    }
+}
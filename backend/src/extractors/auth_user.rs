@@ -0,0 +1,198 @@
+use crate::db::handle::Db;
+use crate::errors::ServiceError;
+use crate::services::session_invalidation::{IatWatermark, SessionInvalidation};
+use crate::services::session_store::SessionStore;
+use crate::services::token_blacklist::TokenBlacklist;
+use crate::utils::clock::{Clock, SystemClock};
+use crate::utils::jwt::{Claims, JWT_LEEWAY_SECS};
+use crate::utils::{extract_claims, extract_handshake_claims};
+use actix_web::{FromRequest, HttpRequest, dev::Payload, web::Data};
+use futures::future::LocalBoxFuture;
+
+/// User yang sudah terautentikasi, dipakai langsung sebagai argumen handler
+#[derive(Debug)]
+pub struct AuthUser {
+    pub user_id: String,
+    pub role: String,
+    pub org_id: Option<String>,
+    pub claims: Claims,
+}
+
+impl From<Claims> for AuthUser {
+    fn from(claims: Claims) -> Self {
+        AuthUser {
+            user_id: claims.sub.clone(),
+            role: claims.role.clone(),
+            org_id: claims.org_id.clone(),
+            claims,
+        }
+    }
+}
+
+pub(crate) async fn authenticate(req: HttpRequest) -> Result<AuthUser, ServiceError> {
+    let claims = extract_claims(&req)?;
+    authenticate_claims(&req, claims).await
+}
+
+/// Autentikasi request upgrade WebSocket. Token boleh dari cookie, header, atau `?token=`,
+/// sisanya (fingerprint, blacklist, `last_seen`) sama dengan `authenticate`
+pub(crate) async fn authenticate_handshake(req: HttpRequest) -> Result<AuthUser, ServiceError> {
+    let claims = extract_handshake_claims(&req)?;
+    authenticate_claims(&req, claims).await
+}
+
+/// Cek yang sama untuk extractor maupun `AuthMiddleware`: watermark `iat`, blacklist dan
+/// logout semua perangkat. Tanpa `Db` di app data request ditolak, bukan dilewati.
+pub(crate) async fn ensure_session_active(
+    req: &HttpRequest,
+    claims: &Claims,
+) -> Result<Db, ServiceError> {
+    // Opsional, hanya aktif jika `IatWatermark` didaftarkan sebagai app data
+    if let Some(watermark) = req.app_data::<Data<IatWatermark>>() {
+        let now = SystemClock.unix_timestamp().max(0) as usize;
+        watermark.observe(claims, now, *JWT_LEEWAY_SECS)?;
+    }
+
+    let db = Db::from_app_data(req)
+        .ok_or_else(|| ServiceError::Unexpected("Database tidak tersedia".into()))?;
+    TokenBlacklist::new(&db).ensure_not_revoked(claims).await?;
+    SessionInvalidation::new(&db)
+        .ensure_not_invalidated(claims)
+        .await?;
+
+    Ok(db)
+}
+
+async fn authenticate_claims(req: &HttpRequest, claims: Claims) -> Result<AuthUser, ServiceError> {
+    let db = ensure_session_active(req, &claims).await?;
+
+    // `last_seen` tidak boleh menahan request, kegagalannya cukup di-log
+    if let Some(jti) = claims.jti.clone() {
+        actix_web::rt::spawn(async move {
+            if let Err(e) = SessionStore::new(&db).touch(&jti).await {
+                log::warn!("Gagal memperbarui last_seen sesi: {}", e);
+            }
+        });
+    }
+
+    Ok(AuthUser::from(claims))
+}
+
+impl FromRequest for AuthUser {
+    type Error = ServiceError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        Box::pin(authenticate(req.clone()))
+    }
+}
+
+/// Seperti `AuthUser`, tapi bernilai `None` jika request tidak membawa token yang valid
+#[derive(Debug)]
+pub struct OptionalAuthUser(pub Option<AuthUser>);
+
+impl FromRequest for OptionalAuthUser {
+    type Error = ServiceError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move { Ok(OptionalAuthUser(authenticate(req).await.ok())) })
+    }
+}
+
+/// `AuthUser` untuk handler handshake WebSocket, menerima token dari query param `?token=`
+#[derive(Debug)]
+pub struct HandshakeAuthUser(pub AuthUser);
+
+impl FromRequest for HandshakeAuthUser {
+    type Error = ServiceError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move { authenticate_handshake(req).await.map(HandshakeAuthUser) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::user::ROLE_USER;
+    use crate::testing::{init_test_config, test_auth_cookie, test_database};
+    use actix_web::http::StatusCode;
+    use actix_web::{App, HttpResponse, test, web};
+    use chrono::Duration;
+
+    async fn whoami(user: AuthUser) -> HttpResponse {
+        HttpResponse::Ok().body(format!("{}:{}", user.user_id, user.role))
+    }
+
+    async fn maybe_whoami(user: OptionalAuthUser) -> HttpResponse {
+        let body = user.0.map(|user| user.user_id).unwrap_or_default();
+        HttpResponse::Ok().body(body)
+    }
+
+    #[actix_web::test]
+    async fn missing_token_is_unauthorized() {
+        init_test_config();
+        let app = test::init_service(App::new().route("/me", web::get().to(whoami))).await;
+
+        let res = test::call_service(&app, test::TestRequest::get().uri("/me").to_request()).await;
+
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn expired_token_is_unauthorized() {
+        init_test_config();
+        let app = test::init_service(App::new().route("/me", web::get().to(whoami))).await;
+        let req = test::TestRequest::get()
+            .uri("/me")
+            .cookie(test_auth_cookie("user-1", ROLE_USER, Duration::minutes(-5)))
+            .to_request();
+
+        let res = test::call_service(&app, req).await;
+
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn optional_user_is_none_without_token() {
+        init_test_config();
+        let app = test::init_service(App::new().route("/me", web::get().to(maybe_whoami))).await;
+
+        let res = test::call_service(&app, test::TestRequest::get().uri("/me").to_request()).await;
+
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(test::read_body(res).await.is_empty());
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn valid_token_yields_auth_user() {
+        init_test_config();
+        let db = Db::new(test_database().await);
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db))
+                .route("/me", web::get().to(whoami))
+                .route("/maybe", web::get().to(maybe_whoami)),
+        )
+        .await;
+        let cookie = test_auth_cookie("user-1", ROLE_USER, Duration::minutes(5));
+
+        let req = test::TestRequest::get()
+            .uri("/me")
+            .cookie(cookie.clone())
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        assert_eq!(body, "user-1:user");
+
+        let req = test::TestRequest::get()
+            .uri("/maybe")
+            .cookie(cookie)
+            .to_request();
+        assert_eq!(test::call_and_read_body(&app, req).await, "user-1");
+    }
+}
@@ -0,0 +1,79 @@
+use crate::errors::ServiceError;
+use actix_web::{FromRequest, HttpRequest, dev::Payload};
+use futures::future::{Ready, ready};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+/// Header `Idempotency-Key` opsional. Key yang kosong, terlalu panjang atau berisi
+/// karakter non-ASCII ditolak dengan `BadRequest`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdempotencyKey(pub Option<String>);
+
+fn extract(req: &HttpRequest) -> Result<IdempotencyKey, ServiceError> {
+    let Some(raw) = req.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(IdempotencyKey(None));
+    };
+
+    let key = raw
+        .to_str()
+        .map(str::trim)
+        .map_err(|_| ServiceError::BadRequest("Idempotency-Key tidak valid".into()))?;
+
+    if key.is_empty()
+        || key.len() > MAX_IDEMPOTENCY_KEY_LEN
+        || !key.chars().all(|c| c.is_ascii_graphic())
+    {
+        return Err(ServiceError::BadRequest(format!(
+            "Idempotency-Key harus 1-{} karakter ASCII tanpa spasi",
+            MAX_IDEMPOTENCY_KEY_LEN
+        )));
+    }
+
+    Ok(IdempotencyKey(Some(key.to_string())))
+}
+
+impl FromRequest for IdempotencyKey {
+    type Error = ServiceError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(extract(req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn key_from(value: Option<&str>) -> Result<IdempotencyKey, ServiceError> {
+        let req = match value {
+            Some(value) => TestRequest::default().insert_header((IDEMPOTENCY_KEY_HEADER, value)),
+            None => TestRequest::default(),
+        };
+        extract(&req.to_http_request())
+    }
+
+    #[test]
+    fn header_is_optional_and_trimmed() {
+        assert_eq!(key_from(None).unwrap(), IdempotencyKey(None));
+        assert_eq!(
+            key_from(Some(" order-1 ")).unwrap(),
+            IdempotencyKey(Some("order-1".into()))
+        );
+    }
+
+    #[test]
+    fn invalid_keys_are_bad_request() {
+        let too_long = "k".repeat(MAX_IDEMPOTENCY_KEY_LEN + 1);
+
+        for value in ["   ", "order 1", too_long.as_str()] {
+            assert!(
+                matches!(key_from(Some(value)), Err(ServiceError::BadRequest(_))),
+                "{}",
+                value
+            );
+        }
+    }
+}
@@ -0,0 +1,15 @@
+pub mod api_key_auth;
+pub mod auth_user;
+pub mod idempotency_key;
+pub mod object_id_path;
+pub mod org_scope;
+pub mod request_id;
+pub mod validated_json;
+
+pub use api_key_auth::ApiKeyAuth;
+pub use auth_user::{AuthUser, HandshakeAuthUser, OptionalAuthUser};
+pub use idempotency_key::IdempotencyKey;
+pub use object_id_path::ObjectIdPath;
+pub use org_scope::OrgScope;
+pub use request_id::RequestId;
+pub use validated_json::ValidatedJson;
@@ -0,0 +1,83 @@
+use crate::errors::ServiceError;
+use crate::utils::parse_object_id_param;
+use actix_web::{FromRequest, HttpRequest, dev::Payload};
+use bson::oid::ObjectId;
+use futures::future::{Ready, ready};
+
+/// Path param `{id}` yang sudah di-parse menjadi `ObjectId`.
+/// Id yang tidak valid langsung ditolak dengan `BadRequest("id tidak valid")`.
+#[derive(Debug, Clone, Copy)]
+pub struct ObjectIdPath(pub ObjectId);
+
+impl ObjectIdPath {
+    pub fn into_inner(self) -> ObjectId {
+        self.0
+    }
+}
+
+fn extract(req: &HttpRequest) -> Result<ObjectIdPath, ServiceError> {
+    let match_info = req.match_info();
+
+    // Pakai `{id}`, atau satu-satunya path param jika namanya berbeda
+    let raw = match match_info.get("id") {
+        Some(raw) => raw,
+        None => match match_info.iter().collect::<Vec<_>>().as_slice() {
+            [(_, raw)] => raw,
+            _ => return Err(ServiceError::BadRequest("id tidak valid".into())),
+        },
+    };
+
+    parse_object_id_param(raw).map(ObjectIdPath)
+}
+
+impl FromRequest for ObjectIdPath {
+    type Error = ServiceError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(extract(req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::{App, HttpResponse, test, web};
+
+    async fn show(id: ObjectIdPath) -> HttpResponse {
+        HttpResponse::Ok().body(id.into_inner().to_hex())
+    }
+
+    async fn call(uri: &str) -> (StatusCode, String) {
+        let app = test::init_service(
+            App::new()
+                .route("/products/{id}", web::get().to(show))
+                .route("/sales/{sale_id}", web::get().to(show)),
+        )
+        .await;
+        let res = test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+        let status = res.status();
+        let body = String::from_utf8(test::read_body(res).await.to_vec()).unwrap();
+        (status, body)
+    }
+
+    #[actix_web::test]
+    async fn valid_id_is_parsed() {
+        let id = ObjectId::new().to_hex();
+
+        assert_eq!(
+            call(&format!("/products/{}", id)).await,
+            (StatusCode::OK, id.clone())
+        );
+        assert_eq!(call(&format!("/sales/{}", id)).await, (StatusCode::OK, id));
+    }
+
+    #[actix_web::test]
+    async fn invalid_id_is_bad_request() {
+        let (status, body) = call("/products/bukan-id").await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.contains("id tidak valid"));
+    }
+}
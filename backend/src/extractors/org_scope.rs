@@ -0,0 +1,38 @@
+use super::auth_user::authenticate;
+use crate::errors::ServiceError;
+use crate::utils::i18n::{Message, t};
+use crate::utils::string_id_to_obj_id;
+use actix_web::{FromRequest, HttpRequest, dev::Payload};
+use bson::oid::ObjectId;
+use futures::future::LocalBoxFuture;
+
+/// `org_id` dari token user yang sudah terautentikasi. Token tanpa organisasi ditolak,
+/// jadi handler yang memakai extractor ini selalu punya scope untuk query.
+#[derive(Debug, Clone, Copy)]
+pub struct OrgScope(pub ObjectId);
+
+impl OrgScope {
+    pub fn into_inner(self) -> ObjectId {
+        self.0
+    }
+}
+
+async fn extract(req: HttpRequest) -> Result<OrgScope, ServiceError> {
+    let auth = authenticate(req).await?;
+    let org_id = auth
+        .org_id
+        .ok_or_else(|| ServiceError::Forbidden("Akun tidak terhubung ke organisasi".into()))?;
+
+    string_id_to_obj_id(&org_id)
+        .map(OrgScope)
+        .ok_or_else(|| ServiceError::Unauthorized(t(Message::TokenInvalid)))
+}
+
+impl FromRequest for OrgScope {
+    type Error = ServiceError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        Box::pin(extract(req.clone()))
+    }
+}
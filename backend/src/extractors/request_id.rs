@@ -0,0 +1,23 @@
+use crate::utils::request_context::current_request_id;
+use actix_web::{FromRequest, HttpMessage, HttpRequest, dev::Payload};
+use futures::future::{Ready, ok};
+use std::convert::Infallible;
+
+/// Request id yang dibuat `RequestIdMiddleware`, bisa langsung dipakai sebagai argumen handler
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+impl FromRequest for RequestId {
+    type Error = Infallible;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let id = req
+            .extensions()
+            .get::<RequestId>()
+            .cloned()
+            .or_else(|| current_request_id().map(RequestId))
+            .unwrap_or_else(|| RequestId("-".to_string()));
+        ok(id)
+    }
+}
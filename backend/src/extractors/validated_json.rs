@@ -0,0 +1,110 @@
+use crate::errors::ServiceError;
+use actix_web::{FromRequest, HttpRequest, dev::Payload, web::Json};
+use futures::future::LocalBoxFuture;
+use serde::de::DeserializeOwned;
+use std::ops::Deref;
+use validator::Validate;
+
+/// Body JSON yang sudah di-deserialize sekaligus divalidasi. Body rusak menjadi 400 lewat
+/// error handler `JsonConfig`, body yang gagal validasi menjadi 422 berisi error per field.
+#[derive(Debug)]
+pub struct ValidatedJson<T>(pub T);
+
+impl<T> ValidatedJson<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for ValidatedJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> FromRequest for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate + 'static,
+{
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let json = Json::<T>::from_request(req, payload);
+        Box::pin(async move {
+            let data = json.await?.into_inner();
+            data.validate().map_err(ServiceError::from)?;
+            Ok(ValidatedJson(data))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::body_limit::BodyLimitConfig;
+    use actix_web::http::StatusCode;
+    use actix_web::{App, HttpResponse, test, web};
+    use serde::Deserialize;
+    use serde_json::{Value, json};
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct NewProduct {
+        #[validate(length(min = 3, message = "Nama minimal 3 karakter"))]
+        name: String,
+        #[validate(range(min = 1.0, message = "Harga minimal 1"))]
+        price: f64,
+    }
+
+    async fn create(body: ValidatedJson<NewProduct>) -> HttpResponse {
+        HttpResponse::Created().body(format!("{}:{}", body.name, body.price))
+    }
+
+    async fn post(payload: Value) -> (StatusCode, actix_web::web::Bytes) {
+        let limits = BodyLimitConfig::default();
+        let app = test::init_service(
+            App::new()
+                .configure(|cfg| limits.configure(cfg))
+                .route("/products", web::post().to(create)),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/products")
+            .set_json(payload)
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        (resp.status(), test::read_body(resp).await)
+    }
+
+    #[actix_web::test]
+    async fn valid_body_reaches_handler() {
+        let (status, body) = post(json!({ "name": "Kopi", "price": 15000 })).await;
+
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body, "Kopi:15000");
+    }
+
+    #[actix_web::test]
+    async fn invalid_body_is_422_with_field_errors() {
+        let (status, body) = post(json!({ "name": "Ko", "price": 0 })).await;
+        let body: Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["status"], "error");
+        assert_eq!(body["errors"]["name"], json!(["Nama minimal 3 karakter"]));
+        assert_eq!(body["errors"]["price"], json!(["Harga minimal 1"]));
+    }
+
+    #[actix_web::test]
+    async fn malformed_body_is_400() {
+        let (status, body) = post(json!({ "name": "Kopi", "price": "mahal" })).await;
+        let body: Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], 400);
+        assert!(body.get("errors").is_none());
+    }
+}
@@ -0,0 +1,11 @@
+pub mod config;
+pub mod db;
+pub mod errors;
+pub mod extractors;
+pub mod middlewares;
+pub mod models;
+pub mod rest;
+pub mod services;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod utils;
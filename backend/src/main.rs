@@ -1,36 +1,136 @@
 // main.rs
-use actix_web::{App, HttpServer, middleware::Logger};
+use actix_web::{App, HttpServer, dev::ServiceRequest, middleware::Logger};
 
-mod config;
-mod db;
-mod errors;
-mod middlewares;
-mod models;
-mod rest;
-mod services;
-mod utils;
-
-use rest::config as rest_api_routes;
+use once_cell::sync::Lazy;
+use qtoky::config::Config;
+use qtoky::db;
+use qtoky::db::handle::Db;
+use qtoky::middlewares::locale_middleware::LocaleMiddleware;
+use qtoky::middlewares::metrics_middleware::{Metrics, MetricsMiddleware};
+use qtoky::middlewares::request_id_middleware::RequestIdMiddleware;
+use qtoky::rest::config as rest_api_routes;
+use qtoky::services::api_key_service::ensure_api_key_indexes;
+use qtoky::services::idempotency_store::IdempotencyStore;
+use qtoky::services::mailer::{Mailer, mailer_from_env};
+use qtoky::services::rate_limiter::LoginRateLimiter;
+use qtoky::services::session_invalidation::IatWatermark;
+use qtoky::services::session_store::SessionStore;
+use qtoky::services::token_blacklist::TokenBlacklist;
+use qtoky::services::user_service::ensure_user_indexes;
+use qtoky::utils::HANDSHAKE_TOKEN_PARAM;
+use qtoky::utils::cookie::COOKIE_CONFIG;
+use qtoky::utils::jwt::JWT_KEYS;
+use qtoky::utils::password::ARGON2_CONFIG;
+use qtoky::utils::redact::redact_query_params;
+use qtoky::utils::shutdown::{ShutdownHook, run_until_stopped};
+use std::time::Duration;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenvy::dotenv().ok();
-    let port = std::env::var("PORT").unwrap_or("7878".to_string());
+    // Gagal di sini lebih baik daripada error saat request pertama masuk
+    let config = match Config::from_env() {
+        Ok(config) => Config::install(config),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    Lazy::force(&ARGON2_CONFIG);
+    Lazy::force(&JWT_KEYS);
+    Lazy::force(&COOKIE_CONFIG);
 
-    let db_client = db::mongo::init_db().await.expect("Failed to initialize db");
+    let db_client = db::mongo::init_db(config)
+        .await
+        .expect("Failed to initialize db");
+    TokenBlacklist::new(&db_client)
+        .ensure_indexes()
+        .await
+        .expect("Failed to create token blacklist indexes");
+    SessionStore::new(&db_client)
+        .ensure_indexes()
+        .await
+        .expect("Failed to create session indexes");
+    IdempotencyStore::new(&db_client)
+        .ensure_indexes()
+        .await
+        .expect("Failed to create idempotency indexes");
+    ensure_api_key_indexes(&db_client)
+        .await
+        .expect("Failed to create api key indexes");
+    ensure_user_indexes(&db_client)
+        .await
+        .expect("Failed to create user indexes");
     unsafe {
         std::env::set_var("RUST_LOG", "info");
         std::env::set_var("RUST_BACKTRACE", "1");
         env_logger::init();
     }
-    HttpServer::new(move || {
-        let logger = Logger::default();
+
+    // Satu instance untuk semua worker agar hitungan percobaan login tidak terpecah
+    let login_limiter =
+        actix_web::web::Data::new(LoginRateLimiter::from_config(&config.login_limit));
+    let cleanup_limiter = login_limiter.clone();
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(cleanup_limiter.window());
+        loop {
+            interval.tick().await;
+            cleanup_limiter.cleanup();
+        }
+    });
+
+    let mailer: actix_web::web::Data<dyn Mailer> = match mailer_from_env(&config.mail_limit) {
+        Ok(mailer) => actix_web::web::Data::from(mailer),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let metrics = actix_web::web::Data::new(Metrics::new());
+    let iat_watermark = actix_web::web::Data::new(IatWatermark::new());
+
+    let body_limit = config.body_limit.clone();
+    let app_db = db_client.clone();
+    let server = HttpServer::new(move || {
+        // Sama dengan format default, tapi `%r` diganti agar `?token=` handshake tidak ter-log
+        let logger =
+            Logger::new(r#"%a "%{request_line}xi" %s %b "%{Referer}i" "%{User-Agent}i" %T"#)
+                .custom_request_replace("request_line", redacted_request_line);
         App::new()
+            .wrap(LocaleMiddleware)
+            .wrap(MetricsMiddleware::new(metrics.clone().into_inner()))
+            .wrap(RequestIdMiddleware)
             .wrap(logger)
-            .app_data(actix_web::web::Data::new(db_client.clone()))
+            .app_data(actix_web::web::Data::new(Db::new(app_db.clone())))
+            .app_data(login_limiter.clone())
+            .app_data(mailer.clone())
+            .app_data(metrics.clone())
+            .app_data(iat_watermark.clone())
+            .configure(|cfg| body_limit.configure(cfg))
             .configure(rest_api_routes)
     })
-    .bind(("127.0.0.1", port.parse::<u16>().unwrap()))?
-    .run()
-    .await
+    .bind(("127.0.0.1", config.port))?
+    // Setelah sinyal stop, worker menunggu request yang sedang berjalan selama batas ini
+    .shutdown_timeout(config.shutdown_timeout_secs)
+    .run();
+
+    let close_timeout = Duration::from_secs(config.shutdown_timeout_secs);
+    let cleanup = ShutdownHook::new(move || async move {
+        db::mongo::close_db(&db_client, close_timeout).await;
+    });
+    run_until_stopped(server, &cleanup).await
+}
+
+/// Request line seperti `%r` milik Logger, dengan token di query string disamarkan
+fn redacted_request_line(req: &ServiceRequest) -> String {
+    let path = match req.query_string() {
+        "" => req.path().to_string(),
+        query => format!(
+            "{}?{}",
+            req.path(),
+            redact_query_params(query, &[HANDSHAKE_TOKEN_PARAM])
+        ),
+    };
+    format!("{} {} {:?}", req.method(), path, req.version())
 }
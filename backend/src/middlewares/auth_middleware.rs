@@ -1,7 +1,16 @@
+use crate::db::handle::Db;
 use crate::errors::ApiError;
-use crate::utils::jwt::decode_jwt;
+use crate::extractors::auth_user::ensure_session_active;
+use crate::services::session_store::SessionStore;
+use crate::utils::csrf::verify_csrf_with_claims;
+use crate::utils::fingerprint::verify_fingerprint_with_claims;
+use crate::utils::i18n::{Message, t};
+use crate::utils::jwt::{
+    Claims, SESSION_REFRESH_THRESHOLD_SECS, maybe_refresh_cookie, validate_access_token,
+};
+use crate::utils::{TokenSource, extract_token};
 use actix_web::{
-    Error,
+    Error, HttpMessage,
     dev::{Service, ServiceRequest, ServiceResponse, Transform},
 };
 use futures::future::{LocalBoxFuture, Ready, ok};
@@ -10,6 +19,36 @@ use std::task::{Context, Poll};
 
 pub struct AuthMiddleware;
 
+/// Role dari token yang sudah lolos `verify_request`, disimpan di extensions request agar
+/// middleware di dalam `AuthMiddleware` (contoh `RequireRole`) tidak memvalidasi ulang
+#[derive(Debug, Clone)]
+pub(crate) struct VerifiedRole(pub String);
+
+/// Validasi lengkap access token request: signature dan waktu, fingerprint, blacklist,
+/// logout semua perangkat, serta CSRF untuk token dari cookie di method selain GET
+pub(crate) async fn verify_request(
+    req: &ServiceRequest,
+) -> Result<(Claims, TokenSource, Db), ApiError> {
+    // Ambil token dari cookie auth_token atau header Authorization
+    let (token, source) = extract_token(req.request())
+        .ok_or_else(|| ApiError::Unauthorized(t(Message::TokenNotFound)))?;
+
+    // Validasi token JWT
+    let claims = validate_access_token(&token)?;
+    verify_fingerprint_with_claims(req.request(), &claims)?;
+
+    // Tolak token yang sudah di-revoke, diterbitkan sebelum logout semua perangkat
+    // atau ganti password
+    let db = ensure_session_active(req.request(), &claims).await?;
+
+    // Cek CSRF token jika method bukan GET dan token dikirim lewat cookie
+    if source == TokenSource::Cookie && req.method() != actix_web::http::Method::GET {
+        verify_csrf_with_claims(req.request(), &claims)?;
+    }
+
+    Ok((claims, source, db))
+}
+
 impl<S, B> Transform<S, ServiceRequest> for AuthMiddleware
 where
     S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
@@ -49,32 +88,31 @@ where
         let service = Rc::clone(&self.service);
 
         Box::pin(async move {
-            // Cek cookie auth_token
-            let token = req.cookie("auth_token").map(|c| c.value().to_string());
-
-            let token = match token {
-                Some(t) => t,
-                None => return Err(ApiError::Unauthorized("Token tidak ditemukan".into()).into()),
-            };
-
-            // Validasi token JWT
-            let _decoded = decode_jwt(&token)
-                .map_err(|_| ApiError::Unauthorized("Token tidak valid atau expired".into()))?;
-
-            // Cek CSRF token jika method bukan GET
-            if req.method() != actix_web::http::Method::GET {
-                let csrf_cookie = req.cookie("csrf_token").map(|c| c.value().to_string());
-                let csrf_header = req
-                    .headers()
-                    .get("x-csrf-token")
-                    .and_then(|v| v.to_str().ok());
-
-                if csrf_cookie.is_none() || csrf_header != csrf_cookie.as_deref() {
-                    return Err(ApiError::Forbidden("CSRF token tidak cocok".into()).into());
+            let (claims, source, db) = verify_request(&req).await?;
+            req.extensions_mut()
+                .insert(VerifiedRole(claims.role.clone()));
+
+            let mut res = service.call(req).await?;
+
+            // Sliding session, perpanjang cookie auth jika hampir expired
+            if source == TokenSource::Cookie
+                && let Some((issued, cookies)) =
+                    maybe_refresh_cookie(&claims, SESSION_REFRESH_THRESHOLD_SECS)
+            {
+                // Sesi harus mengikuti jti baru agar tetap bisa dicabut
+                if let Some(old_jti) = &claims.jti
+                    && let Err(e) = SessionStore::new(&db)
+                        .replace_access_jti(old_jti, &issued.jti)
+                        .await
+                {
+                    log::warn!("Gagal memperbarui jti sesi: {}", e);
+                }
+                for cookie in cookies {
+                    res.response_mut().add_cookie(&cookie)?;
                 }
             }
 
-            service.call(req).await
+            Ok(res)
         })
     }
 }
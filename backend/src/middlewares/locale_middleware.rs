@@ -0,0 +1,54 @@
+use crate::utils::i18n::{Locale, with_locale};
+use actix_web::{
+    Error,
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+};
+use futures::future::{LocalBoxFuture, Ready, ok};
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+/// Set locale pesan error dari header `Accept-Language` selama request diproses
+pub struct LocaleMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for LocaleMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = LocaleMiddlewareImpl<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(LocaleMiddlewareImpl {
+            service: Rc::new(service),
+        })
+    }
+}
+
+pub struct LocaleMiddlewareImpl<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for LocaleMiddlewareImpl<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let locale = Locale::from_request(req.request());
+
+        Box::pin(with_locale(locale, async move { service.call(req).await }))
+    }
+}
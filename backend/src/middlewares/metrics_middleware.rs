@@ -0,0 +1,346 @@
+use actix_web::{
+    Error,
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    http::{Method, StatusCode},
+};
+use futures::future::{LocalBoxFuture, Ready, ok};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+/// Batas atas bucket histogram latency dalam milidetik
+pub const LATENCY_BUCKETS_MS: [u64; 10] = [5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000];
+
+/// Label route untuk path yang tidak cocok dengan resource mana pun, agar path acak
+/// tidak membuat jumlah label tanpa batas
+pub const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Label method untuk extension method (contoh `FOO1`), dengan alasan yang sama seperti
+/// `UNMATCHED_ROUTE`
+pub const OTHER_METHOD: &str = "OTHER";
+
+/// Label method yang dicatat apa adanya
+pub fn method_label(method: &Method) -> &'static str {
+    match *method {
+        Method::GET => "GET",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::PATCH => "PATCH",
+        Method::DELETE => "DELETE",
+        Method::HEAD => "HEAD",
+        Method::OPTIONS => "OPTIONS",
+        _ => OTHER_METHOD,
+    }
+}
+
+const STATUS_CLASSES: [&str; 5] = ["1xx", "2xx", "3xx", "4xx", "5xx"];
+
+/// Counter satu kombinasi method + route, semuanya atomic
+#[derive(Debug, Default)]
+pub struct RouteMetrics {
+    by_status: [AtomicU64; 5],
+    latency_buckets: [AtomicU64; LATENCY_BUCKETS_MS.len()],
+    latency_sum_micros: AtomicU64,
+}
+
+impl RouteMetrics {
+    fn record(&self, status: StatusCode, elapsed_micros: u64) {
+        let class = (status.as_u16() / 100).clamp(1, 5) as usize - 1;
+        self.by_status[class].fetch_add(1, Ordering::Relaxed);
+        self.latency_sum_micros
+            .fetch_add(elapsed_micros, Ordering::Relaxed);
+
+        let elapsed_ms = elapsed_micros / 1000;
+        if let Some(bucket) = LATENCY_BUCKETS_MS.iter().position(|&le| elapsed_ms <= le) {
+            self.latency_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn request_count(&self) -> u64 {
+        self.by_status
+            .iter()
+            .map(|c| c.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// Jumlah response dengan kelas status tertentu, contoh `4` untuk 4xx
+    pub fn status_count(&self, class: u16) -> u64 {
+        match class {
+            1..=5 => self.by_status[class as usize - 1].load(Ordering::Relaxed),
+            _ => 0,
+        }
+    }
+
+    pub fn error_count(&self) -> u64 {
+        self.status_count(4) + self.status_count(5)
+    }
+}
+
+/// Registry metrics in-memory, dibagikan antar worker lewat `web::Data`
+#[derive(Debug, Default)]
+pub struct Metrics {
+    routes: RwLock<HashMap<(String, String), Arc<RouteMetrics>>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Metrics::default()
+    }
+
+    pub fn record(&self, method: &str, route: &str, status: StatusCode, elapsed_micros: u64) {
+        self.route(method, route).record(status, elapsed_micros);
+    }
+
+    /// Counter untuk method + route, dibuat jika belum ada
+    pub fn route(&self, method: &str, route: &str) -> Arc<RouteMetrics> {
+        let key = (method.to_string(), route.to_string());
+        if let Some(metrics) = self.read_routes().get(&key) {
+            return Arc::clone(metrics);
+        }
+
+        let mut routes = self.routes.write().unwrap_or_else(|e| e.into_inner());
+        Arc::clone(routes.entry(key).or_default())
+    }
+
+    fn read_routes(
+        &self,
+    ) -> std::sync::RwLockReadGuard<'_, HashMap<(String, String), Arc<RouteMetrics>>> {
+        self.routes.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Format teks Prometheus
+    pub fn render_prometheus(&self) -> String {
+        let routes = self.read_routes();
+        let mut keys: Vec<_> = routes.keys().collect();
+        keys.sort();
+
+        let mut out = String::new();
+        out.push_str("# TYPE http_requests_total counter\n");
+        for key in &keys {
+            let metrics = &routes[*key];
+            for (class, label) in STATUS_CLASSES.iter().enumerate() {
+                let count = metrics.by_status[class].load(Ordering::Relaxed);
+                if count > 0 {
+                    let _ = writeln!(
+                        out,
+                        "http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}",
+                        key.0, key.1, label, count
+                    );
+                }
+            }
+        }
+
+        out.push_str("# TYPE http_request_duration_seconds histogram\n");
+        for key in &keys {
+            let metrics = &routes[*key];
+            let labels = format!("method=\"{}\",route=\"{}\"", key.0, key.1);
+            let mut cumulative = 0;
+            for (bucket, le) in LATENCY_BUCKETS_MS.iter().enumerate() {
+                cumulative += metrics.latency_buckets[bucket].load(Ordering::Relaxed);
+                let _ = writeln!(
+                    out,
+                    "http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels,
+                    *le as f64 / 1000.0,
+                    cumulative
+                );
+            }
+            let count = metrics.request_count();
+            let _ = writeln!(
+                out,
+                "http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, count
+            );
+            let _ = writeln!(
+                out,
+                "http_request_duration_seconds_sum{{{}}} {}",
+                labels,
+                metrics.latency_sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0
+            );
+            let _ = writeln!(
+                out,
+                "http_request_duration_seconds_count{{{}}} {}",
+                labels, count
+            );
+        }
+
+        out
+    }
+}
+
+/// Catat jumlah request, kelas status dan latency per route. Error dari middleware
+/// atau handler (`ApiError`/`ServiceError`) dihitung dengan status hasil mapping-nya.
+pub struct MetricsMiddleware {
+    metrics: Arc<Metrics>,
+}
+
+impl MetricsMiddleware {
+    pub fn new(metrics: Arc<Metrics>) -> Self {
+        MetricsMiddleware { metrics }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for MetricsMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = MetricsMiddlewareImpl<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(MetricsMiddlewareImpl {
+            service: Rc::new(service),
+            metrics: Arc::clone(&self.metrics),
+        })
+    }
+}
+
+pub struct MetricsMiddlewareImpl<S> {
+    service: Rc<S>,
+    metrics: Arc<Metrics>,
+}
+
+impl<S, B> Service<ServiceRequest> for MetricsMiddlewareImpl<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let metrics = Arc::clone(&self.metrics);
+        let method = method_label(req.method());
+        // Pattern dibaca dari resource map, jadi sudah tersedia sebelum routing
+        let route = req
+            .match_pattern()
+            .unwrap_or_else(|| UNMATCHED_ROUTE.to_string());
+        let started = Instant::now();
+
+        Box::pin(async move {
+            let result = service.call(req).await;
+            let status = match &result {
+                Ok(res) => res.status(),
+                Err(err) => err.as_response_error().status_code(),
+            };
+            let elapsed_micros = started.elapsed().as_micros().min(u64::MAX as u128) as u64;
+            metrics.record(method, &route, status, elapsed_micros);
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ServiceError;
+    use actix_web::{App, HttpResponse, test, web};
+
+    async fn ok_handler() -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    async fn not_found_handler() -> Result<HttpResponse, ServiceError> {
+        Err(ServiceError::NotFound("Produk tidak ditemukan".into()))
+    }
+
+    async fn failing_handler() -> Result<HttpResponse, ServiceError> {
+        Err(ServiceError::DatabaseError("koneksi putus".into()))
+    }
+
+    async fn call(metrics: &Arc<Metrics>, uris: &[&str]) {
+        let app = test::init_service(
+            App::new()
+                .wrap(MetricsMiddleware::new(Arc::clone(metrics)))
+                .route("/products/{id}", web::get().to(ok_handler))
+                .route("/missing", web::get().to(not_found_handler))
+                .route("/failing", web::get().to(failing_handler)),
+        )
+        .await;
+
+        for uri in uris {
+            test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+        }
+    }
+
+    #[actix_web::test]
+    async fn requests_are_counted_per_route_pattern() {
+        let metrics = Arc::new(Metrics::new());
+
+        call(&metrics, &["/products/1", "/products/2"]).await;
+
+        let route = metrics.route("GET", "/products/{id}");
+        assert_eq!(route.request_count(), 2);
+        assert_eq!(route.status_count(2), 2);
+        assert_eq!(route.error_count(), 0);
+        assert!(metrics.render_prometheus().contains(
+            "http_requests_total{method=\"GET\",route=\"/products/{id}\",status=\"2xx\"} 2"
+        ));
+        assert!(metrics.render_prometheus().contains(
+            "http_request_duration_seconds_count{method=\"GET\",route=\"/products/{id}\"} 2"
+        ));
+    }
+
+    #[actix_web::test]
+    async fn service_errors_land_in_their_status_bucket() {
+        let metrics = Arc::new(Metrics::new());
+
+        call(&metrics, &["/missing", "/failing", "/failing"]).await;
+
+        let missing = metrics.route("GET", "/missing");
+        let failing = metrics.route("GET", "/failing");
+        assert_eq!(missing.status_count(4), 1);
+        assert_eq!(failing.status_count(5), 2);
+        assert_eq!(missing.error_count() + failing.error_count(), 3);
+    }
+
+    #[actix_web::test]
+    async fn unknown_paths_share_one_label() {
+        let metrics = Arc::new(Metrics::new());
+
+        call(&metrics, &["/acak-1", "/acak-2"]).await;
+
+        assert_eq!(metrics.route("GET", UNMATCHED_ROUTE).status_count(4), 2);
+    }
+
+    #[actix_web::test]
+    async fn extension_methods_share_one_label() {
+        let metrics = Arc::new(Metrics::new());
+        let app = test::init_service(
+            App::new()
+                .wrap(MetricsMiddleware::new(Arc::clone(&metrics)))
+                .route("/products/{id}", web::get().to(ok_handler)),
+        )
+        .await;
+
+        for method in ["FOO1", "FOO2", "BAR"] {
+            let method = Method::from_bytes(method.as_bytes()).unwrap();
+            let req = test::TestRequest::default()
+                .method(method)
+                .uri("/acak")
+                .to_request();
+            test::call_service(&app, req).await;
+        }
+
+        assert_eq!(
+            metrics.route(OTHER_METHOD, UNMATCHED_ROUTE).request_count(),
+            3
+        );
+        assert!(!metrics.render_prometheus().contains("FOO1"));
+        assert_eq!(method_label(&Method::PATCH), "PATCH");
+    }
+}
@@ -1 +1,5 @@
 pub mod auth_middleware;
+pub mod locale_middleware;
+pub mod metrics_middleware;
+pub mod request_id_middleware;
+pub mod role_middleware;
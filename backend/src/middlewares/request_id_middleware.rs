@@ -0,0 +1,143 @@
+use crate::extractors::RequestId;
+use crate::utils::request_context::{REQUEST_ID_HEADER, with_request_id};
+use actix_web::{
+    Error, HttpMessage,
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    error::InternalError,
+    http::header::{HeaderName, HeaderValue},
+};
+use futures::future::{LocalBoxFuture, Ready, ok};
+use nanoid::nanoid;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+/// Buat request id per request, simpan di extensions dan kirim balik lewat header `X-Request-Id`
+pub struct RequestIdMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestIdMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequestIdMiddlewareImpl<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RequestIdMiddlewareImpl {
+            service: Rc::new(service),
+        })
+    }
+}
+
+pub struct RequestIdMiddlewareImpl<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdMiddlewareImpl<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let request_id = nanoid!();
+        req.extensions_mut().insert(RequestId(request_id.clone()));
+
+        Box::pin(with_request_id(request_id.clone(), async move {
+            let header_value = HeaderValue::from_str(&request_id).ok();
+            let header_name = HeaderName::from_static(REQUEST_ID_HEADER);
+
+            match service.call(req).await {
+                Ok(mut res) => {
+                    if let Some(value) = header_value {
+                        res.headers_mut().insert(header_name, value);
+                    }
+                    Ok(res)
+                }
+                // Error dari middleware lain dirender di sini agar body-nya ikut memuat request id
+                Err(err) => {
+                    let mut response = err.error_response();
+                    if let Some(value) = header_value {
+                        response.headers_mut().insert(header_name, value);
+                    }
+                    Err(InternalError::from_response(err, response).into())
+                }
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ServiceError;
+    use crate::utils::request_context::current_request_id;
+    use actix_web::{App, HttpResponse, test, web};
+
+    async fn echo(id: RequestId) -> HttpResponse {
+        HttpResponse::Ok().body(format!("{}|{}", id.0, current_request_id().unwrap()))
+    }
+
+    async fn fail() -> Result<HttpResponse, ServiceError> {
+        Err(ServiceError::NotFound("produk".into()))
+    }
+
+    fn header(res: &ServiceResponse) -> String {
+        res.headers()
+            .get(REQUEST_ID_HEADER)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[actix_web::test]
+    async fn request_id_is_stable_within_a_request() {
+        let app = test::init_service(
+            App::new()
+                .wrap(RequestIdMiddleware)
+                .route("/echo", web::get().to(echo)),
+        )
+        .await;
+
+        let res =
+            test::call_service(&app, test::TestRequest::get().uri("/echo").to_request()).await;
+        let id = header(&res);
+        let body = test::read_body(res).await;
+
+        assert_eq!(body, format!("{}|{}", id, id));
+
+        let next =
+            test::call_service(&app, test::TestRequest::get().uri("/echo").to_request()).await;
+        assert_ne!(header(&next), id);
+    }
+
+    #[actix_web::test]
+    async fn error_body_echoes_request_id_header() {
+        let app = test::init_service(
+            App::new()
+                .wrap(RequestIdMiddleware)
+                .route("/fail", web::get().to(fail)),
+        )
+        .await;
+
+        let res =
+            test::call_service(&app, test::TestRequest::get().uri("/fail").to_request()).await;
+        let id = header(&res);
+        let body: serde_json::Value = test::read_body_json(res).await;
+
+        assert!(!id.is_empty());
+        assert_eq!(body["request_id"], id);
+    }
+}
@@ -0,0 +1,126 @@
+use crate::errors::{ApiError, ServiceError};
+use crate::middlewares::auth_middleware::{VerifiedRole, verify_request};
+use actix_web::{
+    Error, HttpMessage,
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+};
+use futures::future::{LocalBoxFuture, Ready, ok};
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+/// Batasi akses scope/route hanya untuk role tertentu, contoh:
+/// `.wrap(RequireRole(ROLE_ADMIN))`. Token divalidasi lengkap seperti `AuthMiddleware`.
+pub struct RequireRole(pub &'static str);
+
+impl<S, B> Transform<S, ServiceRequest> for RequireRole
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequireRoleImpl<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RequireRoleImpl {
+            service: Rc::new(service),
+            role: self.0,
+        })
+    }
+}
+
+pub struct RequireRoleImpl<S> {
+    service: Rc<S>,
+    role: &'static str,
+}
+
+impl<S, B> Service<ServiceRequest> for RequireRoleImpl<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let role = self.role;
+
+        Box::pin(async move {
+            // Di dalam `AuthMiddleware` request sudah divalidasi, jika dipasang sendiri
+            // validasinya sama persis dengan `AuthMiddleware`
+            let verified = req
+                .extensions()
+                .get::<VerifiedRole>()
+                .map(|verified| verified.0.clone());
+            let claims_role = match verified {
+                Some(claims_role) => claims_role,
+                None => verify_request(&req).await?.0.role,
+            };
+
+            if claims_role != role {
+                return Err(ApiError::from(ServiceError::Forbidden(format!(
+                    "Akses hanya untuk role {}",
+                    role
+                )))
+                .into());
+            }
+
+            service.call(req).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::user::{ROLE_ADMIN, ROLE_USER};
+    use actix_web::http::StatusCode;
+    use actix_web::{App, HttpResponse, test, web};
+
+    // Meniru `AuthMiddleware` yang sudah memverifikasi token dengan role tertentu
+    async fn call_with_role(role: Option<&'static str>) -> StatusCode {
+        let app = test::init_service(
+            App::new().service(
+                web::resource("/admin")
+                    .wrap(RequireRole(ROLE_ADMIN))
+                    .wrap_fn(move |req, srv| {
+                        if let Some(role) = role {
+                            req.extensions_mut().insert(VerifiedRole(role.to_string()));
+                        }
+                        srv.call(req)
+                    })
+                    .to(HttpResponse::Ok),
+            ),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/admin").to_request();
+        match app.call(req).await {
+            Ok(res) => res.status(),
+            Err(err) => err.as_response_error().status_code(),
+        }
+    }
+
+    #[actix_web::test]
+    async fn matching_role_is_allowed() {
+        assert_eq!(call_with_role(Some(ROLE_ADMIN)).await, StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn other_role_is_forbidden() {
+        assert_eq!(call_with_role(Some(ROLE_USER)).await, StatusCode::FORBIDDEN);
+    }
+
+    #[actix_web::test]
+    async fn unverified_request_is_rejected() {
+        assert_eq!(call_with_role(None).await, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,26 @@
+use crate::models::user::default_role;
+use crate::utils::opt_object_id_as_string;
+use bson::{DateTime, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+
+/// API key untuk partner integrasi, plaintext tidak pernah disimpan
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ApiKey {
+    #[serde(
+        rename = "_id",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "opt_object_id_as_string"
+    )]
+    pub id: Option<ObjectId>,
+    pub key_id: String,
+    pub key_hash: String,
+    pub name: String,
+
+    #[serde(default = "default_role")]
+    pub role: String,
+
+    #[serde(default)]
+    pub created_at: Option<DateTime>,
+    #[serde(default)]
+    pub revoked_at: Option<DateTime>,
+}
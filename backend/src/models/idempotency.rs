@@ -0,0 +1,17 @@
+use bson::{DateTime, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+
+/// Dokumen yang dibuat untuk satu `Idempotency-Key`, unik per user
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IdempotencyRecord {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub user_id: String,
+    pub key: String,
+    // SHA-256 body request, key yang sama dengan body berbeda ditolak
+    pub request_hash: String,
+    // `_id` dokumen yang dibuat `create_idempotent`, dipakai untuk replay
+    pub resource_id: ObjectId,
+    pub created_at: DateTime,
+    pub expires_at: DateTime,
+}
@@ -1,3 +1,8 @@
+pub mod api_key;
+pub mod idempotency;
 pub mod product;
 pub mod sale;
+pub mod session;
+pub mod status;
+pub mod token;
 pub mod user;
@@ -1,4 +1,4 @@
-use crate::utils::opt_object_id_as_string;
+use crate::utils::{deserialize_opt_object_id, opt_object_id_as_string};
 use bson::{DateTime, oid::ObjectId};
 use serde::{Deserialize, Serialize};
 use validator::Validate;
@@ -14,6 +14,9 @@ pub struct Product {
     pub user_id: ObjectId,
     pub name: String,
     pub sku: String,
+    // Produk lama belum punya slug
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
     pub price: f64,
     pub stock: u32,
 
@@ -39,7 +42,8 @@ pub struct ProductDTO {
     pub stock: u32,
 
     // Optional: kategori (boleh kosong)
-    pub category_id: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_opt_object_id")]
+    pub category_id: Option<ObjectId>,
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -55,7 +59,8 @@ pub struct UpdateProductDTO {
     #[validate(range(min = 100.0, message = "Harga minimal 100"))]
     pub price: Option<f64>,
 
-    pub category_id: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_opt_object_id")]
+    pub category_id: Option<ObjectId>,
 }
 
 #[derive(Debug, Serialize)]
@@ -66,6 +71,7 @@ pub struct ProductResponse {
 
     pub name: String,
     pub sku: String,
+    pub slug: Option<String>,
     pub price: f64,
     pub stock: u32,
 
@@ -84,6 +90,7 @@ impl From<Product> for ProductResponse {
             user_id: p.user_id.to_hex(),
             name: p.name,
             sku: p.sku,
+            slug: p.slug,
             price: p.price,
             stock: p.stock,
             category_id: p.category_id.map(|c| c.to_hex()),
@@ -1,9 +1,10 @@
+use crate::models::status::PaymentStatus;
 use crate::utils::{object_id_as_string, opt_object_id_as_string};
 use mongodb::bson::{DateTime, oid::ObjectId};
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SaleItem {
     #[serde(serialize_with = "object_id_as_string")]
     pub product_id: ObjectId,
@@ -15,7 +16,7 @@ pub struct SaleItem {
     pub subtotal: f64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Sale {
     #[serde(
         rename = "_id",
@@ -35,7 +36,7 @@ pub struct Sale {
 
     pub paid_amount: f64,
     pub remaining_amount: f64,
-    pub status: String, // "paid", "partial", "unpaid"
+    pub status: PaymentStatus,
 
     pub invoice_number: Option<String>,
 
@@ -50,7 +51,7 @@ pub struct Sale {
     pub updated_at: Option<DateTime>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Validate)]
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
 pub struct SaleItemDTO {
     pub product_id: ObjectId, // tidak divalidasi karena sudah pasti BSON ID yang valid
 
@@ -64,7 +65,7 @@ pub struct SaleItemDTO {
     pub discount: Option<f64>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
 pub struct SaleDTO {
     pub customer_id: Option<ObjectId>,
 
@@ -98,7 +99,7 @@ pub struct SaleResponse {
 
     pub paid_amount: f64,
     pub remaining_amount: f64,
-    pub status: String,
+    pub status: PaymentStatus,
 
     pub invoice_number: Option<String>,
     pub payment_method_id: Option<String>,
@@ -0,0 +1,46 @@
+use crate::utils::datetime_as_iso_string;
+use bson::{DateTime, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+
+/// Sesi login per perangkat. `jti` mengikuti access token terbaru, `refresh_jti`
+/// mengikuti refresh token terbaru, keduanya berganti setiap refresh.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Session {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub user_id: ObjectId,
+    pub jti: String,
+    pub refresh_jti: String,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: DateTime,
+    pub last_seen: DateTime,
+    // Ikut `exp` refresh token, dihapus TTL index setelah lewat
+    pub expires_at: DateTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionResponse {
+    pub jti: String,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    #[serde(serialize_with = "datetime_as_iso_string")]
+    pub created_at: DateTime,
+    #[serde(serialize_with = "datetime_as_iso_string")]
+    pub last_seen: DateTime,
+    // Sesi yang sedang dipakai request ini
+    pub current: bool,
+}
+
+impl SessionResponse {
+    pub fn from_session(session: Session, current_jti: Option<&str>) -> Self {
+        SessionResponse {
+            current: current_jti == Some(session.jti.as_str()),
+            jti: session.jti,
+            user_agent: session.user_agent,
+            ip: session.ip,
+            created_at: session.created_at,
+            last_seen: session.last_seen,
+        }
+    }
+}
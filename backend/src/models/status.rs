@@ -0,0 +1,73 @@
+use crate::string_enum;
+
+string_enum! {
+    /// Status pesanan
+    pub enum OrderStatus {
+        Pending => "pending",
+        Paid => "paid",
+        Shipped => "shipped",
+        Completed => "completed",
+        Cancelled => "cancelled",
+    }
+}
+
+string_enum! {
+    /// Status pembayaran penjualan
+    pub enum PaymentStatus {
+        Paid => "paid",
+        Partial => "partial",
+        Unpaid => "unpaid",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::doc;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct Order {
+        status: OrderStatus,
+    }
+
+    #[test]
+    fn every_status_round_trips_as_lowercase_string() {
+        for status in OrderStatus::ALL {
+            let json = serde_json::to_value(status).unwrap();
+
+            assert_eq!(json, serde_json::Value::String(status.to_string()));
+            assert_eq!(
+                serde_json::from_value::<OrderStatus>(json).unwrap(),
+                *status
+            );
+        }
+        assert_eq!(
+            serde_json::to_string(&PaymentStatus::Partial).unwrap(),
+            "\"partial\""
+        );
+    }
+
+    #[test]
+    fn stored_document_round_trips() {
+        let order: Order = bson::from_document(doc! { "status": "shipped" }).unwrap();
+
+        assert_eq!(order.status, OrderStatus::Shipped);
+        assert_eq!(
+            bson::to_bson(&order.status).unwrap(),
+            bson::Bson::String("shipped".into())
+        );
+    }
+
+    #[test]
+    fn unknown_status_fails_loudly() {
+        let err = bson::from_document::<Order>(doc! { "status": "shiped" }).unwrap_err();
+        let message = err.to_string();
+
+        assert!(message.contains("'shiped' tidak dikenal untuk OrderStatus"));
+        assert!(message.contains("pending, paid, shipped, completed, cancelled"));
+        // Nilai harus persis lowercase, bukan case-insensitive
+        assert!(serde_json::from_str::<OrderStatus>("\"Paid\"").is_err());
+        assert!(serde_json::from_str::<OrderStatus>("1").is_err());
+    }
+}
@@ -0,0 +1,17 @@
+use bson::DateTime;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+/// Token yang sudah di-revoke, dihapus otomatis oleh TTL index setelah `expires_at`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RevokedToken {
+    pub jti: String,
+    pub expires_at: DateTime,
+}
+
+/// Body untuk endpoint yang menerima token aksi, contoh unlock akun
+#[derive(Debug, Deserialize, Validate)]
+pub struct ActionTokenDTO {
+    #[validate(length(min = 1, message = "Token wajib diisi"))]
+    pub token: String,
+}
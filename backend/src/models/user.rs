@@ -1,8 +1,16 @@
 use crate::utils::opt_object_id_as_string;
-use bson::oid::ObjectId;
+use bson::{DateTime, oid::ObjectId};
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
+pub const ROLE_USER: &str = "user";
+pub const ROLE_ADMIN: &str = "admin";
+
+/// Role paling rendah, dipakai untuk data lama yang belum punya field role
+pub fn default_role() -> String {
+    ROLE_USER.to_string()
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct User {
     #[serde(
@@ -16,6 +24,30 @@ pub struct User {
     pub email: String,
     pub password_hash: String,
     pub phone_number: Option<String>,
+
+    // Bentuk ternormalisasi untuk query dan unique index, `email`/`phone_number` tetap
+    // menyimpan input user. Data lama belum punya field ini.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email_normalized: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phone_number_normalized: Option<String>,
+
+    #[serde(default = "default_role")]
+    pub role: String,
+
+    // Organisasi pemilik akun, data lama belum punya field ini
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<ObjectId>,
+
+    // Login gagal berturut-turut sejak login sukses terakhir
+    #[serde(default)]
+    pub failed_attempts: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locked_until: Option<DateTime>,
+
+    // Token dengan `iat` sebelum waktu ini ditolak (ganti password / logout semua perangkat)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sessions_invalidated_at: Option<DateTime>,
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -42,6 +74,8 @@ pub struct CreateUserDTO {
     pub password: Option<String>,
 }
 
+/// Tanpa password, password hanya bisa diganti lewat `POST /users/{id}/password` yang
+/// memverifikasi password saat ini dan mencabut sesi lama
 #[derive(Debug, Deserialize, Validate)]
 pub struct UpdateUserDTO {
     #[validate(length(min = 3, message = "Username minimal 3 karakter"))]
@@ -52,9 +86,14 @@ pub struct UpdateUserDTO {
 
     #[validate(length(min = 10, message = "Nomor HP minimal 10 digit"))]
     pub phone_number: Option<String>,
+}
 
+#[derive(Debug, Deserialize, Validate)]
+pub struct ChangePasswordDTO {
+    #[validate(length(min = 1, message = "Password saat ini wajib diisi"))]
+    pub current_password: String,
     #[validate(length(min = 6, message = "Password minimal 6 karakter"))]
-    pub password: Option<String>,
+    pub new_password: String,
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -71,6 +110,7 @@ pub struct UserResponse {
     pub username: String,
     pub email: String,
     pub phone_number: Option<String>,
+    pub role: String,
 }
 
 impl From<User> for UserResponse {
@@ -83,6 +123,7 @@ impl From<User> for UserResponse {
             username: user.username,
             email: user.email,
             phone_number: user.phone_number,
+            role: user.role,
         }
     }
 }
@@ -95,6 +136,13 @@ impl From<RegisterDTO> for User {
             email: dto.email,
             password_hash: String::new(), // nanti diisi setelah hash password
             phone_number: dto.phone_number,
+            email_normalized: None,
+            phone_number_normalized: None,
+            role: default_role(),
+            org_id: None,
+            failed_attempts: 0,
+            locked_until: None,
+            sessions_invalidated_at: None,
         }
     }
 }
@@ -1,58 +1,117 @@
+use crate::db::handle::Db;
 use crate::{
     errors::ApiError,
+    extractors::{AuthUser, ValidatedJson},
+    models::session::SessionResponse,
+    models::token::ActionTokenDTO,
     models::user::{LoginDTO, RegisterDTO, UserResponse},
+    services::account_lockout::unlock_with_token,
     services::auth_service::{login_service, register_service},
-    utils::jwt::{Claims, create_auth_cookie, create_csrf_cookie, encode_jwt, generate_csrf_token},
+    services::mailer::Mailer,
+    services::rate_limiter::{LoginRateLimiter, login_attempt_key},
+    services::session_invalidation::SessionInvalidation,
+    services::session_store::{DeviceInfo, SessionStore},
+    services::token_blacklist::TokenBlacklist,
+    utils::cookie::ensure_secure_cookie_context,
+    utils::csrf::generate_csrf_token,
+    utils::fingerprint::{
+        binds_fingerprint, create_fingerprint_cookie, generate_fingerprint,
+        verify_fingerprint_with_claims,
+    },
+    utils::i18n::{Message, t},
+    utils::jwt::{
+        REFRESH_COOKIE_NAME, create_auth_cookie, create_csrf_cookie, create_refresh_cookie,
+        generate_access_token, generate_refresh_token, rotate_tokens, validate_refresh_token,
+    },
 };
 use actix_web::{
-    Error as ActixError, HttpResponse, Result,
-    web::{Data, Json},
+    HttpRequest, HttpResponse, Result,
+    web::{Data, Path},
 };
-use mongodb::Database;
 use serde_json::json;
-use validator::Validate;
 
-pub async fn login_handler(
-    payload: Result<Json<LoginDTO>, ActixError>,
-    db: Data<Database>,
-) -> Result<HttpResponse, ApiError> {
-    let data = payload?.into_inner();
-    data.validate()?;
-    let user = login_service(data, &db).await?;
-    let user_response: UserResponse = user.clone().into();
-    // Generate JWT & CSRF token
-    let claims = Claims {
-        sub: user.id.unwrap().to_hex(), // pastikan user.id ada
-        exp: (chrono::Utc::now() + chrono::Duration::hours(24)).timestamp() as usize,
-    };
-    let jwt_token = encode_jwt(&claims).map_err(|e| match e.kind() {
+fn map_jwt_error(e: jsonwebtoken::errors::Error) -> ApiError {
+    match e.kind() {
         jsonwebtoken::errors::ErrorKind::InvalidKeyFormat => {
             ApiError::InternalError("JWT key tidak valid".into())
         }
         _ => ApiError::InternalError(format!("JWT Error: {}", e)),
-    })?;
+    }
+}
+
+pub async fn login_handler(
+    req: HttpRequest,
+    ValidatedJson(data): ValidatedJson<LoginDTO>,
+    db: Data<Db>,
+    limiter: Data<LoginRateLimiter>,
+    mailer: Data<dyn Mailer>,
+) -> Result<HttpResponse, ApiError> {
+    // Cek sebelum kredensial diperiksa agar percobaan login tidak terbuang
+    ensure_secure_cookie_context(&req)?;
+    // Batasi brute-force per kombinasi IP + username, percobaan dicatat sebelum
+    // kredensial diperiksa dan dihapus lagi jika login berhasil
+    let attempt_key = login_attempt_key(&req, &data.username);
+    limiter.check_and_record(&attempt_key)?;
 
-    let csrf_token = generate_csrf_token();
+    let user = login_service(data, &db, mailer.get_ref()).await?;
+    limiter.reset_attempts(&attempt_key);
+    let user_response: UserResponse = user.clone().into();
+    // Generate JWT (access & refresh) & CSRF token
+    let user_id = user.id.unwrap().to_hex(); // pastikan user.id ada
+    // Token diikat ke fingerprint di cookie terpisah agar cookie token curian tidak cukup,
+    // kecuali untuk client bearer-only yang tidak mengirim cookie
+    let fingerprint = binds_fingerprint(&req).then(generate_fingerprint);
+    let org_id = user.org_id.map(|id| id.to_hex());
+    let access_token = generate_access_token(
+        &user_id,
+        &user.role,
+        org_id.as_deref(),
+        fingerprint.as_deref(),
+    )
+    .map_err(map_jwt_error)?;
+    let refresh_token = generate_refresh_token(
+        &user_id,
+        &user.role,
+        org_id.as_deref(),
+        fingerprint.as_deref(),
+    )
+    .map_err(map_jwt_error)?;
+
+    SessionStore::new(&db)
+        .create(
+            &user_id,
+            &access_token,
+            &refresh_token,
+            DeviceInfo::from_request(&req),
+        )
+        .await?;
 
-    // Buat cookie untuk auth & csrf
-    let auth_cookie = create_auth_cookie(&jwt_token);
+    // CSRF token terikat ke jti access token, jadi ikut berganti tiap sesi
+    let csrf_token = generate_csrf_token(&access_token.jti);
+
+    // Buat cookie untuk auth, refresh & csrf
+    let auth_cookie = create_auth_cookie(&access_token.token);
+    let refresh_cookie = create_refresh_cookie(&refresh_token.token);
     let csrf_cookie = create_csrf_cookie(&csrf_token);
-    Ok(HttpResponse::Ok()
+    let mut response = HttpResponse::Ok();
+    response
         .cookie(auth_cookie)
-        .cookie(csrf_cookie)
-        .json(json!({
-            "status": "success",
-            "data": user_response,
-            "code":200
-        })))
+        .cookie(refresh_cookie)
+        .cookie(csrf_cookie);
+    if let Some(fingerprint) = &fingerprint {
+        response.cookie(create_fingerprint_cookie(fingerprint));
+    }
+    Ok(response.json(json!({
+        "status": "success",
+        "data": user_response,
+        "code":200
+    })))
 }
 
 pub async fn register_handler(
-    payload: Result<Json<RegisterDTO>, ActixError>,
-    db: Data<Database>,
+    ValidatedJson(data): ValidatedJson<RegisterDTO>,
+    db: Data<Db>,
 ) -> Result<HttpResponse, ApiError> {
-    let data = payload?.into_inner();
-    data.validate()?;
     let user = register_service(data, &db).await?;
 
     Ok(HttpResponse::Created().json(json!({
@@ -61,3 +120,123 @@ pub async fn register_handler(
         "code": 201
     })))
 }
+
+pub async fn refresh_handler(req: HttpRequest, db: Data<Db>) -> Result<HttpResponse, ApiError> {
+    ensure_secure_cookie_context(&req)?;
+    let refresh_token = req
+        .cookie(REFRESH_COOKIE_NAME)
+        .map(|c| c.value().to_string())
+        .ok_or_else(|| ApiError::Unauthorized("Refresh token tidak ditemukan".into()))?;
+
+    let claims = validate_refresh_token(&refresh_token)?;
+    verify_fingerprint_with_claims(&req, &claims)?;
+    // Refresh token dari sebelum logout semua perangkat tidak boleh menerbitkan token baru
+    SessionInvalidation::new(&db)
+        .ensure_not_invalidated(&claims)
+        .await?;
+
+    // Refresh token sekali pakai: `jti` ditandai terpakai sebelum token baru dibuat agar
+    // dua request bersamaan dengan cookie yang sama tidak sama-sama mendapat token baru
+    let jti = claims
+        .jti
+        .as_deref()
+        .ok_or_else(|| ApiError::Unauthorized(t(Message::TokenInvalid)))?;
+    if !TokenBlacklist::new(&db).consume(jti, claims.exp).await? {
+        return Err(ApiError::Unauthorized(t(Message::TokenRevoked)));
+    }
+
+    let (access_token, refresh_token) = rotate_tokens(&refresh_token)?;
+    let csrf_token = generate_csrf_token(&access_token.jti);
+    SessionStore::new(&db)
+        .rotate(jti, &access_token, &refresh_token)
+        .await?;
+
+    Ok(HttpResponse::Ok()
+        .cookie(create_auth_cookie(&access_token.token))
+        .cookie(create_refresh_cookie(&refresh_token.token))
+        .cookie(create_csrf_cookie(&csrf_token))
+        .json(json!({
+            "status": "success",
+            "code": 200
+        })))
+}
+
+/// Access dan refresh token sesi ini langsung ditolak walaupun belum expired
+pub async fn logout_handler(req: HttpRequest, db: Data<Db>) -> Result<HttpResponse, ApiError> {
+    let logout_cookies = SessionStore::new(&db).logout(&req).await?;
+
+    let mut response = HttpResponse::Ok();
+    for cookie in logout_cookies {
+        response.cookie(cookie);
+    }
+    Ok(response.json(json!({
+        "status": "success",
+        "code": 200
+    })))
+}
+
+pub async fn list_sessions_handler(auth: AuthUser, db: Data<Db>) -> Result<HttpResponse, ApiError> {
+    let current_jti = auth.claims.jti.as_deref();
+    let sessions: Vec<SessionResponse> = SessionStore::new(&db)
+        .list_sessions(&auth.user_id)
+        .await?
+        .into_iter()
+        .map(|session| SessionResponse::from_session(session, current_jti))
+        .collect();
+
+    Ok(HttpResponse::Ok().json(json!({
+        "status": "success",
+        "data": sessions,
+        "code": 200
+    })))
+}
+
+/// Logout perangkat lain berdasarkan `jti` sesinya
+pub async fn revoke_session_handler(
+    auth: AuthUser,
+    jti: Path<String>,
+    db: Data<Db>,
+) -> Result<HttpResponse, ApiError> {
+    SessionStore::new(&db)
+        .revoke_session(&auth.user_id, &jti)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "status": "success",
+        "code": 200
+    })))
+}
+
+pub async fn revoke_other_sessions_handler(
+    auth: AuthUser,
+    db: Data<Db>,
+) -> Result<HttpResponse, ApiError> {
+    let current_jti = auth
+        .claims
+        .jti
+        .as_deref()
+        .ok_or_else(|| ApiError::Unauthorized("Token tidak memiliki jti".into()))?;
+    let revoked = SessionStore::new(&db)
+        .revoke_all_other_sessions(&auth.user_id, current_jti)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "status": "success",
+        "data": { "revoked": revoked },
+        "code": 200
+    })))
+}
+
+/// Buka kunci akun dari link unlock yang dikirim lewat email
+pub async fn unlock_account_handler(
+    ValidatedJson(data): ValidatedJson<ActionTokenDTO>,
+    db: Data<Db>,
+) -> Result<HttpResponse, ApiError> {
+    unlock_with_token(&data.token, &db).await?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "status": "success",
+        "data": "Akun berhasil dibuka",
+        "code": 200
+    })))
+}
@@ -1,11 +1,28 @@
 use actix_web::web;
 
-use super::handler::{login_handler, register_handler};
+use super::handler::{
+    list_sessions_handler, login_handler, logout_handler, refresh_handler, register_handler,
+    revoke_other_sessions_handler, revoke_session_handler, unlock_account_handler,
+};
+use crate::middlewares::auth_middleware::AuthMiddleware;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/auth")
             .route("/login", web::post().to(login_handler))
-            .route("/register", web::post().to(register_handler)),
+            .route("/register", web::post().to(register_handler))
+            .route("/refresh", web::post().to(refresh_handler))
+            .route("/logout", web::post().to(logout_handler))
+            .route("/unlock", web::post().to(unlock_account_handler))
+            .service(
+                web::scope("/sessions")
+                    .wrap(AuthMiddleware)
+                    .route("", web::get().to(list_sessions_handler))
+                    .route(
+                        "/revoke-others",
+                        web::post().to(revoke_other_sessions_handler),
+                    )
+                    .route("/{jti}", web::delete().to(revoke_session_handler)),
+            ),
     );
 }
@@ -0,0 +1,22 @@
+use crate::db::handle::Db;
+use crate::db::mongo::health_status;
+use actix_web::{HttpResponse, web::Data};
+use serde_json::json;
+
+pub async fn health_handler(db: Data<Db>) -> HttpResponse {
+    let status = health_status(db.client()).await;
+
+    if status.db_ok {
+        HttpResponse::Ok().json(json!({
+            "status": "success",
+            "data": status,
+            "code": 200
+        }))
+    } else {
+        HttpResponse::ServiceUnavailable().json(json!({
+            "status": "error",
+            "data": status,
+            "code": 503
+        }))
+    }
+}
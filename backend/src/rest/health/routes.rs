@@ -0,0 +1,7 @@
+use actix_web::web;
+
+use super::handler::health_handler;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.route("/health", web::get().to(health_handler));
+}
@@ -0,0 +1,9 @@
+use crate::middlewares::metrics_middleware::Metrics;
+use actix_web::{HttpResponse, web::Data};
+
+/// Metrics dalam format teks Prometheus
+pub async fn metrics_handler(metrics: Data<Metrics>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics.render_prometheus())
+}
@@ -0,0 +1,16 @@
+use actix_web::web;
+
+use super::handler::metrics_handler;
+use crate::middlewares::auth_middleware::AuthMiddleware;
+use crate::middlewares::role_middleware::RequireRole;
+use crate::models::user::ROLE_ADMIN;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    // Metrics membocorkan pola traffic, hanya untuk admin yang sudah login
+    cfg.service(
+        web::resource("/metrics")
+            .wrap(RequireRole(ROLE_ADMIN))
+            .wrap(AuthMiddleware)
+            .route(web::get().to(metrics_handler)),
+    );
+}
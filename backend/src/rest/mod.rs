@@ -1,5 +1,7 @@
 use actix_web::web;
 mod auth;
+mod health;
+mod metrics;
 mod products;
 mod users;
 mod sales;
@@ -7,6 +9,8 @@ mod sales;
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api")
+            .configure(health::routes::config)
+            .configure(metrics::routes::config)
             .configure(users::routes::config)
             .configure(auth::routes::config)
             .configure(products::routes::config)
@@ -1,24 +1,19 @@
 use actix_web::{
-    Error as ActixError, HttpRequest, HttpResponse, Result,
-    web::{Data, Json, Path},
+    HttpResponse, Result,
+    web::{Data, Path},
 };
 
+use crate::db::handle::Db;
 use crate::errors::ApiError;
+use crate::extractors::{AuthUser, ValidatedJson};
 use crate::models::product::{ProductDTO, ProductResponse, UpdateProductDTO};
 use crate::services::product_service::{
     create_product_service, delete_product_service, get_product_service, get_products_service,
     update_product_service,
 };
-use crate::utils::extract_user_id_from_cookie;
-use mongodb::Database;
-use validator::Validate;
 
-pub async fn get_products_handler(
-    req: HttpRequest,
-    db: Data<Database>,
-) -> Result<HttpResponse, ApiError> {
-    let user_id_str = extract_user_id_from_cookie(&req)?;
-    let products = get_products_service(&db, &user_id_str).await?;
+pub async fn get_products_handler(user: AuthUser, db: Data<Db>) -> Result<HttpResponse, ApiError> {
+    let products = get_products_service(&db, &user.user_id).await?;
 
     let products_response: Vec<ProductResponse> =
         products.into_iter().map(ProductResponse::from).collect();
@@ -31,13 +26,12 @@ pub async fn get_products_handler(
 }
 
 pub async fn get_product_handler(
-    req: HttpRequest,
+    user: AuthUser,
     path: Path<String>,
-    db: Data<Database>,
+    db: Data<Db>,
 ) -> Result<HttpResponse, ApiError> {
     let product_id = path.into_inner();
-    let user_id_str = extract_user_id_from_cookie(&req)?;
-    let product = get_product_service(&product_id, &db, &user_id_str).await?;
+    let product = get_product_service(&product_id, &db, &user.user_id).await?;
 
     let product_response: ProductResponse = product.into(); // konversi eksplisit dulu
 
@@ -48,15 +42,11 @@ pub async fn get_product_handler(
     })))
 }
 pub async fn post_product_handler(
-    req: HttpRequest,
-    payload: Result<Json<ProductDTO>, ActixError>,
-    db: Data<Database>,
+    user: AuthUser,
+    ValidatedJson(data): ValidatedJson<ProductDTO>,
+    db: Data<Db>,
 ) -> Result<HttpResponse, ApiError> {
-    let user_id_str = extract_user_id_from_cookie(&req)?;
-    let data = payload?.into_inner();
-    data.validate()?;
-
-    let product = create_product_service(data, &db, &user_id_str).await?;
+    let product = create_product_service(data, &db, &user.user_id).await?;
 
     Ok(HttpResponse::Created().json({
         serde_json::json!({
@@ -68,16 +58,13 @@ pub async fn post_product_handler(
 }
 
 pub async fn patch_product_handler(
-    req: HttpRequest,
-    payload: Result<Json<UpdateProductDTO>, ActixError>,
-    db: Data<Database>,
+    user: AuthUser,
+    ValidatedJson(data): ValidatedJson<UpdateProductDTO>,
+    db: Data<Db>,
     path: Path<String>,
 ) -> Result<HttpResponse, ApiError> {
     let product_id = path.into_inner();
-    let user_id_str = extract_user_id_from_cookie(&req)?;
-    let data = payload?.into_inner();
-    data.validate()?;
-    let product = update_product_service(&product_id, data, &db, &user_id_str).await?;
+    let product = update_product_service(&product_id, data, &db, &user.user_id).await?;
 
     Ok(HttpResponse::Ok().json({
         serde_json::json!({
@@ -89,13 +76,12 @@ pub async fn patch_product_handler(
 }
 
 pub async fn delete_product_handler(
-    req: HttpRequest,
-    db: Data<Database>,
+    user: AuthUser,
+    db: Data<Db>,
     path: Path<String>,
 ) -> Result<HttpResponse, ApiError> {
     let product_id = path.into_inner();
-    let user_id_str = extract_user_id_from_cookie(&req)?;
-    let _delete_product = delete_product_service(&product_id, &db, &user_id_str).await?;
+    let _delete_product = delete_product_service(&product_id, &db, &user.user_id).await?;
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "status": "success",
         "code": 204
@@ -1,35 +1,46 @@
-use crate::models::sale::{Sale, SaleItem, SaleDTO, SaleResponse};
+use crate::models::sale::{SaleDTO, SaleResponse};
 use actix_web::{
-    Error as ActixError, HttpRequest, HttpResponse, Result,
-    web::{Data, Json, Path},
+    Error as ActixError, HttpResponse, Result,
+    web::{Data, Json},
 };
 
-use crate::errors::ApiError;
-use crate::models::product::{ProductDTO, ProductResponse, UpdateProductDTO};
-use crate::services::sale_service::{create_sale_service, get_sales_service};
-use crate::utils::extract_user_id_from_cookie;
-use mongodb::Database;
+use crate::db::handle::Db;
+use crate::errors::{ApiError, ServiceError};
+use crate::extractors::{AuthUser, IdempotencyKey};
+use crate::services::idempotency_store::hash_request_body;
+use crate::services::sale_service::{create_sale_idempotent, create_sale_service};
 use validator::Validate;
 
-
-
 pub async fn post_sale_handler(
-    req: HttpRequest,
+    user: AuthUser,
+    idempotency_key: IdempotencyKey,
     payload: Result<Json<SaleDTO>, ActixError>,
-    db: Data<Database>,
+    db: Data<Db>,
 ) -> Result<HttpResponse, ApiError> {
-    println!("LOGG{:?}", &payload);
-    let user_id_str = extract_user_id_from_cookie(&req)?;
     let data = payload?.into_inner();
     data.validate()?;
 
-    let sale = create_sale_service(data, &db, &user_id_str).await?;
-
-    Ok(HttpResponse::Created().json({
-        serde_json::json!({
+    // Tanpa header, perilaku sama seperti sebelumnya
+    let Some(key) = idempotency_key.0 else {
+        let sale = create_sale_service(data, &db, &user.user_id).await?;
+        return Ok(HttpResponse::Created().json(serde_json::json!({
             "status" : "success",
             "data" : SaleResponse::from(sale),
             "code" : 201
-        })
-    }))
+        })));
+    };
+
+    // Body gagal di-serialize tidak boleh jatuh ke hash body kosong yang sama untuk semua request
+    let body = serde_json::to_vec(&data)
+        .map_err(|e| ServiceError::internal("Gagal serialize body untuk Idempotency-Key", e))?;
+    let request_hash = hash_request_body(&body);
+    let created = create_sale_idempotent(data, &db, &user.user_id, &key, &request_hash).await?;
+
+    Ok(HttpResponse::Created()
+        .insert_header(("Idempotent-Replayed", created.replayed.to_string()))
+        .json(serde_json::json!({
+            "status" : "success",
+            "data" : SaleResponse::from(created.document),
+            "code" : 201
+        })))
 }
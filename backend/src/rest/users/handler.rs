@@ -1,23 +1,22 @@
 use crate::errors::ApiError;
-use crate::models::user::{CreateUserDTO, UpdateUserDTO, UserResponse};
+use crate::extractors::{AuthUser, ObjectIdPath, ValidatedJson};
+use crate::models::user::{ChangePasswordDTO, CreateUserDTO, UpdateUserDTO, UserResponse};
 use crate::services::user_service::{
-    create_user_service, delete_user_service, get_user_service, get_users_service,
-    update_user_service,
-};
-use actix_web::{
-    Error as ActixError, HttpResponse, Result,
-    web::{Data, Json, Path},
+    change_password_service, create_user_service, delete_user_service, get_user_service,
+    get_users_service, update_user_service,
 };
+use crate::utils::ownership::{assert_admin, assert_owner, assert_owner_or_admin};
+use actix_web::{HttpResponse, Result, web::Data};
 
-use mongodb::Database;
-use validator::Validate;
+use crate::db::handle::Db;
 
 pub async fn get_user_handler(
-    path: Path<String>,
-    db: Data<Database>,
+    auth: AuthUser,
+    ObjectIdPath(user_id): ObjectIdPath,
+    db: Data<Db>,
 ) -> Result<HttpResponse, ApiError> {
-    let user_id = path.into_inner();
-    let user = get_user_service(&user_id, &db).await?;
+    assert_owner_or_admin(&user_id, &auth)?;
+    let user = get_user_service(user_id, &db).await?;
 
     let user_response: UserResponse = user.into(); // konversi eksplisit dulu
 
@@ -28,7 +27,8 @@ pub async fn get_user_handler(
     })))
 }
 
-pub async fn get_users_handler(db: Data<Database>) -> Result<HttpResponse, ApiError> {
+pub async fn get_users_handler(auth: AuthUser, db: Data<Db>) -> Result<HttpResponse, ApiError> {
+    assert_admin(&auth)?;
     let users = get_users_service(&db).await?;
 
     let users_response: Vec<UserResponse> = users.into_iter().map(UserResponse::from).collect();
@@ -41,11 +41,11 @@ pub async fn get_users_handler(db: Data<Database>) -> Result<HttpResponse, ApiEr
 }
 
 pub async fn post_user_handler(
-    payload: Result<Json<CreateUserDTO>, ActixError>,
-    db: Data<Database>,
+    auth: AuthUser,
+    ValidatedJson(data): ValidatedJson<CreateUserDTO>,
+    db: Data<Db>,
 ) -> Result<HttpResponse, ApiError> {
-    let data = payload?.into_inner();
-    data.validate()?;
+    assert_admin(&auth)?;
     let new_user = create_user_service(data, &db).await?;
     let user_response: UserResponse = new_user.into();
     Ok(HttpResponse::Created()
@@ -58,14 +58,12 @@ pub async fn post_user_handler(
 }
 
 pub async fn patch_user_handler(
-    path: Path<String>,
-    payload: Result<Json<UpdateUserDTO>, ActixError>,
-    db: Data<Database>,
+    auth: AuthUser,
+    ObjectIdPath(user_id): ObjectIdPath,
+    ValidatedJson(data): ValidatedJson<UpdateUserDTO>,
+    db: Data<Db>,
 ) -> Result<HttpResponse, ApiError> {
-    let user_id = path.into_inner();
-
-    let data = payload?.into_inner();
-    data.validate()?;
+    assert_owner_or_admin(&user_id, &auth)?;
     // Validasi semua field kosong atau berisi string kosong
     let no_fields = data
         .username
@@ -81,11 +79,6 @@ pub async fn patch_user_handler(
             .phone_number
             .as_ref()
             .map(|s| s.trim().is_empty())
-            .unwrap_or(true)
-        && data
-            .password
-            .as_ref()
-            .map(|s| s.trim().is_empty())
             .unwrap_or(true);
 
     if no_fields {
@@ -94,7 +87,7 @@ pub async fn patch_user_handler(
         ));
     }
 
-    let user = update_user_service(&user_id, data, &db).await?;
+    let user = update_user_service(user_id, data, &db).await?;
     let user_response: UserResponse = user.into();
 
     Ok(HttpResponse::Ok().json(serde_json::json!({
@@ -105,13 +98,30 @@ pub async fn patch_user_handler(
 }
 
 pub async fn delete_user_handler(
-    path: Path<String>,
-    db: Data<Database>,
+    auth: AuthUser,
+    ObjectIdPath(user_id): ObjectIdPath,
+    db: Data<Db>,
 ) -> Result<HttpResponse, ApiError> {
-    let user_id = path.into_inner();
-    let _delete_user = delete_user_service(&user_id, &db).await?;
+    assert_owner_or_admin(&user_id, &auth)?;
+    let _delete_user = delete_user_service(user_id, &db).await?;
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "status": "success",
         "code": 204
     })))
 }
+
+/// Hanya pemilik akun, admin pun harus tahu password saat ini
+pub async fn change_password_handler(
+    auth: AuthUser,
+    ObjectIdPath(user_id): ObjectIdPath,
+    ValidatedJson(data): ValidatedJson<ChangePasswordDTO>,
+    db: Data<Db>,
+) -> Result<HttpResponse, ApiError> {
+    assert_owner(&user_id, &auth.user_id)?;
+    change_password_service(user_id, data, &db).await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": "Password berhasil diganti",
+        "code": 200
+    })))
+}
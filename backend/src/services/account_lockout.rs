@@ -0,0 +1,319 @@
+use crate::config::{Config, config_error, parse_env};
+use crate::errors::ServiceError;
+use crate::models::user::User;
+use crate::services::mailer::Mailer;
+use crate::services::token_blacklist::TokenBlacklist;
+use crate::utils::action_token::{ActionPurpose, generate_action_token};
+use crate::utils::clock::{Clock, SystemClock};
+use crate::utils::jwt::IssuedToken;
+use crate::utils::{map_mongo_error, parse_object_id_param};
+use bson::DateTime as BsonDateTime;
+use chrono::Duration;
+use mongodb::{
+    Collection, Database,
+    bson::{doc, oid::ObjectId},
+    options::ReturnDocument,
+};
+use once_cell::sync::Lazy;
+
+pub const DEFAULT_LOCKOUT_THRESHOLD: u32 = 10;
+pub const DEFAULT_LOCKOUT_MINUTES: i64 = 30;
+
+/// Kunci akun setelah `threshold` login gagal berturut-turut. Berbeda dengan
+/// `LoginRateLimiter` yang per IP + username di memory, status ini disimpan di dokumen
+/// user sehingga berlaku lintas instance.
+#[derive(Debug, Clone)]
+pub struct AccountLockout {
+    pub threshold: u32,
+    pub duration: Duration,
+}
+
+impl Default for AccountLockout {
+    fn default() -> Self {
+        AccountLockout {
+            threshold: DEFAULT_LOCKOUT_THRESHOLD,
+            duration: Duration::minutes(DEFAULT_LOCKOUT_MINUTES),
+        }
+    }
+}
+
+/// Hasil `register_failed_login`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FailedLogin {
+    pub failed_attempts: u32,
+    // Diisi jika percobaan ini membuat akun terkunci
+    pub locked_until: Option<BsonDateTime>,
+}
+
+impl AccountLockout {
+    /// Baca dari `LOCKOUT_THRESHOLD` dan `LOCKOUT_MINUTES`, keduanya harus lebih dari 0
+    pub fn from_env() -> Result<Self, ServiceError> {
+        let threshold = parse_env("LOCKOUT_THRESHOLD", DEFAULT_LOCKOUT_THRESHOLD)?;
+        if threshold == 0 {
+            return Err(config_error("LOCKOUT_THRESHOLD harus lebih dari 0"));
+        }
+        let minutes = parse_env("LOCKOUT_MINUTES", DEFAULT_LOCKOUT_MINUTES)?;
+        if minutes <= 0 {
+            return Err(config_error("LOCKOUT_MINUTES harus lebih dari 0"));
+        }
+
+        Ok(AccountLockout {
+            threshold,
+            duration: Duration::minutes(minutes),
+        })
+    }
+
+    pub fn is_locked(&self, user: &User) -> bool {
+        self.is_locked_with(user, &SystemClock)
+    }
+
+    pub fn is_locked_with(&self, user: &User, clock: &impl Clock) -> bool {
+        user.locked_until
+            .is_some_and(|until| until.timestamp_millis() > clock.now().timestamp_millis())
+    }
+
+    /// `Forbidden` dengan sisa waktu kunci jika akun masih terkunci
+    pub fn ensure_not_locked(&self, user: &User) -> Result<(), ServiceError> {
+        self.ensure_not_locked_with(user, &SystemClock)
+    }
+
+    pub fn ensure_not_locked_with(
+        &self,
+        user: &User,
+        clock: &impl Clock,
+    ) -> Result<(), ServiceError> {
+        let Some(until) = user.locked_until else {
+            return Ok(());
+        };
+
+        let remaining_ms = until.timestamp_millis() - clock.now().timestamp_millis();
+        if remaining_ms <= 0 {
+            return Ok(());
+        }
+
+        // Dibulatkan ke atas agar tidak pernah tampil "0 menit"
+        let remaining_minutes = (remaining_ms + 59_999) / 60_000;
+        Err(ServiceError::Forbidden(format!(
+            "Akun terkunci karena terlalu banyak login gagal, coba lagi dalam {} menit",
+            remaining_minutes
+        )))
+    }
+
+    /// Tambah hitungan login gagal secara atomik dan kunci akun jika batas tercapai.
+    /// Hitungan direset saat akun dikunci agar periode berikutnya mulai dari nol.
+    pub async fn register_failed_login(
+        &self,
+        collection: &Collection<User>,
+        user_id: ObjectId,
+    ) -> Result<FailedLogin, ServiceError> {
+        let updated = collection
+            .find_one_and_update(
+                doc! { "_id": user_id },
+                doc! { "$inc": { "failed_attempts": 1 } },
+            )
+            .return_document(ReturnDocument::After)
+            .await
+            .map_err(map_mongo_error)?
+            .ok_or_else(|| ServiceError::NotFound("User tidak ditemukan".into()))?;
+
+        if updated.failed_attempts < self.threshold {
+            return Ok(FailedLogin {
+                failed_attempts: updated.failed_attempts,
+                locked_until: None,
+            });
+        }
+
+        let now = SystemClock.now();
+        let locked_until =
+            BsonDateTime::from_millis(now.timestamp_millis() + self.duration.num_milliseconds());
+        collection
+            .update_one(
+                doc! { "_id": user_id },
+                doc! { "$set": { "failed_attempts": 0, "locked_until": locked_until } },
+            )
+            .await
+            .map_err(map_mongo_error)?;
+
+        Ok(FailedLogin {
+            failed_attempts: updated.failed_attempts,
+            locked_until: Some(locked_until),
+        })
+    }
+}
+
+pub static ACCOUNT_LOCKOUT: Lazy<AccountLockout> = Lazy::new(|| Config::global().lockout.clone());
+
+/// Reset hitungan login gagal dan buka kunci akun
+pub async fn clear_lockout(
+    collection: &Collection<User>,
+    user_id: ObjectId,
+) -> Result<(), ServiceError> {
+    collection
+        .update_one(
+            doc! { "_id": user_id },
+            doc! {
+                "$set": { "failed_attempts": 0 },
+                "$unset": { "locked_until": "" },
+            },
+        )
+        .await
+        .map_err(map_mongo_error)?;
+
+    Ok(())
+}
+
+/// Token untuk link unlock yang dikirim ke email user
+pub fn issue_unlock_token(user_id: &ObjectId) -> Result<IssuedToken, ServiceError> {
+    generate_action_token(&user_id.to_hex(), ActionPurpose::Unlock)
+}
+
+/// Kirim link unlock ke email pemilik akun yang baru saja dikunci
+pub async fn send_unlock_email(
+    mailer: &dyn Mailer,
+    user_id: &ObjectId,
+    email: &str,
+) -> Result<(), ServiceError> {
+    let token = issue_unlock_token(user_id)?;
+    let body = format!(
+        "Akun Anda dikunci sementara karena terlalu banyak percobaan login gagal.\n\n\
+         Gunakan token berikut untuk membuka kunci akun:\n{}\n\n\
+         Abaikan email ini jika Anda tidak merasa mencoba login.",
+        token.token
+    );
+    mailer.send(email, "Buka kunci akun", &body).await
+}
+
+/// Verifikasi token unlock (sekali pakai) lalu buka kunci akun pemiliknya
+pub async fn unlock_with_token(token: &str, db: &Database) -> Result<(), ServiceError> {
+    let user_id = TokenBlacklist::new(db)
+        .consume_action_token(token, ActionPurpose::Unlock)
+        .await?;
+    let user_id = parse_object_id_param(&user_id)?;
+
+    clear_lockout(&db.collection("users"), user_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{init_test_config, test_database};
+    use crate::utils::clock::FixedClock;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn user(locked_until: Option<i64>) -> User {
+        User {
+            id: Some(ObjectId::new()),
+            username: "budi".into(),
+            email: "budi@mail.com".into(),
+            password_hash: String::new(),
+            phone_number: None,
+            email_normalized: None,
+            phone_number_normalized: None,
+            role: "user".into(),
+            org_id: None,
+            failed_attempts: 0,
+            locked_until: locked_until.map(|secs| BsonDateTime::from_millis(secs * 1000)),
+            sessions_invalidated_at: None,
+        }
+    }
+
+    fn lockout(threshold: u32) -> AccountLockout {
+        AccountLockout {
+            threshold,
+            duration: Duration::minutes(30),
+        }
+    }
+
+    #[test]
+    fn user_without_lock_is_allowed() {
+        let clock = FixedClock::from_unix(NOW);
+
+        assert!(!lockout(3).is_locked_with(&user(None), &clock));
+        assert!(
+            lockout(3)
+                .ensure_not_locked_with(&user(None), &clock)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn locked_user_is_forbidden_with_remaining_minutes() {
+        let clock = FixedClock::from_unix(NOW);
+        let locked = user(Some(NOW + 10 * 60 + 1));
+
+        assert!(lockout(3).is_locked_with(&locked, &clock));
+        assert!(matches!(
+            lockout(3).ensure_not_locked_with(&locked, &clock),
+            Err(ServiceError::Forbidden(msg)) if msg.contains("dalam 11 menit")
+        ));
+    }
+
+    #[test]
+    fn expired_lock_no_longer_applies() {
+        let clock = FixedClock::from_unix(NOW);
+        let expired = user(Some(NOW));
+
+        assert!(!lockout(3).is_locked_with(&expired, &clock));
+        assert!(lockout(3).ensure_not_locked_with(&expired, &clock).is_ok());
+    }
+
+    async fn stored_user(db: &Database) -> (Collection<User>, ObjectId) {
+        let users = db.collection::<User>("users");
+        // `_id` diserialisasi sebagai string, biarkan MongoDB yang membuatnya
+        let id = users
+            .insert_one(User {
+                id: None,
+                ..user(None)
+            })
+            .await
+            .unwrap()
+            .inserted_id
+            .as_object_id()
+            .unwrap();
+        (users, id)
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn crossing_threshold_locks_account() {
+        let (users, id) = stored_user(&test_database().await).await;
+        let lockout = lockout(3);
+
+        lockout.register_failed_login(&users, id).await.unwrap();
+        let second = lockout.register_failed_login(&users, id).await.unwrap();
+        let third = lockout.register_failed_login(&users, id).await.unwrap();
+
+        assert_eq!(second.locked_until, None);
+        assert_eq!(third.failed_attempts, 3);
+        assert!(third.locked_until.is_some());
+        let stored = users.find_one(doc! { "_id": id }).await.unwrap().unwrap();
+        assert_eq!(stored.failed_attempts, 0);
+        assert!(lockout.is_locked(&stored));
+        assert!(matches!(
+            lockout.ensure_not_locked(&stored),
+            Err(ServiceError::Forbidden(_))
+        ));
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn unlock_token_clears_lockout_once() {
+        init_test_config();
+        let db = test_database().await;
+        let (users, id) = stored_user(&db).await;
+        let lockout = lockout(1);
+        lockout.register_failed_login(&users, id).await.unwrap();
+        let token = issue_unlock_token(&id).unwrap();
+
+        unlock_with_token(&token.token, &db).await.unwrap();
+
+        let stored = users.find_one(doc! { "_id": id }).await.unwrap().unwrap();
+        assert!(!lockout.is_locked(&stored));
+        assert_eq!(stored.locked_until, None);
+        assert!(matches!(
+            unlock_with_token(&token.token, &db).await,
+            Err(ServiceError::Unauthorized(_))
+        ));
+    }
+}
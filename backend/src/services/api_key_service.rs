@@ -0,0 +1,74 @@
+use crate::errors::ServiceError;
+use crate::models::api_key::ApiKey;
+use crate::utils::api_key::{api_key_id, generate_api_key, verify_api_key};
+use crate::utils::{clock, map_mongo_error};
+use mongodb::{Collection, Database, IndexModel, bson::doc, options::IndexOptions};
+
+/// Unique index pada `key_id`, dipanggil sekali saat startup
+pub async fn ensure_api_key_indexes(db: &Database) -> Result<(), ServiceError> {
+    let collection: Collection<ApiKey> = db.collection("api_keys");
+    let key_id_index = IndexModel::builder()
+        .keys(doc! { "key_id": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+
+    collection
+        .create_index(key_id_index)
+        .await
+        .map_err(map_mongo_error)?;
+
+    Ok(())
+}
+
+fn invalid_key() -> ServiceError {
+    ServiceError::Unauthorized("API key tidak valid".into())
+}
+
+/// Buat API key baru, plaintext dikembalikan sekali untuk diberikan ke partner
+pub async fn create_api_key_service(
+    name: &str,
+    role: &str,
+    db: &Database,
+) -> Result<(String, ApiKey), ServiceError> {
+    let collection: Collection<ApiKey> = db.collection("api_keys");
+
+    let (plaintext, key_hash) = generate_api_key();
+    let key_id = api_key_id(&plaintext)
+        .ok_or_else(|| ServiceError::Unexpected("Format API key tidak valid".into()))?
+        .to_string();
+
+    let api_key = ApiKey {
+        id: None,
+        key_id,
+        key_hash,
+        name: name.to_string(),
+        role: role.to_string(),
+        created_at: Some(clock::now()),
+        revoked_at: None,
+    };
+
+    collection
+        .insert_one(&api_key)
+        .await
+        .map_err(map_mongo_error)?;
+
+    Ok((plaintext, api_key))
+}
+
+/// Cari API key aktif berdasarkan key id lalu verifikasi hash-nya
+pub async fn authenticate_api_key(presented: &str, db: &Database) -> Result<ApiKey, ServiceError> {
+    let key_id = api_key_id(presented).ok_or_else(invalid_key)?;
+    let collection: Collection<ApiKey> = db.collection("api_keys");
+
+    let api_key = collection
+        .find_one(doc! { "key_id": key_id, "revoked_at": null })
+        .await
+        .map_err(map_mongo_error)?
+        .ok_or_else(invalid_key)?;
+
+    if !verify_api_key(presented, &api_key.key_hash) {
+        return Err(invalid_key());
+    }
+
+    Ok(api_key)
+}
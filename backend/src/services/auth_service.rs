@@ -1,31 +1,96 @@
+use crate::db::helpers::find_one_ci;
 use crate::errors::ServiceError;
-use crate::models::user::{LoginDTO, RegisterDTO, User};
-use crate::utils::{
-    handle_duplicate_key_error, hash_password, verify_password,
+use crate::models::user::{LoginDTO, RegisterDTO, User, default_role};
+use crate::services::account_lockout::{ACCOUNT_LOCKOUT, clear_lockout, send_unlock_email};
+use crate::services::mailer::Mailer;
+use crate::services::user_service::map_user_write_error;
+use crate::utils::i18n::{Message, t};
+use crate::utils::normalize::NormalizedField;
+use crate::utils::request_context::log_with_context;
+use crate::utils::password::{
+    ARGON2_CONFIG, hash_password_async, validate_password_strength, verify_and_maybe_rehash,
+    verify_password_timing_safe,
 };
+use actix_web::web;
+use log::Level;
 use mongodb::{Collection, Database, bson::doc};
 
-pub async fn login_service(payload: LoginDTO, db: &Database) -> Result<User, ServiceError> {
+pub async fn login_service(
+    payload: LoginDTO,
+    db: &Database,
+    mailer: &dyn Mailer,
+) -> Result<User, ServiceError> {
     let collection: Collection<User> = db.collection("users");
 
-    let user = collection
-        .find_one(doc! { "username": &payload.username })
-        .await
-        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+    // Username dicocokkan tanpa membedakan huruf besar/kecil
+    let user = find_one_ci(&collection, "username", &payload.username).await?;
 
-    let user = match user {
+    let mut user = match user {
         Some(user) => user,
         None => {
-            return Err(ServiceError::Unauthorized(
-                "username atau password salah".into(),
-            ));
+            // Tetap jalankan Argon2 agar waktu respons sama dengan kasus password salah
+            let password = payload.password;
+            web::block(move || verify_password_timing_safe(&password, None))
+                .await
+                .map_err(|e| ServiceError::internal("Thread hashing gagal", e))?;
+            return Err(ServiceError::Unauthorized(t(Message::InvalidCredentials)));
         }
     };
 
-    if !verify_password(&payload.password, &user.password_hash) {
-        return Err(ServiceError::Unauthorized(
-            "username atau password salah".into(),
-        ));
+    // Akun terkunci ditolak sebelum password diverifikasi
+    ACCOUNT_LOCKOUT.ensure_not_locked(&user)?;
+
+    // Verifikasi (dan rehash) Argon2 dijalankan di thread pool blocking
+    let password = payload.password;
+    let stored_hash = user.password_hash.clone();
+    let verified = web::block(move || verify_and_maybe_rehash(&password, &stored_hash, &ARGON2_CONFIG))
+        .await
+        .map_err(|e| ServiceError::internal("Thread hashing gagal", e))?;
+
+    let rehashed = match verified {
+        Ok(rehashed) => rehashed,
+        Err(ServiceError::Unauthorized(msg)) => {
+            if let Some(user_id) = user.id {
+                let failed = ACCOUNT_LOCKOUT.register_failed_login(&collection, user_id).await?;
+                if failed.locked_until.is_some() {
+                    log_with_context(
+                        Level::Warn,
+                        &format!("Akun {} dikunci setelah {} login gagal", user_id, failed.failed_attempts),
+                    );
+                    // Gagal kirim email tidak mengubah respons login
+                    if let Err(e) = send_unlock_email(mailer, &user_id, &user.email).await {
+                        log_with_context(
+                            Level::Warn,
+                            &format!("Gagal mengirim email unlock akun {}: {}", user_id, e),
+                        );
+                    }
+                }
+            }
+            return Err(ServiceError::Unauthorized(msg));
+        }
+        Err(err) => return Err(err),
+    };
+
+    if (user.failed_attempts > 0 || user.locked_until.is_some())
+        && let Some(user_id) = user.id
+        && let Err(e) = clear_lockout(&collection, user_id).await
+    {
+        log_with_context(Level::Warn, &format!("Gagal mereset hitungan login gagal: {}", e));
+    }
+
+    // Perbarui hash jika parameter Argon2 yang tersimpan sudah usang
+    if let Some(new_hash) = rehashed {
+        let result = collection
+            .update_one(
+                doc! { "_id": user.id },
+                doc! { "$set": { "password_hash": &new_hash } },
+            )
+            .await;
+
+        if let Err(e) = result {
+            log_with_context(Level::Warn, &format!("Gagal memperbarui hash password: {}", e));
+        }
+        user.password_hash = new_hash;
     }
 
     Ok(user)
@@ -41,26 +106,36 @@ pub async fn register_service(payload: RegisterDTO, db: &Database) -> Result<Use
         password,
     } = payload;
 
-    let hashed_password = hash_password(&password)
-        .map_err(|e| ServiceError::HashingError(format!("Gagal hashing password: {}", e)))?;
+    let email = NormalizedField::email(&email)?;
+    let phone_number = phone_number
+        .as_deref()
+        .map(NormalizedField::phone)
+        .transpose()?;
+    let (phone_number, phone_number_normalized) = phone_number
+        .map(|phone| (phone.display, phone.normalized))
+        .unzip();
+    validate_password_strength(&password)?;
+
+    let hashed_password = hash_password_async(password).await?;
 
     let new_user = User {
         id: None,
         username,
-        email,
+        email: email.display,
         password_hash: hashed_password,
         phone_number,
+        email_normalized: Some(email.normalized),
+        phone_number_normalized,
+        role: default_role(),
+        org_id: None,
+        failed_attempts: 0,
+        locked_until: None,
+        sessions_invalidated_at: None,
     };
 
     let result = collection.insert_one(&new_user).await;
     match result {
         Ok(_) => Ok(new_user),
-        Err(err) => {
-            if let Some(conflict_error) = handle_duplicate_key_error(&err) {
-                return Err(conflict_error);
-            }
-
-            Err(ServiceError::DatabaseError(err.to_string()))
-        }
+        Err(err) => Err(map_user_write_error(err)),
     }
 }
@@ -0,0 +1,348 @@
+use crate::db::transaction::TransactionError;
+use crate::errors::ServiceError;
+use crate::models::idempotency::IdempotencyRecord;
+use crate::utils::{clock, is_duplicate_key_error, map_mongo_error};
+use bson::{DateTime as BsonDateTime, oid::ObjectId};
+use mongodb::{ClientSession, Collection, Database, IndexModel, bson::doc, options::IndexOptions};
+use serde::{Serialize, de::DeserializeOwned};
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::time::Duration;
+
+/// Lama key disimpan, retry setelah ini akan membuat dokumen baru
+pub const IDEMPOTENCY_TTL_HOURS: i64 = 24;
+
+/// Hasil `IdempotencyStore::create_idempotent`
+#[derive(Debug, Clone)]
+pub struct IdempotentCreate<T> {
+    pub document: T,
+    // `true` jika dokumen sudah dibuat oleh request sebelumnya dengan key yang sama
+    pub replayed: bool,
+}
+
+/// SHA-256 hex dari body request, dipakai untuk mendeteksi key yang dipakai ulang
+/// dengan body berbeda
+pub fn hash_request_body(body: &[u8]) -> String {
+    hex::encode(Sha256::digest(body))
+}
+
+#[derive(Clone)]
+pub struct IdempotencyStore {
+    collection: Collection<IdempotencyRecord>,
+}
+
+impl IdempotencyStore {
+    pub fn new(db: &Database) -> Self {
+        IdempotencyStore {
+            collection: db.collection("idempotency_keys"),
+        }
+    }
+
+    /// Unique index `(user_id, key)` dan TTL index di `expires_at`
+    pub async fn ensure_indexes(&self) -> Result<(), ServiceError> {
+        let key_index = IndexModel::builder()
+            .keys(doc! { "user_id": 1, "key": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        let ttl_index = IndexModel::builder()
+            .keys(doc! { "expires_at": 1 })
+            .options(
+                IndexOptions::builder()
+                    .expire_after(Duration::from_secs(0))
+                    .build(),
+            )
+            .build();
+
+        self.collection
+            .create_indexes([key_index, ttl_index])
+            .await
+            .map_err(map_mongo_error)?;
+
+        Ok(())
+    }
+
+    /// Cek dan simpan key di transaksi yang sama dengan insert dokumen baru, dipanggil di
+    /// dalam `with_transaction`. Jika key sudah ada, dokumen yang dibuat sebelumnya
+    /// dikembalikan dengan `replayed: true`. Dua request bersamaan dengan key yang sama
+    /// menulis record yang sama sehingga salah satunya kena write conflict atau duplicate
+    /// key, diulang oleh `with_transaction` dan di percobaan berikutnya mendapat dokumen
+    /// yang sudah dibuat.
+    /// `build_doc` hanya dipanggil jika key belum ada, sehingga retry dengan key yang sama
+    /// tidak mengulang validasi/lookup yang bisa sudah berubah hasilnya. Error dari
+    /// `build_doc` membatalkan transaksi. `_id` dokumen diisi oleh MongoDB.
+    pub async fn create_idempotent<T, F, Fut>(
+        &self,
+        session: &mut ClientSession,
+        target: &Collection<T>,
+        user_id: &str,
+        key: &str,
+        request_hash: &str,
+        build_doc: F,
+    ) -> Result<IdempotentCreate<T>, TransactionError>
+    where
+        T: Serialize + DeserializeOwned + Send + Sync,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, ServiceError>>,
+    {
+        let existing = self
+            .collection
+            .find_one(doc! { "user_id": user_id, "key": key })
+            .session(&mut *session)
+            .await?;
+
+        if let Some(existing) = existing {
+            if existing.request_hash != request_hash {
+                return Err(ServiceError::BadRequest(
+                    "Idempotency-Key sudah dipakai untuk request yang berbeda".into(),
+                )
+                .into());
+            }
+            let document = target
+                .find_one(doc! { "_id": existing.resource_id })
+                .session(&mut *session)
+                .await?
+                .ok_or_else(|| {
+                    ServiceError::NotFound("Data untuk Idempotency-Key ini sudah dihapus".into())
+                })?;
+            return Ok(IdempotentCreate {
+                document,
+                replayed: true,
+            });
+        }
+
+        let document = build_doc().await?;
+        let inserted = target.insert_one(document).session(&mut *session).await?;
+        let resource_id = inserted
+            .inserted_id
+            .as_object_id()
+            .ok_or_else(|| ServiceError::Unexpected("_id dokumen baru bukan ObjectId".into()))?;
+
+        let record = new_record(user_id, key, request_hash, resource_id);
+        match self
+            .collection
+            .insert_one(&record)
+            .session(&mut *session)
+            .await
+        {
+            Ok(_) => {}
+            // Transaksi lain sudah commit key yang sama setelah snapshot ini dibaca, ulangi
+            // transaksi agar dokumen yang sudah dibuat itu dikembalikan
+            Err(err) if is_duplicate_key_error(&err) => return Err(TransactionError::Retry),
+            Err(err) => return Err(err.into()),
+        }
+
+        // Dibaca ulang agar hasilnya sama persis dengan yang didapat saat replay
+        let document = target
+            .find_one(doc! { "_id": resource_id })
+            .session(&mut *session)
+            .await?
+            .ok_or_else(|| ServiceError::Unexpected("Dokumen baru tidak ditemukan".into()))?;
+
+        Ok(IdempotentCreate {
+            document,
+            replayed: false,
+        })
+    }
+}
+
+fn new_record(
+    user_id: &str,
+    key: &str,
+    request_hash: &str,
+    resource_id: ObjectId,
+) -> IdempotencyRecord {
+    let now = clock::now();
+    IdempotencyRecord {
+        id: None,
+        user_id: user_id.to_string(),
+        key: key.to_string(),
+        request_hash: request_hash.to_string(),
+        resource_id,
+        created_at: now,
+        expires_at: BsonDateTime::from_millis(
+            now.timestamp_millis() + IDEMPOTENCY_TTL_HOURS * 3600 * 1000,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::transaction::with_transaction;
+    use crate::testing::test_database;
+    use bson::Document;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Fixture {
+        db: Database,
+        store: IdempotencyStore,
+        orders: Collection<Document>,
+        // Berapa kali `build_doc` benar-benar dijalankan
+        executed: Arc<AtomicUsize>,
+    }
+
+    impl Fixture {
+        async fn new() -> Self {
+            let db = test_database().await;
+            db.create_collection("orders").await.unwrap();
+            let store = IdempotencyStore::new(&db);
+            store.ensure_indexes().await.unwrap();
+            Fixture {
+                orders: db.collection("orders"),
+                store,
+                db,
+                executed: Arc::default(),
+            }
+        }
+
+        async fn create(
+            &self,
+            user_id: &str,
+            key: &str,
+            body: &str,
+        ) -> Result<IdempotentCreate<Document>, ServiceError> {
+            self.create_with(user_id, key, body, false).await
+        }
+
+        // `build_fails` mensimulasikan validasi yang gagal, contoh produk sudah dihapus
+        async fn create_with(
+            &self,
+            user_id: &str,
+            key: &str,
+            body: &str,
+            build_fails: bool,
+        ) -> Result<IdempotentCreate<Document>, ServiceError> {
+            let request_hash = hash_request_body(body.as_bytes());
+            with_transaction(self.db.client(), |session| {
+                let (store, orders) = (self.store.clone(), self.orders.clone());
+                let (user_id, key, request_hash) =
+                    (user_id.to_string(), key.to_string(), request_hash.clone());
+                let executed = self.executed.clone();
+                let body = body.to_string();
+                Box::pin(async move {
+                    store
+                        .create_idempotent(
+                            session,
+                            &orders,
+                            &user_id,
+                            &key,
+                            &request_hash,
+                            || async {
+                                executed.fetch_add(1, Ordering::SeqCst);
+                                if build_fails {
+                                    return Err(ServiceError::BadRequest(
+                                        "Produk tidak ditemukan".into(),
+                                    ));
+                                }
+                                Ok(doc! { "body": body })
+                            },
+                        )
+                        .await
+                })
+            })
+            .await
+        }
+    }
+
+    #[test]
+    fn request_hash_depends_on_body() {
+        assert_eq!(hash_request_body(b"{}"), hash_request_body(b"{}"));
+        assert_ne!(hash_request_body(b"{}"), hash_request_body(b"{ }"));
+        assert_eq!(hash_request_body(b"").len(), 64);
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB replica set"]
+    async fn repeat_with_same_key_returns_cached_document() {
+        let fixture = Fixture::new().await;
+
+        let first = fixture.create("user-1", "order-1", "{}").await.unwrap();
+        let repeat = fixture.create("user-1", "order-1", "{}").await.unwrap();
+
+        assert!(!first.replayed);
+        assert!(repeat.replayed);
+        assert_eq!(first.document, repeat.document);
+        assert_eq!(fixture.executed.load(Ordering::SeqCst), 1);
+        assert_eq!(fixture.orders.count_documents(doc! {}).await.unwrap(), 1);
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB replica set"]
+    async fn different_key_or_user_executes_again() {
+        let fixture = Fixture::new().await;
+
+        let first = fixture.create("user-1", "order-1", "{}").await.unwrap();
+        let other_key = fixture.create("user-1", "order-2", "{}").await.unwrap();
+        let other_user = fixture.create("user-2", "order-1", "{}").await.unwrap();
+
+        assert!(!other_key.replayed && !other_user.replayed);
+        assert_ne!(first.document.get("_id"), other_key.document.get("_id"));
+        assert_eq!(fixture.executed.load(Ordering::SeqCst), 3);
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB replica set"]
+    async fn same_key_with_different_body_is_rejected() {
+        let fixture = Fixture::new().await;
+        fixture.create("user-1", "order-1", "{}").await.unwrap();
+
+        let result = fixture.create("user-1", "order-1", r#"{"qty":2}"#).await;
+
+        assert!(matches!(result, Err(ServiceError::BadRequest(_))));
+        assert_eq!(fixture.executed.load(Ordering::SeqCst), 1);
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB replica set"]
+    async fn concurrent_calls_with_same_key_create_one_order() {
+        let fixture = Fixture::new().await;
+
+        let (first, second) = futures::join!(
+            fixture.create("user-1", "order-1", "{}"),
+            fixture.create("user-1", "order-1", "{}")
+        );
+
+        let (first, second) = (first.unwrap(), second.unwrap());
+        assert_eq!(first.document, second.document);
+        assert!(first.replayed != second.replayed);
+        assert_eq!(fixture.orders.count_documents(doc! {}).await.unwrap(), 1);
+        let records = fixture
+            .store
+            .collection
+            .count_documents(doc! { "user_id": "user-1", "key": "order-1" })
+            .await
+            .unwrap();
+        assert_eq!(records, 1);
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB replica set"]
+    async fn replay_does_not_rebuild_document() {
+        let fixture = Fixture::new().await;
+        let first = fixture.create("user-1", "order-1", "{}").await.unwrap();
+
+        // Builder akan gagal jika dijalankan, replay tetap mengembalikan dokumen tersimpan
+        let repeat = fixture
+            .create_with("user-1", "order-1", "{}", true)
+            .await
+            .unwrap();
+
+        assert!(repeat.replayed);
+        assert_eq!(first.document, repeat.document);
+        assert_eq!(fixture.executed.load(Ordering::SeqCst), 1);
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB replica set"]
+    async fn failed_build_does_not_store_key() {
+        let fixture = Fixture::new().await;
+
+        let failed = fixture.create_with("user-1", "order-1", "{}", true).await;
+        let retried = fixture.create("user-1", "order-1", "{}").await.unwrap();
+
+        assert!(matches!(failed, Err(ServiceError::BadRequest(_))));
+        assert!(!retried.replayed);
+        assert_eq!(fixture.orders.count_documents(doc! {}).await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,267 @@
+use crate::errors::ServiceError;
+use crate::services::rate_limiter::{RateLimitConfig, prune};
+use futures::future::BoxFuture;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_MAIL_MAX_PER_RECIPIENT: usize = 3;
+pub const DEFAULT_MAIL_WINDOW_SECS: u64 = 15 * 60;
+
+/// Pengirim email. Flow (reset, verifikasi, unlock) memakai `&dyn Mailer` dari
+/// `web::Data<dyn Mailer>` sehingga test bisa memasang `MockMailer`.
+pub trait Mailer: Send + Sync {
+    fn send<'a>(
+        &'a self,
+        to: &'a str,
+        subject: &'a str,
+        body: &'a str,
+    ) -> BoxFuture<'a, Result<(), ServiceError>>;
+}
+
+/// Email yang dicatat `MockMailer`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentMail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Mailer untuk test, tidak mengirim apa pun dan hanya mencatat email yang dikirim
+#[derive(Debug, Default)]
+pub struct MockMailer {
+    sent: Mutex<Vec<SentMail>>,
+}
+
+impl MockMailer {
+    pub fn new() -> Self {
+        MockMailer::default()
+    }
+
+    pub fn sent(&self) -> Vec<SentMail> {
+        self.sent.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+impl Mailer for MockMailer {
+    fn send<'a>(
+        &'a self,
+        to: &'a str,
+        subject: &'a str,
+        body: &'a str,
+    ) -> BoxFuture<'a, Result<(), ServiceError>> {
+        self.sent
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(SentMail {
+                to: to.to_string(),
+                subject: subject.to_string(),
+                body: body.to_string(),
+            });
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Dipakai jika SMTP belum dikonfigurasi. Body tidak ikut di-log karena bisa berisi token.
+#[derive(Debug, Default)]
+pub struct LogMailer;
+
+impl Mailer for LogMailer {
+    fn send<'a>(
+        &'a self,
+        to: &'a str,
+        subject: &'a str,
+        _body: &'a str,
+    ) -> BoxFuture<'a, Result<(), ServiceError>> {
+        log::warn!(
+            "SMTP belum dikonfigurasi, email '{}' ke {} tidak dikirim",
+            subject,
+            to
+        );
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Batas email per penerima dari `MAIL_MAX_PER_RECIPIENT` dan `MAIL_WINDOW_SECS`
+pub fn mail_limit_from_env() -> Result<RateLimitConfig, ServiceError> {
+    RateLimitConfig::from_env(
+        "MAIL_MAX_PER_RECIPIENT",
+        DEFAULT_MAIL_MAX_PER_RECIPIENT,
+        "MAIL_WINDOW_SECS",
+        DEFAULT_MAIL_WINDOW_SECS,
+    )
+}
+
+/// Batasi jumlah email per penerima dengan sliding window di memory, agar request reset
+/// berulang tidak membanjiri satu mailbox. Pengiriman yang gagal tetap dihitung.
+pub struct RateLimitedMailer<M> {
+    inner: M,
+    max_per_recipient: usize,
+    window: Duration,
+    sent: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl<M: Mailer> RateLimitedMailer<M> {
+    pub fn new(inner: M, max_per_recipient: usize, window: Duration) -> Self {
+        RateLimitedMailer {
+            inner,
+            max_per_recipient,
+            window,
+            sent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Mailer dengan batas dari `Config.mail_limit`
+    pub fn from_config(inner: M, config: &RateLimitConfig) -> Self {
+        RateLimitedMailer::new(inner, config.max, config.window)
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.inner
+    }
+
+    /// Cek batas lalu catat pengiriman untuk `to`
+    fn acquire(&self, to: &str, now: Instant) -> Result<(), ServiceError> {
+        let mut map = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+        let sent = map.entry(to.trim().to_lowercase()).or_default();
+        prune(sent, now, self.window);
+
+        if sent.len() >= self.max_per_recipient {
+            let oldest = sent.front().copied().unwrap_or(now);
+            let retry_after_secs = self
+                .window
+                .saturating_sub(now.duration_since(oldest))
+                .as_secs()
+                .max(1);
+            return Err(ServiceError::TooManyRequests {
+                message: format!(
+                    "Terlalu banyak email ke alamat ini, coba lagi dalam {} detik",
+                    retry_after_secs
+                ),
+                retry_after_secs,
+            });
+        }
+
+        sent.push_back(now);
+        Ok(())
+    }
+}
+
+impl<M: Mailer> Mailer for RateLimitedMailer<M> {
+    fn send<'a>(
+        &'a self,
+        to: &'a str,
+        subject: &'a str,
+        body: &'a str,
+    ) -> BoxFuture<'a, Result<(), ServiceError>> {
+        if let Err(err) = self.acquire(to, Instant::now()) {
+            return Box::pin(async { Err(err) });
+        }
+        self.inner.send(to, subject, body)
+    }
+}
+
+/// Mailer aplikasi: SMTP jika feature `smtp` aktif dan `SMTP_HOST` di-set, selain itu
+/// `LogMailer`. Keduanya dibungkus `RateLimitedMailer` dengan batas `limit`.
+pub fn mailer_from_env(limit: &RateLimitConfig) -> Result<Arc<dyn Mailer>, ServiceError> {
+    #[cfg(feature = "smtp")]
+    if let Some(config) = crate::utils::smtp::SmtpConfig::from_env()? {
+        let smtp = crate::utils::smtp::SmtpMailer::new(config);
+        return Ok(Arc::new(RateLimitedMailer::from_config(smtp, limit)));
+    }
+
+    Ok(Arc::new(RateLimitedMailer::from_config(LogMailer, limit)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::{TestRequest, call_service, init_service};
+    use actix_web::web::Data;
+    use actix_web::{App, HttpResponse, web};
+
+    fn limited(max: usize) -> RateLimitedMailer<MockMailer> {
+        RateLimitedMailer::new(MockMailer::new(), max, Duration::from_secs(60))
+    }
+
+    #[actix_web::test]
+    async fn mock_records_sent_messages() {
+        let mailer = MockMailer::new();
+
+        mailer
+            .send("budi@mail.com", "Reset password", "Kode: 123456")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            mailer.sent(),
+            vec![SentMail {
+                to: "budi@mail.com".into(),
+                subject: "Reset password".into(),
+                body: "Kode: 123456".into(),
+            }]
+        );
+    }
+
+    #[actix_web::test]
+    async fn rapid_second_send_is_blocked() {
+        let mailer = limited(1);
+
+        mailer.send("budi@mail.com", "Reset", "1").await.unwrap();
+        let second = mailer.send(" Budi@Mail.com ", "Reset", "2").await;
+
+        assert!(matches!(
+            second,
+            Err(ServiceError::TooManyRequests { retry_after_secs, .. }) if retry_after_secs <= 60
+        ));
+        assert_eq!(mailer.inner().sent().len(), 1);
+        // Penerima lain punya kuota sendiri
+        mailer.send("sari@mail.com", "Reset", "3").await.unwrap();
+        assert_eq!(mailer.inner().sent().len(), 2);
+    }
+
+    #[test]
+    fn quota_returns_after_window() {
+        let mailer = limited(2);
+        let start = Instant::now();
+
+        assert!(mailer.acquire("budi@mail.com", start).is_ok());
+        assert!(mailer.acquire("budi@mail.com", start).is_ok());
+        assert!(
+            mailer
+                .acquire("budi@mail.com", start + Duration::from_secs(59))
+                .is_err()
+        );
+        assert!(
+            mailer
+                .acquire("budi@mail.com", start + Duration::from_secs(61))
+                .is_ok()
+        );
+    }
+
+    #[actix_web::test]
+    async fn handlers_receive_injected_mailer() {
+        async fn send_reset(mailer: Data<dyn Mailer>) -> HttpResponse {
+            mailer
+                .send("budi@mail.com", "Reset password", "link")
+                .await
+                .unwrap();
+            HttpResponse::Ok().finish()
+        }
+
+        let mock = Arc::new(MockMailer::new());
+        let mailer: Data<dyn Mailer> = Data::from(mock.clone() as Arc<dyn Mailer>);
+        let app = init_service(
+            App::new()
+                .app_data(mailer)
+                .route("/reset", web::post().to(send_reset)),
+        )
+        .await;
+
+        let req = TestRequest::post().uri("/reset").to_request();
+        call_service(&app, req).await;
+
+        assert_eq!(mock.sent().len(), 1);
+        assert_eq!(mock.sent()[0].subject, "Reset password");
+    }
+}
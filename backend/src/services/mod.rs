@@ -1,4 +1,12 @@
+pub mod account_lockout;
+pub mod api_key_service;
 pub mod auth_service;
+pub mod idempotency_store;
+pub mod mailer;
 pub mod product_service;
+pub mod rate_limiter;
 pub mod user_service;
 pub mod sale_service;
+pub mod session_invalidation;
+pub mod session_store;
+pub mod token_blacklist;
@@ -1,34 +1,43 @@
+use crate::db::cursor::collect_lenient;
+use crate::db::helpers::{
+    OWNED_IMMUTABLE_FIELDS, delete_one_checked, ensure_unique, find_one_or_not_found,
+    strip_immutable, update_one_checked,
+};
+use crate::db::patch::build_set_update;
 use crate::errors::ServiceError;
 use crate::models::product::{Product, ProductDTO, UpdateProductDTO};
-use crate::utils::{generate_random_sku, handle_duplicate_key_error, string_id_to_obj_id};
-use bson::datetime::DateTime as BsonDateTime;
-
-use bson::oid::ObjectId;
-use chrono::Utc;
-use futures::stream::TryStreamExt;
-use mongodb::{Collection, Database, bson::doc};
+use crate::utils::clock;
+use crate::utils::request_context::log_with_context;
+use crate::utils::sku::{generate_unique_sku, normalize_sku};
+use crate::utils::slug::generate_unique_slug;
+use crate::utils::{map_mongo_error, parse_object_id_param};
+use log::Level;
+use mongodb::{
+    Collection, Database,
+    bson::{doc, oid::ObjectId},
+};
 
 pub async fn get_products_service(db: &Database, id: &str) -> Result<Vec<Product>, ServiceError> {
-    let user_id = match string_id_to_obj_id(id) {
-        Some(oid) => oid,
-        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
-    };
+    let user_id = parse_object_id_param(id)?;
 
     let collection: Collection<Product> = db.collection("products");
 
-    let mut cursor = collection
+    let cursor = collection
         .find(doc! {"user_id": user_id})
         .await
-        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
-
-    let mut products: Vec<Product> = Vec::new();
-
-    while let Some(product) = cursor
-        .try_next()
-        .await
-        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
-    {
-        products.push(product);
+        .map_err(map_mongo_error)?;
+
+    // Satu dokumen rusak (mis. hasil import) tidak boleh menggagalkan seluruh daftar
+    let (products, skipped) = collect_lenient(cursor).await?;
+    for error in &skipped {
+        log_with_context(
+            Level::Warn,
+            &format!(
+                "Produk {} dilewati karena tidak sesuai format: {}",
+                error.id.as_deref().unwrap_or("-"),
+                error.message
+            ),
+        );
     }
 
     Ok(products)
@@ -39,15 +48,9 @@ pub async fn get_product_service(
     db: &Database,
     user_id: &str,
 ) -> Result<Product, ServiceError> {
-    let user_id = match string_id_to_obj_id(user_id) {
-        Some(oid) => oid,
-        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
-    };
+    let user_id = parse_object_id_param(user_id)?;
 
-    let product_id = match string_id_to_obj_id(product_id) {
-        Some(oid) => oid,
-        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
-    };
+    let product_id = parse_object_id_param(product_id)?;
 
     let collection: Collection<Product> = db.collection("products");
 
@@ -57,17 +60,7 @@ pub async fn get_product_service(
         "user_id": user_id,
     };
 
-    let product = collection
-        .find_one(filter)
-        .await
-        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
-
-    product.ok_or_else(|| {
-        ServiceError::NotFound(format!(
-            "Product dengan ID '{}' tidak ditemukan",
-            product_id
-        ))
-    })
+    find_one_or_not_found(&collection, filter, "Produk").await
 }
 
 pub async fn create_product_service(
@@ -75,33 +68,34 @@ pub async fn create_product_service(
     db: &Database,
     id: &str,
 ) -> Result<Product, ServiceError> {
-    let user_id = match string_id_to_obj_id(id) {
-        Some(oid) => oid,
-        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
-    };
+    let user_id = parse_object_id_param(id)?;
 
     let collection: Collection<Product> = db.collection("products");
 
     // Pakai SKU dari input, atau generate otomatis jika kosong
 
     let final_sku = match &payload.sku {
-        Some(sku) if !sku.trim().is_empty() => sku.clone(),
-        _ => generate_random_sku(),
+        Some(sku) if !sku.trim().is_empty() => {
+            let sku = normalize_sku(sku)?;
+            ensure_unique(&collection, doc! { "user_id": user_id, "sku": &sku }).await?;
+            sku
+        }
+        _ => generate_unique_sku(&collection).await?,
     };
 
-    let now = BsonDateTime::from_chrono(Utc::now());
+    let slug = generate_unique_slug(&collection, &payload.name).await?;
+    let now = clock::now();
 
     // Buat produk baru (sementara id None dulu)
     let mut product = Product {
         id: None,
-        user_id: user_id,
+        user_id,
         name: payload.name,
         sku: final_sku,
+        slug: Some(slug),
         price: payload.price,
         stock: payload.stock,
-        category_id: payload
-            .category_id
-            .and_then(|id| ObjectId::parse_str(&id).ok()),
+        category_id: payload.category_id,
         created_at: Some(now),
         updated_at: Some(now),
     };
@@ -117,12 +111,7 @@ pub async fn create_product_service(
                 .map(|oid| oid.to_owned());
             Ok(product)
         }
-        Err(e) => {
-            if let Some(err) = handle_duplicate_key_error(&e) {
-                return Err(err);
-            }
-            Err(ServiceError::DatabaseError(e.to_string()))
-        }
+        Err(e) => Err(map_mongo_error(e)),
     }
 }
 
@@ -132,15 +121,11 @@ pub async fn update_product_service(
     db: &Database,
     user_id: &str,
 ) -> Result<Product, ServiceError> {
-    let product_id = match string_id_to_obj_id(product_id) {
-        Some(oid) => oid,
-        None => return Err(ServiceError::InvalidId("Invalid product ID".into())),
-    };
+    let product_id = parse_object_id_param(product_id)?;
 
-    let user_id = match string_id_to_obj_id(user_id) {
-        Some(oid) => oid,
-        None => return Err(ServiceError::InvalidId("Invalid user ID".into())),
-    };
+    let user_id = parse_object_id_param(user_id)?;
+
+    let collection: Collection<Product> = db.collection("products");
 
     let mut update_doc = doc! {};
 
@@ -148,6 +133,13 @@ pub async fn update_product_service(
         update_doc.insert("name", name);
     }
     if let Some(sku) = payload.sku {
+        let sku = normalize_sku(&sku)?;
+        // Produk lain milik user yang sama tidak boleh memakai SKU ini
+        ensure_unique(
+            &collection,
+            doc! { "_id": { "$ne": product_id }, "user_id": user_id, "sku": &sku },
+        )
+        .await?;
         update_doc.insert("sku", sku);
     }
     if let Some(price) = payload.price {
@@ -166,9 +158,9 @@ pub async fn update_product_service(
         ));
     }
 
-    update_doc.insert("updated_at", BsonDateTime::from_chrono(Utc::now()));
-
-    let collection: Collection<Product> = db.collection("products");
+    update_doc.extend(clock::touch_updated_at());
+    let mut update = build_set_update(update_doc);
+    strip_immutable(&mut update, &OWNED_IMMUTABLE_FIELDS);
 
     // Tambahkan filter user_id di sini
     let filter = doc! {
@@ -176,29 +168,15 @@ pub async fn update_product_service(
         "user_id": user_id,
     };
 
-    let update_result = collection
-        .update_one(filter.clone(), doc! { "$set": update_doc })
-        .await
-        .map_err(|err| {
-            if let Some(conflict_error) = handle_duplicate_key_error(&err) {
-                return conflict_error;
-            }
-            ServiceError::DatabaseError(err.to_string())
-        })?;
-
-    if update_result.matched_count == 0 {
-        return Err(ServiceError::NotFound(
-            "Produk tidak ditemukan atau tidak dimiliki oleh user ini".to_string(),
-        ));
-    }
-
-    let updated_product = collection
-        .find_one(filter)
-        .await
-        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
-        .ok_or_else(|| ServiceError::NotFound("Produk tidak ditemukan!".to_string()))?;
+    update_one_checked(
+        &collection,
+        filter.clone(),
+        update,
+        "Produk tidak ditemukan atau tidak dimiliki oleh user ini",
+    )
+    .await?;
 
-    Ok(updated_product)
+    find_one_or_not_found(&collection, filter, "Produk").await
 }
 
 pub async fn delete_product_service(
@@ -206,14 +184,8 @@ pub async fn delete_product_service(
     db: &Database,
     user_id: &str,
 ) -> Result<bool, ServiceError> {
-    let product_id = match string_id_to_obj_id(product_id) {
-        Some(oid) => oid,
-        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
-    };
-    let user_id = match string_id_to_obj_id(user_id) {
-        Some(oid) => oid,
-        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
-    };
+    let product_id = parse_object_id_param(product_id)?;
+    let user_id = parse_object_id_param(user_id)?;
 
     let collection: Collection<Product> = db.collection("products");
 
@@ -223,14 +195,158 @@ pub async fn delete_product_service(
         "user_id": user_id,
     };
 
+    delete_one_checked(&collection, filter, "Product tidak ditemukan!").await?;
+
+    Ok(true)
+}
+
+/// Kurangi stok dalam satu update bersyarat, jadi dua order bersamaan tidak bisa
+/// membuat stok negatif. Stok yang kurang menghasilkan `Conflict`.
+pub async fn decrement_stock(
+    collection: &Collection<Product>,
+    product_id: ObjectId,
+    qty: u32,
+) -> Result<(), ServiceError> {
+    if qty == 0 {
+        return Err(ServiceError::BadRequest(
+            "Jumlah stok harus lebih dari 0".into(),
+        ));
+    }
+
     let result = collection
-        .delete_one(filter)
+        .update_one(
+            doc! { "_id": product_id, "stock": { "$gte": qty as i64 } },
+            doc! { "$inc": { "stock": -(qty as i64) } },
+        )
         .await
-        .map_err(|err| ServiceError::DatabaseError(err.to_string()))?;
+        .map_err(map_mongo_error)?;
 
-    if result.deleted_count == 0 {
-        return Err(ServiceError::NotFound("Product tidak ditemukan!".into()));
+    if result.matched_count == 0 {
+        // Bedakan produk yang tidak ada dengan stok yang kurang
+        find_one_or_not_found(collection, doc! { "_id": product_id }, "Produk").await?;
+        return Err(ServiceError::Conflict("stok tidak mencukupi".into()));
     }
 
-    Ok(true)
+    Ok(())
+}
+
+/// Tambah stok, dipakai untuk restock atau pembatalan order
+pub async fn increment_stock(
+    collection: &Collection<Product>,
+    product_id: ObjectId,
+    qty: u32,
+) -> Result<(), ServiceError> {
+    if qty == 0 {
+        return Err(ServiceError::BadRequest(
+            "Jumlah stok harus lebih dari 0".into(),
+        ));
+    }
+
+    update_one_checked(
+        collection,
+        doc! { "_id": product_id },
+        doc! { "$inc": { "stock": qty as i64 } },
+        "Produk tidak ditemukan",
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_database;
+    use mongodb::bson::Document;
+
+    async fn products_with_stock(stock: u32) -> (Collection<Product>, ObjectId) {
+        let db = test_database().await;
+        let id = ObjectId::new();
+        db.collection::<Document>("products")
+            .insert_one(doc! {
+                "_id": id,
+                "user_id": ObjectId::new(),
+                "name": "Kopi",
+                "sku": "QT-1",
+                "price": 10000.0,
+                "stock": stock as i64,
+                "category_id": null,
+            })
+            .await
+            .unwrap();
+        (db.collection::<Product>("products"), id)
+    }
+
+    async fn stock_of(products: &Collection<Product>, id: ObjectId) -> u32 {
+        products
+            .find_one(doc! { "_id": id })
+            .await
+            .unwrap()
+            .unwrap()
+            .stock
+    }
+
+    #[actix_web::test]
+    async fn zero_quantity_is_rejected_before_querying() {
+        let client = mongodb::Client::with_uri_str("mongodb://127.0.0.1:1/")
+            .await
+            .unwrap();
+        let products = client
+            .database("qtoky_test")
+            .collection::<Product>("products");
+
+        for result in [
+            decrement_stock(&products, ObjectId::new(), 0).await,
+            increment_stock(&products, ObjectId::new(), 0).await,
+        ] {
+            assert!(matches!(result, Err(ServiceError::BadRequest(_))));
+        }
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn sufficient_stock_is_decremented() {
+        let (products, id) = products_with_stock(5).await;
+
+        decrement_stock(&products, id, 5).await.unwrap();
+
+        assert_eq!(stock_of(&products, id).await, 0);
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn insufficient_stock_is_conflict_and_unchanged() {
+        let (products, id) = products_with_stock(2).await;
+
+        let result = decrement_stock(&products, id, 3).await;
+
+        assert!(matches!(
+            result,
+            Err(ServiceError::Conflict(msg)) if msg == "stok tidak mencukupi"
+        ));
+        assert_eq!(stock_of(&products, id).await, 2);
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn missing_product_is_not_found() {
+        let (products, _) = products_with_stock(5).await;
+
+        for result in [
+            decrement_stock(&products, ObjectId::new(), 1).await,
+            increment_stock(&products, ObjectId::new(), 1).await,
+        ] {
+            assert!(matches!(result, Err(ServiceError::NotFound(_))));
+        }
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn increment_restocks() {
+        let (products, id) = products_with_stock(1).await;
+
+        increment_stock(&products, id, 4).await.unwrap();
+
+        assert_eq!(stock_of(&products, id).await, 5);
+    }
 }
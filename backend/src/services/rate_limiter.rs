@@ -0,0 +1,241 @@
+use crate::config::{config_error, parse_env};
+use crate::errors::ServiceError;
+use actix_web::HttpRequest;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_LOGIN_MAX_ATTEMPTS: usize = 5;
+pub const DEFAULT_LOGIN_WINDOW_SECS: u64 = 15 * 60;
+
+/// Jumlah maksimal kejadian dalam satu sliding window, dipakai limiter login dan email
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitConfig {
+    pub max: usize,
+    pub window: Duration,
+}
+
+impl RateLimitConfig {
+    /// Baca batas dari `max_key` dan lebar window dalam detik dari `window_key`,
+    /// keduanya harus lebih dari 0
+    pub fn from_env(
+        max_key: &str,
+        default_max: usize,
+        window_key: &str,
+        default_window_secs: u64,
+    ) -> Result<Self, ServiceError> {
+        let max = parse_env(max_key, default_max)?;
+        if max == 0 {
+            return Err(config_error(format!("{} harus lebih dari 0", max_key)));
+        }
+        let window_secs = parse_env(window_key, default_window_secs)?;
+        if window_secs == 0 {
+            return Err(config_error(format!("{} harus lebih dari 0", window_key)));
+        }
+
+        Ok(RateLimitConfig {
+            max,
+            window: Duration::from_secs(window_secs),
+        })
+    }
+
+    /// Batas login dari `LOGIN_MAX_ATTEMPTS` dan `LOGIN_WINDOW_SECS`
+    pub fn login_from_env() -> Result<Self, ServiceError> {
+        RateLimitConfig::from_env(
+            "LOGIN_MAX_ATTEMPTS",
+            DEFAULT_LOGIN_MAX_ATTEMPTS,
+            "LOGIN_WINDOW_SECS",
+            DEFAULT_LOGIN_WINDOW_SECS,
+        )
+    }
+}
+
+/// Pembatas percobaan login per key dengan sliding window, disimpan di memory.
+/// Dibagikan antar worker lewat `web::Data`, bersihkan secara berkala dengan `cleanup`.
+pub struct LoginRateLimiter {
+    max_attempts: usize,
+    window: Duration,
+    attempts: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl Default for LoginRateLimiter {
+    fn default() -> Self {
+        LoginRateLimiter::new(
+            DEFAULT_LOGIN_MAX_ATTEMPTS,
+            Duration::from_secs(DEFAULT_LOGIN_WINDOW_SECS),
+        )
+    }
+}
+
+impl LoginRateLimiter {
+    pub fn new(max_attempts: usize, window: Duration) -> Self {
+        LoginRateLimiter {
+            max_attempts,
+            window,
+            attempts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Limiter dengan batas dari `Config.login_limit`
+    pub fn from_config(config: &RateLimitConfig) -> Self {
+        LoginRateLimiter::new(config.max, config.window)
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Tolak jika jumlah percobaan dalam window sudah mencapai batas, selain itu langsung
+    /// catat percobaan ini. Cek dan catat di bawah satu lock sehingga request login
+    /// bersamaan tidak bisa melewati batas. Setiap percobaan dihitung sampai login
+    /// berhasil dan `reset_attempts` dipanggil.
+    pub fn check_and_record(&self, key: &str) -> Result<(), ServiceError> {
+        self.check_and_record_at(key, Instant::now())
+    }
+
+    /// Hapus riwayat gagal setelah login berhasil
+    pub fn reset_attempts(&self, key: &str) {
+        self.lock().remove(key);
+    }
+
+    /// Buang key yang semua percobaannya sudah di luar window
+    pub fn cleanup(&self) {
+        let now = Instant::now();
+        let window = self.window;
+        self.lock().retain(|_, attempts| {
+            prune(attempts, now, window);
+            !attempts.is_empty()
+        });
+    }
+
+    fn check_and_record_at(&self, key: &str, now: Instant) -> Result<(), ServiceError> {
+        let mut map = self.lock();
+        let attempts = map.entry(key.to_string()).or_default();
+        prune(attempts, now, self.window);
+
+        if attempts.len() < self.max_attempts {
+            attempts.push_back(now);
+            return Ok(());
+        }
+
+        // Window bergeser, akses dibuka lagi saat percobaan tertua keluar dari window
+        let oldest = attempts.front().copied().unwrap_or(now);
+        let retry_after = self.window.saturating_sub(now.duration_since(oldest));
+        let retry_after_secs = retry_after.as_secs().max(1);
+
+        Err(ServiceError::TooManyRequests {
+            message: format!(
+                "Terlalu banyak percobaan login, coba lagi dalam {} detik",
+                retry_after_secs
+            ),
+            retry_after_secs,
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, VecDeque<Instant>>> {
+        // Data tetap konsisten walau ada thread yang panic saat memegang lock
+        self.attempts.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+pub(crate) fn prune(attempts: &mut VecDeque<Instant>, now: Instant, window: Duration) {
+    while let Some(&oldest) = attempts.front() {
+        if now.duration_since(oldest) < window {
+            break;
+        }
+        attempts.pop_front();
+    }
+}
+
+/// Key rate limit gabungan IP dan username, agar satu IP di belakang NAT
+/// tidak langsung terkunci untuk semua user
+pub fn login_attempt_key(req: &HttpRequest, username: &str) -> String {
+    let ip = req
+        .peer_addr()
+        .map(|addr| addr.ip().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    format!("{}:{}", ip, username.trim().to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn limiter() -> LoginRateLimiter {
+        LoginRateLimiter::new(3, Duration::from_secs(60))
+    }
+
+    #[test]
+    fn attempts_over_threshold_are_rejected_with_retry_after() {
+        let limiter = limiter();
+        let start = Instant::now();
+
+        for i in 0..3 {
+            assert!(
+                limiter
+                    .check_and_record_at("ip:budi", start + Duration::from_secs(i))
+                    .is_ok()
+            );
+        }
+
+        match limiter.check_and_record_at("ip:budi", start + Duration::from_secs(10)) {
+            Err(ServiceError::TooManyRequests {
+                retry_after_secs, ..
+            }) => assert_eq!(retry_after_secs, 50),
+            other => panic!("hasil tidak terduga: {:?}", other),
+        }
+        assert!(limiter.check_and_record_at("ip:siti", start).is_ok());
+    }
+
+    #[test]
+    fn reset_after_success_clears_attempts() {
+        let limiter = limiter();
+        let now = Instant::now();
+        for _ in 0..3 {
+            limiter.check_and_record_at("ip:budi", now).unwrap();
+        }
+
+        limiter.reset_attempts("ip:budi");
+
+        assert!(limiter.check_and_record_at("ip:budi", now).is_ok());
+    }
+
+    #[test]
+    fn attempts_outside_window_expire() {
+        let limiter = limiter();
+        let start = Instant::now();
+        for i in 0..3 {
+            limiter
+                .check_and_record_at("ip:budi", start + Duration::from_secs(i))
+                .unwrap();
+        }
+
+        // Hanya percobaan pertama yang keluar dari window
+        let later = start + Duration::from_secs(60);
+        assert!(limiter.check_and_record_at("ip:budi", later).is_ok());
+        assert!(limiter.check_and_record_at("ip:budi", later).is_err());
+    }
+
+    #[test]
+    fn key_combines_ip_and_normalized_username() {
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.7:5000".parse().unwrap())
+            .to_http_request();
+
+        assert_eq!(login_attempt_key(&req, " Budi "), "10.0.0.7:budi");
+    }
+
+    #[test]
+    fn zero_limits_are_rejected() {
+        assert!(
+            RateLimitConfig::from_env("QTOKY_TEST_UNSET_MAX", 0, "QTOKY_TEST_UNSET_WINDOW", 60)
+                .is_err()
+        );
+        assert!(
+            RateLimitConfig::from_env("QTOKY_TEST_UNSET_MAX", 5, "QTOKY_TEST_UNSET_WINDOW", 0)
+                .is_err()
+        );
+    }
+}
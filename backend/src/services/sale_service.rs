@@ -1,30 +1,38 @@
 use crate::models::product::Product;
 use crate::errors::ServiceError;
+use crate::db::cursor::collect_all;
 use futures::stream::TryStreamExt;
-use crate::utils::{handle_duplicate_key_error, string_id_to_obj_id};
-use bson::datetime::DateTime as BsonDateTime;
-use chrono::Utc;
-use mongodb::{Collection, Database, bson::doc};
+use crate::utils::{map_mongo_error, parse_object_id_param};
+use crate::utils::clock;
+use crate::utils::request_context::log_with_context;
+use log::Level;
+use crate::utils::sanitize::sanitize_text;
+use crate::utils::validation::{require_non_empty_list, require_non_negative, validate_all};
+use mongodb::{Collection, Database, bson::{doc, oid::ObjectId}};
+use crate::db::transaction::with_transaction;
+use crate::services::idempotency_store::{IdempotencyStore, IdempotentCreate};
 use crate::models::sale::{Sale, SaleItem, SaleDTO};
+use crate::models::status::PaymentStatus;
+use std::collections::{HashMap, HashSet};
+
+/// Sama dengan batas validasi `SaleDTO.notes`
+const MAX_NOTES_LEN: usize = 255;
 
 pub async fn get_sales_service(db: &Database, id:&str) -> Result<Vec<Sale>, ServiceError>{
-    let user_id = match string_id_to_obj_id(id) {
-        Some(oid) => oid,
-        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
-    };
+    let user_id = parse_object_id_param(id)?;
     let collection: Collection<Sale> = db.collection("sales");
 
     let mut cursor = collection
         .find(doc! {"user_id":user_id})
         .await
-        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+        .map_err(map_mongo_error)?;
     
     let mut sales: Vec<Sale> = Vec::new();
 
     while let Some(sale) = cursor
         .try_next()
         .await
-        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+        .map_err(map_mongo_error)?
     {
         sales.push(sale);
     }
@@ -38,33 +46,97 @@ pub async fn create_sale_service(
     db: &Database,
     id: &str,
 ) -> Result<Sale, ServiceError> {
-    let user_id = match string_id_to_obj_id(id) {
-        Some(oid) => oid,
-        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
-    };
-
-    if payload.items.is_empty() {
-        return Err(ServiceError::BadRequest("Items tidak boleh kosong".into()));
-    }
+    let user_id = parse_object_id_param(id)?;
+    let sale = build_sale(payload, db, user_id).await?;
     
-    if payload.paid_amount < 0.0 {
-        return Err(ServiceError::BadRequest("Paid amount tidak boleh negatif".into()));
+    let collection: Collection<Sale> = db.collection("sales");
+    let result = collection.insert_one(&sale).await;
+    
+    match result {
+        Ok(insert_result) => {
+            Ok(Sale {
+                id: insert_result.inserted_id.as_object_id().map(|oid| oid.to_owned()),
+                ..sale
+            })
+        }
+        Err(e) => Err(map_mongo_error(e)),
     }
+}
+
+/// Seperti `create_sale_service`, tapi cek `Idempotency-Key` dan insert sale di satu
+/// transaksi sehingga dua request bersamaan dengan key yang sama hanya membuat satu sale.
+/// Key yang sudah dipakai mengembalikan sale yang dibuat sebelumnya (`replayed: true`).
+/// Butuh MongoDB replica set karena memakai transaksi.
+pub async fn create_sale_idempotent(
+    payload: SaleDTO,
+    db: &Database,
+    id: &str,
+    key: &str,
+    request_hash: &str,
+) -> Result<IdempotentCreate<Sale>, ServiceError> {
+    let user_id = parse_object_id_param(id)?;
+    let store = IdempotencyStore::new(db);
+    let collection: Collection<Sale> = db.collection("sales");
+
+    // `build_sale` baru dijalankan setelah key terbukti belum dipakai, retry dengan key yang
+    // sama mendapat sale tersimpan walaupun produknya sudah dihapus atau berubah
+    with_transaction(db.client(), |session| {
+        let (store, collection, db) = (store.clone(), collection.clone(), db.clone());
+        let payload = payload.clone();
+        let (id, key, request_hash) = (id.to_string(), key.to_string(), request_hash.to_string());
+        Box::pin(async move {
+            store
+                .create_idempotent(session, &collection, &id, &key, &request_hash, || {
+                    build_sale(payload, &db, user_id)
+                })
+                .await
+        })
+    })
+    .await
+}
+
+// Validasi payload dan hitung total dari harga produk di database, belum disimpan
+async fn build_sale(
+    payload: SaleDTO,
+    db: &Database,
+    user_id: ObjectId,
+) -> Result<Sale, ServiceError> {
+    validate_all(&[
+        require_non_empty_list("Items", &payload.items),
+        require_non_negative("Paid amount", payload.paid_amount),
+    ])?;
     
     let product_collection: Collection<Product> = db.collection("products");
+
+    // Semua produk di-load dengan satu query `$in`, seluruh id yang salah dilaporkan sekaligus
+    let product_ids: Vec<_> = payload.items.iter().map(|item| item.product_id).collect();
+    let cursor = product_collection
+        .find(doc! { "_id": { "$in": &product_ids }, "user_id": &user_id })
+        .await
+        .map_err(map_mongo_error)?;
+    let products: HashMap<ObjectId, Product> = collect_all(cursor)
+        .await?
+        .into_iter()
+        .filter_map(|product| product.id.map(|id| (id, product)))
+        .collect();
+
+    let mut reported = HashSet::new();
+    let missing: Vec<String> = product_ids
+        .iter()
+        .filter(|id| !products.contains_key(id) && reported.insert(**id))
+        .map(|id| id.to_hex())
+        .collect();
+    if !missing.is_empty() {
+        return Err(ServiceError::BadRequest(format!("Produk tidak ditemukan: {}", missing.join(", "))));
+    }
+
     let mut sale_items: Vec<SaleItem> = Vec::new();
     let mut discount_total = 0.0;
     let mut total_amount = 0.0;
     
     for item_dto in &payload.items {
-        let filter = doc! { "_id": item_dto.product_id, "user_id": &user_id };
-        
-        // Ambil detail produk dari DB
-        let product = product_collection
-            .find_one(filter)
-            .await
-            .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
-            .ok_or_else(|| ServiceError::NotFound("Produk tidak ditemukan".to_string()))?;
+        // Sudah dipastikan ada di atas
+        let product = &products[&item_dto.product_id];
         
         let actual_price = product.price; // Harga dari database
         
@@ -72,8 +144,13 @@ pub async fn create_sale_service(
             let price_diff = (frontend_price - actual_price).abs();
             if price_diff > 0.01 {
                 // Log untuk debugging, tapi tetap pakai harga database
-                eprintln!("Warning: Price mismatch for product {}: frontend={}, db={}", 
-                         product.name, frontend_price, actual_price);
+                log_with_context(
+                    Level::Debug,
+                    &format!(
+                        "Harga produk {} berbeda: frontend={}, db={}",
+                        product.name, frontend_price, actual_price
+                    ),
+                );
             }
         }
         
@@ -81,7 +158,10 @@ pub async fn create_sale_service(
         
         let max_discount = actual_price * item_dto.quantity as f64;
         let validated_discount = if discount > max_discount {
-            eprintln!("Warning: Discount {} exceeds item total {}", discount, max_discount);
+            log_with_context(
+                Level::Debug,
+                &format!("Diskon {} melebihi total item {}", discount, max_discount),
+            );
             0.0 // Reset discount jika berlebihan
         } else {
             discount
@@ -93,9 +173,9 @@ pub async fn create_sale_service(
         total_amount += subtotal;
         
         sale_items.push(SaleItem {
-            product_id: item_dto.product_id.clone(),
-            product_name: product.name,
-            sku: product.sku,
+            product_id: item_dto.product_id,
+            product_name: product.name.clone(),
+            sku: product.sku.clone(),
             quantity: item_dto.quantity,
             price: actual_price, // 🔐 Simpan harga dari database
             discount: validated_discount,
@@ -105,12 +185,12 @@ pub async fn create_sale_service(
     
     let final_amount = total_amount - discount_total;
     let remaining_amount = final_amount - payload.paid_amount;
-    let now = BsonDateTime::from_chrono(Utc::now());
+    let now = clock::now();
     
     let sale = Sale {
         id: None,
         user_id,
-        customer_id: payload.customer_id.clone(),
+        customer_id: payload.customer_id,
         items: sale_items,
         total_amount,
         discount_total,
@@ -118,37 +198,21 @@ pub async fn create_sale_service(
         paid_amount: payload.paid_amount,
         remaining_amount,
         status: if remaining_amount <= 0.0 {
-            "paid".to_string()
+            PaymentStatus::Paid
         } else if payload.paid_amount > 0.0 {
-            "partial".to_string()
+            PaymentStatus::Partial
         } else {
-            "unpaid".to_string()
+            PaymentStatus::Unpaid
         },
         invoice_number: None,
-        payment_method_id: payload.payment_method_id.clone(),
-        sale_date: Some(now.clone()),
-        notes: payload.notes.clone(),
-        created_at: Some(now.clone()),
+        payment_method_id: payload.payment_method_id,
+        sale_date: Some(now),
+        notes: payload.notes.as_deref().map(|notes| sanitize_text(notes, MAX_NOTES_LEN)),
+        created_at: Some(now),
         updated_at: Some(now),
     };
-    
-    let collection: Collection<Sale> = db.collection("sales");
-    let result = collection.insert_one(&sale).await;
-    
-    match result {
-        Ok(insert_result) => {
-            Ok(Sale {
-                id: insert_result.inserted_id.as_object_id().map(|oid| oid.to_owned()),
-                ..sale
-            })
-        }
-        Err(e) => {
-            if let Some(err) = handle_duplicate_key_error(&e) {
-                return Err(err);
-            }
-            Err(ServiceError::DatabaseError(e.to_string()))
-        }
-    }
+
+    Ok(sale)
 }
 
 
@@ -163,8 +227,7 @@ pub async fn create_sale_service(
         let filter = doc! { "_id": item.product_id, "user_id": user_id };
         let product = product_collection
             .find_one(filter)
-            .await
-            .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+            .await?
             .ok_or_else(|| ServiceError::NotFound("Produk tidak ditemukan".to_string()))?;
         
         // Cek stock jika field stock ada di Product model
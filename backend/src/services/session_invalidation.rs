@@ -0,0 +1,287 @@
+use crate::errors::ServiceError;
+use crate::utils::i18n::{Message, t};
+use crate::utils::jwt::{Claims, JWT_CONFIG, ensure_issued_after};
+use crate::utils::{clock, map_mongo_error};
+use bson::{DateTime as BsonDateTime, Document, oid::ObjectId};
+use mongodb::{Collection, Database, bson::doc};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Logout dari semua perangkat lewat `users.sessions_invalidated_at`: token yang
+/// diterbitkan sebelum waktu itu ditolak walaupun belum expired.
+pub struct SessionInvalidation {
+    collection: Collection<Document>,
+}
+
+impl SessionInvalidation {
+    pub fn new(db: &Database) -> Self {
+        SessionInvalidation {
+            collection: db.collection("users"),
+        }
+    }
+
+    /// Set `sessions_invalidated_at` ke sekarang, semua token user yang sudah ada jadi tidak berlaku
+    pub async fn invalidate_all(&self, user_id: &ObjectId) -> Result<(), ServiceError> {
+        self.collection
+            .update_one(
+                doc! { "_id": user_id },
+                doc! { "$set": { "sessions_invalidated_at": clock::now() } },
+            )
+            .await
+            .map_err(map_mongo_error)?;
+        Ok(())
+    }
+
+    /// Waktu invalidasi terakhir, `None` jika belum pernah di-set
+    pub async fn invalidated_at(
+        &self,
+        user_id: &ObjectId,
+    ) -> Result<Option<BsonDateTime>, ServiceError> {
+        let user = self
+            .collection
+            .find_one(doc! { "_id": user_id })
+            .projection(doc! { "sessions_invalidated_at": 1 })
+            .await
+            .map_err(map_mongo_error)?;
+
+        Ok(user.and_then(|u| u.get_datetime("sessions_invalidated_at").ok().copied()))
+    }
+
+    /// Tolak token yang `iat`-nya lebih lama dari `sessions_invalidated_at` milik user.
+    /// `iat` hanya berpresisi detik, jadi token yang diterbitkan di detik yang sama tepat
+    /// sebelum invalidasi masih diterima, lihat `ensure_issued_after`.
+    pub async fn ensure_not_invalidated(&self, claims: &Claims) -> Result<(), ServiceError> {
+        let user_id = ObjectId::parse_str(&claims.sub)
+            .map_err(|_| ServiceError::Unauthorized(t(Message::TokenInvalid)))?;
+
+        let invalidated_at = self.invalidated_at(&user_id).await?;
+        ensure_issued_after(
+            claims,
+            invalidated_at.map(|at| at.timestamp_millis() / 1000),
+        )
+    }
+}
+
+/// `iat` tertinggi yang pernah dilihat per user, disimpan di memori. Jika jam server mundur,
+/// token yang sudah expired bisa terlihat valid lagi; selama jam masih di belakang `iat`
+/// yang pernah diterima, token user tersebut ditolak.
+/// Entry dibuang setelah `now` melewati `iat + retention + leeway`, saat itu semua token
+/// dengan `iat` tersebut sudah expired, sehingga ukuran map mengikuti jumlah user aktif.
+#[derive(Debug)]
+pub struct IatWatermark {
+    retention_secs: usize,
+    state: Mutex<WatermarkState>,
+}
+
+#[derive(Debug, Default)]
+struct WatermarkState {
+    highest: HashMap<String, usize>,
+    // Waktu sweep terakhir, sweep paling sering sekali per `retention_secs`
+    last_sweep: usize,
+}
+
+impl Default for IatWatermark {
+    fn default() -> Self {
+        IatWatermark::new()
+    }
+}
+
+impl IatWatermark {
+    /// Retensi sama dengan umur access token, token yang dicek extractor auth
+    pub fn new() -> Self {
+        IatWatermark::with_retention(JWT_CONFIG.ttl.access.num_seconds().max(0) as usize)
+    }
+
+    pub fn with_retention(retention_secs: usize) -> Self {
+        IatWatermark {
+            retention_secs,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Catat `iat` token lalu cek jam `now` tidak mundur melewati `iat` tertinggi user
+    pub fn observe(
+        &self,
+        claims: &Claims,
+        now: usize,
+        leeway_secs: u64,
+    ) -> Result<(), ServiceError> {
+        let Some(iat) = claims.iat else {
+            return Ok(());
+        };
+
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        self.sweep(&mut state, now, leeway_secs);
+        let seen = state.highest.entry(claims.sub.clone()).or_insert(iat);
+
+        if *seen > now.saturating_add(leeway_secs as usize) {
+            log::warn!("Jam server mundur dari iat token yang pernah diterima, token ditolak");
+            return Err(ServiceError::Unauthorized(t(Message::TokenInvalid)));
+        }
+
+        *seen = (*seen).max(iat);
+        Ok(())
+    }
+
+    fn sweep(&self, state: &mut WatermarkState, now: usize, leeway_secs: u64) {
+        if now < state.last_sweep.saturating_add(self.retention_secs) {
+            return;
+        }
+        let keep_for = self.retention_secs.saturating_add(leeway_secs as usize);
+        state
+            .highest
+            .retain(|_, seen| seen.saturating_add(keep_for) >= now);
+        state.last_sweep = now;
+    }
+
+    pub fn highest_iat(&self, user_id: &str) -> Option<usize> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .highest
+            .get(user_id)
+            .copied()
+    }
+
+    /// Jumlah user yang sedang dicatat
+    pub fn len(&self) -> usize {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .highest
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{init_test_config, make_test_claims, test_database};
+    use chrono::Duration;
+
+    fn claims_issued_at(user_id: &str, iat: Option<usize>) -> Claims {
+        init_test_config();
+        let mut claims = make_test_claims(user_id, "user", Duration::minutes(5));
+        claims.iat = iat;
+        claims
+    }
+
+    #[test]
+    fn token_before_invalidation_is_rejected() {
+        let old = claims_issued_at("user-1", Some(1_000));
+        let fresh = claims_issued_at("user-1", Some(2_000));
+        let unknown = claims_issued_at("user-1", None);
+
+        assert!(matches!(
+            ensure_issued_after(&old, Some(1_500)),
+            Err(ServiceError::Unauthorized(msg)) if msg == t(Message::TokenRevoked)
+        ));
+        assert!(ensure_issued_after(&fresh, Some(1_500)).is_ok());
+        assert!(ensure_issued_after(&fresh, Some(2_000)).is_ok());
+        assert!(ensure_issued_after(&unknown, Some(1_500)).is_err());
+    }
+
+    #[test]
+    fn check_is_skipped_without_recorded_timestamp() {
+        assert!(ensure_issued_after(&claims_issued_at("user-1", Some(1_000)), None).is_ok());
+        assert!(ensure_issued_after(&claims_issued_at("user-1", None), None).is_ok());
+    }
+
+    #[test]
+    fn watermark_rejects_clock_rollback_per_user() {
+        let watermark = IatWatermark::with_retention(900);
+        let budi = claims_issued_at("budi", Some(10_000));
+        let sari = claims_issued_at("sari", Some(500));
+
+        assert!(watermark.observe(&budi, 10_000, 30).is_ok());
+        assert_eq!(watermark.highest_iat("budi"), Some(10_000));
+        // Jam mundur jauh melewati iat tertinggi budi
+        assert!(matches!(
+            watermark.observe(&budi, 9_000, 30),
+            Err(ServiceError::Unauthorized(_))
+        ));
+        assert!(watermark.observe(&budi, 9_980, 30).is_ok());
+        // User lain tidak terpengaruh
+        assert!(watermark.observe(&sari, 9_000, 30).is_ok());
+        assert!(
+            watermark
+                .observe(&claims_issued_at("budi", None), 0, 0)
+                .is_ok()
+        );
+    }
+
+    #[actix_web::test]
+    async fn malformed_subject_is_rejected_before_querying() {
+        let client = mongodb::Client::with_uri_str("mongodb://127.0.0.1:1/")
+            .await
+            .unwrap();
+        let invalidation = SessionInvalidation::new(&client.database("qtoky_test"));
+
+        let result = invalidation
+            .ensure_not_invalidated(&claims_issued_at("bukan-object-id", Some(1)))
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::Unauthorized(_))));
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn invalidate_all_rejects_older_tokens_only() {
+        let db = test_database().await;
+        let user_id = ObjectId::new();
+        db.collection::<Document>("users")
+            .insert_one(doc! { "_id": user_id, "username": "budi" })
+            .await
+            .unwrap();
+        let invalidation = SessionInvalidation::new(&db);
+        let now = (clock::now().timestamp_millis() / 1000) as usize;
+        let before = claims_issued_at(&user_id.to_hex(), Some(now - 60));
+        let after = claims_issued_at(&user_id.to_hex(), Some(now + 60));
+
+        // Belum pernah di-invalidate, token lama tetap berlaku
+        assert!(invalidation.ensure_not_invalidated(&before).await.is_ok());
+        invalidation.invalidate_all(&user_id).await.unwrap();
+
+        assert!(
+            invalidation
+                .invalidated_at(&user_id)
+                .await
+                .unwrap()
+                .is_some()
+        );
+        assert!(matches!(
+            invalidation.ensure_not_invalidated(&before).await,
+            Err(ServiceError::Unauthorized(_))
+        ));
+        assert!(invalidation.ensure_not_invalidated(&after).await.is_ok());
+    }
+
+    #[test]
+    fn watermark_forgets_users_after_retention() {
+        let watermark = IatWatermark::with_retention(900);
+        let budi = claims_issued_at("budi", Some(10_000));
+        let sari = claims_issued_at("sari", Some(10_800));
+
+        assert!(watermark.observe(&budi, 10_000, 30).is_ok());
+        assert!(watermark.observe(&sari, 10_800, 30).is_ok());
+        assert_eq!(watermark.len(), 2);
+
+        // Token budi sudah expired semua, hanya sari yang masih dicatat
+        let later = 10_000 + 900 + 30 + 1;
+        assert!(
+            watermark
+                .observe(&claims_issued_at("sari", Some(later)), later, 30)
+                .is_ok()
+        );
+        assert_eq!(watermark.highest_iat("budi"), None);
+        assert_eq!(watermark.highest_iat("sari"), Some(later));
+        assert_eq!(watermark.len(), 1);
+    }
+}
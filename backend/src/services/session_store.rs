@@ -0,0 +1,496 @@
+use crate::db::cursor::collect_all;
+use crate::errors::ServiceError;
+use crate::models::session::Session;
+use crate::services::token_blacklist::TokenBlacklist;
+use crate::utils::jwt::{
+    IssuedToken, REFRESH_COOKIE_NAME, create_logout_cookies, decode_jwt_ignoring_exp,
+};
+use crate::utils::{clock, extract_token, map_mongo_error, parse_object_id_param};
+use actix_web::http::header::USER_AGENT;
+use actix_web::{HttpRequest, cookie::Cookie};
+use bson::DateTime as BsonDateTime;
+use mongodb::{Collection, Database, IndexModel, bson::doc, options::IndexOptions};
+use std::time::Duration;
+
+/// `last_seen` hanya ditulis ulang jika sudah lebih lama dari ini, agar tidak ada
+/// write ke database di setiap request
+pub const LAST_SEEN_RESOLUTION_SECS: i64 = 60;
+
+// Batasi panjang user agent yang disimpan
+const MAX_USER_AGENT_LEN: usize = 256;
+
+pub struct SessionStore {
+    collection: Collection<Session>,
+    blacklist: TokenBlacklist,
+}
+
+/// Info perangkat dari request login
+#[derive(Debug, Clone, Default)]
+pub struct DeviceInfo {
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+}
+
+impl DeviceInfo {
+    pub fn from_request(req: &HttpRequest) -> Self {
+        DeviceInfo {
+            user_agent: req
+                .headers()
+                .get(USER_AGENT)
+                .and_then(|v| v.to_str().ok())
+                .map(|ua| ua.chars().take(MAX_USER_AGENT_LEN).collect()),
+            ip: req.peer_addr().map(|addr| addr.ip().to_string()),
+        }
+    }
+}
+
+fn exp_to_datetime(exp: usize) -> BsonDateTime {
+    BsonDateTime::from_millis(exp as i64 * 1000)
+}
+
+impl SessionStore {
+    pub fn new(db: &Database) -> Self {
+        SessionStore {
+            collection: db.collection("sessions"),
+            blacklist: TokenBlacklist::new(db),
+        }
+    }
+
+    /// TTL index di `expires_at`, unique index di `jti` dan `refresh_jti`
+    pub async fn ensure_indexes(&self) -> Result<(), ServiceError> {
+        let ttl_index = IndexModel::builder()
+            .keys(doc! { "expires_at": 1 })
+            .options(
+                IndexOptions::builder()
+                    .expire_after(Duration::from_secs(0))
+                    .build(),
+            )
+            .build();
+        let jti_index = IndexModel::builder()
+            .keys(doc! { "jti": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        let refresh_jti_index = IndexModel::builder()
+            .keys(doc! { "refresh_jti": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        let user_index = IndexModel::builder()
+            .keys(doc! { "user_id": 1, "last_seen": -1 })
+            .build();
+
+        self.collection
+            .create_indexes([ttl_index, jti_index, refresh_jti_index, user_index])
+            .await
+            .map_err(map_mongo_error)?;
+
+        Ok(())
+    }
+
+    /// Simpan sesi baru saat login
+    pub async fn create(
+        &self,
+        user_id: &str,
+        access: &IssuedToken,
+        refresh: &IssuedToken,
+        device: DeviceInfo,
+    ) -> Result<Session, ServiceError> {
+        let now = clock::now();
+        let mut session = Session {
+            id: None,
+            user_id: parse_object_id_param(user_id)?,
+            jti: access.jti.clone(),
+            refresh_jti: refresh.jti.clone(),
+            user_agent: device.user_agent,
+            ip: device.ip,
+            created_at: now,
+            last_seen: now,
+            expires_at: exp_to_datetime(refresh.exp),
+        };
+
+        let result = self
+            .collection
+            .insert_one(&session)
+            .await
+            .map_err(map_mongo_error)?;
+        session.id = result.inserted_id.as_object_id();
+
+        Ok(session)
+    }
+
+    /// Pindahkan sesi ke pasangan token hasil rotasi. Sesi yang sudah dicabut tidak
+    /// dibuat ulang.
+    pub async fn rotate(
+        &self,
+        old_refresh_jti: &str,
+        access: &IssuedToken,
+        refresh: &IssuedToken,
+    ) -> Result<(), ServiceError> {
+        self.collection
+            .update_one(
+                doc! { "refresh_jti": old_refresh_jti },
+                doc! { "$set": {
+                    "jti": &access.jti,
+                    "refresh_jti": &refresh.jti,
+                    "last_seen": clock::now(),
+                    "expires_at": exp_to_datetime(refresh.exp),
+                } },
+            )
+            .await
+            .map_err(map_mongo_error)?;
+
+        Ok(())
+    }
+
+    /// Ganti `jti` access token sesi setelah sliding session menerbitkan token baru
+    pub async fn replace_access_jti(
+        &self,
+        old_jti: &str,
+        new_jti: &str,
+    ) -> Result<(), ServiceError> {
+        self.collection
+            .update_one(
+                doc! { "jti": old_jti },
+                doc! { "$set": { "jti": new_jti, "last_seen": clock::now() } },
+            )
+            .await
+            .map_err(map_mongo_error)?;
+
+        Ok(())
+    }
+
+    /// Perbarui `last_seen` jika sudah lebih lama dari `LAST_SEEN_RESOLUTION_SECS`
+    pub async fn touch(&self, jti: &str) -> Result<(), ServiceError> {
+        let now = clock::now();
+        let stale_before =
+            BsonDateTime::from_millis(now.timestamp_millis() - LAST_SEEN_RESOLUTION_SECS * 1000);
+
+        self.collection
+            .update_one(
+                doc! { "jti": jti, "last_seen": { "$lt": stale_before } },
+                doc! { "$set": { "last_seen": now } },
+            )
+            .await
+            .map_err(map_mongo_error)?;
+
+        Ok(())
+    }
+
+    /// Semua sesi aktif milik user, yang terakhir dipakai lebih dulu
+    pub async fn list_sessions(&self, user_id: &str) -> Result<Vec<Session>, ServiceError> {
+        let user_id = parse_object_id_param(user_id)?;
+        let cursor = self
+            .collection
+            .find(doc! { "user_id": user_id })
+            .sort(doc! { "last_seen": -1 })
+            .await
+            .map_err(map_mongo_error)?;
+
+        collect_all(cursor).await
+    }
+
+    /// Hapus sesi lalu masukkan access dan refresh token-nya ke blacklist
+    pub async fn revoke_session(&self, user_id: &str, jti: &str) -> Result<(), ServiceError> {
+        let user_id = parse_object_id_param(user_id)?;
+        let session = self
+            .collection
+            .find_one_and_delete(doc! { "user_id": user_id, "jti": jti })
+            .await
+            .map_err(map_mongo_error)?
+            .ok_or_else(|| ServiceError::NotFound("Sesi tidak ditemukan".into()))?;
+
+        self.blacklist_session(&session).await
+    }
+
+    /// Cabut semua sesi user kecuali sesi `current_jti`, mengembalikan jumlah sesi
+    /// yang dicabut
+    pub async fn revoke_all_other_sessions(
+        &self,
+        user_id: &str,
+        current_jti: &str,
+    ) -> Result<usize, ServiceError> {
+        let others: Vec<Session> = self
+            .list_sessions(user_id)
+            .await?
+            .into_iter()
+            .filter(|session| session.jti != current_jti)
+            .collect();
+
+        for session in &others {
+            self.blacklist_session(session).await?;
+        }
+
+        let ids: Vec<_> = others.iter().filter_map(|session| session.id).collect();
+        if !ids.is_empty() {
+            self.collection
+                .delete_many(doc! { "_id": { "$in": ids } })
+                .await
+                .map_err(map_mongo_error)?;
+        }
+
+        Ok(others.len())
+    }
+
+    /// Hapus sesi yang access atau refresh token-nya ber-`jti` ini lalu masukkan kedua
+    /// token sesi ke blacklist. Tidak error jika sesinya sudah tidak ada.
+    pub async fn revoke_by_jti(&self, jti: &str) -> Result<(), ServiceError> {
+        let session = self
+            .collection
+            .find_one_and_delete(doc! { "$or": [{ "jti": jti }, { "refresh_jti": jti }] })
+            .await
+            .map_err(map_mongo_error)?;
+
+        match session {
+            Some(session) => self.blacklist_session(&session).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Logout sesi yang dipakai request: token yang dibawa dan token pasangannya di sesi
+    /// masuk blacklist, lalu sesinya dihapus. Cookie refresh hanya dikirim ke
+    /// `/api/auth/refresh`, jadi sesi dicari dari access token; access token yang sudah
+    /// expired tetap dipakai agar refresh token-nya ikut dicabut. Mengembalikan cookie
+    /// untuk menghapus semua cookie sesi.
+    pub async fn logout(&self, req: &HttpRequest) -> Result<Vec<Cookie<'static>>, ServiceError> {
+        let access = extract_token(req).map(|(token, _source)| token);
+        let refresh = req
+            .cookie(REFRESH_COOKIE_NAME)
+            .map(|cookie| cookie.value().to_string());
+
+        for token in access.iter().chain(refresh.iter()) {
+            // Token yang tidak valid tidak perlu dicabut, cookie-nya tetap dihapus
+            let Ok(decoded) = decode_jwt_ignoring_exp(token) else {
+                continue;
+            };
+            let Some(jti) = &decoded.claims.jti else {
+                continue;
+            };
+            self.blacklist.revoke_token(jti, decoded.claims.exp).await?;
+            self.revoke_by_jti(jti).await?;
+        }
+
+        Ok(create_logout_cookies())
+    }
+
+    // Access token selalu expired lebih dulu dari refresh token, jadi `expires_at`
+    // cukup untuk keduanya
+    async fn blacklist_session(&self, session: &Session) -> Result<(), ServiceError> {
+        let exp = (session.expires_at.timestamp_millis() / 1000).max(0) as usize;
+        self.blacklist.revoke_token(&session.jti, exp).await?;
+        self.blacklist.revoke_token(&session.refresh_jti, exp).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::session::SessionResponse;
+    use crate::testing::test_database;
+    use actix_web::test::TestRequest;
+    use bson::oid::ObjectId;
+
+    fn issued(jti: &str) -> IssuedToken {
+        IssuedToken {
+            token: String::new(),
+            jti: jti.to_string(),
+            exp: (clock::now().timestamp_millis() / 1000) as usize + 3600,
+        }
+    }
+
+    async fn login(store: &SessionStore, user_id: &str, device: &str) -> Session {
+        let device_info = DeviceInfo {
+            user_agent: Some(device.to_string()),
+            ip: Some("10.0.0.1".into()),
+        };
+        store
+            .create(
+                user_id,
+                &issued(&format!("{}-access", device)),
+                &issued(&format!("{}-refresh", device)),
+                device_info,
+            )
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn device_info_reads_and_truncates_request_headers() {
+        let req = TestRequest::default()
+            .insert_header((USER_AGENT, "a".repeat(MAX_USER_AGENT_LEN + 50)))
+            .peer_addr("10.0.0.7:51234".parse().unwrap())
+            .to_http_request();
+
+        let device = DeviceInfo::from_request(&req);
+
+        assert_eq!(
+            device.user_agent.map(|ua| ua.chars().count()),
+            Some(MAX_USER_AGENT_LEN)
+        );
+        assert_eq!(device.ip.as_deref(), Some("10.0.0.7"));
+    }
+
+    #[test]
+    fn response_marks_current_session() {
+        let session = Session {
+            id: None,
+            user_id: ObjectId::new(),
+            jti: "laptop-access".into(),
+            refresh_jti: "laptop-refresh".into(),
+            user_agent: None,
+            ip: None,
+            created_at: clock::now(),
+            last_seen: clock::now(),
+            expires_at: clock::now(),
+        };
+
+        assert!(SessionResponse::from_session(session.clone(), Some("laptop-access")).current);
+        assert!(!SessionResponse::from_session(session.clone(), Some("hp-access")).current);
+        assert!(!SessionResponse::from_session(session, None).current);
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn listing_reflects_created_sessions() {
+        let store = SessionStore::new(&test_database().await);
+        let user_id = ObjectId::new().to_hex();
+        login(&store, &user_id, "laptop").await;
+        login(&store, &user_id, "hp").await;
+        login(&store, &ObjectId::new().to_hex(), "user-lain").await;
+
+        let sessions = store.list_sessions(&user_id).await.unwrap();
+
+        let mut jtis: Vec<_> = sessions.iter().map(|s| s.jti.as_str()).collect();
+        jtis.sort();
+        assert_eq!(jtis, vec!["hp-access", "laptop-access"]);
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn revoking_a_session_removes_and_blacklists_it() {
+        let db = test_database().await;
+        let store = SessionStore::new(&db);
+        let blacklist = TokenBlacklist::new(&db);
+        let user_id = ObjectId::new().to_hex();
+        login(&store, &user_id, "laptop").await;
+        login(&store, &user_id, "hp").await;
+
+        store.revoke_session(&user_id, "hp-access").await.unwrap();
+
+        let remaining = store.list_sessions(&user_id).await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].jti, "laptop-access");
+        assert!(blacklist.is_revoked("hp-access").await.unwrap());
+        assert!(blacklist.is_revoked("hp-refresh").await.unwrap());
+        assert!(!blacklist.is_revoked("laptop-access").await.unwrap());
+        assert!(matches!(
+            store.revoke_session(&user_id, "hp-access").await,
+            Err(ServiceError::NotFound(_))
+        ));
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn revoking_other_sessions_keeps_the_current_one() {
+        let db = test_database().await;
+        let store = SessionStore::new(&db);
+        let blacklist = TokenBlacklist::new(&db);
+        let user_id = ObjectId::new().to_hex();
+        for device in ["laptop", "hp", "tablet"] {
+            login(&store, &user_id, device).await;
+        }
+
+        let revoked = store
+            .revoke_all_other_sessions(&user_id, "laptop-access")
+            .await
+            .unwrap();
+
+        assert_eq!(revoked, 2);
+        let remaining = store.list_sessions(&user_id).await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].jti, "laptop-access");
+        assert!(blacklist.is_revoked("tablet-access").await.unwrap());
+        assert!(!blacklist.is_revoked("laptop-refresh").await.unwrap());
+    }
+
+    async fn offline_store() -> SessionStore {
+        // Client tanpa server, request tanpa token tidak boleh menyentuh database
+        let client = mongodb::Client::with_uri_str("mongodb://127.0.0.1:1/")
+            .await
+            .unwrap();
+        SessionStore::new(&client.database("qtoky_test"))
+    }
+
+    #[actix_web::test]
+    async fn logout_without_valid_token_only_clears_cookies() {
+        let store = offline_store().await;
+        let anonymous = TestRequest::default().to_http_request();
+        let garbage = TestRequest::default()
+            .cookie(Cookie::new(
+                crate::utils::cookie::AUTH_COOKIE_NAME,
+                "bukan-jwt",
+            ))
+            .to_http_request();
+
+        for req in [anonymous, garbage] {
+            let cookies = store.logout(&req).await.unwrap();
+
+            let names: Vec<&str> = cookies.iter().map(|cookie| cookie.name()).collect();
+            assert!(names.contains(&crate::utils::cookie::AUTH_COOKIE_NAME));
+            assert!(names.contains(&REFRESH_COOKIE_NAME));
+            assert!(cookies.iter().all(|cookie| cookie.value().is_empty()));
+        }
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn token_is_rejected_after_logout() {
+        use crate::db::handle::Db;
+        use crate::extractors::auth_user::AuthUser;
+        use crate::models::user::ROLE_USER;
+        use crate::testing::{init_test_config, test_auth_cookie};
+        use actix_web::http::StatusCode;
+        use actix_web::{App, HttpResponse, test, web};
+
+        async fn me(user: AuthUser) -> HttpResponse {
+            HttpResponse::Ok().body(user.user_id)
+        }
+
+        async fn logout(req: HttpRequest, db: web::Data<Db>) -> HttpResponse {
+            SessionStore::new(&db).logout(&req).await.unwrap();
+            HttpResponse::Ok().finish()
+        }
+
+        init_test_config();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Db::new(test_database().await)))
+                .route("/me", web::get().to(me))
+                .route("/logout", web::post().to(logout)),
+        )
+        .await;
+        let cookie = test_auth_cookie("user-1", ROLE_USER, chrono::Duration::minutes(5));
+        let me_req = || {
+            test::TestRequest::get()
+                .uri("/me")
+                .cookie(cookie.clone())
+                .to_request()
+        };
+
+        assert_eq!(
+            test::call_service(&app, me_req()).await.status(),
+            StatusCode::OK
+        );
+        let logout_req = test::TestRequest::post()
+            .uri("/logout")
+            .cookie(cookie.clone())
+            .to_request();
+        assert_eq!(
+            test::call_service(&app, logout_req).await.status(),
+            StatusCode::OK
+        );
+
+        assert_eq!(
+            test::call_service(&app, me_req()).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use crate::errors::ServiceError;
+use crate::models::token::RevokedToken;
+use crate::utils::action_token::{ActionPurpose, decode_action_token};
+use crate::utils::i18n::{Message, t};
+use crate::utils::jwt::Claims;
+use crate::utils::map_mongo_error;
+use bson::DateTime as BsonDateTime;
+use mongodb::{Collection, Database, IndexModel, bson::doc, options::IndexOptions};
+use std::time::Duration;
+
+pub struct TokenBlacklist {
+    collection: Collection<RevokedToken>,
+}
+
+impl TokenBlacklist {
+    pub fn new(db: &Database) -> Self {
+        TokenBlacklist {
+            collection: db.collection("revoked_tokens"),
+        }
+    }
+
+    /// Buat TTL index di `expires_at` supaya entry terhapus sendiri setelah token expired
+    pub async fn ensure_indexes(&self) -> Result<(), ServiceError> {
+        let ttl_index = IndexModel::builder()
+            .keys(doc! { "expires_at": 1 })
+            .options(
+                IndexOptions::builder()
+                    .expire_after(Duration::from_secs(0))
+                    .build(),
+            )
+            .build();
+
+        let jti_index = IndexModel::builder()
+            .keys(doc! { "jti": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+
+        self.collection
+            .create_indexes([ttl_index, jti_index])
+            .await
+            .map_err(map_mongo_error)?;
+
+        Ok(())
+    }
+
+    /// Revoke token sampai waktu expired aslinya (`exp` dalam UNIX timestamp)
+    pub async fn revoke_token(&self, jti: &str, exp: usize) -> Result<(), ServiceError> {
+        let expires_at = BsonDateTime::from_millis(exp as i64 * 1000);
+
+        self.collection
+            .update_one(
+                doc! { "jti": jti },
+                doc! { "$set": { "jti": jti, "expires_at": expires_at } },
+            )
+            .upsert(true)
+            .await
+            .map_err(map_mongo_error)?;
+
+        Ok(())
+    }
+
+    /// Tandai `jti` sebagai terpakai untuk token sekali pakai. `Ok(false)` jika `jti`
+    /// sudah pernah dipakai. Insert memakai unique index `jti` sehingga aman dari race.
+    pub async fn consume(&self, jti: &str, exp: usize) -> Result<bool, ServiceError> {
+        let entry = RevokedToken {
+            jti: jti.to_string(),
+            expires_at: BsonDateTime::from_millis(exp as i64 * 1000),
+        };
+
+        match self.collection.insert_one(&entry).await {
+            Ok(_) => Ok(true),
+            Err(err) => match map_mongo_error(err) {
+                ServiceError::DuplicateField { .. } | ServiceError::Conflict(_) => Ok(false),
+                other => Err(other),
+            },
+        }
+    }
+
+    pub async fn is_revoked(&self, jti: &str) -> Result<bool, ServiceError> {
+        let count = self
+            .collection
+            .count_documents(doc! { "jti": jti })
+            .limit(1)
+            .await
+            .map_err(map_mongo_error)?;
+
+        Ok(count > 0)
+    }
+
+    /// Verifikasi token aksi lalu tandai sebagai terpakai, token yang sama tidak bisa
+    /// dipakai dua kali. Mengembalikan `user_id` pemilik token.
+    pub async fn consume_action_token(
+        &self,
+        token: &str,
+        expected: ActionPurpose,
+    ) -> Result<String, ServiceError> {
+        let claims = decode_action_token(token, expected)?;
+
+        if !self.consume(&claims.jti, claims.exp).await? {
+            return Err(ServiceError::Unauthorized(t(Message::TokenRevoked)));
+        }
+
+        Ok(claims.sub)
+    }
+
+    /// Tolak token yang `jti`-nya sudah di-revoke
+    pub async fn ensure_not_revoked(&self, claims: &Claims) -> Result<(), ServiceError> {
+        if let Some(jti) = &claims.jti
+            && self.is_revoked(jti).await?
+        {
+            return Err(ServiceError::Unauthorized(t(Message::TokenRevoked)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{init_test_config, make_test_claims, test_database};
+    use crate::utils::action_token::generate_action_token;
+    use chrono::Duration as ChronoDuration;
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn revoked_jti_is_rejected_while_others_pass() {
+        init_test_config();
+        let blacklist = TokenBlacklist::new(&test_database().await);
+        blacklist.ensure_indexes().await.unwrap();
+        let revoked = make_test_claims("user-revoked", "user", ChronoDuration::minutes(5));
+        let active = make_test_claims("user-active", "user", ChronoDuration::minutes(5));
+
+        blacklist
+            .revoke_token(revoked.jti.as_deref().unwrap(), revoked.exp)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            blacklist.ensure_not_revoked(&revoked).await,
+            Err(ServiceError::Unauthorized(_))
+        ));
+        assert!(blacklist.ensure_not_revoked(&active).await.is_ok());
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn action_token_can_only_be_consumed_once() {
+        init_test_config();
+        let blacklist = TokenBlacklist::new(&test_database().await);
+        let issued = generate_action_token("user-1", ActionPurpose::Reset).unwrap();
+
+        let first = blacklist
+            .consume_action_token(&issued.token, ActionPurpose::Reset)
+            .await;
+        let second = blacklist
+            .consume_action_token(&issued.token, ActionPurpose::Reset)
+            .await;
+
+        assert_eq!(first.unwrap(), "user-1");
+        assert!(matches!(second, Err(ServiceError::Unauthorized(_))));
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn concurrent_consume_succeeds_once() {
+        init_test_config();
+        let blacklist = TokenBlacklist::new(&test_database().await);
+        blacklist.ensure_indexes().await.unwrap();
+        let refresh = make_test_claims("user-1", "user", ChronoDuration::days(7));
+        let jti = refresh.jti.as_deref().unwrap();
+
+        let (first, second) = futures::join!(
+            blacklist.consume(jti, refresh.exp),
+            blacklist.consume(jti, refresh.exp)
+        );
+
+        assert!(first.unwrap() ^ second.unwrap());
+        assert!(matches!(
+            blacklist.ensure_not_revoked(&refresh).await,
+            Err(ServiceError::Unauthorized(_))
+        ));
+    }
+}
@@ -1,46 +1,77 @@
+use crate::db::cursor::collect_all;
+use crate::db::helpers::{
+    case_insensitive_collation, delete_one_checked, find_one_or_not_found, update_one_checked,
+};
 use crate::errors::ServiceError;
-use crate::models::user::{CreateUserDTO, UpdateUserDTO, User};
-use crate::utils::{handle_duplicate_key_error, hash_password, string_id_to_obj_id};
-use futures::stream::TryStreamExt;
-use mongodb::{Collection, Database, bson::doc};
-
-pub async fn get_users_service(db: &Database) -> Result<Vec<User>, ServiceError> {
+use crate::models::user::{ChangePasswordDTO, CreateUserDTO, UpdateUserDTO, User, default_role};
+use crate::services::session_invalidation::SessionInvalidation;
+use crate::utils::normalize::NormalizedField;
+use crate::utils::password::{change_password, hash_password_async, validate_password_strength};
+use crate::utils::{is_duplicate_key_error, map_mongo_error};
+use actix_web::web;
+use mongodb::{
+    Collection, Database, IndexModel,
+    bson::{doc, oid::ObjectId},
+    options::IndexOptions,
+};
+
+/// Nama unique index `username` case-insensitive, lihat `map_user_write_error`
+pub const USERNAME_INDEX: &str = "username_ci";
+
+/// Unique index pada `email_normalized` dan `username`, dipanggil sekali saat startup.
+/// Index email sparse karena data lama belum punya field ini. Index username memakai
+/// `case_insensitive_collation` sehingga `Budi` dan `budi` tidak bisa sama-sama terdaftar
+/// dan `find_one_ci` saat login memakai index; username kembar di data lama harus
+/// dirapikan dulu sebelum index ini bisa dibuat.
+pub async fn ensure_user_indexes(db: &Database) -> Result<(), ServiceError> {
     let collection: Collection<User> = db.collection("users");
+    let email_index = IndexModel::builder()
+        .keys(doc! { "email_normalized": 1 })
+        .options(IndexOptions::builder().unique(true).sparse(true).build())
+        .build();
+    let username_index = IndexModel::builder()
+        .keys(doc! { "username": 1 })
+        .options(
+            IndexOptions::builder()
+                .name(USERNAME_INDEX.to_string())
+                .unique(true)
+                .collation(case_insensitive_collation())
+                .build(),
+        )
+        .build();
 
-    let mut cursor = collection
-        .find(doc! {})
+    collection
+        .create_indexes([email_index, username_index])
         .await
-        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+        .map_err(map_mongo_error)?;
 
-    let mut users: Vec<User> = Vec::new();
+    Ok(())
+}
 
-    while let Some(user) = cursor
-        .try_next()
-        .await
-        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
-    {
-        users.push(user);
+/// Seperti `map_mongo_error`, tapi duplicate key di `USERNAME_INDEX` selalu menjadi
+/// `DuplicateField { ["username"] }`. Pesan duplicate key dari index dengan collation
+/// berisi collation key, bukan username aslinya.
+pub fn map_user_write_error(err: mongodb::error::Error) -> ServiceError {
+    if is_duplicate_key_error(&err) && err.to_string().contains(USERNAME_INDEX) {
+        return ServiceError::DuplicateField {
+            fields: vec!["username".to_string()],
+        };
     }
-
-    Ok(users)
+    map_mongo_error(err)
 }
 
-pub async fn get_user_service(id: &str, db: &Database) -> Result<User, ServiceError> {
+pub async fn get_users_service(db: &Database) -> Result<Vec<User>, ServiceError> {
     let collection: Collection<User> = db.collection("users");
 
-    let object_id = match string_id_to_obj_id(id) {
-        Some(oid) => oid,
-        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
-    };
+    let cursor = collection.find(doc! {}).await.map_err(map_mongo_error)?;
 
-    let filter = doc! { "_id": object_id };
+    collect_all(cursor).await
+}
 
-    let user = collection
-        .find_one(filter)
-        .await
-        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+pub async fn get_user_service(object_id: ObjectId, db: &Database) -> Result<User, ServiceError> {
+    let collection: Collection<User> = db.collection("users");
 
-    user.ok_or_else(|| ServiceError::NotFound(format!("User dengan ID '{}' tidak ditemukan", id)))
+    find_one_or_not_found(&collection, doc! { "_id": object_id }, "User").await
 }
 
 pub async fn create_user_service(
@@ -50,68 +81,65 @@ pub async fn create_user_service(
     let collection: Collection<User> = db.collection("users");
 
     let username = payload.username;
-    let email = payload.email;
-    let phone_number = payload.phone_number;
-    let mut password = payload.password.unwrap_or_else(|| "".to_string());
-
-    if password.trim().is_empty() {
-        if username.len() < 6 {
-            password = format!("{}123", username);
-        } else {
-            password = username.clone();
-        }
-    }
-
-    let hashed_password = hash_password(&password)
-        .map_err(|e| ServiceError::HashingError(format!("Gagal hashing password: {}", e)))?;
+    let email = NormalizedField::email(&payload.email)?;
+    let phone_number = payload
+        .phone_number
+        .as_deref()
+        .map(NormalizedField::phone)
+        .transpose()?;
+    let (phone_number, phone_number_normalized) = phone_number
+        .map(|phone| (phone.display, phone.normalized))
+        .unzip();
+    // Tidak ada password default, admin wajib mengisi password awal user
+    let password = payload
+        .password
+        .filter(|password| !password.trim().is_empty())
+        .ok_or_else(|| ServiceError::BadRequest("Password wajib diisi".into()))?;
+    validate_password_strength(&password)?;
+
+    let hashed_password = hash_password_async(password).await?;
 
     let new_user = User {
         id: None,
         username,
-        email,
+        email: email.display,
         password_hash: hashed_password,
         phone_number,
+        email_normalized: Some(email.normalized),
+        phone_number_normalized,
+        role: default_role(),
+        org_id: None,
+        failed_attempts: 0,
+        locked_until: None,
+        sessions_invalidated_at: None,
     };
 
     let result = collection.insert_one(&new_user).await;
     match result {
         Ok(_) => Ok(new_user),
-        Err(err) => {
-            if let Some(conflict_error) = handle_duplicate_key_error(&err) {
-                return Err(conflict_error);
-            }
-
-            Err(ServiceError::DatabaseError(err.to_string()))
-        }
+        Err(err) => Err(map_user_write_error(err)),
     }
 }
 
 pub async fn update_user_service(
-    id: &str,
+    object_id: ObjectId,
     payload: UpdateUserDTO,
     db: &Database,
 ) -> Result<User, ServiceError> {
-    let object_id = match string_id_to_obj_id(id) {
-        Some(oid) => oid,
-        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
-    };
-
     let mut update_doc = doc! {};
 
     if let Some(username) = payload.username {
         update_doc.insert("username", username);
     }
     if let Some(email) = payload.email {
-        update_doc.insert("email", email);
+        let email = NormalizedField::email(&email)?;
+        update_doc.insert("email", email.display);
+        update_doc.insert("email_normalized", email.normalized);
     }
     if let Some(phone_number) = payload.phone_number {
-        update_doc.insert("phone_number", phone_number);
-    }
-
-    if let Some(password) = payload.password {
-        let hashed = hash_password(&password)
-            .map_err(|e| ServiceError::HashingError(format!("Gagal hashing password: {}", e)))?;
-        update_doc.insert("password_hash", hashed);
+        let phone = NormalizedField::phone(&phone_number)?;
+        update_doc.insert("phone_number", phone.display);
+        update_doc.insert("phone_number_normalized", phone.normalized);
     }
 
     if update_doc.is_empty() {
@@ -122,41 +150,99 @@ pub async fn update_user_service(
 
     let collection: Collection<User> = db.collection("users");
 
-    collection
-        .update_one(doc! { "_id": object_id }, doc! { "$set": update_doc })
-        .await
-        .map_err(|err| {
-            if let Some(conflict_error) = handle_duplicate_key_error(&err) {
-                return conflict_error;
-            }
-            ServiceError::DatabaseError(err.to_string())
-        })?;
-
-    let updated_user = collection
-        .find_one(doc! { "_id": object_id })
-        .await
-        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
-        .ok_or_else(|| ServiceError::NotFound("User tidak ditemukan!".to_string()))?;
+    update_one_checked(
+        &collection,
+        doc! { "_id": object_id },
+        doc! { "$set": update_doc },
+        "User tidak ditemukan!",
+    )
+    .await?;
 
-    Ok(updated_user)
+    find_one_or_not_found(&collection, doc! { "_id": object_id }, "User").await
 }
 
-pub async fn delete_user_service(id: &str, db: &Database) -> Result<bool, ServiceError> {
-    let object_id = match string_id_to_obj_id(id) {
-        Some(oid) => oid,
-        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
-    };
+/// Ganti password setelah password saat ini terverifikasi
+pub async fn change_password_service(
+    object_id: ObjectId,
+    payload: ChangePasswordDTO,
+    db: &Database,
+) -> Result<(), ServiceError> {
+    let collection: Collection<User> = db.collection("users");
+    let user = find_one_or_not_found(&collection, doc! { "_id": object_id }, "User").await?;
+
+    // Dua kali verifikasi Argon2, jadi jalankan di thread pool blocking
+    let ChangePasswordDTO {
+        current_password,
+        new_password,
+    } = payload;
+    let new_hash =
+        web::block(move || change_password(&user.password_hash, &current_password, &new_password))
+            .await
+            .map_err(|e| ServiceError::internal("Thread hashing gagal", e))??;
+
+    update_one_checked(
+        &collection,
+        doc! { "_id": object_id },
+        doc! { "$set": { "password_hash": new_hash } },
+        "User tidak ditemukan!",
+    )
+    .await?;
+
+    // Token yang diterbitkan sebelum ganti password tidak boleh dipakai lagi
+    SessionInvalidation::new(db)
+        .invalidate_all(&object_id)
+        .await?;
+
+    Ok(())
+}
 
+pub async fn delete_user_service(object_id: ObjectId, db: &Database) -> Result<bool, ServiceError> {
     let collection: Collection<User> = db.collection("users");
 
-    let result = collection
-        .delete_one(doc! {"_id": object_id})
-        .await
-        .map_err(|err| ServiceError::DatabaseError(err.to_string()))?;
+    delete_one_checked(
+        &collection,
+        doc! {"_id": object_id},
+        "User tidak ditemukan!",
+    )
+    .await?;
+
+    Ok(true)
+}
 
-    if result.deleted_count == 0 {
-        return Err(ServiceError::NotFound("User tidak ditemukan!".into()));
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{init_test_config, test_database};
+
+    fn new_user(username: &str, email: &str) -> CreateUserDTO {
+        CreateUserDTO {
+            username: username.to_string(),
+            email: email.to_string(),
+            phone_number: None,
+            password: Some("Kopi-Susu#2026!".to_string()),
+        }
     }
 
-    Ok(true)
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn username_differing_only_in_case_is_duplicate() {
+        init_test_config();
+        let db = test_database().await;
+        ensure_user_indexes(&db).await.unwrap();
+        create_user_service(new_user("Budi", "budi@toko.id"), &db)
+            .await
+            .unwrap();
+
+        let result = create_user_service(new_user("budi", "budi2@toko.id"), &db).await;
+
+        assert!(matches!(
+            result,
+            Err(ServiceError::DuplicateField { fields }) if fields == ["username"]
+        ));
+        assert!(
+            create_user_service(new_user("budi2", "budi3@toko.id"), &db)
+                .await
+                .is_ok()
+        );
+    }
 }
@@ -0,0 +1,174 @@
+//! Helper untuk test integrasi handler. Hanya dikompilasi dengan `cfg(test)` atau feature
+//! `testing` (`cargo test --features testing`), tidak ikut di build release.
+
+use crate::config::{
+    Config, DEFAULT_MONGODB_DATABASE, DEFAULT_MONGODB_URI, DEFAULT_PORT,
+    DEFAULT_SHUTDOWN_TIMEOUT_SECS,
+};
+use crate::services::account_lockout::AccountLockout;
+use crate::services::mailer::{DEFAULT_MAIL_MAX_PER_RECIPIENT, DEFAULT_MAIL_WINDOW_SECS};
+use crate::services::rate_limiter::{
+    DEFAULT_LOGIN_MAX_ATTEMPTS, DEFAULT_LOGIN_WINDOW_SECS, RateLimitConfig,
+};
+use crate::utils::body_limit::BodyLimitConfig;
+use crate::utils::clock::{Clock, SystemClock};
+use crate::utils::cookie::{AUTH_COOKIE_NAME, CookieConfig};
+use crate::utils::jwt::{
+    Claims, DEFAULT_JWT_LEEWAY_SECS, JWT_CONFIG, JwtConfig, JwtKeys, TokenTtlConfig, TokenType,
+    encode_jwt, exp_at,
+};
+use crate::utils::password::Argon2Config;
+use actix_web::cookie::Cookie;
+use chrono::Duration;
+use mongodb::{Client, Database};
+use std::sync::Arc;
+
+/// Secret HS256 untuk test, panjangnya memenuhi `MIN_SECRET_LEN`
+pub const TEST_SECRET: &str = "qtoky-test-secret-0123456789abcdef";
+
+/// Config lengkap tanpa membaca environment. Argon2 memakai parameter minimal agar
+/// hashing di test cepat, sisanya nilai default.
+pub fn test_config() -> Config {
+    Config {
+        port: DEFAULT_PORT,
+        mongodb_uri: DEFAULT_MONGODB_URI.to_string(),
+        mongodb_database: DEFAULT_MONGODB_DATABASE.to_string(),
+        secret: TEST_SECRET.to_string(),
+        previous_secrets: Vec::new(),
+        jwt: JwtConfig {
+            issuer: "qtoky".to_string(),
+            audience: "qtoky-api".to_string(),
+            ttl: TokenTtlConfig::default(),
+            leeway_secs: DEFAULT_JWT_LEEWAY_SECS,
+        },
+        jwt_keys: Arc::new(JwtKeys::hs256(TEST_SECRET.as_bytes())),
+        argon2: Argon2Config {
+            memory_kib: 8,
+            iterations: 1,
+            parallelism: 1,
+            pepper: None,
+        },
+        cookie: CookieConfig::default(),
+        body_limit: BodyLimitConfig::default(),
+        login_limit: RateLimitConfig {
+            max: DEFAULT_LOGIN_MAX_ATTEMPTS,
+            window: std::time::Duration::from_secs(DEFAULT_LOGIN_WINDOW_SECS),
+        },
+        mail_limit: RateLimitConfig {
+            max: DEFAULT_MAIL_MAX_PER_RECIPIENT,
+            window: std::time::Duration::from_secs(DEFAULT_MAIL_WINDOW_SECS),
+        },
+        lockout: AccountLockout::default(),
+        shutdown_timeout_secs: DEFAULT_SHUTDOWN_TIMEOUT_SECS,
+    }
+}
+
+/// Pasang `test_config()` sebagai config global. Panggil di awal test yang memakai JWT,
+/// cookie atau Argon2 sebelum config dibaca dari environment; aman dipanggil berkali-kali.
+pub fn init_test_config() -> &'static Config {
+    Config::install(test_config())
+}
+
+/// Database kosong dengan nama unik di `MONGODB_TEST_URI` (default MongoDB lokal), untuk
+/// test yang butuh database sungguhan. Test seperti ini ditandai `#[ignore]`, jalankan
+/// dengan `cargo test -- --ignored` saat MongoDB tersedia.
+pub async fn test_database() -> Database {
+    let uri = std::env::var("MONGODB_TEST_URI").unwrap_or_else(|_| DEFAULT_MONGODB_URI.into());
+    let client = Client::with_uri_str(&uri)
+        .await
+        .expect("MONGODB_TEST_URI tidak valid");
+    client.database(&format!("qtoky_test_{}", nanoid::nanoid!(8, &ALPHANUMERIC)))
+}
+
+// Nama database MongoDB tidak boleh berisi `-` dan karakter khusus lain dari nanoid
+const ALPHANUMERIC: [char; 36] = [
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i',
+    'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z',
+];
+
+/// Claims access token untuk test. `jti` diturunkan dari `user_id` agar hasilnya
+/// deterministik, tanpa fingerprint sehingga tidak butuh cookie fingerprint.
+pub fn make_test_claims(user_id: &str, role: &str, ttl: Duration) -> Claims {
+    let now = SystemClock.utc_now();
+    Claims {
+        sub: user_id.to_string(),
+        exp: exp_at(now, ttl),
+        token_type: TokenType::Access,
+        jti: Some(format!("test-{}", user_id)),
+        role: role.to_string(),
+        iss: JWT_CONFIG.issuer.clone(),
+        aud: JWT_CONFIG.audience.clone(),
+        auth_time: Some(now.timestamp() as usize),
+        iat: Some(now.timestamp() as usize),
+        nbf: None,
+        fgp: None,
+        org_id: None,
+    }
+}
+
+/// Access token yang lolos validasi dengan secret dari environment (`SECRET`)
+pub fn make_test_token(user_id: &str, role: &str, ttl: Duration) -> String {
+    encode_jwt(&make_test_claims(user_id, role, ttl)).expect("Gagal membuat token test")
+}
+
+/// Cookie `auth_token` berisi `make_test_token`, contoh:
+/// `TestRequest::get().cookie(test_auth_cookie(&id, ROLE_USER, Duration::minutes(5)))`
+pub fn test_auth_cookie(user_id: &str, role: &str, ttl: Duration) -> Cookie<'static> {
+    Cookie::new(AUTH_COOKIE_NAME, make_test_token(user_id, role, ttl))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::user::ROLE_ADMIN;
+    use crate::utils::extract_claims;
+    use crate::utils::jwt::validate_access_token;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn test_token_passes_access_token_validation() {
+        init_test_config();
+        let token = make_test_token("user-1", ROLE_ADMIN, Duration::minutes(5));
+
+        let claims = validate_access_token(&token).unwrap();
+
+        assert_eq!(claims.sub, "user-1");
+        assert_eq!(claims.role, ROLE_ADMIN);
+        assert_eq!(claims.jti.as_deref(), Some("test-user-1"));
+    }
+
+    #[test]
+    fn expired_test_token_is_rejected() {
+        init_test_config();
+        let token = make_test_token("user-1", ROLE_ADMIN, Duration::minutes(-5));
+
+        assert!(validate_access_token(&token).is_err());
+    }
+
+    #[test]
+    fn test_cookie_authenticates_request() {
+        init_test_config();
+        let cookie = test_auth_cookie("user-1", ROLE_ADMIN, Duration::minutes(5));
+        let req = TestRequest::get().cookie(cookie.clone()).to_http_request();
+
+        assert_eq!(cookie.name(), AUTH_COOKIE_NAME);
+        assert_eq!(extract_claims(&req).unwrap().sub, "user-1");
+    }
+
+    #[actix_web::test]
+    async fn test_database_names_are_unique_and_valid() {
+        // Membuat client belum membuka koneksi, jadi tidak butuh MongoDB
+        let first = test_database().await;
+        let second = test_database().await;
+
+        assert_ne!(first.name(), second.name());
+        for db in [first, second] {
+            assert!(db.name().starts_with("qtoky_test_"));
+            assert!(
+                db.name()
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            );
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use crate::errors::ServiceError;
+use crate::utils::clock::{Clock, SystemClock};
+use crate::utils::i18n::{Message, t};
+use crate::utils::jwt::{IssuedToken, JWT_CONFIG, JWT_KEYS, JWT_LEEWAY_SECS, exp_at};
+use jsonwebtoken::errors::ErrorKind as JwtErrorKind;
+use nanoid::nanoid;
+use serde::{Deserialize, Serialize};
+
+/// Peruntukan token aksi, token untuk satu peruntukan ditolak oleh peruntukan lain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActionPurpose {
+    Reset,
+    Verify,
+    // Buka akun yang terkunci karena terlalu banyak login gagal
+    Unlock,
+}
+
+/// Claims token aksi (link reset password / verifikasi email), terpisah dari `Claims` auth
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ActionClaims {
+    pub sub: String,
+    pub purpose: ActionPurpose,
+    pub exp: usize,
+    pub iat: usize,
+    pub jti: String,
+    pub iss: String,
+    pub aud: String,
+}
+
+// Audience berbeda agar token aksi tidak bisa dipakai sebagai access token, dan sebaliknya
+fn action_audience() -> String {
+    format!("{}-action", JWT_CONFIG.audience)
+}
+
+/// Buat token aksi untuk `user_id`, masa berlakunya dari `JWT_CONFIG.ttl` sesuai `purpose`
+pub fn generate_action_token(
+    user_id: &str,
+    purpose: ActionPurpose,
+) -> Result<IssuedToken, ServiceError> {
+    let now = SystemClock.utc_now();
+    let claims = ActionClaims {
+        sub: user_id.to_string(),
+        purpose,
+        exp: exp_at(now, JWT_CONFIG.ttl.for_action(purpose)),
+        iat: now.timestamp() as usize,
+        jti: nanoid!(),
+        iss: JWT_CONFIG.issuer.clone(),
+        aud: action_audience(),
+    };
+
+    let token = JWT_KEYS
+        .sign(&claims)
+        .map_err(|e| ServiceError::internal("JWT Error", e))?;
+
+    Ok(IssuedToken {
+        token,
+        jti: claims.jti,
+        exp: claims.exp,
+    })
+}
+
+/// Verifikasi signature, expiry dan peruntukan token aksi, lalu kembalikan claims-nya.
+/// Belum menandai token sebagai terpakai, gunakan `TokenBlacklist::consume` untuk itu.
+pub fn decode_action_token(
+    token: &str,
+    expected: ActionPurpose,
+) -> Result<ActionClaims, ServiceError> {
+    let mut validation = JWT_CONFIG.validation_for(JWT_KEYS.algorithm(), &action_audience());
+    validation.leeway = *JWT_LEEWAY_SECS;
+
+    let claims = JWT_KEYS
+        .verify::<ActionClaims>(token, &validation)
+        .map_err(|e| match e.kind() {
+            JwtErrorKind::ExpiredSignature => ServiceError::Unauthorized(t(Message::TokenExpired)),
+            _ => ServiceError::Unauthorized(t(Message::TokenInvalid)),
+        })?
+        .claims;
+
+    if claims.purpose != expected {
+        return Err(ServiceError::Unauthorized(t(Message::TokenTypeMismatch)));
+    }
+
+    Ok(claims)
+}
+
+/// Verifikasi token aksi dan kembalikan `user_id` pemiliknya
+pub fn verify_action_token(token: &str, expected: ActionPurpose) -> Result<String, ServiceError> {
+    Ok(decode_action_token(token, expected)?.sub)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{init_test_config, make_test_token};
+    use chrono::Duration;
+
+    // Token dengan `exp` yang sudah lewat jauh dari leeway
+    fn expired_token(purpose: ActionPurpose) -> String {
+        let now = SystemClock.utc_now().timestamp() as usize;
+        let claims = ActionClaims {
+            sub: "user-1".into(),
+            purpose,
+            exp: now - *JWT_LEEWAY_SECS as usize - 60,
+            iat: now - 3600,
+            jti: nanoid!(),
+            iss: JWT_CONFIG.issuer.clone(),
+            aud: action_audience(),
+        };
+        JWT_KEYS.sign(&claims).unwrap()
+    }
+
+    #[test]
+    fn token_verifies_for_its_own_purpose() {
+        init_test_config();
+        let issued = generate_action_token("user-1", ActionPurpose::Reset).unwrap();
+
+        assert_eq!(
+            verify_action_token(&issued.token, ActionPurpose::Reset).unwrap(),
+            "user-1"
+        );
+        assert_eq!(
+            decode_action_token(&issued.token, ActionPurpose::Reset)
+                .unwrap()
+                .jti,
+            issued.jti
+        );
+    }
+
+    #[test]
+    fn verify_token_is_rejected_by_reset_consumer() {
+        init_test_config();
+        let issued = generate_action_token("user-1", ActionPurpose::Verify).unwrap();
+
+        assert!(matches!(
+            verify_action_token(&issued.token, ActionPurpose::Reset),
+            Err(ServiceError::Unauthorized(msg)) if msg == t(Message::TokenTypeMismatch)
+        ));
+    }
+
+    #[test]
+    fn expired_token_is_rejected() {
+        init_test_config();
+
+        assert!(matches!(
+            verify_action_token(&expired_token(ActionPurpose::Reset), ActionPurpose::Reset),
+            Err(ServiceError::Unauthorized(msg)) if msg == t(Message::TokenExpired)
+        ));
+    }
+
+    #[test]
+    fn access_token_is_not_an_action_token() {
+        init_test_config();
+        let access = make_test_token("user-1", "user", Duration::minutes(5));
+
+        assert!(matches!(
+            verify_action_token(&access, ActionPurpose::Reset),
+            Err(ServiceError::Unauthorized(msg)) if msg == t(Message::TokenInvalid)
+        ));
+    }
+}
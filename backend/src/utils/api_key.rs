@@ -0,0 +1,92 @@
+use crate::utils::token_hash::{hash_token, verify_token};
+use nanoid::nanoid;
+
+/// Prefix API key agar mudah dikenali saat bocor di log/repo
+pub const API_KEY_PREFIX: &str = "qtk";
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+const KEY_ID_LEN: usize = 12;
+const SECRET_LEN: usize = 32;
+
+/// Generate API key baru, contoh: "qtk_V1StGXR8Z5jd_<secret>".
+/// Plaintext hanya ditampilkan sekali ke partner, yang disimpan cukup hash-nya.
+/// Bagian tengah adalah key id untuk mencari record tanpa perlu membandingkan semua hash.
+pub fn generate_api_key() -> (String, String) {
+    // Alfabet tanpa `_` agar key id bisa dipisah dengan aman
+    const ALPHABET: [char; 62] = [
+        '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H',
+        'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z',
+        'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r',
+        's', 't', 'u', 'v', 'w', 'x', 'y', 'z',
+    ];
+
+    let plaintext = format!(
+        "{}_{}_{}",
+        API_KEY_PREFIX,
+        nanoid!(KEY_ID_LEN, &ALPHABET),
+        nanoid!(SECRET_LEN, &ALPHABET)
+    );
+    let stored_hash = hash_api_key(&plaintext);
+    (plaintext, stored_hash)
+}
+
+/// Ambil key id dari plaintext API key
+pub fn api_key_id(presented: &str) -> Option<&str> {
+    let rest = presented.strip_prefix(API_KEY_PREFIX)?.strip_prefix('_')?;
+    let (key_id, secret) = rest.split_once('_')?;
+    (!key_id.is_empty() && !secret.is_empty()).then_some(key_id)
+}
+
+/// Hash API key dalam format `<salt_hex>$<digest_hex>`, lihat `token_hash`
+pub fn hash_api_key(presented: &str) -> String {
+    hash_token(presented)
+}
+
+/// Bandingkan API key dengan hash tersimpan secara constant-time
+pub fn verify_api_key(presented: &str, stored_hash: &str) -> bool {
+    verify_token(presented, stored_hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn generated_keys_are_unique_and_prefixed() {
+        let keys: Vec<(String, String)> = (0..200).map(|_| generate_api_key()).collect();
+
+        let plaintexts: HashSet<&str> = keys.iter().map(|(key, _)| key.as_str()).collect();
+        let key_ids: HashSet<&str> = keys.iter().filter_map(|(key, _)| api_key_id(key)).collect();
+        assert_eq!(plaintexts.len(), keys.len());
+        assert_eq!(key_ids.len(), keys.len());
+        for (plaintext, stored) in &keys {
+            assert!(plaintext.starts_with("qtk_"));
+            assert!(!stored.contains(plaintext.as_str()));
+        }
+    }
+
+    #[test]
+    fn generated_key_verifies_against_its_hash() {
+        let (plaintext, stored) = generate_api_key();
+        let (other, _) = generate_api_key();
+
+        assert!(verify_api_key(&plaintext, &stored));
+        assert!(!verify_api_key(&other, &stored));
+        assert!(!verify_api_key(&plaintext, "bukan-hash"));
+    }
+
+    #[test]
+    fn key_id_requires_full_format() {
+        assert_eq!(api_key_id("qtk_abc_rahasia"), Some("abc"));
+        for key in [
+            "abc_rahasia",
+            "qtk_abc",
+            "qtk__rahasia",
+            "qtk_abc_",
+            "xyz_abc_rahasia",
+        ] {
+            assert_eq!(api_key_id(key), None, "{}", key);
+        }
+    }
+}
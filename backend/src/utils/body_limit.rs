@@ -0,0 +1,255 @@
+use crate::config::{config_error, parse_env};
+use crate::errors::{ApiError, ServiceError};
+use actix_web::web::{
+    FormConfig, JsonConfig, PathConfig, PayloadConfig, QueryConfig, ServiceConfig,
+};
+
+pub const DEFAULT_JSON_LIMIT: usize = 256 * 1024;
+pub const DEFAULT_FORM_LIMIT: usize = 64 * 1024;
+/// Body mentah (`Bytes`/`String`), dipakai untuk upload gambar
+pub const DEFAULT_UPLOAD_LIMIT: usize = 5 * 1024 * 1024;
+
+/// Batas ukuran body per jenis konten, dalam byte
+#[derive(Debug, Clone)]
+pub struct BodyLimitConfig {
+    pub json: usize,
+    pub form: usize,
+    pub upload: usize,
+}
+
+impl Default for BodyLimitConfig {
+    fn default() -> Self {
+        BodyLimitConfig {
+            json: DEFAULT_JSON_LIMIT,
+            form: DEFAULT_FORM_LIMIT,
+            upload: DEFAULT_UPLOAD_LIMIT,
+        }
+    }
+}
+
+impl BodyLimitConfig {
+    /// Baca dari `JSON_BODY_LIMIT`, `FORM_BODY_LIMIT` dan `UPLOAD_BODY_LIMIT`
+    pub fn from_env() -> Result<Self, ServiceError> {
+        let default = BodyLimitConfig::default();
+        let config = BodyLimitConfig {
+            json: parse_env("JSON_BODY_LIMIT", default.json)?,
+            form: parse_env("FORM_BODY_LIMIT", default.form)?,
+            upload: parse_env("UPLOAD_BODY_LIMIT", default.upload)?,
+        };
+
+        if config.json == 0 || config.form == 0 || config.upload == 0 {
+            return Err(config_error("batas ukuran body harus lebih dari 0"));
+        }
+        Ok(config)
+    }
+
+    /// Body JSON yang terlalu besar atau rusak dirender lewat format error `ApiError`
+    pub fn json_config(&self) -> JsonConfig {
+        JsonConfig::default()
+            .limit(self.json)
+            .error_handler(|err, _req| ApiError::from(&err).into())
+    }
+
+    pub fn form_config(&self) -> FormConfig {
+        FormConfig::default()
+            .limit(self.form)
+            .error_handler(|err, _req| ApiError::from(&err).into())
+    }
+
+    pub fn payload_config(&self) -> PayloadConfig {
+        PayloadConfig::new(self.upload)
+    }
+
+    /// Pasang config extractor body sekaligus, termasuk error handler `Path` dan `Query`
+    /// agar semua 400 memakai format JSON yang sama
+    pub fn configure(&self, cfg: &mut ServiceConfig) {
+        cfg.app_data(self.json_config())
+            .app_data(self.form_config())
+            .app_data(self.payload_config())
+            .app_data(path_config())
+            .app_data(query_config());
+    }
+}
+
+pub fn path_config() -> PathConfig {
+    PathConfig::default().error_handler(|err, _req| ApiError::from(&err).into())
+}
+
+pub fn query_config() -> QueryConfig {
+    QueryConfig::default().error_handler(|err, _req| ApiError::from(&err).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::{App, HttpResponse, test, web};
+    use serde_json::{Value, json};
+    use std::collections::HashMap;
+
+    fn small_limits() -> BodyLimitConfig {
+        BodyLimitConfig {
+            json: 64,
+            form: 32,
+            upload: 128,
+        }
+    }
+
+    async fn echo_json(body: web::Json<Value>) -> HttpResponse {
+        HttpResponse::Ok().json(body.into_inner())
+    }
+
+    async fn echo_form(body: web::Form<HashMap<String, String>>) -> HttpResponse {
+        HttpResponse::Ok().json(body.into_inner())
+    }
+
+    #[actix_web::test]
+    async fn oversized_json_is_rejected_as_json_error() {
+        let limits = small_limits();
+        let app = test::init_service(
+            App::new()
+                .configure(|cfg| limits.configure(cfg))
+                .route("/", web::post().to(echo_json)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/")
+            .set_json(json!({ "name": "x".repeat(100) }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["status"], "error");
+        assert_eq!(body["code"], 413);
+        assert!(
+            body["message"]
+                .as_str()
+                .unwrap()
+                .contains("melebihi batas 64 byte")
+        );
+    }
+
+    #[actix_web::test]
+    async fn limits_are_separate_per_content_type() {
+        let limits = small_limits();
+        let app = test::init_service(
+            App::new()
+                .configure(|cfg| limits.configure(cfg))
+                .route("/json", web::post().to(echo_json))
+                .route("/form", web::post().to(echo_form)),
+        )
+        .await;
+
+        // 40 byte masih di bawah batas JSON tapi melewati batas form
+        let value = "x".repeat(30);
+        let json_req = test::TestRequest::post()
+            .uri("/json")
+            .set_json(json!({ "name": value }))
+            .to_request();
+        let form_req = test::TestRequest::post()
+            .uri("/form")
+            .set_form([("name", value.as_str())])
+            .to_request();
+
+        assert_eq!(
+            test::call_service(&app, json_req).await.status(),
+            StatusCode::OK
+        );
+        let resp = test::call_service(&app, form_req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], 413);
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    struct NewProduct {
+        #[allow(dead_code)]
+        name: String,
+        #[allow(dead_code)]
+        price: f64,
+    }
+
+    async fn create_product(_body: web::Json<NewProduct>) -> HttpResponse {
+        HttpResponse::Created().finish()
+    }
+
+    async fn show_product(_id: web::Path<u32>) -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    async fn list_products(_query: web::Query<HashMap<String, u32>>) -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    async fn bad_request_message(req: test::TestRequest) -> String {
+        let limits = BodyLimitConfig::default();
+        let app = test::init_service(
+            App::new()
+                .configure(|cfg| limits.configure(cfg))
+                .route("/products", web::post().to(create_product))
+                .route("/products", web::get().to(list_products))
+                .route("/products/{id}", web::get().to(show_product)),
+        )
+        .await;
+
+        let resp = test::call_service(&app, req.to_request()).await;
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["status"], "error");
+        assert_eq!(body["code"], 400);
+        body["message"].as_str().unwrap().to_string()
+    }
+
+    fn post_raw(body: &'static str) -> test::TestRequest {
+        test::TestRequest::post()
+            .uri("/products")
+            .insert_header(("content-type", "application/json"))
+            .set_payload(body)
+    }
+
+    #[actix_web::test]
+    async fn malformed_json_is_a_json_bad_request() {
+        let message = bad_request_message(post_raw(r#"{"name": "Kopi","#)).await;
+
+        assert!(message.contains("Format JSON tidak valid"), "{}", message);
+    }
+
+    #[actix_web::test]
+    async fn missing_and_mistyped_fields_are_named() {
+        let missing = bad_request_message(post_raw(r#"{"name": "Kopi"}"#)).await;
+        let mistyped = bad_request_message(post_raw(r#"{"name": "Kopi", "price": "mahal"}"#)).await;
+
+        assert!(missing.contains("Kolom price wajib diisi"), "{}", missing);
+        assert!(mistyped.contains("tipe data yang salah"), "{}", mistyped);
+    }
+
+    #[actix_web::test]
+    async fn wrong_content_type_is_a_json_bad_request() {
+        let req = test::TestRequest::post()
+            .uri("/products")
+            .insert_header(("content-type", "text/plain"))
+            .set_payload(r#"{"name": "Kopi", "price": 1}"#);
+
+        assert!(bad_request_message(req).await.contains("application/json"));
+    }
+
+    #[actix_web::test]
+    async fn path_and_query_errors_use_the_same_shape() {
+        let path = test::TestRequest::get().uri("/products/abc");
+        let query = test::TestRequest::get().uri("/products?page=satu");
+
+        assert!(
+            bad_request_message(path)
+                .await
+                .contains("Parameter URL tidak valid")
+        );
+        assert!(
+            bad_request_message(query)
+                .await
+                .contains("Query parameter tidak valid")
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use crate::errors::ServiceError;
+use crate::utils::request_context::log_with_context;
+use futures::future::BoxFuture;
+use log::Level;
+use sha1::{Digest, Sha1};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::{
+    TlsConnector,
+    rustls::{ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName},
+};
+
+pub const HIBP_HOST: &str = "api.pwnedpasswords.com";
+/// Batas waktu lookup, lewat dari ini dianggap gagal dan password diloloskan
+pub const HIBP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Sumber data range HIBP, dipisah agar bisa diganti di luar jaringan
+pub trait RangeFetcher: Send + Sync {
+    /// Ambil body `GET /range/{prefix}`, berisi baris `SUFFIX:COUNT`
+    fn fetch_range<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, Result<String, String>>;
+}
+
+/// Client HTTPS minimal ke `api.pwnedpasswords.com`
+#[derive(Clone)]
+pub struct HibpClient {
+    connector: TlsConnector,
+}
+
+impl Default for HibpClient {
+    fn default() -> Self {
+        let mut roots = RootCertStore::empty();
+        roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+            OwnedTrustAnchor::from_subject_spki_name_constraints(
+                ta.subject,
+                ta.spki,
+                ta.name_constraints,
+            )
+        }));
+        let config = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+
+        HibpClient {
+            connector: TlsConnector::from(Arc::new(config)),
+        }
+    }
+}
+
+impl HibpClient {
+    async fn get_range(&self, prefix: &str) -> Result<String, String> {
+        let server_name = ServerName::try_from(HIBP_HOST).map_err(|e| e.to_string())?;
+        let tcp = TcpStream::connect((HIBP_HOST, 443))
+            .await
+            .map_err(|e| e.to_string())?;
+        let mut tls = self
+            .connector
+            .connect(server_name, tcp)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        // HTTP/1.0 agar server tidak memakai chunked transfer encoding
+        let request = format!(
+            "GET /range/{} HTTP/1.0\r\nHost: {}\r\nUser-Agent: qtoky\r\nAdd-Padding: true\r\n\r\n",
+            prefix, HIBP_HOST
+        );
+        tls.write_all(request.as_bytes())
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut raw = Vec::new();
+        tls.read_to_end(&mut raw).await.map_err(|e| e.to_string())?;
+        let response = String::from_utf8_lossy(&raw);
+
+        let (head, body) = response
+            .split_once("\r\n\r\n")
+            .ok_or_else(|| "Response HIBP tidak valid".to_string())?;
+        let status = head.lines().next().unwrap_or_default();
+        if status.split_whitespace().nth(1) != Some("200") {
+            return Err(format!("Status HIBP tidak OK: {}", status));
+        }
+
+        Ok(body.to_string())
+    }
+}
+
+impl RangeFetcher for HibpClient {
+    fn fetch_range<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, Result<String, String>> {
+        Box::pin(self.get_range(prefix))
+    }
+}
+
+/// SHA-1 password dalam hex huruf besar, dipecah menjadi prefix 5 karakter dan sisanya
+fn sha1_prefix_suffix(password: &str) -> (String, String) {
+    let digest = hex::encode_upper(Sha1::digest(password.as_bytes()));
+    let (prefix, suffix) = digest.split_at(5);
+    (prefix.to_string(), suffix.to_string())
+}
+
+// Baris padding dari `Add-Padding` punya count 0, jangan dianggap bocor
+fn suffix_in_range(body: &str, suffix: &str) -> bool {
+    body.lines().any(|line| match line.trim().split_once(':') {
+        Some((candidate, count)) => {
+            candidate.eq_ignore_ascii_case(suffix) && count.trim().parse::<u64>().unwrap_or(0) > 0
+        }
+        None => false,
+    })
+}
+
+/// Cek apakah password pernah bocor memakai k-anonymity: hanya 5 karakter pertama
+/// hash SHA-1 yang dikirim, pencocokan sisanya dilakukan lokal. Jika lookup gagal
+/// (jaringan, timeout, status bukan 200) hasilnya `Ok(false)` agar login/registrasi
+/// tidak ikut terhenti.
+pub async fn is_password_breached(password: &str) -> Result<bool, ServiceError> {
+    is_password_breached_with(password, &HibpClient::default()).await
+}
+
+pub async fn is_password_breached_with(
+    password: &str,
+    fetcher: &dyn RangeFetcher,
+) -> Result<bool, ServiceError> {
+    let (prefix, suffix) = sha1_prefix_suffix(password);
+
+    let body = match actix_web::rt::time::timeout(HIBP_TIMEOUT, fetcher.fetch_range(&prefix)).await
+    {
+        Ok(Ok(body)) => body,
+        Ok(Err(e)) => {
+            log_with_context(
+                Level::Warn,
+                &format!("Cek password bocor dilewati, lookup HIBP gagal: {}", e),
+            );
+            return Ok(false);
+        }
+        Err(_) => {
+            log_with_context(
+                Level::Warn,
+                "Cek password bocor dilewati, lookup HIBP timeout",
+            );
+            return Ok(false);
+        }
+    };
+
+    Ok(suffix_in_range(&body, &suffix))
+}
+
+/// Pelengkap `validate_password_strength` untuk akun dengan hak akses tinggi
+pub async fn validate_password_not_breached(password: &str) -> Result<(), ServiceError> {
+    if is_password_breached(password).await? {
+        return Err(ServiceError::BadRequest(
+            "Password ini pernah muncul di kebocoran data, gunakan password lain".into(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // SHA-1("password") = 5BAA6 1E4C9B93F3F0682250B6CF8331B7EE68FD8
+    const PASSWORD_SUFFIX: &str = "1E4C9B93F3F0682250B6CF8331B7EE68FD8";
+
+    /// Fetcher palsu yang mencatat prefix yang diminta
+    struct MockFetcher {
+        response: Result<String, String>,
+        requested: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl MockFetcher {
+        fn new(response: Result<&str, &str>) -> Self {
+            MockFetcher {
+                response: response.map(str::to_string).map_err(str::to_string),
+                requested: Default::default(),
+            }
+        }
+    }
+
+    impl RangeFetcher for MockFetcher {
+        fn fetch_range<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, Result<String, String>> {
+            self.requested.lock().unwrap().push(prefix.to_string());
+            Box::pin(std::future::ready(self.response.clone()))
+        }
+    }
+
+    #[actix_web::test]
+    async fn matching_suffix_is_breached() {
+        let body = format!(
+            "0018A45C4D1DEF81644B54AB7F969B88D65:1\r\n{}:3861493\r\n",
+            PASSWORD_SUFFIX.to_lowercase()
+        );
+        let fetcher = MockFetcher::new(Ok(&body));
+
+        assert!(
+            is_password_breached_with("password", &fetcher)
+                .await
+                .unwrap()
+        );
+        // Hanya prefix yang keluar, bukan hash lengkap
+        assert_eq!(*fetcher.requested.lock().unwrap(), vec!["5BAA6"]);
+    }
+
+    #[actix_web::test]
+    async fn missing_or_padding_suffix_is_not_breached() {
+        let padding = format!("{}:0\r\n", PASSWORD_SUFFIX);
+        let other = "0018A45C4D1DEF81644B54AB7F969B88D65:1";
+
+        for body in [padding.as_str(), other, ""] {
+            let fetcher = MockFetcher::new(Ok(body));
+            assert!(
+                !is_password_breached_with("password", &fetcher)
+                    .await
+                    .unwrap()
+            );
+        }
+    }
+
+    #[actix_web::test]
+    async fn lookup_failure_fails_open() {
+        let fetcher = MockFetcher::new(Err("connection refused"));
+
+        assert!(
+            !is_password_breached_with("password", &fetcher)
+                .await
+                .unwrap()
+        );
+    }
+}
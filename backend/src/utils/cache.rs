@@ -0,0 +1,291 @@
+use crate::errors::ServiceError;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex as AsyncMutex;
+
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Cache in-memory dengan TTL untuk data referensi yang jarang berubah (kategori, satuan).
+/// Dibagikan antar worker lewat `web::Data`. Load bersamaan untuk key yang sama digabung:
+/// hanya satu loader yang jalan, sisanya menunggu lalu memakai hasilnya.
+pub struct Cache<K, V> {
+    ttl: Duration,
+    entries: RwLock<HashMap<K, (V, Instant)>>,
+    // Lock per key selama loader berjalan
+    loading: Mutex<HashMap<K, Arc<AsyncMutex<()>>>>,
+}
+
+impl<K, V> Default for Cache<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    fn default() -> Self {
+        Cache::new(DEFAULT_CACHE_TTL)
+    }
+}
+
+impl<K, V> Cache<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    pub fn new(ttl: Duration) -> Self {
+        Cache {
+            ttl,
+            entries: RwLock::new(HashMap::new()),
+            loading: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Nilai yang masih berlaku, entry yang sudah expired dianggap tidak ada
+    pub fn get(&self, key: &K) -> Option<V> {
+        let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+        entries
+            .get(key)
+            .filter(|(_, stored_at)| stored_at.elapsed() < self.ttl)
+            .map(|(value, _)| value.clone())
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        self.entries
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key, (value, Instant::now()));
+    }
+
+    /// Ambil dari cache, jika tidak ada atau expired jalankan `loader` lalu simpan hasilnya.
+    /// Error dari loader tidak di-cache.
+    pub async fn get_or_load<F, Fut>(&self, key: K, loader: F) -> Result<V, ServiceError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<V, ServiceError>>,
+    {
+        if let Some(value) = self.get(&key) {
+            return Ok(value);
+        }
+
+        // Lock dilepas lewat `Drop` agar tetap dibuang saat return awal atau future
+        // di-drop di tengah jalan (contoh client memutus koneksi)
+        let key_lock = KeyLock {
+            lock: self.key_lock(&key),
+            cache: self,
+            key,
+        };
+        let _guard = key_lock.lock.lock().await;
+
+        // Request lain mungkin sudah selesai load selama kita menunggu lock
+        if let Some(value) = self.get(&key_lock.key) {
+            return Ok(value);
+        }
+
+        let result = loader().await;
+        if let Ok(value) = &result {
+            self.insert(key_lock.key.clone(), value.clone());
+        }
+        result
+    }
+
+    /// Hapus satu key, panggil setelah data sumbernya diubah
+    pub fn invalidate(&self, key: &K) {
+        self.entries
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(key);
+    }
+
+    pub fn invalidate_all(&self) {
+        self.entries
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    /// Buang entry yang sudah expired, bisa dijalankan berkala
+    pub fn cleanup(&self) {
+        let ttl = self.ttl;
+        self.entries
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|_, (_, stored_at)| stored_at.elapsed() < ttl);
+    }
+
+    fn key_lock(&self, key: &K) -> Arc<AsyncMutex<()>> {
+        self.loading
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(key.clone())
+            .or_default()
+            .clone()
+    }
+
+    // Lock dibuang hanya jika masih milik load ini, request yang datang belakangan
+    // langsung mendapat nilai dari cache sehingga tidak butuh lock lagi
+    fn release_key_lock(&self, key: &K, lock: &Arc<AsyncMutex<()>>) {
+        let mut loading = self.loading.lock().unwrap_or_else(|e| e.into_inner());
+        if loading
+            .get(key)
+            .is_some_and(|current| Arc::ptr_eq(current, lock))
+        {
+            loading.remove(key);
+        }
+    }
+}
+
+// Lock per key yang sedang dipegang `get_or_load`, dihapus dari `loading` saat di-drop
+struct KeyLock<'a, K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    cache: &'a Cache<K, V>,
+    key: K,
+    lock: Arc<AsyncMutex<()>>,
+}
+
+impl<K, V> Drop for KeyLock<'_, K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    fn drop(&mut self) {
+        self.cache.release_key_lock(&self.key, &self.lock);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::rt::time::sleep;
+    use std::cell::Cell;
+
+    async fn load(
+        cache: &Cache<&'static str, String>,
+        key: &'static str,
+        calls: &Cell<u32>,
+    ) -> String {
+        cache
+            .get_or_load(key, || async {
+                calls.set(calls.get() + 1);
+                Ok(format!("{}-{}", key, calls.get()))
+            })
+            .await
+            .unwrap()
+    }
+
+    #[actix_web::test]
+    async fn miss_loads_and_hit_reuses_within_ttl() {
+        let cache = Cache::new(Duration::from_secs(60));
+        let calls = Cell::new(0);
+
+        assert_eq!(load(&cache, "kategori", &calls).await, "kategori-1");
+        assert_eq!(load(&cache, "kategori", &calls).await, "kategori-1");
+        assert_eq!(load(&cache, "satuan", &calls).await, "satuan-2");
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[actix_web::test]
+    async fn entry_reloads_after_ttl() {
+        let cache = Cache::new(Duration::from_millis(30));
+        let calls = Cell::new(0);
+
+        load(&cache, "kategori", &calls).await;
+        sleep(Duration::from_millis(50)).await;
+
+        assert!(cache.get(&"kategori").is_none());
+        assert_eq!(load(&cache, "kategori", &calls).await, "kategori-2");
+    }
+
+    #[actix_web::test]
+    async fn invalidation_forces_reload() {
+        let cache = Cache::new(Duration::from_secs(60));
+        let calls = Cell::new(0);
+        load(&cache, "kategori", &calls).await;
+        load(&cache, "satuan", &calls).await;
+
+        cache.invalidate(&"kategori");
+
+        assert_eq!(load(&cache, "kategori", &calls).await, "kategori-3");
+        assert_eq!(load(&cache, "satuan", &calls).await, "satuan-2");
+        cache.invalidate_all();
+        assert!(cache.get(&"satuan").is_none());
+    }
+
+    #[actix_web::test]
+    async fn loader_errors_are_not_cached() {
+        let cache: Cache<&str, u32> = Cache::new(Duration::from_secs(60));
+
+        let failed = cache
+            .get_or_load("kategori", || async {
+                Err(ServiceError::ServiceUnavailable("db mati".into()))
+            })
+            .await;
+        let loaded = cache.get_or_load("kategori", || async { Ok(7) }).await;
+
+        assert!(failed.is_err());
+        assert_eq!(loaded.unwrap(), 7);
+    }
+
+    #[actix_web::test]
+    async fn concurrent_loads_of_same_key_are_coalesced() {
+        let cache: Cache<&str, u32> = Cache::new(Duration::from_secs(60));
+        let calls = Cell::new(0);
+        let slow_load = || {
+            cache.get_or_load("kategori", || async {
+                calls.set(calls.get() + 1);
+                sleep(Duration::from_millis(20)).await;
+                Ok(42)
+            })
+        };
+
+        let (a, b, c) = futures::join!(slow_load(), slow_load(), slow_load());
+
+        assert_eq!((a.unwrap(), b.unwrap(), c.unwrap()), (42, 42, 42));
+        assert_eq!(calls.get(), 1);
+    }
+
+    fn pending_locks(cache: &Cache<&'static str, String>) -> usize {
+        cache.loading.lock().unwrap().len()
+    }
+
+    #[actix_web::test]
+    async fn waiter_served_from_cache_releases_lock() {
+        let cache = Cache::new(Duration::from_secs(60));
+        let calls = Cell::new(0);
+
+        let slow = cache.get_or_load("kategori", || async {
+            sleep(Duration::from_millis(20)).await;
+            calls.set(calls.get() + 1);
+            Ok("kategori".to_string())
+        });
+        let (first, second) = futures::join!(slow, load(&cache, "kategori", &calls));
+
+        assert_eq!(first.unwrap(), second);
+        assert_eq!(calls.get(), 1);
+        assert_eq!(pending_locks(&cache), 0);
+    }
+
+    #[actix_web::test]
+    async fn dropped_loader_releases_lock() {
+        let cache = Cache::new(Duration::from_secs(60));
+        let calls = Cell::new(0);
+
+        let abandoned = actix_web::rt::time::timeout(
+            Duration::from_millis(10),
+            cache.get_or_load("kategori", std::future::pending),
+        )
+        .await;
+
+        assert!(abandoned.is_err());
+        assert_eq!(pending_locks(&cache), 0);
+        assert_eq!(load(&cache, "kategori", &calls).await, "kategori-1");
+        assert_eq!(pending_locks(&cache), 0);
+    }
+}
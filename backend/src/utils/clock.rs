@@ -0,0 +1,127 @@
+use bson::{DateTime as BsonDateTime, Document, doc};
+use chrono::{DateTime, Utc};
+
+/// Sumber waktu untuk timestamp dokumen dan JWT, bisa diganti `FixedClock` di test
+pub trait Clock {
+    fn now(&self) -> BsonDateTime;
+
+    fn utc_now(&self) -> DateTime<Utc> {
+        self.now().to_chrono()
+    }
+
+    /// Detik sejak UNIX epoch, format yang dipakai claim `exp`/`iat`/`nbf`
+    fn unix_timestamp(&self) -> i64 {
+        self.now().timestamp_millis().div_euclid(1000)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> BsonDateTime {
+        BsonDateTime::now()
+    }
+}
+
+/// Clock yang selalu mengembalikan waktu yang sama
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub BsonDateTime);
+
+impl FixedClock {
+    pub fn from_unix(secs: i64) -> Self {
+        FixedClock(BsonDateTime::from_millis(secs.saturating_mul(1000)))
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> BsonDateTime {
+        self.0
+    }
+}
+
+/// Pembuat field `created_at`/`updated_at` agar semua model memakai sumber waktu yang sama
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Timestamps<C: Clock = SystemClock> {
+    clock: C,
+}
+
+impl Timestamps<SystemClock> {
+    pub fn new() -> Self {
+        Timestamps { clock: SystemClock }
+    }
+}
+
+impl<C: Clock> Timestamps<C> {
+    pub fn with_clock(clock: C) -> Self {
+        Timestamps { clock }
+    }
+
+    pub fn now(&self) -> BsonDateTime {
+        self.clock.now()
+    }
+
+    /// Field untuk digabung ke `$set` saat update, hanya `updated_at`
+    pub fn touch_updated_at(&self) -> Document {
+        doc! { "updated_at": self.now() }
+    }
+
+    /// Field `created_at` dan `updated_at` dengan waktu yang sama untuk dokumen baru
+    pub fn new_document_timestamps(&self) -> Document {
+        let now = self.now();
+        doc! { "created_at": now, "updated_at": now }
+    }
+}
+
+pub fn now() -> BsonDateTime {
+    Timestamps::new().now()
+}
+
+pub fn touch_updated_at() -> Document {
+    Timestamps::new().touch_updated_at()
+}
+
+pub fn new_document_timestamps() -> Document {
+    Timestamps::new().new_document_timestamps()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixed() -> Timestamps<FixedClock> {
+        Timestamps::with_clock(FixedClock::from_unix(1_752_309_000))
+    }
+
+    #[test]
+    fn touch_sets_only_updated_at() {
+        let touched = fixed().touch_updated_at();
+
+        assert_eq!(
+            touched,
+            doc! { "updated_at": BsonDateTime::from_millis(1_752_309_000_000) }
+        );
+        assert!(!touched.contains_key("created_at"));
+    }
+
+    #[test]
+    fn new_document_sets_both_to_the_same_time() {
+        let stamps = fixed().new_document_timestamps();
+
+        let created = stamps.get_datetime("created_at").unwrap();
+        assert_eq!(created.timestamp_millis(), 1_752_309_000_000);
+        assert_eq!(stamps.get_datetime("updated_at").unwrap(), created);
+    }
+
+    #[test]
+    fn unix_timestamp_rounds_down_before_epoch() {
+        assert_eq!(
+            FixedClock(BsonDateTime::from_millis(1_500)).unix_timestamp(),
+            1
+        );
+        assert_eq!(
+            FixedClock(BsonDateTime::from_millis(-1_500)).unix_timestamp(),
+            -2
+        );
+    }
+}
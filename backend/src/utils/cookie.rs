@@ -0,0 +1,286 @@
+use crate::config::{Config, config_error, optional_env, parse_bool_env};
+use crate::errors::ServiceError;
+use actix_web::HttpRequest;
+use actix_web::cookie::{Cookie, CookieBuilder, SameSite, time::Duration as CookieDuration};
+use chrono::Duration;
+use once_cell::sync::Lazy;
+
+pub const AUTH_COOKIE_NAME: &str = "auth_token";
+
+/// Atribut cookie yang berbeda per environment
+#[derive(Debug, Clone)]
+pub struct CookieConfig {
+    // Matikan hanya untuk development lokal lewat HTTP
+    pub secure: bool,
+    pub domain: Option<String>,
+    // SameSite untuk cookie `auth_token`. `None` dibutuhkan webview mobile, wajib Secure
+    same_site: SameSite,
+}
+
+impl Default for CookieConfig {
+    fn default() -> Self {
+        CookieConfig {
+            secure: true,
+            domain: None,
+            same_site: SameSite::Lax,
+        }
+    }
+}
+
+impl CookieConfig {
+    /// Kombinasi `SameSite=None` tanpa `Secure` ditolak karena cookie-nya akan diabaikan browser
+    pub fn new(secure: bool, domain: Option<String>, same_site: SameSite) -> Result<Self, String> {
+        if same_site == SameSite::None && !secure {
+            return Err("COOKIE_SAMESITE=None wajib memakai COOKIE_SECURE=true".into());
+        }
+
+        Ok(CookieConfig {
+            secure,
+            domain,
+            same_site,
+        })
+    }
+
+    /// Baca dari `COOKIE_SECURE` (default `true`), `COOKIE_DOMAIN` (boleh kosong) dan
+    /// `COOKIE_SAMESITE` (`lax`, `strict` atau `none`, default `lax`).
+    pub fn from_env() -> Result<Self, ServiceError> {
+        let default = CookieConfig::default();
+        let secure = parse_bool_env("COOKIE_SECURE", default.secure)?;
+        let domain = optional_env("COOKIE_DOMAIN");
+        let same_site = match optional_env("COOKIE_SAMESITE") {
+            Some(raw) => parse_same_site(&raw)
+                .ok_or_else(|| config_error(format!("COOKIE_SAMESITE tidak didukung: {}", raw)))?,
+            None => default.same_site,
+        };
+
+        CookieConfig::new(secure, domain, same_site).map_err(config_error)
+    }
+
+    pub fn same_site(&self) -> SameSite {
+        self.same_site
+    }
+
+    /// Pasang atribut `Secure` dan `Domain` sesuai konfigurasi
+    pub fn apply<'c>(&self, builder: CookieBuilder<'c>) -> CookieBuilder<'c> {
+        let builder = builder.secure(self.secure);
+        match &self.domain {
+            Some(domain) => builder.domain(domain.clone()),
+            None => builder,
+        }
+    }
+}
+
+fn parse_same_site(raw: &str) -> Option<SameSite> {
+    match raw.trim().to_lowercase().as_str() {
+        "lax" => Some(SameSite::Lax),
+        "strict" => Some(SameSite::Strict),
+        "none" => Some(SameSite::None),
+        _ => None,
+    }
+}
+
+pub static COOKIE_CONFIG: Lazy<CookieConfig> = Lazy::new(|| Config::global().cookie.clone());
+
+/// Cookie `auth_token` dengan HttpOnly, path `/`, serta SameSite/Secure/Domain dari config
+pub fn build_auth_cookie(token: &str, max_age: Duration) -> Cookie<'static> {
+    build_auth_cookie_with(token, max_age, &COOKIE_CONFIG)
+}
+
+pub fn build_auth_cookie_with(
+    token: &str,
+    max_age: Duration,
+    config: &CookieConfig,
+) -> Cookie<'static> {
+    let builder = Cookie::build(AUTH_COOKIE_NAME, token.to_string())
+        .http_only(true)
+        .same_site(config.same_site)
+        .path("/")
+        .max_age(CookieDuration::seconds(max_age.num_seconds()));
+    config.apply(builder).finish()
+}
+
+/// Cookie pengganti untuk menghapus `auth_token` di browser. Path dan domain harus
+/// sama dengan saat cookie dibuat, jika tidak browser akan mengabaikannya.
+pub fn build_logout_cookie() -> Cookie<'static> {
+    build_logout_cookie_with(&COOKIE_CONFIG)
+}
+
+pub fn build_logout_cookie_with(config: &CookieConfig) -> Cookie<'static> {
+    let mut cookie = build_auth_cookie_with("", Duration::zero(), config);
+    cookie.make_removal();
+    cookie
+}
+
+/// Scheme efektif request adalah HTTPS. Di belakang proxy dibaca dari `Forwarded` atau
+/// `X-Forwarded-Proto`; header palsu hanya membuat cookie Secure tidak tersimpan di
+/// browser pengirimnya sendiri.
+pub fn is_secure_request(req: &HttpRequest) -> bool {
+    req.connection_info().scheme().eq_ignore_ascii_case("https")
+}
+
+// Browser menerima cookie Secure dari http://localhost, development lokal tetap jalan
+fn is_loopback_host(host: &str) -> bool {
+    let host = match host.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or(rest),
+        None => host.split(':').next().unwrap_or(host),
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
+}
+
+/// Tolak pemasangan cookie auth jika config mewajibkan `Secure` tapi request datang lewat
+/// HTTP, karena browser akan membuang cookie tersebut dan login gagal tanpa pesan.
+pub fn ensure_secure_cookie_context(req: &HttpRequest) -> Result<(), ServiceError> {
+    ensure_secure_cookie_context_with(req, &COOKIE_CONFIG)
+}
+
+pub fn ensure_secure_cookie_context_with(
+    req: &HttpRequest,
+    config: &CookieConfig,
+) -> Result<(), ServiceError> {
+    if !config.secure || is_secure_request(req) {
+        return Ok(());
+    }
+    let info = req.connection_info();
+    if is_loopback_host(info.host()) {
+        return Ok(());
+    }
+
+    log::error!(
+        "Request ke {} lewat {} padahal COOKIE_SECURE=true, periksa konfigurasi HTTPS/proxy (X-Forwarded-Proto)",
+        info.host(),
+        info.scheme()
+    );
+    Err(ServiceError::BadRequest(
+        "Koneksi tidak aman, login harus melalui HTTPS".into(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::cookie::time::OffsetDateTime;
+
+    fn config(secure: bool, domain: Option<&str>) -> CookieConfig {
+        CookieConfig::new(secure, domain.map(str::to_string), SameSite::Lax).unwrap()
+    }
+
+    #[test]
+    fn auth_cookie_has_security_attributes() {
+        let cookie = build_auth_cookie_with(
+            "token-abc",
+            Duration::minutes(15),
+            &config(true, Some("qtoky.id")),
+        );
+
+        assert_eq!(cookie.name(), AUTH_COOKIE_NAME);
+        assert_eq!(cookie.value(), "token-abc");
+        assert_eq!(cookie.http_only(), Some(true));
+        assert_eq!(cookie.secure(), Some(true));
+        assert_eq!(cookie.same_site(), Some(SameSite::Lax));
+        assert_eq!(cookie.path(), Some("/"));
+        assert_eq!(cookie.domain(), Some("qtoky.id"));
+        assert_eq!(cookie.max_age(), Some(CookieDuration::seconds(900)));
+    }
+
+    #[test]
+    fn secure_flag_follows_config() {
+        let cookie =
+            build_auth_cookie_with("token-abc", Duration::minutes(15), &config(false, None));
+
+        assert_eq!(cookie.secure(), Some(false));
+        assert_eq!(cookie.domain(), None);
+        assert_eq!(cookie.http_only(), Some(true));
+    }
+
+    #[test]
+    fn logout_cookie_clears_with_same_path_and_domain() {
+        let cookie = build_logout_cookie_with(&config(true, Some("qtoky.id")));
+
+        assert_eq!(cookie.name(), AUTH_COOKIE_NAME);
+        assert_eq!(cookie.value(), "");
+        assert_eq!(cookie.max_age(), Some(CookieDuration::ZERO));
+        assert!(
+            cookie
+                .expires_datetime()
+                .is_some_and(|at| at < OffsetDateTime::now_utc())
+        );
+        assert_eq!(cookie.path(), Some("/"));
+        assert_eq!(cookie.domain(), Some("qtoky.id"));
+        assert_eq!(cookie.http_only(), Some(true));
+        assert_eq!(cookie.secure(), Some(true));
+    }
+
+    #[test]
+    fn same_site_none_without_secure_is_refused() {
+        assert!(CookieConfig::new(false, None, SameSite::None).is_err());
+        assert!(CookieConfig::new(false, None, SameSite::Strict).is_ok());
+
+        let mobile = CookieConfig::new(true, None, SameSite::None).unwrap();
+        let cookie = build_auth_cookie_with("token-abc", Duration::minutes(15), &mobile);
+        assert_eq!(cookie.same_site(), Some(SameSite::None));
+        assert_eq!(cookie.secure(), Some(true));
+    }
+
+    #[test]
+    fn same_site_values_are_parsed_case_insensitively() {
+        assert_eq!(parse_same_site(" Lax "), Some(SameSite::Lax));
+        assert_eq!(parse_same_site("STRICT"), Some(SameSite::Strict));
+        assert_eq!(parse_same_site("none"), Some(SameSite::None));
+        assert_eq!(parse_same_site("kadang"), None);
+    }
+
+    fn request(host: &str, forwarded_proto: Option<&str>) -> HttpRequest {
+        let mut req = actix_web::test::TestRequest::default()
+            .insert_header((actix_web::http::header::HOST, host));
+        if let Some(proto) = forwarded_proto {
+            req = req.insert_header(("x-forwarded-proto", proto));
+        }
+        req.to_http_request()
+    }
+
+    #[test]
+    fn forwarded_proto_decides_scheme() {
+        assert!(is_secure_request(&request("api.qtoky.id", Some("https"))));
+        assert!(is_secure_request(&request("api.qtoky.id", Some("HTTPS"))));
+        assert!(!is_secure_request(&request("api.qtoky.id", Some("http"))));
+        assert!(!is_secure_request(&request("api.qtoky.id", None)));
+    }
+
+    #[test]
+    fn secure_cookie_requires_https() {
+        let secure = config(true, None);
+
+        assert!(
+            ensure_secure_cookie_context_with(&request("api.qtoky.id", Some("https")), &secure)
+                .is_ok()
+        );
+        assert!(matches!(
+            ensure_secure_cookie_context_with(&request("api.qtoky.id", Some("http")), &secure),
+            Err(ServiceError::BadRequest(_))
+        ));
+        assert!(
+            ensure_secure_cookie_context_with(
+                &request("api.qtoky.id", Some("http")),
+                &config(false, None)
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn loopback_host_is_allowed_over_http() {
+        let secure = config(true, None);
+
+        for host in ["localhost:8080", "127.0.0.1", "[::1]:8080"] {
+            assert!(
+                ensure_secure_cookie_context_with(&request(host, None), &secure).is_ok(),
+                "{}",
+                host
+            );
+        }
+        assert!(
+            ensure_secure_cookie_context_with(&request("localhost.qtoky.id", None), &secure)
+                .is_err()
+        );
+    }
+}
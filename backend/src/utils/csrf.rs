@@ -0,0 +1,149 @@
+use crate::errors::ServiceError;
+use crate::utils::extract_claims;
+use crate::utils::jwt::{Claims, PREVIOUS_SECRETS, SECRET};
+use crate::utils::token_hash::constant_time_eq;
+use actix_web::HttpRequest;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+fn csrf_mac(jti: &str) -> HmacSha256 {
+    csrf_mac_with(SECRET.as_bytes(), jti)
+}
+
+fn csrf_mac_with(secret: &[u8], jti: &str) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(secret).expect("HMAC menerima key dengan panjang apapun");
+    mac.update(b"csrf:");
+    mac.update(jti.as_bytes());
+    mac
+}
+
+/// CSRF token = HMAC-SHA256(SECRET, jti) dalam hex, tidak bisa dipalsukan tanpa secret
+pub fn generate_csrf_token(jti: &str) -> String {
+    hex::encode(csrf_mac(jti).finalize().into_bytes())
+}
+
+/// Bandingkan token dengan nilai yang diharapkan secara constant-time.
+/// Token dari secret lama tetap diterima selama masa rotasi secret.
+pub fn verify_csrf_token(jti: &str, token: &str) -> bool {
+    let Ok(bytes) = hex::decode(token) else {
+        return false;
+    };
+
+    let matches = |mac: HmacSha256| constant_time_eq(&mac.finalize().into_bytes(), &bytes);
+
+    matches(csrf_mac(jti))
+        || PREVIOUS_SECRETS
+            .iter()
+            .any(|secret| matches(csrf_mac_with(secret.as_bytes(), jti)))
+}
+
+/// Cek header `X-CSRF-Token` terhadap jti dari claims yang sudah divalidasi
+pub fn verify_csrf_with_claims(req: &HttpRequest, claims: &Claims) -> Result<(), ServiceError> {
+    let header = req
+        .headers()
+        .get(CSRF_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| ServiceError::Forbidden("CSRF token tidak ditemukan".into()))?;
+
+    let jti = claims
+        .jti
+        .as_deref()
+        .ok_or_else(|| ServiceError::Forbidden("CSRF token tidak cocok".into()))?;
+
+    if !verify_csrf_token(jti, header) {
+        return Err(ServiceError::Forbidden("CSRF token tidak cocok".into()));
+    }
+    Ok(())
+}
+
+pub fn verify_csrf(req: &HttpRequest) -> Result<(), ServiceError> {
+    let claims = extract_claims(req)?;
+    verify_csrf_with_claims(req, &claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::user::ROLE_USER;
+    use crate::testing::{init_test_config, make_test_claims, test_auth_cookie};
+    use actix_web::test::TestRequest;
+    use chrono::Duration;
+
+    fn claims() -> Claims {
+        make_test_claims("user-1", ROLE_USER, Duration::minutes(5))
+    }
+
+    #[test]
+    fn matching_token_is_accepted() {
+        init_test_config();
+        let claims = claims();
+        let token = generate_csrf_token(claims.jti.as_deref().unwrap());
+        let req = TestRequest::post()
+            .insert_header((CSRF_HEADER, token))
+            .to_http_request();
+
+        assert!(verify_csrf_with_claims(&req, &claims).is_ok());
+    }
+
+    #[test]
+    fn missing_token_is_forbidden() {
+        init_test_config();
+        let req = TestRequest::post().to_http_request();
+
+        assert!(matches!(
+            verify_csrf_with_claims(&req, &claims()),
+            Err(ServiceError::Forbidden(msg)) if msg.contains("tidak ditemukan")
+        ));
+    }
+
+    #[test]
+    fn token_of_another_session_is_forbidden() {
+        init_test_config();
+        let other = generate_csrf_token("jti-lain");
+        for token in [other.as_str(), "bukan-hex"] {
+            let req = TestRequest::post()
+                .insert_header((CSRF_HEADER, token))
+                .to_http_request();
+
+            assert!(matches!(
+                verify_csrf_with_claims(&req, &claims()),
+                Err(ServiceError::Forbidden(msg)) if msg.contains("tidak cocok")
+            ));
+        }
+    }
+
+    #[test]
+    fn verify_csrf_reads_claims_from_cookie() {
+        init_test_config();
+        let token = generate_csrf_token(claims().jti.as_deref().unwrap());
+        let req = TestRequest::post()
+            .cookie(test_auth_cookie("user-1", ROLE_USER, Duration::minutes(5)))
+            .insert_header((CSRF_HEADER, token))
+            .to_http_request();
+
+        assert!(verify_csrf(&req).is_ok());
+    }
+
+    #[test]
+    fn truncated_or_altered_token_is_rejected() {
+        init_test_config();
+        let token = generate_csrf_token("jti-1");
+        let truncated = &token[..token.len() - 2];
+        let altered = format!("{}00", truncated);
+        let altered = if altered == token {
+            format!("{}ff", truncated)
+        } else {
+            altered
+        };
+
+        assert!(verify_csrf_token("jti-1", &token));
+        assert!(!verify_csrf_token("jti-1", truncated));
+        assert!(!verify_csrf_token("jti-1", &altered));
+        assert!(!verify_csrf_token("jti-1", &format!("{}00", token)));
+    }
+}
@@ -0,0 +1,219 @@
+use crate::errors::ServiceError;
+use crate::utils::format_iso_datetime;
+use actix_web::web::Bytes;
+use bson::Bson;
+use futures::stream::{self, Stream, StreamExt};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Akhir baris CSV sesuai RFC 4180, dibaca benar oleh Excel
+const LINE_END: &str = "\r\n";
+
+/// Export CSV lengkap dalam satu `String`, untuk data kecil. Baris pertama berisi
+/// `columns`, urutan kolom mengikuti `columns` dan nama kolom adalah nama field serde.
+/// Field yang tidak ada di item (contoh `Option` yang di-skip) menjadi sel kosong.
+pub fn to_csv<T: Serialize>(items: &[T], columns: &[&str]) -> Result<String, ServiceError> {
+    let mut csv = csv_header(columns);
+    for item in items {
+        csv.push_str(&csv_row(item, columns)?);
+    }
+    Ok(csv)
+}
+
+/// Baris header beserta akhir baris
+pub fn csv_header<C: AsRef<str>>(columns: &[C]) -> String {
+    let mut header = columns
+        .iter()
+        .map(|column| escape_csv_field(column.as_ref()))
+        .collect::<Vec<_>>()
+        .join(",");
+    header.push_str(LINE_END);
+    header
+}
+
+/// Satu baris CSV untuk `item` beserta akhir baris. Item di-serialize dengan format JSON
+/// yang sama dengan response API, `ObjectId` menjadi hex dan `DateTime` menjadi RFC 3339.
+pub fn csv_row<T, C>(item: &T, columns: &[C]) -> Result<String, ServiceError>
+where
+    T: Serialize,
+    C: AsRef<str>,
+{
+    let value = serde_json::to_value(item)
+        .map_err(|e| ServiceError::internal("Gagal serialize data export", e))?;
+    let Value::Object(fields) = value else {
+        return Err(ServiceError::Unexpected(
+            "Data export harus berupa object".into(),
+        ));
+    };
+
+    let mut row = columns
+        .iter()
+        .map(|column| {
+            fields
+                .get(column.as_ref())
+                .map(render_cell)
+                .map(|cell| escape_csv_field(&cell))
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    row.push_str(LINE_END);
+    Ok(row)
+}
+
+/// Export CSV sebagai stream per baris untuk data besar, contoh:
+/// `HttpResponse::Ok().content_type("text/csv").streaming(csv_stream(products, columns))`
+pub fn csv_stream<T, S>(
+    items: S,
+    columns: Vec<String>,
+) -> impl Stream<Item = Result<Bytes, ServiceError>>
+where
+    T: Serialize,
+    S: Stream<Item = Result<T, ServiceError>>,
+{
+    let header = csv_header(&columns);
+
+    stream::once(async move { Ok(Bytes::from(header)) }).chain(items.map(move |item| {
+        let row = csv_row(&item?, &columns)?;
+        Ok(Bytes::from(row))
+    }))
+}
+
+// ObjectId dan DateTime keluar sebagai extended JSON (`{"$oid": ...}`) jika model tidak
+// memakai serializer string, ubah dulu ke BSON agar tetap ditulis sebagai string biasa
+fn render_cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => neutralize_formula(s),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::Object(_) => match Bson::try_from(value.clone()) {
+            Ok(Bson::ObjectId(id)) => id.to_hex(),
+            Ok(Bson::DateTime(dt)) => format_iso_datetime(&dt),
+            _ => value.to_string(),
+        },
+        Value::Array(_) => value.to_string(),
+    }
+}
+
+// Teks yang diawali karakter formula dijalankan sebagai rumus oleh Excel (CSV injection),
+// beri prefix `'` agar dibaca sebagai teks
+fn neutralize_formula(s: &str) -> String {
+    match s.chars().next() {
+        Some('=' | '+' | '-' | '@' | '\t' | '\r') => format!("'{}", s),
+        _ => s.to_string(),
+    }
+}
+
+/// Bungkus field dengan tanda kutip jika berisi koma, kutip atau baris baru.
+/// Kutip di dalam field digandakan.
+pub fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::{DateTime, oid::ObjectId};
+
+    #[derive(Serialize)]
+    struct Row {
+        name: String,
+        #[serde(rename = "unitPrice")]
+        price: f64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        note: Option<String>,
+    }
+
+    fn row(name: &str, price: f64, note: Option<&str>) -> Row {
+        Row {
+            name: name.into(),
+            price,
+            note: note.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn special_characters_are_escaped() {
+        assert_eq!(escape_csv_field("Kopi"), "Kopi");
+        assert_eq!(escape_csv_field("Kopi, Susu"), "\"Kopi, Susu\"");
+        assert_eq!(escape_csv_field("Kopi \"Enak\""), "\"Kopi \"\"Enak\"\"\"");
+        assert_eq!(escape_csv_field("baris\nbaru"), "\"baris\nbaru\"");
+
+        let csv = to_csv(
+            &[row("Kopi, \"Susu\"", 1.5, Some("a\nb"))],
+            &["name", "note"],
+        )
+        .unwrap();
+        assert_eq!(csv, "name,note\r\n\"Kopi, \"\"Susu\"\"\",\"a\nb\"\r\n");
+    }
+
+    #[test]
+    fn columns_follow_requested_order_and_subset() {
+        let items = [row("Kopi", 10.0, None), row("Teh", 5.5, Some("promo"))];
+
+        assert_eq!(
+            to_csv(&items, &["unitPrice", "name"]).unwrap(),
+            "unitPrice,name\r\n10.0,Kopi\r\n5.5,Teh\r\n"
+        );
+        assert_eq!(
+            to_csv(&items, &["note", "price"]).unwrap(),
+            "note,price\r\n,\r\npromo,\r\n"
+        );
+        assert_eq!(to_csv::<Row>(&[], &["name"]).unwrap(), "name\r\n");
+    }
+
+    #[test]
+    fn bson_values_render_as_strings() {
+        #[derive(Serialize)]
+        struct Record {
+            id: ObjectId,
+            created_at: DateTime,
+        }
+        let id = ObjectId::new();
+        let created_at = DateTime::from_millis(1_700_000_000_000);
+
+        let csv = to_csv(&[Record { id, created_at }], &["id", "created_at"]).unwrap();
+
+        assert_eq!(
+            csv,
+            format!(
+                "id,created_at\r\n{},{}\r\n",
+                id.to_hex(),
+                format_iso_datetime(&created_at)
+            )
+        );
+    }
+
+    #[test]
+    fn formula_text_is_neutralized() {
+        let csv = to_csv(
+            &[row("=HYPERLINK(\"x\")", 1.0, Some("+1"))],
+            &["name", "note"],
+        )
+        .unwrap();
+
+        assert_eq!(csv, "name,note\r\n\"'=HYPERLINK(\"\"x\"\")\",'+1\r\n");
+    }
+
+    #[actix_web::test]
+    async fn stream_matches_to_csv() {
+        let items = vec![row("Kopi", 10.0, None), row("Teh, Tawar", 5.5, None)];
+        let columns = vec!["name".to_string(), "unitPrice".to_string()];
+        let expected = to_csv(&items, &["name", "unitPrice"]).unwrap();
+
+        let chunks: Vec<_> = csv_stream(stream::iter(items.into_iter().map(Ok)), columns)
+            .collect()
+            .await;
+        let streamed: Vec<u8> = chunks
+            .into_iter()
+            .flat_map(|chunk| chunk.unwrap().to_vec())
+            .collect();
+
+        assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+    }
+}
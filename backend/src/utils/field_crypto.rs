@@ -0,0 +1,290 @@
+//! Enkripsi field PII (alamat lengkap, NIK) sebelum disimpan, dengan ChaCha20-Poly1305.
+//! Pasang di field model:
+//!
+//! ```ignore
+//! #[serde(serialize_with = "encrypt_string", deserialize_with = "decrypt_string")]
+//! pub national_id: String,
+//! ```
+//!
+//! Model yang memakai helper ini jangan dikirim langsung sebagai response JSON, karena
+//! serialize selalu menghasilkan ciphertext; pakai struct response terpisah.
+
+use crate::config::{config_error, optional_env};
+use crate::errors::ServiceError;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use once_cell::sync::Lazy;
+use ring::aead::{Aad, CHACHA20_POLY1305, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Deserializer, Serializer, de::Error as DeError, ser::Error as SerError};
+use std::fmt;
+
+/// Prefix ciphertext, versi dinaikkan jika format atau algoritma berubah
+pub const ENCRYPTED_PREFIX: &str = "enc:v1:";
+pub const FIELD_KEY_LEN: usize = 32;
+
+/// Key aktif untuk enkripsi dan key lama yang masih dicoba saat dekripsi selama rotasi
+#[derive(Clone)]
+pub struct FieldKeys {
+    current: [u8; FIELD_KEY_LEN],
+    previous: Vec<[u8; FIELD_KEY_LEN]>,
+}
+
+impl fmt::Debug for FieldKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FieldKeys")
+            .field("current", &"***")
+            .field("previous", &self.previous.len())
+            .finish()
+    }
+}
+
+fn parse_key(name: &str, raw: &str) -> Result<[u8; FIELD_KEY_LEN], ServiceError> {
+    STANDARD
+        .decode(raw.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| {
+            config_error(format!(
+                "{} harus berisi {} byte dalam base64",
+                name, FIELD_KEY_LEN
+            ))
+        })
+}
+
+impl FieldKeys {
+    pub fn new(current: [u8; FIELD_KEY_LEN], previous: Vec<[u8; FIELD_KEY_LEN]>) -> Self {
+        FieldKeys { current, previous }
+    }
+
+    /// Baca `FIELD_ENCRYPTION_KEY` dan `FIELD_ENCRYPTION_PREVIOUS_KEYS` (dipisah koma,
+    /// terbaru dulu), keduanya 32 byte dalam base64. Buat dengan `openssl rand -base64 32`.
+    pub fn from_env() -> Result<FieldKeys, ServiceError> {
+        let current = optional_env("FIELD_ENCRYPTION_KEY")
+            .ok_or_else(|| config_error("FIELD_ENCRYPTION_KEY belum di-set"))?;
+        let previous = optional_env("FIELD_ENCRYPTION_PREVIOUS_KEYS")
+            .map(|raw| {
+                raw.split(',')
+                    .filter(|key| !key.trim().is_empty())
+                    .map(|key| parse_key("FIELD_ENCRYPTION_PREVIOUS_KEYS", key))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok(FieldKeys::new(
+            parse_key("FIELD_ENCRYPTION_KEY", &current)?,
+            previous,
+        ))
+    }
+}
+
+// Dibaca saat field terenkripsi pertama kali dipakai, error config dilaporkan per operasi
+static FIELD_KEYS: Lazy<Result<FieldKeys, String>> =
+    Lazy::new(|| FieldKeys::from_env().map_err(|e| e.to_string()));
+
+fn field_keys() -> Result<&'static FieldKeys, ServiceError> {
+    FIELD_KEYS
+        .as_ref()
+        .map_err(|e| ServiceError::internal("Key enkripsi field tidak tersedia", e.clone()))
+}
+
+fn aead_key(key: &[u8; FIELD_KEY_LEN]) -> LessSafeKey {
+    LessSafeKey::new(
+        UnboundKey::new(&CHACHA20_POLY1305, key).expect("Panjang key ChaCha20 selalu 32 byte"),
+    )
+}
+
+/// Enkripsi `plaintext` dengan key dari environment, hasilnya `enc:v1:<base64>`
+pub fn encrypt_field(plaintext: &str) -> Result<String, ServiceError> {
+    encrypt_field_with(field_keys()?, plaintext)
+}
+
+/// Nonce 96 bit acak per nilai, aman untuk jumlah enkripsi yang wajar per key
+pub fn encrypt_field_with(keys: &FieldKeys, plaintext: &str) -> Result<String, ServiceError> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| ServiceError::Unexpected("Gagal membuat nonce enkripsi".into()))?;
+
+    let mut in_out = plaintext.as_bytes().to_vec();
+    aead_key(&keys.current)
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::empty(),
+            &mut in_out,
+        )
+        .map_err(|_| ServiceError::Unexpected("Gagal mengenkripsi data".into()))?;
+
+    let mut payload = nonce.to_vec();
+    payload.extend_from_slice(&in_out);
+    Ok(format!("{}{}", ENCRYPTED_PREFIX, STANDARD.encode(payload)))
+}
+
+/// Dekripsi nilai dari `encrypt_field`. Key aktif dicoba dulu lalu key lama; jika tidak
+/// ada yang cocok (key salah/hilang atau data rusak) hasilnya `Internal`, bukan teks acak.
+pub fn decrypt_field(stored: &str) -> Result<String, ServiceError> {
+    decrypt_field_with(field_keys()?, stored)
+}
+
+pub fn decrypt_field_with(keys: &FieldKeys, stored: &str) -> Result<String, ServiceError> {
+    let invalid =
+        |reason: &str| ServiceError::internal("Gagal mendekripsi data", reason.to_string());
+
+    let encoded = stored
+        .strip_prefix(ENCRYPTED_PREFIX)
+        .ok_or_else(|| invalid("nilai belum terenkripsi atau versi tidak dikenal"))?;
+    let payload = STANDARD
+        .decode(encoded)
+        .map_err(|_| invalid("base64 tidak valid"))?;
+    if payload.len() < NONCE_LEN {
+        return Err(invalid("ciphertext terlalu pendek"));
+    }
+    let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+
+    for key in std::iter::once(&keys.current).chain(&keys.previous) {
+        let nonce =
+            Nonce::try_assume_unique_for_key(nonce).map_err(|_| invalid("nonce tidak valid"))?;
+        let mut in_out = ciphertext.to_vec();
+        if let Ok(plaintext) = aead_key(key).open_in_place(nonce, Aad::empty(), &mut in_out) {
+            return String::from_utf8(plaintext.to_vec()).map_err(|_| invalid("hasil bukan UTF-8"));
+        }
+    }
+    Err(invalid("key tidak cocok atau data rusak"))
+}
+
+/// `serialize_with` untuk field `String` yang disimpan terenkripsi
+pub fn encrypt_string<S>(value: &str, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let encrypted = encrypt_field(value).map_err(|e| S::Error::custom(e.detail()))?;
+    serializer.serialize_str(&encrypted)
+}
+
+pub fn encrypt_opt_string<S>(value: &Option<String>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match value {
+        Some(value) => encrypt_string(value, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Pasangan `encrypt_string` untuk `#[serde(deserialize_with = ...)]`
+pub fn decrypt_string<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let stored = String::deserialize(deserializer)?;
+    decrypt_field(&stored).map_err(|e| D::Error::custom(e.detail()))
+}
+
+pub fn decrypt_opt_string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let stored: Option<String> = Option::deserialize(deserializer)?;
+    stored
+        .map(|stored| decrypt_field(&stored).map_err(|e| D::Error::custom(e.detail())))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(current: u8, previous: &[u8]) -> FieldKeys {
+        FieldKeys::new(
+            [current; FIELD_KEY_LEN],
+            previous.iter().map(|&b| [b; FIELD_KEY_LEN]).collect(),
+        )
+    }
+
+    fn is_decrypt_error(result: Result<String, ServiceError>) -> bool {
+        matches!(result, Err(ServiceError::Internal { context, .. }) if context == "Gagal mendekripsi data")
+    }
+
+    #[test]
+    fn round_trip_returns_plaintext() {
+        let keys = keys(1, &[]);
+        let plaintext = "Jl. Merdeka No. 10, RT 01/RW 02 — Bandung";
+
+        let first = encrypt_field_with(&keys, plaintext).unwrap();
+        let second = encrypt_field_with(&keys, plaintext).unwrap();
+
+        assert!(first.starts_with(ENCRYPTED_PREFIX));
+        assert!(!first.contains("Merdeka"));
+        assert_ne!(first, second);
+        assert_eq!(decrypt_field_with(&keys, &first).unwrap(), plaintext);
+        assert_eq!(decrypt_field_with(&keys, &second).unwrap(), plaintext);
+        assert_eq!(
+            decrypt_field_with(&keys, &encrypt_field_with(&keys, "").unwrap()).unwrap(),
+            ""
+        );
+    }
+
+    #[test]
+    fn wrong_key_fails_with_internal_error() {
+        let stored = encrypt_field_with(&keys(1, &[]), "3273010101010001").unwrap();
+
+        assert!(is_decrypt_error(decrypt_field_with(&keys(2, &[]), &stored)));
+    }
+
+    #[test]
+    fn previous_key_still_decrypts_after_rotation() {
+        let stored = encrypt_field_with(&keys(1, &[]), "3273010101010001").unwrap();
+        let rotated = keys(2, &[3, 1]);
+
+        assert_eq!(
+            decrypt_field_with(&rotated, &stored).unwrap(),
+            "3273010101010001"
+        );
+        let reencrypted = encrypt_field_with(&rotated, "3273010101010001").unwrap();
+        assert!(is_decrypt_error(decrypt_field_with(
+            &keys(1, &[]),
+            &reencrypted
+        )));
+    }
+
+    #[test]
+    fn tampered_or_plain_values_are_rejected() {
+        let keys = keys(1, &[]);
+        let stored = encrypt_field_with(&keys, "rahasia").unwrap();
+        let mut payload = STANDARD.decode(&stored[ENCRYPTED_PREFIX.len()..]).unwrap();
+        *payload.last_mut().unwrap() ^= 1;
+        let tampered = format!("{}{}", ENCRYPTED_PREFIX, STANDARD.encode(payload));
+
+        for value in [
+            tampered.as_str(),
+            "rahasia",
+            "enc:v2:AAAA",
+            "enc:v1:bukan base64!",
+            "enc:v1:AAAA",
+        ] {
+            assert!(
+                is_decrypt_error(decrypt_field_with(&keys, value)),
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn key_must_be_32_bytes_base64() {
+        let valid = STANDARD.encode([7u8; FIELD_KEY_LEN]);
+
+        assert_eq!(parse_key("KEY", &valid).unwrap(), [7u8; FIELD_KEY_LEN]);
+        for raw in [STANDARD.encode([7u8; 16]), "bukan base64!".to_string()] {
+            assert!(parse_key("KEY", &raw).is_err());
+        }
+    }
+
+    #[test]
+    fn debug_hides_key_material() {
+        let debug = format!("{:?}", keys(0xAB, &[1]));
+
+        assert!(debug.contains("***"));
+        assert!(!debug.contains("171"));
+    }
+}
@@ -0,0 +1,147 @@
+use crate::errors::ServiceError;
+use crate::utils::cookie::COOKIE_CONFIG;
+use crate::utils::i18n::{Message, t};
+use crate::utils::jwt::Claims;
+use crate::utils::token_hash::constant_time_eq;
+use actix_web::{
+    HttpRequest,
+    cookie::{Cookie, SameSite},
+};
+use nanoid::nanoid;
+use sha2::{Digest, Sha256};
+
+/// Cookie berisi fingerprint mentah, token hanya menyimpan hash-nya di claim `fgp`
+pub const FINGERPRINT_COOKIE_NAME: &str = "token_fgp";
+
+/// Header login untuk client yang mengirim token lewat `Authorization: Bearer` dan tidak
+/// menyimpan cookie, contoh aplikasi mobile. Nilainya `bearer`.
+pub const AUTH_TRANSPORT_HEADER: &str = "X-Auth-Transport";
+
+/// Token login diikat ke fingerprint kecuali client menyatakan diri bearer-only lewat
+/// `AUTH_TRANSPORT_HEADER`, karena client seperti itu tidak pernah mengirim cookie
+/// `token_fgp`. Token tanpa `fgp` tetap lolos `verify_fingerprint`.
+pub fn binds_fingerprint(req: &HttpRequest) -> bool {
+    !req.headers()
+        .get(AUTH_TRANSPORT_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.trim().eq_ignore_ascii_case("bearer"))
+}
+
+/// Fingerprint acak baru untuk satu sesi login
+pub fn generate_fingerprint() -> String {
+    nanoid!(32)
+}
+
+/// SHA-256 fingerprint dalam hex, nilai inilah yang ditulis ke claim `fgp`
+pub fn hash_fingerprint(raw: &str) -> String {
+    hex::encode(Sha256::digest(raw.as_bytes()))
+}
+
+/// Bandingkan hash di token dengan fingerprint dari request secara constant-time.
+/// Token tanpa `fgp` (token lama) selalu lolos, token dengan `fgp` wajib disertai
+/// fingerprint yang cocok.
+pub fn verify_fingerprint(
+    expected_hash: Option<&str>,
+    presented: Option<&str>,
+) -> Result<(), ServiceError> {
+    let Some(expected_hash) = expected_hash else {
+        return Ok(());
+    };
+
+    let matched = presented.is_some_and(|raw| {
+        let actual = hash_fingerprint(raw);
+        constant_time_eq(actual.as_bytes(), expected_hash.as_bytes())
+    });
+
+    if !matched {
+        return Err(ServiceError::Unauthorized(t(Message::TokenInvalid)));
+    }
+    Ok(())
+}
+
+/// Cek claim `fgp` terhadap cookie `token_fgp` di request
+pub fn verify_fingerprint_with_claims(
+    req: &HttpRequest,
+    claims: &Claims,
+) -> Result<(), ServiceError> {
+    let cookie = req.cookie(FINGERPRINT_COOKIE_NAME);
+    verify_fingerprint(claims.fgp.as_deref(), cookie.as_ref().map(|c| c.value()))
+}
+
+/// Cookie fingerprint, HttpOnly agar tidak bisa dicuri lewat XSS bersama token
+pub fn create_fingerprint_cookie(fingerprint: &str) -> Cookie<'static> {
+    let builder = Cookie::build(FINGERPRINT_COOKIE_NAME, fingerprint.to_string())
+        .http_only(true)
+        .same_site(SameSite::Strict)
+        .path("/");
+    COOKIE_CONFIG.apply(builder).finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::init_test_config;
+    use crate::utils::jwt::{generate_access_token, validate_access_token};
+    use actix_web::test::TestRequest;
+
+    fn request_with_fingerprint(fingerprint: Option<&str>) -> HttpRequest {
+        let req = TestRequest::default();
+        match fingerprint {
+            Some(raw) => req.cookie(Cookie::new(FINGERPRINT_COOKIE_NAME, raw.to_string())),
+            None => req,
+        }
+        .to_http_request()
+    }
+
+    fn claims_bound_to(fingerprint: Option<&str>) -> Claims {
+        init_test_config();
+        let issued = generate_access_token("user-1", "user", None, fingerprint).unwrap();
+        validate_access_token(&issued.token).unwrap()
+    }
+
+    #[test]
+    fn matching_fingerprint_passes() {
+        let fingerprint = generate_fingerprint();
+        let claims = claims_bound_to(Some(&fingerprint));
+
+        assert_eq!(claims.fgp, Some(hash_fingerprint(&fingerprint)));
+        assert!(
+            verify_fingerprint_with_claims(&request_with_fingerprint(Some(&fingerprint)), &claims)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn mismatched_or_missing_fingerprint_is_unauthorized() {
+        let claims = claims_bound_to(Some(&generate_fingerprint()));
+
+        for presented in [Some("fingerprint-lain"), Some(""), None] {
+            assert!(matches!(
+                verify_fingerprint_with_claims(&request_with_fingerprint(presented), &claims),
+                Err(ServiceError::Unauthorized(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn token_without_fingerprint_still_validates() {
+        let claims = claims_bound_to(None);
+
+        assert_eq!(claims.fgp, None);
+        assert!(verify_fingerprint_with_claims(&request_with_fingerprint(None), &claims).is_ok());
+        assert!(
+            verify_fingerprint_with_claims(&request_with_fingerprint(Some("apa-saja")), &claims)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn bearer_transport_skips_binding() {
+        let bearer = TestRequest::default()
+            .insert_header((AUTH_TRANSPORT_HEADER, " Bearer "))
+            .to_http_request();
+
+        assert!(!binds_fingerprint(&bearer));
+        assert!(binds_fingerprint(&TestRequest::default().to_http_request()));
+    }
+}
@@ -0,0 +1,149 @@
+use actix_web::{HttpRequest, http::header::ACCEPT_LANGUAGE};
+
+/// Bahasa pesan error, default Indonesia
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    Id,
+    En,
+}
+
+impl Locale {
+    /// Ambil bahasa pertama yang didukung dari header `Accept-Language`,
+    /// contoh: "en-US,en;q=0.9" menjadi `Locale::En`
+    pub fn from_accept_language(header: &str) -> Self {
+        let mut tags: Vec<(&str, f32)> = header
+            .split(',')
+            .filter_map(|part| {
+                let mut pieces = part.trim().split(';');
+                let tag = pieces.next()?.trim();
+                let quality = pieces
+                    .find_map(|p| p.trim().strip_prefix("q="))
+                    .and_then(|q| q.parse().ok())
+                    .unwrap_or(1.0);
+                (!tag.is_empty()).then_some((tag, quality))
+            })
+            .collect();
+        tags.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        tags.into_iter()
+            .find_map(|(tag, _)| Locale::from_tag(tag))
+            .unwrap_or_default()
+    }
+
+    pub fn from_request(req: &HttpRequest) -> Self {
+        req.headers()
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|v| v.to_str().ok())
+            .map(Locale::from_accept_language)
+            .unwrap_or_default()
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        let primary = tag.split('-').next()?.to_ascii_lowercase();
+        match primary.as_str() {
+            "id" | "in" => Some(Locale::Id),
+            "en" => Some(Locale::En),
+            _ => None,
+        }
+    }
+}
+
+tokio::task_local! {
+    static LOCALE: Locale;
+}
+
+/// Locale request yang sedang diproses, di-set oleh `LocaleMiddleware`
+pub fn current_locale() -> Locale {
+    LOCALE.try_with(|locale| *locale).unwrap_or_default()
+}
+
+/// Jalankan future dengan locale tertentu, dipakai `LocaleMiddleware`
+pub async fn with_locale<F: Future>(locale: Locale, future: F) -> F::Output {
+    LOCALE.scope(locale, future).await
+}
+
+/// Kunci pesan yang bisa diterjemahkan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Message {
+    AlreadyUsed,
+    DuplicateData,
+    TokenNotFound,
+    TokenInvalid,
+    TokenExpired,
+    TokenNotYetValid,
+    TokenTypeMismatch,
+    TokenRevoked,
+    InvalidCredentials,
+}
+
+impl Message {
+    pub fn render(self, locale: Locale) -> &'static str {
+        match locale {
+            Locale::Id => match self {
+                Message::AlreadyUsed => "sudah digunakan",
+                Message::DuplicateData => "Data sudah ada",
+                Message::TokenNotFound => "Token tidak ditemukan",
+                Message::TokenInvalid => "Token tidak valid",
+                Message::TokenExpired => "Token sudah expired",
+                Message::TokenNotYetValid => "Token belum berlaku",
+                Message::TokenTypeMismatch => "Jenis token tidak sesuai",
+                Message::TokenRevoked => "Token sudah dicabut",
+                Message::InvalidCredentials => "username atau password salah",
+            },
+            Locale::En => match self {
+                Message::AlreadyUsed => "is already in use",
+                Message::DuplicateData => "Data already exists",
+                Message::TokenNotFound => "Token not found",
+                Message::TokenInvalid => "Invalid token",
+                Message::TokenExpired => "Token has expired",
+                Message::TokenNotYetValid => "Token is not valid yet",
+                Message::TokenTypeMismatch => "Unexpected token type",
+                Message::TokenRevoked => "Token has been revoked",
+                Message::InvalidCredentials => "invalid username or password",
+            },
+        }
+    }
+}
+
+/// Render pesan dengan locale request saat ini
+pub fn t(message: Message) -> String {
+    message.render(current_locale()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_render_per_locale() {
+        assert_eq!(Message::AlreadyUsed.render(Locale::Id), "sudah digunakan");
+        assert_eq!(Message::AlreadyUsed.render(Locale::En), "is already in use");
+        assert_eq!(
+            Message::TokenInvalid.render(Locale::Id),
+            "Token tidak valid"
+        );
+        assert_eq!(Message::TokenInvalid.render(Locale::En), "Invalid token");
+    }
+
+    #[test]
+    fn accept_language_picks_highest_supported_quality() {
+        assert_eq!(Locale::from_accept_language("en-US,en;q=0.9"), Locale::En);
+        assert_eq!(
+            Locale::from_accept_language("en;q=0.5, id;q=0.8"),
+            Locale::Id
+        );
+        assert_eq!(Locale::from_accept_language("fr-FR, en;q=0.3"), Locale::En);
+        assert_eq!(Locale::from_accept_language("fr, de"), Locale::Id);
+        assert_eq!(Locale::from_accept_language(""), Locale::Id);
+    }
+
+    #[actix_web::test]
+    async fn t_uses_current_locale_and_defaults_to_indonesian() {
+        assert_eq!(t(Message::TokenExpired), "Token sudah expired");
+
+        let english = with_locale(Locale::En, async { t(Message::TokenExpired) }).await;
+
+        assert_eq!(english, "Token has expired");
+    }
+}
@@ -1,16 +1,54 @@
+pub mod action_token;
+pub mod api_key;
+pub mod body_limit;
+#[cfg(feature = "hibp")]
+pub mod breach;
+pub mod cache;
+pub mod clock;
+pub mod cookie;
+pub mod csrf;
+pub mod csv;
+pub mod field_crypto;
+pub mod fingerprint;
+pub mod i18n;
 pub mod jwt;
-use nanoid::nanoid;
+pub mod money;
+pub mod normalize;
+pub mod otp;
+pub mod ownership;
+pub mod pagination_headers;
+pub mod password;
+pub mod quantity;
+pub mod redact;
+pub mod request_context;
+pub mod sanitize;
+pub mod shutdown;
+pub mod signed_url;
+pub mod sku;
+pub mod slug;
+#[cfg(feature = "smtp")]
+pub mod smtp;
+pub mod string_enum;
+pub mod token_hash;
+pub mod totp;
+pub mod upload;
+pub mod validation;
+pub mod webhook;
 
 use crate::errors::ServiceError;
-use crate::utils::jwt::{decode_jwt, is_jwt_expired};
-use actix_web::HttpRequest;
-use argon2::{
-    Argon2,
-    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
-};
-use bson::oid::ObjectId;
-use mongodb::error::{Error, ErrorKind, WriteFailure};
-use serde::Serializer;
+use crate::utils::fingerprint::verify_fingerprint_with_claims;
+use crate::utils::i18n::{Message, t};
+use crate::utils::jwt::{Claims, validate_access_token};
+use crate::utils::request_context::log_with_context;
+use actix_web::{HttpRequest, http::header::AUTHORIZATION, web};
+use bson::{DateTime as BsonDateTime, oid::ObjectId};
+use chrono::{SecondsFormat, Utc};
+use log::Level;
+use mongodb::error::{Error, ErrorKind, IndexedWriteError, WriteFailure};
+pub use password::{hash_password, verify_password};
+use serde::{Deserialize, Deserializer, Serializer, de::Error as DeError};
+pub use sku::generate_random_sku;
+use std::collections::HashMap;
 
 pub fn object_id_as_string<S>(id: &ObjectId, serializer: S) -> Result<S::Ok, S::Error>
 where
@@ -29,75 +67,917 @@ where
     }
 }
 
+/// Pasangan `object_id_as_string` untuk `#[serde(deserialize_with = ...)]`
+pub fn deserialize_object_id<'de, D>(deserializer: D) -> Result<ObjectId, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw = String::deserialize(deserializer)?;
+    ObjectId::parse_str(raw.trim())
+        .map_err(|_| D::Error::custom(format!("ID '{}' tidak valid", raw)))
+}
+
+/// String kosong atau `null` dianggap `None`. Pakai bersama `#[serde(default)]`
+pub fn deserialize_opt_object_id<'de, D>(deserializer: D) -> Result<Option<ObjectId>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw: Option<String> = Option::deserialize(deserializer)?;
+    match raw.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(id) => ObjectId::parse_str(id)
+            .map(Some)
+            .map_err(|_| D::Error::custom(format!("ID '{}' tidak valid", id))),
+    }
+}
+
+/// Serialize `Vec<ObjectId>` sebagai array string hex, contoh field referensi `tags`
+pub fn object_id_vec_as_strings<S>(ids: &[ObjectId], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_seq(ids.iter().map(|id| id.to_hex()))
+}
+
+/// Pasangan `object_id_vec_as_strings`, error menyebutkan index elemen yang rusak
+pub fn deserialize_object_id_vec<'de, D>(deserializer: D) -> Result<Vec<ObjectId>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw: Vec<String> = Vec::deserialize(deserializer)?;
+    raw.iter()
+        .enumerate()
+        .map(|(index, id)| {
+            ObjectId::parse_str(id.trim()).map_err(|_| {
+                D::Error::custom(format!("ID '{}' pada index {} tidak valid", id, index))
+            })
+        })
+        .collect()
+}
+
+/// Format `bson::DateTime` sebagai string RFC 3339, contoh: "2025-07-12T08:30:00.000Z"
+pub fn format_iso_datetime(dt: &BsonDateTime) -> String {
+    dt.to_chrono().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Serialize `bson::DateTime` dengan format `format_iso_datetime`
+pub fn datetime_as_iso_string<S>(dt: &BsonDateTime, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&format_iso_datetime(dt))
+}
+
+pub fn opt_datetime_as_iso_string<S>(
+    dt: &Option<BsonDateTime>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match dt {
+        Some(dt) => datetime_as_iso_string(dt, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn parse_iso_datetime<E: DeError>(raw: &str) -> Result<BsonDateTime, E> {
+    chrono::DateTime::parse_from_rfc3339(raw)
+        .map(|dt| BsonDateTime::from_chrono(dt.with_timezone(&Utc)))
+        .map_err(|_| E::custom(format!("Tanggal '{}' bukan format RFC 3339", raw)))
+}
+
+pub fn deserialize_iso_datetime<'de, D>(deserializer: D) -> Result<BsonDateTime, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw = String::deserialize(deserializer)?;
+    parse_iso_datetime(&raw)
+}
+
+pub fn deserialize_opt_iso_datetime<'de, D>(
+    deserializer: D,
+) -> Result<Option<BsonDateTime>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw: Option<String> = Option::deserialize(deserializer)?;
+    raw.map(|raw| parse_iso_datetime(&raw)).transpose()
+}
+
 pub fn string_id_to_obj_id(id: &str) -> Option<ObjectId> {
     ObjectId::parse_str(id).ok()
 }
 
-pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
-    let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
+/// Parse id dari path/token, id yang rusak selalu menjadi `BadRequest("id tidak valid")`
+pub fn parse_object_id_param(id: &str) -> Result<ObjectId, ServiceError> {
+    string_id_to_obj_id(id.trim()).ok_or_else(|| ServiceError::BadRequest("id tidak valid".into()))
+}
+
+/// Ubah error 11000 menjadi `DuplicateField`, atau `Conflict` generik jika nama field
+/// tidak bisa dibaca dari pesan error. `None` berarti bukan error duplicate key.
+pub fn handle_duplicate_key_error(err: &Error) -> Option<ServiceError> {
+    let message = match err.kind.as_ref() {
+        ErrorKind::Write(WriteFailure::WriteError(write_error)) if write_error.code == 11000 => {
+            &write_error.message
+        }
+        ErrorKind::Command(command_error) if command_error.code == 11000 => &command_error.message,
+        ErrorKind::Write(_) => {
+            log_with_context(
+                Level::Warn,
+                "Write failure bukan WriteError, tidak diproses sebagai duplicate key.",
+            );
+            return None;
+        }
+        _ => return None,
+    };
 
-    let hash = argon2
-        .hash_password(password.as_bytes(), &salt)?
-        .to_string();
-    Ok(hash)
+    Some(duplicate_key_from_message(message))
 }
 
-pub fn verify_password(password: &str, password_hash: &str) -> bool {
-    let parsed_hash = PasswordHash::new(password_hash);
-    if let Ok(parsed_hash) = parsed_hash {
-        Argon2::default()
-            .verify_password(password.as_bytes(), &parsed_hash)
-            .is_ok()
-    } else {
-        false
+/// Bagian `handle_duplicate_key_error` yang membaca pesan 11000, dipakai juga untuk
+/// error per item `insert_many`
+fn duplicate_key_from_message(message: &str) -> ServiceError {
+    let fields = extract_duplicate_fields(message);
+    if fields.is_empty() {
+        log_with_context(
+            Level::Warn,
+            &format!("Gagal membaca field dari error duplicate key: {}", message),
+        );
+        return ServiceError::Conflict(t(Message::DuplicateData));
     }
+
+    ServiceError::DuplicateField { fields }
 }
 
-pub fn handle_duplicate_key_error(err: &Error) -> Option<ServiceError> {
-    if let ErrorKind::Write(write_failure) = err.kind.as_ref() {
-        match write_failure {
-            WriteFailure::WriteError(write_error) => {
-                if write_error.code == 11000 {
-                    if let Some(field) = extract_duplicate_field(&write_error.message) {
-                        return Some(ServiceError::Conflict(format!("{} sudah digunakan", field)));
-                    }
-                }
-            }
-            _ => {
-                log::warn!("Write failure bukan WriteError, tidak diproses sebagai duplicate key.");
-            }
+// Kode error server MongoDB yang diterjemahkan khusus oleh `map_mongo_error`
+const DUPLICATE_KEY_CODE: i32 = 11000;
+const DOCUMENT_VALIDATION_FAILURE_CODE: i32 = 121;
+const MAX_TIME_MS_EXPIRED_CODE: i32 = 50;
+const WRITE_CONCERN_FAILED_CODE: i32 = 64;
+
+fn server_error_code(err: &Error) -> Option<i32> {
+    match err.kind.as_ref() {
+        ErrorKind::Command(command_error) => Some(command_error.code),
+        ErrorKind::Write(WriteFailure::WriteError(write_error)) => Some(write_error.code),
+        ErrorKind::Write(WriteFailure::WriteConcernError(concern_error)) => {
+            Some(concern_error.code)
         }
+        _ => None,
     }
+}
 
-    None
+/// Error 11000, dipakai untuk memutuskan retry sebelum diterjemahkan
+pub fn is_duplicate_key_error(err: &Error) -> bool {
+    server_error_code(err) == Some(DUPLICATE_KEY_CODE)
 }
 
-fn extract_duplicate_field(message: &str) -> Option<String> {
-    let re = regex::Regex::new(r#"dup key: \{ ([^:]+):"#).ok()?;
-    re.captures(message)
-        .and_then(|caps| caps.get(1))
+/// Terjemahkan error MongoDB ke `ServiceError` yang sesuai:
+/// duplicate key (11000) lewat `handle_duplicate_key_error`, validasi schema (121) menjadi
+/// `BadRequest`, timeout dan gangguan jaringan menjadi `ServiceUnavailable`, dokumen yang
+/// tidak sesuai model menjadi `Unexpected`, sisanya `Internal` dengan error aslinya.
+pub fn map_mongo_error(err: Error) -> ServiceError {
+    match server_error_code(&err) {
+        Some(DUPLICATE_KEY_CODE) => {
+            return handle_duplicate_key_error(&err)
+                .unwrap_or_else(|| ServiceError::Conflict(t(Message::DuplicateData)));
+        }
+        Some(DOCUMENT_VALIDATION_FAILURE_CODE) => {
+            return ServiceError::BadRequest("Data tidak memenuhi aturan validasi database".into());
+        }
+        Some(MAX_TIME_MS_EXPIRED_CODE | WRITE_CONCERN_FAILED_CODE) => {
+            return ServiceError::ServiceUnavailable(
+                "Database terlalu lama merespons, coba lagi nanti".into(),
+            );
+        }
+        _ => {}
+    }
+
+    match err.kind.as_ref() {
+        ErrorKind::Io(_)
+        | ErrorKind::ConnectionPoolCleared { .. }
+        | ErrorKind::ServerSelection { .. }
+        | ErrorKind::DnsResolve { .. } => {
+            log_with_context(
+                Level::Error,
+                &format!("Database tidak dapat dihubungi: {}", err),
+            );
+            ServiceError::ServiceUnavailable("Database tidak dapat dihubungi".into())
+        }
+        ErrorKind::BsonDeserialization(e) => {
+            ServiceError::Unexpected(format!("Data di database tidak sesuai format: {}", e))
+        }
+        _ => ServiceError::internal("Operasi database gagal", err),
+    }
+}
+
+/// Terjemahkan error satu dokumen dari `insert_many`, aturannya sama dengan `map_mongo_error`
+pub fn map_indexed_write_error(err: &IndexedWriteError) -> ServiceError {
+    match err.code {
+        DUPLICATE_KEY_CODE => duplicate_key_from_message(&err.message),
+        DOCUMENT_VALIDATION_FAILURE_CODE => {
+            ServiceError::BadRequest("Data tidak memenuhi aturan validasi database".into())
+        }
+        code => ServiceError::DatabaseError(format!("code {}: {}", code, err.message)),
+    }
+}
+
+/// Ambil semua nama field dari pesan error 11000, termasuk compound index
+/// seperti `dup key: { user_id: ObjectId('...'), sku: "A1" }`. Nama field boleh diapit
+/// kutip atau backtick. Jika bagian `dup key` tidak bisa dibaca, pakai nama index.
+fn extract_duplicate_fields(message: &str) -> Vec<String> {
+    let fields = extract_dup_key_fields(message);
+    if !fields.is_empty() {
+        return fields;
+    }
+
+    extract_index_fields(message)
+}
+
+fn extract_dup_key_fields(message: &str) -> Vec<String> {
+    let Some(start) = message.find("dup key: {") else {
+        return Vec::new();
+    };
+    let body = &message[start + "dup key: {".len()..];
+
+    let re = match regex::Regex::new(r#"(?:^|,)\s*["'`]?([A-Za-z_][\w.]*)["'`]?\s*:\s"#) {
+        Ok(re) => re,
+        Err(_) => return Vec::new(),
+    };
+
+    re.captures_iter(body)
+        .filter_map(|caps| caps.get(1))
         .map(|m| m.as_str().to_string())
+        .collect()
+}
+
+// Nama index default MongoDB berbentuk `<field>_<arah>`, contoh: `user_id_1_sku_-1`
+fn extract_index_fields(message: &str) -> Vec<String> {
+    let re = match regex::Regex::new(r#"index:\s*["'`]?([\w.$-]+)["'`]?"#) {
+        Ok(re) => re,
+        Err(_) => return Vec::new(),
+    };
+    let Some(index_name) = re
+        .captures(message)
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str())
+    else {
+        return Vec::new();
+    };
+
+    let mut fields = Vec::new();
+    let mut parts: Vec<&str> = Vec::new();
+    for token in index_name.split('_') {
+        if matches!(token, "1" | "-1" | "text" | "hashed" | "2d" | "2dsphere") && !parts.is_empty()
+        {
+            fields.push(parts.join("_"));
+            parts.clear();
+        } else {
+            parts.push(token);
+        }
+    }
+
+    // Index dengan nama custom, kembalikan apa adanya
+    if fields.is_empty() {
+        fields.push(index_name.to_string());
+    }
+    fields
+}
+
+/// Asal token JWT pada sebuah request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenSource {
+    Cookie,
+    Header,
+    // Query param `?token=`, hanya diterima saat handshake WebSocket
+    Query,
+}
+
+/// Nama query param token untuk handshake WebSocket. Nilainya tidak boleh ikut ter-log
+pub const HANDSHAKE_TOKEN_PARAM: &str = "token";
+
+/// Ambil token JWT dari cookie `auth_token`, fallback ke header `Authorization: Bearer <token>`.
+/// Jika keduanya ada tapi berbeda, token dari header yang dipakai.
+pub fn extract_token(req: &HttpRequest) -> Option<(String, TokenSource)> {
+    let cookie_token = req
+        .cookie(cookie::AUTH_COOKIE_NAME)
+        .map(|c| c.value().to_string());
+    let header_token = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty());
+
+    match (cookie_token, header_token) {
+        (Some(cookie), Some(header)) => {
+            if cookie != header {
+                log_with_context(
+                    Level::Warn,
+                    "Token di cookie dan header Authorization berbeda, memakai header.",
+                );
+            }
+            Some((header, TokenSource::Header))
+        }
+        (Some(cookie), None) => Some((cookie, TokenSource::Cookie)),
+        (None, Some(header)) => Some((header, TokenSource::Header)),
+        (None, None) => None,
+    }
+}
+
+/// Token untuk handshake WebSocket. Browser tidak bisa mengirim header `Authorization` saat
+/// upgrade, jadi selain cookie/header token juga diterima dari query param `?token=`.
+/// Cookie/header tetap diutamakan jika ada.
+pub fn extract_handshake_token(req: &HttpRequest) -> Option<(String, TokenSource)> {
+    if let Some(found) = extract_token(req) {
+        return Some(found);
+    }
+
+    let query = web::Query::<HashMap<String, String>>::from_query(req.query_string()).ok()?;
+    query
+        .get(HANDSHAKE_TOKEN_PARAM)
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .map(|t| (t, TokenSource::Query))
+}
+
+/// Ekstrak claims JWT yang sudah divalidasi dari cookie atau header Authorization
+pub fn extract_claims(req: &HttpRequest) -> Result<Claims, ServiceError> {
+    let (token, _source) =
+        extract_token(req).ok_or_else(|| ServiceError::Unauthorized(t(Message::TokenNotFound)))?;
+
+    let claims = validate_access_token(&token)?;
+    verify_fingerprint_with_claims(req, &claims)?;
+    Ok(claims)
+}
+
+/// Seperti `extract_claims`, tapi token juga boleh datang dari query param handshake WebSocket
+pub fn extract_handshake_claims(req: &HttpRequest) -> Result<Claims, ServiceError> {
+    let (token, _source) = extract_handshake_token(req)
+        .ok_or_else(|| ServiceError::Unauthorized(t(Message::TokenNotFound)))?;
+
+    let claims = validate_access_token(&token)?;
+    verify_fingerprint_with_claims(req, &claims)?;
+    Ok(claims)
 }
 
-/// Generate SKU otomatis, contoh: "SKU-X7D2F"
-pub fn generate_random_sku() -> String {
-    format!("SKU-{}", nanoid!(5).to_uppercase())
+/// Ekstrak user_id dari token JWT (cookie atau header Authorization)
+pub fn extract_user_id(req: &HttpRequest) -> Result<String, ServiceError> {
+    Ok(extract_claims(req)?.sub)
 }
 
-/// Ekstrak user_id dari cookie JWT
-pub fn extract_user_id_from_cookie(req: &HttpRequest) -> Result<String, ServiceError> {
-    let cookie = req
-        .cookie("auth_token")
-        .ok_or_else(|| ServiceError::Unauthorized("Token tidak ditemukan".into()))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{init_test_config, make_test_token};
+    use actix_web::cookie::Cookie;
+    use actix_web::test::TestRequest;
+    use bson::doc;
+    use chrono::Duration;
+    use mongodb::error::CommandError;
+    use serde::Serialize;
+
+    fn bearer(token: &str) -> (actix_web::http::header::HeaderName, String) {
+        (AUTHORIZATION, format!("Bearer {}", token))
+    }
+
+    #[test]
+    fn extract_token_reads_cookie() {
+        let req = TestRequest::default()
+            .cookie(Cookie::new(cookie::AUTH_COOKIE_NAME, "dari-cookie"))
+            .to_http_request();
+
+        assert_eq!(
+            extract_token(&req),
+            Some(("dari-cookie".to_string(), TokenSource::Cookie))
+        );
+    }
+
+    #[test]
+    fn extract_token_falls_back_to_bearer_header() {
+        let req = TestRequest::default()
+            .insert_header(bearer("dari-header"))
+            .to_http_request();
+
+        assert_eq!(
+            extract_token(&req),
+            Some(("dari-header".to_string(), TokenSource::Header))
+        );
+    }
+
+    #[test]
+    fn extract_token_prefers_header_when_both_differ() {
+        let req = TestRequest::default()
+            .cookie(Cookie::new(cookie::AUTH_COOKIE_NAME, "dari-cookie"))
+            .insert_header(bearer("dari-header"))
+            .to_http_request();
+
+        assert_eq!(
+            extract_token(&req),
+            Some(("dari-header".to_string(), TokenSource::Header))
+        );
+    }
+
+    #[test]
+    fn extract_token_ignores_non_bearer_header() {
+        let req = TestRequest::default()
+            .insert_header((AUTHORIZATION, "Basic dXNlcjpwYXNz"))
+            .to_http_request();
 
-    let token = cookie.value();
+        assert_eq!(extract_token(&req), None);
+    }
+
+    #[test]
+    fn extract_user_id_accepts_cookie_and_header() {
+        init_test_config();
+        let token = make_test_token("user-1", "user", Duration::minutes(5));
+
+        let from_cookie = TestRequest::default()
+            .cookie(Cookie::new(cookie::AUTH_COOKIE_NAME, token.clone()))
+            .to_http_request();
+        let from_header = TestRequest::default()
+            .insert_header(bearer(&token))
+            .to_http_request();
+
+        assert_eq!(extract_user_id(&from_cookie).unwrap(), "user-1");
+        assert_eq!(extract_user_id(&from_header).unwrap(), "user-1");
+    }
+
+    #[test]
+    fn extract_user_id_rejects_expired_token_from_both_sources() {
+        init_test_config();
+        let token = make_test_token("user-1", "user", Duration::minutes(-5));
+
+        let from_cookie = TestRequest::default()
+            .cookie(Cookie::new(cookie::AUTH_COOKIE_NAME, token.clone()))
+            .to_http_request();
+        let from_header = TestRequest::default()
+            .insert_header(bearer(&token))
+            .to_http_request();
+
+        let cookie_err = extract_user_id(&from_cookie).unwrap_err();
+        let header_err = extract_user_id(&from_header).unwrap_err();
+        assert!(matches!(cookie_err, ServiceError::Unauthorized(_)));
+        assert_eq!(cookie_err.to_string(), header_err.to_string());
+    }
+
+    #[test]
+    fn extract_user_id_without_token_is_unauthorized() {
+        let req = TestRequest::default().to_http_request();
+
+        match extract_user_id(&req) {
+            Err(ServiceError::Unauthorized(msg)) => assert_eq!(msg, t(Message::TokenNotFound)),
+            other => panic!("hasil tidak terduga: {:?}", other.map(|_| ())),
+        }
+    }
+
+    fn write_error(code: i32, message: &str) -> Error {
+        let write_error = bson::from_document(doc! { "code": code, "errmsg": message }).unwrap();
+        Error::from(ErrorKind::Write(WriteFailure::WriteError(write_error)))
+    }
+
+    fn duplicate_fields(message: &str) -> Vec<String> {
+        match handle_duplicate_key_error(&write_error(11000, message)) {
+            Some(ServiceError::DuplicateField { fields }) => fields,
+            other => panic!("hasil tidak terduga: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn duplicate_key_single_field() {
+        let fields = duplicate_fields(
+            r#"E11000 duplicate key error collection: qtoky.users index: email_1 dup key: { email: "a@b.co" }"#,
+        );
+
+        assert_eq!(fields, vec!["email"]);
+    }
+
+    #[test]
+    fn duplicate_key_compound_index_returns_all_fields() {
+        let fields = duplicate_fields(
+            r#"E11000 duplicate key error collection: qtoky.products index: user_id_1_sku_1 dup key: { user_id: ObjectId('64b7f0c2a1b2c3d4e5f60718'), sku: "SKU-A1" }"#,
+        );
+
+        assert_eq!(fields, vec!["user_id", "sku"]);
+    }
+
+    #[test]
+    fn duplicate_key_keeps_indonesian_message() {
+        let err = handle_duplicate_key_error(&write_error(
+            11000,
+            r#"E11000 duplicate key error collection: qtoky.users index: username_1 dup key: { username: "budi" }"#,
+        ))
+        .unwrap();
+
+        assert_eq!(
+            err.to_string(),
+            format!("username {}", t(Message::AlreadyUsed))
+        );
+    }
+
+    #[test]
+    fn non_duplicate_write_error_is_ignored() {
+        assert!(
+            handle_duplicate_key_error(&write_error(121, "Document failed validation")).is_none()
+        );
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct IdPayload {
+        #[serde(
+            serialize_with = "object_id_as_string",
+            deserialize_with = "deserialize_object_id"
+        )]
+        id: ObjectId,
+        #[serde(
+            default,
+            serialize_with = "opt_object_id_as_string",
+            deserialize_with = "deserialize_opt_object_id"
+        )]
+        parent_id: Option<ObjectId>,
+    }
+
+    #[test]
+    fn object_id_fields_round_trip_as_hex() {
+        let payload = IdPayload {
+            id: ObjectId::new(),
+            parent_id: Some(ObjectId::new()),
+        };
+
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json["id"], payload.id.to_hex());
+        assert_eq!(serde_json::from_value::<IdPayload>(json).unwrap(), payload);
+
+        let without_parent = IdPayload {
+            parent_id: None,
+            ..payload
+        };
+        let json = serde_json::to_string(&without_parent).unwrap();
+        assert_eq!(
+            serde_json::from_str::<IdPayload>(&json).unwrap(),
+            without_parent
+        );
+    }
+
+    #[test]
+    fn empty_or_null_optional_id_is_none() {
+        let id = ObjectId::new().to_hex();
+        for parent in ["\"\"", "null"] {
+            let json = format!(r#"{{"id":"{}","parent_id":{}}}"#, id, parent);
+            assert_eq!(
+                serde_json::from_str::<IdPayload>(&json).unwrap().parent_id,
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_object_id_is_a_serde_error() {
+        let err = serde_json::from_str::<IdPayload>(r#"{"id":"bukan-id"}"#).unwrap_err();
+        assert!(err.to_string().contains("ID 'bukan-id' tidak valid"));
+
+        let json = format!(
+            r#"{{"id":"{}","parent_id":"rusak"}}"#,
+            ObjectId::new().to_hex()
+        );
+        assert!(serde_json::from_str::<IdPayload>(&json).is_err());
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct DatePayload {
+        #[serde(
+            serialize_with = "datetime_as_iso_string",
+            deserialize_with = "deserialize_iso_datetime"
+        )]
+        created_at: BsonDateTime,
+        #[serde(
+            default,
+            serialize_with = "opt_datetime_as_iso_string",
+            deserialize_with = "deserialize_opt_iso_datetime"
+        )]
+        paid_at: Option<BsonDateTime>,
+    }
+
+    #[test]
+    fn datetime_serializes_as_exact_iso_string() {
+        let payload = DatePayload {
+            created_at: BsonDateTime::from_millis(1_752_309_000_123),
+            paid_at: None,
+        };
+
+        let json = serde_json::to_value(&payload).unwrap();
+
+        assert_eq!(json["created_at"], "2025-07-12T08:30:00.123Z");
+        assert!(json["paid_at"].is_null());
+    }
+
+    #[test]
+    fn datetime_round_trip_keeps_milliseconds() {
+        let payload = DatePayload {
+            created_at: BsonDateTime::from_millis(1_752_309_000_123),
+            paid_at: Some(BsonDateTime::from_millis(1_752_309_999_007)),
+        };
+
+        let json = serde_json::to_string(&payload).unwrap();
+        let parsed: DatePayload = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed, payload);
+        assert_eq!(parsed.created_at.timestamp_millis(), 1_752_309_000_123);
+    }
+
+    #[test]
+    fn offset_datetime_is_converted_to_utc() {
+        let json = r#"{"created_at":"2025-07-12T15:30:00.123+07:00"}"#;
+
+        let parsed: DatePayload = serde_json::from_str(json).unwrap();
+
+        assert_eq!(parsed.created_at.timestamp_millis(), 1_752_309_000_123);
+        assert_eq!(parsed.paid_at, None);
+    }
+
+    #[test]
+    fn invalid_datetime_is_a_serde_error() {
+        let err =
+            serde_json::from_str::<DatePayload>(r#"{"created_at":"12/07/2025"}"#).unwrap_err();
+        assert!(err.to_string().contains("bukan format RFC 3339"));
+
+        let json = r#"{"created_at":"2025-07-12T08:30:00Z","paid_at":"kemarin"}"#;
+        assert!(serde_json::from_str::<DatePayload>(json).is_err());
+    }
+
+    #[test]
+    fn duplicate_key_quoted_field_names() {
+        assert_eq!(
+            duplicate_fields(
+                r#"E11000 duplicate key error collection: qtoky.users index: email_1 dup key: { "email": "a@b.co" }"#
+            ),
+            vec!["email"]
+        );
+        assert_eq!(
+            duplicate_fields(
+                r#"E11000 duplicate key error collection: qtoky.products index: sku_1 dup key: { `user_id`: ObjectId('64b7f0c2a1b2c3d4e5f60718'), 'sku': "A1" }"#
+            ),
+            vec!["user_id", "sku"]
+        );
+    }
+
+    #[test]
+    fn duplicate_key_falls_back_to_index_name() {
+        assert_eq!(
+            duplicate_fields(
+                "E11000 duplicate key error collection: qtoky.products index: user_id_1_sku_-1"
+            ),
+            vec!["user_id", "sku"]
+        );
+        assert_eq!(
+            duplicate_fields(
+                r#"E11000 duplicate key error collection: qtoky.users index: "email_1" dup key: { : "a@b.co" }"#
+            ),
+            vec!["email"]
+        );
+        assert_eq!(
+            duplicate_fields(
+                "E11000 duplicate key error collection: qtoky.products index: uniq_sku"
+            ),
+            vec!["uniq_sku"]
+        );
+    }
+
+    #[test]
+    fn unparseable_duplicate_key_is_still_conflict() {
+        let err = write_error(11000, "E11000 duplicate key error");
+
+        assert!(matches!(
+            handle_duplicate_key_error(&err),
+            Some(ServiceError::Conflict(_))
+        ));
+        assert!(matches!(map_mongo_error(err), ServiceError::Conflict(_)));
+    }
+
+    #[test]
+    fn duplicate_key_from_command_error() {
+        let command_error: CommandError = bson::from_document(doc! {
+            "code": 11000,
+            "codeName": "DuplicateKey",
+            "errmsg": r#"E11000 duplicate key error collection: qtoky.users index: username_1 dup key: { username: "budi" }"#,
+        })
+        .unwrap();
+        let err = Error::from(ErrorKind::Command(command_error));
+
+        assert!(matches!(
+            map_mongo_error(err),
+            ServiceError::DuplicateField { fields } if fields == vec!["username"]
+        ));
+    }
+
+    fn command_error(code: i32, code_name: &str) -> Error {
+        let command_error: CommandError = bson::from_document(doc! {
+            "code": code,
+            "codeName": code_name,
+            "errmsg": "pesan dari server",
+        })
+        .unwrap();
+        Error::from(ErrorKind::Command(command_error))
+    }
+
+    #[test]
+    fn schema_validation_failure_is_bad_request() {
+        let err = write_error(121, "Document failed validation");
+
+        assert!(matches!(
+            map_mongo_error(err),
+            ServiceError::BadRequest(msg) if msg.contains("aturan validasi")
+        ));
+    }
+
+    #[test]
+    fn timeouts_are_service_unavailable() {
+        let concern_error = bson::from_document(doc! {
+            "code": 64,
+            "codeName": "WriteConcernFailed",
+            "errmsg": "waiting for replication timed out",
+        })
+        .unwrap();
+        let write_concern = Error::from(ErrorKind::Write(WriteFailure::WriteConcernError(
+            concern_error,
+        )));
+
+        for err in [command_error(50, "MaxTimeMSExpired"), write_concern] {
+            assert!(matches!(
+                map_mongo_error(err),
+                ServiceError::ServiceUnavailable(msg) if msg.contains("terlalu lama")
+            ));
+        }
+    }
+
+    #[test]
+    fn network_error_is_service_unavailable() {
+        let io = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "reset");
+        let err = Error::from(ErrorKind::Io(std::sync::Arc::new(io)));
+
+        assert!(matches!(
+            map_mongo_error(err),
+            ServiceError::ServiceUnavailable(msg) if msg == "Database tidak dapat dihubungi"
+        ));
+    }
+
+    #[test]
+    fn bad_document_is_unexpected() {
+        let bson_error = bson::from_document::<IdPayload>(doc! { "id": 5 }).unwrap_err();
+        let err = Error::from(ErrorKind::BsonDeserialization(bson_error));
+
+        assert!(matches!(map_mongo_error(err), ServiceError::Unexpected(_)));
+    }
+
+    #[test]
+    fn unknown_server_error_is_internal() {
+        assert!(matches!(
+            map_mongo_error(command_error(13, "Unauthorized")),
+            ServiceError::Internal { .. }
+        ));
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct TagsPayload {
+        #[serde(
+            serialize_with = "object_id_vec_as_strings",
+            deserialize_with = "deserialize_object_id_vec"
+        )]
+        tag_ids: Vec<ObjectId>,
+    }
+
+    #[test]
+    fn object_id_vec_round_trips_as_hex_strings() {
+        let payload = TagsPayload {
+            tag_ids: vec![ObjectId::new(), ObjectId::new()],
+        };
+
+        let json = serde_json::to_value(&payload).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "tag_ids": [payload.tag_ids[0].to_hex(), payload.tag_ids[1].to_hex()]
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<TagsPayload>(json).unwrap(),
+            payload
+        );
+    }
+
+    #[test]
+    fn empty_object_id_vec_round_trips() {
+        let payload = TagsPayload { tag_ids: vec![] };
+
+        let json = serde_json::to_value(&payload).unwrap();
+
+        assert_eq!(json, serde_json::json!({ "tag_ids": [] }));
+        assert_eq!(
+            serde_json::from_value::<TagsPayload>(json).unwrap(),
+            payload
+        );
+    }
+
+    #[test]
+    fn malformed_vec_element_names_its_index() {
+        let json = serde_json::json!({
+            "tag_ids": [ObjectId::new().to_hex(), "bukan-id"]
+        });
+
+        let err = serde_json::from_value::<TagsPayload>(json).unwrap_err();
+
+        assert!(
+            err.to_string()
+                .contains("ID 'bukan-id' pada index 1 tidak valid")
+        );
+    }
+
+    fn indexed_write_error(code: i32, message: &str) -> IndexedWriteError {
+        bson::from_document(doc! { "index": 2, "code": code, "errmsg": message }).unwrap()
+    }
+
+    #[test]
+    fn indexed_write_errors_are_translated_per_item() {
+        let duplicate = indexed_write_error(
+            11000,
+            r#"E11000 duplicate key error collection: qtoky.products index: sku_1 dup key: { sku: "KOPI" }"#,
+        );
+
+        assert!(matches!(
+            map_indexed_write_error(&duplicate),
+            ServiceError::DuplicateField { fields } if fields == vec!["sku".to_string()]
+        ));
+        assert!(matches!(
+            map_indexed_write_error(&indexed_write_error(121, "Document failed validation")),
+            ServiceError::BadRequest(_)
+        ));
+        assert!(matches!(
+            map_indexed_write_error(&indexed_write_error(2, "bad value")),
+            ServiceError::DatabaseError(msg) if msg.contains("code 2")
+        ));
+    }
+
+    #[test]
+    fn handshake_accepts_token_from_query_param() {
+        init_test_config();
+        let token = make_test_token("user-ws", "user", Duration::minutes(5));
+        let req = TestRequest::get()
+            .uri(&format!(
+                "/ws/inventory?{}={}",
+                HANDSHAKE_TOKEN_PARAM, token
+            ))
+            .to_http_request();
+
+        let claims = extract_handshake_claims(&req).unwrap();
+
+        assert_eq!(claims.sub, "user-ws");
+        assert_eq!(
+            extract_handshake_token(&req).map(|(_, source)| source),
+            Some(TokenSource::Query)
+        );
+        // Request biasa tidak menerima token dari query string
+        assert!(matches!(
+            extract_claims(&req),
+            Err(ServiceError::Unauthorized(msg)) if msg == t(Message::TokenNotFound)
+        ));
+    }
+
+    #[test]
+    fn handshake_rejects_missing_or_invalid_token_as_unauthorized() {
+        init_test_config();
+        let expired = make_test_token("user-ws", "user", Duration::minutes(-5));
+        let requests = [
+            TestRequest::get().uri("/ws/inventory").to_http_request(),
+            TestRequest::get()
+                .uri("/ws/inventory?token=")
+                .to_http_request(),
+            TestRequest::get()
+                .uri("/ws/inventory?token=bukan-jwt")
+                .to_http_request(),
+            TestRequest::get()
+                .uri(&format!("/ws/inventory?token={}", expired))
+                .to_http_request(),
+        ];
+
+        for req in requests {
+            assert!(matches!(
+                extract_handshake_claims(&req),
+                Err(ServiceError::Unauthorized(_))
+            ));
+        }
+    }
 
-    let decoded =
-        decode_jwt(&token).map_err(|_| ServiceError::Unauthorized("Token tidak valid".into()))?;
+    #[test]
+    fn handshake_prefers_cookie_over_query_param() {
+        init_test_config();
+        let cookie_token = make_test_token("user-cookie", "user", Duration::minutes(5));
+        let req = TestRequest::get()
+            .uri("/ws/inventory?token=bukan-jwt")
+            .cookie(Cookie::new(cookie::AUTH_COOKIE_NAME, cookie_token))
+            .to_http_request();
 
-    if is_jwt_expired(decoded.claims.exp) {
-        return Err(ServiceError::Unauthorized("Token sudah expired".into()));
+        assert_eq!(extract_handshake_claims(&req).unwrap().sub, "user-cookie");
     }
-    Ok(decoded.claims.sub) // atau decoded.claims.user_id
 }
@@ -0,0 +1,317 @@
+use crate::errors::ServiceError;
+use serde::{
+    Deserializer, Serializer,
+    de::{Error as DeError, Visitor},
+};
+use std::fmt;
+
+/// Jumlah digit desimal yang disimpan, nilai uang disimpan sebagai integer minor unit
+/// (contoh: Rp 12.500,50 disimpan sebagai 1250050)
+pub const MONEY_SCALE: u32 = 2;
+const MINOR_PER_MAJOR: i64 = 10_i64.pow(MONEY_SCALE);
+
+/// Format minor unit menjadi string desimal dengan skala tetap, contoh: 1250050 -> "12500.50"
+pub fn format_money(minor: i64) -> String {
+    let sign = if minor < 0 { "-" } else { "" };
+    let abs = minor.unsigned_abs();
+    let per_major = MINOR_PER_MAJOR as u64;
+    format!(
+        "{}{}.{:0width$}",
+        sign,
+        abs / per_major,
+        abs % per_major,
+        width = MONEY_SCALE as usize
+    )
+}
+
+/// Parse string desimal menjadi minor unit, ditolak jika digit desimalnya melebihi `MONEY_SCALE`
+pub fn parse_money(raw: &str) -> Result<i64, String> {
+    let raw = raw.trim();
+    let (negative, unsigned) = match raw.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, raw),
+    };
+    let (major, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+
+    let is_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if major.is_empty() || !is_digits(major) || !is_digits(fraction) {
+        return Err(format!("Nilai uang '{}' tidak valid", raw));
+    }
+    if fraction.len() > MONEY_SCALE as usize {
+        return Err(format!(
+            "Nilai uang maksimal {} digit di belakang koma",
+            MONEY_SCALE
+        ));
+    }
+
+    let overflow = || format!("Nilai uang '{}' terlalu besar", raw);
+    let major: i64 = major.parse().map_err(|_| overflow())?;
+    let fraction: i64 = if fraction.is_empty() {
+        0
+    } else {
+        // "5" berarti 50 sen, bukan 5 sen
+        let padded = format!("{:0<width$}", fraction, width = MONEY_SCALE as usize);
+        padded.parse().map_err(|_| overflow())?
+    };
+
+    let minor = major
+        .checked_mul(MINOR_PER_MAJOR)
+        .and_then(|m| m.checked_add(fraction))
+        .ok_or_else(overflow)?;
+    Ok(if negative { -minor } else { minor })
+}
+
+/// Parse input rupiah format Indonesia menjadi minor unit, contoh `"Rp1.000.000"` atau
+/// `"1.000.000,50"`. Titik adalah pemisah ribuan dan koma pemisah desimal; prefix `Rp`/`Rp.`
+/// dan spasi diabaikan. Input ambigu seperti `"1.5"` atau `"1.00.000"` ditolak dengan
+/// `BadRequest` agar tidak salah dibaca sebagai desimal.
+pub fn parse_rupiah(raw: &str) -> Result<i64, ServiceError> {
+    let invalid =
+        || ServiceError::BadRequest(format!("Nominal rupiah '{}' tidak valid", raw.trim()));
+
+    let compact: String = raw.chars().filter(|c| !c.is_whitespace()).collect();
+    let (negative, unsigned) = match compact.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, compact.as_str()),
+    };
+    let unsigned = match unsigned.get(..2) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("rp") => {
+            let rest = &unsigned[2..];
+            rest.strip_prefix('.').unwrap_or(rest)
+        }
+        _ => unsigned,
+    };
+
+    let (major, fraction) = match unsigned.split_once(',') {
+        Some((major, fraction)) => (major, Some(fraction)),
+        None => (unsigned, None),
+    };
+
+    // Grup ribuan: grup pertama 1-3 digit, grup berikutnya tepat 3 digit
+    let is_digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    let mut groups = major.split('.');
+    let first = groups.next().unwrap_or_default();
+    let rest: Vec<&str> = groups.collect();
+    let grouped = !rest.is_empty();
+    if !is_digits(first)
+        || (grouped && first.len() > 3)
+        || rest.iter().any(|g| g.len() != 3 || !is_digits(g))
+    {
+        return Err(invalid());
+    }
+    if fraction.is_some_and(|f| !is_digits(f)) {
+        return Err(invalid());
+    }
+
+    let mut normalized = String::new();
+    if negative {
+        normalized.push('-');
+    }
+    normalized.push_str(&major.replace('.', ""));
+    if let Some(fraction) = fraction {
+        normalized.push('.');
+        normalized.push_str(fraction);
+    }
+
+    parse_money(&normalized).map_err(ServiceError::BadRequest)
+}
+
+/// Format minor unit sebagai rupiah untuk ditampilkan, contoh 100000050 -> "Rp1.000.000,50".
+/// Desimal `,00` tidak ditulis.
+pub fn format_rupiah(minor: i64) -> String {
+    let sign = if minor < 0 { "-" } else { "" };
+    let abs = minor.unsigned_abs();
+    let per_major = MINOR_PER_MAJOR as u64;
+
+    let digits = (abs / per_major).to_string();
+    let mut major = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            major.push('.');
+        }
+        major.push(c);
+    }
+
+    match abs % per_major {
+        0 => format!("{}Rp{}", sign, major),
+        fraction => format!(
+            "{}Rp{},{:0width$}",
+            sign,
+            major,
+            fraction,
+            width = MONEY_SCALE as usize
+        ),
+    }
+}
+
+/// Tampilkan uang sebagai string desimal di JSON, tapi tetap simpan integer minor unit di BSON
+/// (insert/find driver memakai serializer non human-readable). Pakai bersama `deserialize_money`.
+pub fn money_as_string<S>(minor: &i64, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&format_money(*minor))
+    } else {
+        serializer.serialize_i64(*minor)
+    }
+}
+
+/// Pasangan `money_as_string` untuk `#[serde(deserialize_with = ...)]`.
+/// Dari JSON menerima string atau angka dalam satuan utuh, dari BSON menerima minor unit.
+pub fn deserialize_money<'de, D>(deserializer: D) -> Result<i64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let human_readable = deserializer.is_human_readable();
+    deserializer.deserialize_any(MoneyVisitor { human_readable })
+}
+
+struct MoneyVisitor {
+    human_readable: bool,
+}
+
+impl<'de> Visitor<'de> for MoneyVisitor {
+    type Value = i64;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("nilai uang berupa angka atau string desimal")
+    }
+
+    fn visit_i64<E: DeError>(self, v: i64) -> Result<i64, E> {
+        if self.human_readable {
+            v.checked_mul(MINOR_PER_MAJOR)
+                .ok_or_else(|| E::custom("Nilai uang terlalu besar"))
+        } else {
+            Ok(v)
+        }
+    }
+
+    fn visit_u64<E: DeError>(self, v: u64) -> Result<i64, E> {
+        let v = i64::try_from(v).map_err(|_| E::custom("Nilai uang terlalu besar"))?;
+        self.visit_i64(v)
+    }
+
+    // f64 selalu dalam satuan utuh, termasuk data harga lama di BSON.
+    // Representasi terpendek f64 dipakai, jadi 0.1 + 0.2 ikut ditolak karena terlalu presisi.
+    fn visit_f64<E: DeError>(self, v: f64) -> Result<i64, E> {
+        parse_money(&v.to_string()).map_err(E::custom)
+    }
+
+    fn visit_str<E: DeError>(self, v: &str) -> Result<i64, E> {
+        parse_money(v).map_err(E::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Price {
+        #[serde(
+            serialize_with = "money_as_string",
+            deserialize_with = "deserialize_money"
+        )]
+        amount: i64,
+    }
+
+    #[test]
+    fn money_is_formatted_with_fixed_scale() {
+        assert_eq!(format_money(1_250_050), "12500.50");
+        assert_eq!(format_money(5), "0.05");
+        assert_eq!(format_money(-1_250), "-12.50");
+        assert_eq!(
+            serde_json::to_value(Price { amount: 1_250_050 }).unwrap(),
+            serde_json::json!({ "amount": "12500.50" })
+        );
+    }
+
+    #[test]
+    fn json_accepts_decimal_string_and_whole_numbers() {
+        let parse = |json: &str| serde_json::from_str::<Price>(json).unwrap().amount;
+
+        assert_eq!(parse(r#"{"amount":"12500.5"}"#), 1_250_050);
+        assert_eq!(parse(r#"{"amount":"12500"}"#), 1_250_000);
+        assert_eq!(parse(r#"{"amount":12500}"#), 1_250_000);
+        assert_eq!(parse(r#"{"amount":0.25}"#), 25);
+    }
+
+    #[test]
+    fn over_precise_input_is_rejected() {
+        for json in [
+            r#"{"amount":"1.234"}"#,
+            r#"{"amount":0.005}"#,
+            r#"{"amount":"12,50"}"#,
+            r#"{"amount":""}"#,
+        ] {
+            assert!(
+                serde_json::from_str::<Price>(json).is_err(),
+                "{} harus ditolak",
+                json
+            );
+        }
+        assert!(parse_money("99999999999999999999").is_err());
+    }
+
+    #[test]
+    fn bson_stores_exact_minor_units() {
+        let price = Price { amount: 1_250_050 };
+
+        // Serializer raw ini yang dipakai driver saat insert dan find
+        let raw = bson::to_raw_document_buf(&price).unwrap();
+
+        assert_eq!(raw.get_i64("amount").unwrap(), 1_250_050);
+        assert_eq!(bson::from_slice::<Price>(raw.as_bytes()).unwrap(), price);
+    }
+
+    #[test]
+    fn rupiah_input_follows_indonesian_locale() {
+        for (raw, minor) in [
+            ("Rp1.000.000", 100_000_000),
+            ("Rp. 1.000.000", 100_000_000),
+            ("rp 25.000", 2_500_000),
+            ("1.000.000,50", 100_000_050),
+            ("12.500,5", 1_250_050),
+            ("750", 75_000),
+            ("1000000", 100_000_000),
+            (" -Rp2.500 ", -250_000),
+        ] {
+            assert_eq!(parse_rupiah(raw).unwrap(), minor, "{}", raw);
+        }
+    }
+
+    #[test]
+    fn ambiguous_or_invalid_rupiah_is_rejected() {
+        for raw in [
+            "1.5",
+            "1.00.000",
+            "1000.000",
+            "Rp",
+            "",
+            "1.000,",
+            "1,000.50",
+            "Rp1.000,505",
+            "abc",
+        ] {
+            assert!(
+                matches!(parse_rupiah(raw), Err(ServiceError::BadRequest(_))),
+                "{} harus ditolak",
+                raw
+            );
+        }
+    }
+
+    #[test]
+    fn rupiah_is_formatted_with_thousand_separators() {
+        assert_eq!(format_rupiah(100_000_050), "Rp1.000.000,50");
+        assert_eq!(format_rupiah(2_500_000), "Rp25.000");
+        assert_eq!(format_rupiah(75_000), "Rp750");
+        assert_eq!(format_rupiah(-250_000), "-Rp2.500");
+        assert_eq!(
+            parse_rupiah(&format_rupiah(123_456_789)).unwrap(),
+            123_456_789
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use crate::errors::ServiceError;
+
+/// Normalisasi email sebelum disimpan agar `User@Example.com ` dan `user@example.com`
+/// dianggap akun yang sama oleh unique index
+pub fn normalize_email(raw: &str) -> Result<String, ServiceError> {
+    normalize_email_with(raw, true)
+}
+
+/// Sama seperti `normalize_email`, `lowercase_local` menentukan apakah bagian sebelum `@`
+/// ikut di-lowercase. Domain selalu di-lowercase.
+pub fn normalize_email_with(raw: &str, lowercase_local: bool) -> Result<String, ServiceError> {
+    let invalid = || ServiceError::BadRequest("Email tidak valid".into());
+    let email = raw.trim();
+
+    let (local, domain) = email.split_once('@').ok_or_else(invalid)?;
+
+    if local.is_empty() || domain.is_empty() || domain.contains('@') {
+        return Err(invalid());
+    }
+    if email.chars().any(char::is_whitespace) {
+        return Err(invalid());
+    }
+    if !domain.contains('.') || domain.starts_with('.') || domain.ends_with('.') {
+        return Err(invalid());
+    }
+
+    let local = if lowercase_local {
+        local.to_lowercase()
+    } else {
+        local.to_string()
+    };
+
+    Ok(format!("{}@{}", local, domain.to_lowercase()))
+}
+
+/// Jumlah digit setelah kode negara 62, contoh: 8123456789
+const PHONE_MIN_DIGITS: usize = 8;
+const PHONE_MAX_DIGITS: usize = 13;
+
+/// Normalisasi nomor HP Indonesia ke bentuk `+62...`.
+/// `08123456789`, `+628123456789` dan `62812-3456-789` menjadi `+628123456789`.
+pub fn normalize_phone_id(raw: &str) -> Result<String, ServiceError> {
+    let invalid = || ServiceError::BadRequest("Nomor HP tidak valid".into());
+
+    let mut cleaned = String::with_capacity(raw.len());
+    for (i, c) in raw.trim().chars().enumerate() {
+        match c {
+            '0'..='9' => cleaned.push(c),
+            '+' if i == 0 => {}
+            ' ' | '-' | '.' | '(' | ')' => {}
+            _ => return Err(invalid()),
+        }
+    }
+
+    let national = if let Some(rest) = cleaned.strip_prefix("62") {
+        rest
+    } else if let Some(rest) = cleaned.strip_prefix('0') {
+        rest
+    } else {
+        return Err(invalid());
+    };
+
+    if national.starts_with('0')
+        || national.len() < PHONE_MIN_DIGITS
+        || national.len() > PHONE_MAX_DIGITS
+    {
+        return Err(invalid());
+    }
+
+    Ok(format!("+62{}", national))
+}
+
+/// Input user yang disimpan dua kali: `display` apa adanya (hanya di-trim) untuk
+/// ditampilkan, `normalized` untuk query dan unique index.
+/// Contoh: `email: " Budi@Mail.com"` menjadi `email: "Budi@Mail.com"` dan
+/// `email_normalized: "budi@mail.com"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizedField {
+    pub display: String,
+    pub normalized: String,
+}
+
+impl NormalizedField {
+    pub fn store(
+        raw: &str,
+        normalize: impl Fn(&str) -> Result<String, ServiceError>,
+    ) -> Result<NormalizedField, ServiceError> {
+        Ok(NormalizedField {
+            normalized: normalize(raw)?,
+            display: raw.trim().to_string(),
+        })
+    }
+
+    pub fn email(raw: &str) -> Result<NormalizedField, ServiceError> {
+        NormalizedField::store(raw, normalize_email)
+    }
+
+    pub fn phone(raw: &str) -> Result<NormalizedField, ServiceError> {
+        NormalizedField::store(raw, normalize_phone_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn email_is_trimmed_and_lowercased() {
+        assert_eq!(
+            normalize_email(" User@Example.COM ").unwrap(),
+            "user@example.com"
+        );
+        assert_eq!(
+            normalize_email_with("User.Name@Example.com", false).unwrap(),
+            "User.Name@example.com"
+        );
+    }
+
+    #[test]
+    fn invalid_email_is_rejected() {
+        for raw in [
+            "",
+            "user",
+            "@example.com",
+            "user@",
+            "user@@example.com",
+            "us er@example.com",
+            "user@localhost",
+            "user@.example.com",
+            "user@example.",
+        ] {
+            assert!(
+                matches!(normalize_email(raw), Err(ServiceError::BadRequest(_))),
+                "{:?} harus ditolak",
+                raw
+            );
+        }
+    }
+
+    #[test]
+    fn every_phone_format_becomes_canonical() {
+        for raw in [
+            "08123456789",
+            "+628123456789",
+            "628123456789",
+            "62812-3456-789",
+            " 0812 3456 789 ",
+            "(0812) 3456.789",
+        ] {
+            assert_eq!(
+                normalize_phone_id(raw).unwrap(),
+                "+628123456789",
+                "{:?}",
+                raw
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_phone_is_rejected() {
+        for raw in [
+            "0812345",
+            "081234567890123",
+            "0812abc6789",
+            "8123456789",
+            "+6208123456789",
+            "0812+3456789",
+        ] {
+            assert!(
+                matches!(normalize_phone_id(raw), Err(ServiceError::BadRequest(_))),
+                "{:?} harus ditolak",
+                raw
+            );
+        }
+    }
+
+    #[test]
+    fn normalized_field_keeps_trimmed_display() {
+        let field = NormalizedField::email(" Budi@Mail.com").unwrap();
+
+        assert_eq!(field.display, "Budi@Mail.com");
+        assert_eq!(field.normalized, "budi@mail.com");
+    }
+
+    #[test]
+    fn phone_field_keeps_display_and_canonical_value() {
+        let raw = " (0812) 3456-789 ";
+        let field = NormalizedField::phone(raw).unwrap();
+
+        assert_eq!(field.display, "(0812) 3456-789");
+        assert_eq!(field.normalized, normalize_phone_id(raw).unwrap());
+    }
+
+    #[test]
+    fn email_field_matches_normalize_email() {
+        for raw in ["Budi@Mail.com", " SITI@example.CO.id ", "a.b@Toko.ID"] {
+            let field = NormalizedField::email(raw).unwrap();
+
+            assert_eq!(field.display, raw.trim());
+            assert_eq!(field.normalized, normalize_email(raw).unwrap());
+        }
+    }
+
+    #[test]
+    fn invalid_field_is_rejected_before_storing() {
+        assert!(matches!(
+            NormalizedField::email("bukan-email"),
+            Err(ServiceError::BadRequest(_))
+        ));
+        assert!(matches!(
+            NormalizedField::phone("0812abc"),
+            Err(ServiceError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn store_uses_given_normalizer() {
+        let field =
+            NormalizedField::store(" Kopi Susu ", |raw| Ok(raw.trim().to_uppercase())).unwrap();
+
+        assert_eq!(field.display, "Kopi Susu");
+        assert_eq!(field.normalized, "KOPI SUSU");
+    }
+}
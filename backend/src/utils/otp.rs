@@ -0,0 +1,221 @@
+use crate::errors::ServiceError;
+use crate::utils::clock::{Clock, SystemClock};
+use crate::utils::token_hash::{hash_token, verify_token};
+use bson::DateTime as BsonDateTime;
+use chrono::Duration;
+use rand::{Rng, TryRngCore, rngs::OsRng};
+use serde::{Deserialize, Serialize};
+
+pub const NUMERIC_ALPHABET: &[u8] = b"0123456789";
+/// Huruf besar dan angka tanpa karakter yang mirip (0/O, 1/I/L), aman di URL
+pub const ALPHANUMERIC_ALPHABET: &[u8] = b"23456789ABCDEFGHJKMNPQRSTUVWXYZ";
+
+pub const DEFAULT_OTP_TTL_MINUTES: i64 = 5;
+pub const DEFAULT_OTP_MAX_ATTEMPTS: u32 = 5;
+
+/// Kode OTP acak dari OsRng. `random_range` memakai rejection sampling sehingga setiap
+/// karakter punya peluang yang sama (tanpa modulo bias).
+pub fn generate_otp(len: usize, numeric: bool) -> String {
+    let alphabet = if numeric {
+        NUMERIC_ALPHABET
+    } else {
+        ALPHANUMERIC_ALPHABET
+    };
+    let mut rng = OsRng.unwrap_err();
+
+    (0..len)
+        .map(|_| alphabet[rng.random_range(0..alphabet.len())] as char)
+        .collect()
+}
+
+fn normalize_code(code: &str) -> String {
+    code.trim().to_ascii_uppercase()
+}
+
+// Ruang kode OTP kecil, yang membuatnya aman adalah masa berlaku pendek dan batas
+// percobaan, bukan hash yang lambat
+pub fn hash_otp(code: &str) -> String {
+    hash_token(&normalize_code(code))
+}
+
+/// OTP yang disimpan di database, hanya hash-nya
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OtpChallenge {
+    pub code_hash: String,
+    pub expires_at: BsonDateTime,
+    #[serde(default)]
+    pub attempts: u32,
+}
+
+impl OtpChallenge {
+    pub fn new(code: &str, ttl: Duration) -> Self {
+        Self::new_with(code, ttl, &SystemClock)
+    }
+
+    pub fn new_with(code: &str, ttl: Duration, clock: &impl Clock) -> Self {
+        let expires_at = clock.now().timestamp_millis() + ttl.num_milliseconds();
+        OtpChallenge {
+            code_hash: hash_otp(code),
+            expires_at: BsonDateTime::from_millis(expires_at),
+            attempts: 0,
+        }
+    }
+
+    pub fn is_expired_with(&self, clock: &impl Clock) -> bool {
+        clock.now().timestamp_millis() >= self.expires_at.timestamp_millis()
+    }
+}
+
+/// Cek kode terhadap challenge tanpa menghitung percobaan
+pub fn verify_otp(challenge: &OtpChallenge, code: &str) -> Result<(), ServiceError> {
+    verify_otp_with(challenge, code, &SystemClock)
+}
+
+pub fn verify_otp_with(
+    challenge: &OtpChallenge,
+    code: &str,
+    clock: &impl Clock,
+) -> Result<(), ServiceError> {
+    if challenge.is_expired_with(clock) {
+        return Err(ServiceError::Unauthorized(
+            "Kode OTP sudah kedaluwarsa".into(),
+        ));
+    }
+    if !verify_token(&normalize_code(code), &challenge.code_hash) {
+        return Err(ServiceError::Unauthorized("Kode OTP salah".into()));
+    }
+
+    Ok(())
+}
+
+/// Seperti `verify_otp`, tapi setiap percobaan menaikkan `attempts` dan setelah
+/// `max_attempts` challenge ditolak walaupun kodenya benar. Simpan kembali `attempts`
+/// ke database setelah memanggil fungsi ini, baik hasilnya sukses maupun gagal.
+pub fn verify_otp_with_attempts(
+    challenge: &mut OtpChallenge,
+    code: &str,
+    max_attempts: u32,
+) -> Result<(), ServiceError> {
+    verify_otp_with_attempts_at(challenge, code, max_attempts, &SystemClock)
+}
+
+pub fn verify_otp_with_attempts_at(
+    challenge: &mut OtpChallenge,
+    code: &str,
+    max_attempts: u32,
+    clock: &impl Clock,
+) -> Result<(), ServiceError> {
+    if challenge.attempts >= max_attempts {
+        return Err(ServiceError::Forbidden(
+            "Terlalu banyak percobaan, minta kode OTP baru".into(),
+        ));
+    }
+    challenge.attempts += 1;
+
+    verify_otp_with(challenge, code, clock)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::clock::FixedClock;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn challenge(code: &str) -> OtpChallenge {
+        OtpChallenge::new_with(
+            code,
+            Duration::minutes(DEFAULT_OTP_TTL_MINUTES),
+            &FixedClock::from_unix(NOW),
+        )
+    }
+
+    #[test]
+    fn generated_codes_use_the_right_charset() {
+        for len in [4, 6, 8] {
+            let numeric = generate_otp(len, true);
+            let alphanumeric = generate_otp(len, false);
+
+            assert_eq!(numeric.len(), len);
+            assert!(numeric.bytes().all(|b| NUMERIC_ALPHABET.contains(&b)));
+            assert_eq!(alphanumeric.len(), len);
+            assert!(
+                alphanumeric
+                    .bytes()
+                    .all(|b| ALPHANUMERIC_ALPHABET.contains(&b))
+            );
+        }
+    }
+
+    #[test]
+    fn numeric_digits_are_roughly_uniform() {
+        let mut counts = [0usize; 10];
+        for digit in generate_otp(20_000, true).bytes() {
+            counts[(digit - b'0') as usize] += 1;
+        }
+
+        // Rata-rata 2000 per digit, batas longgar agar tidak flaky
+        assert!(counts.iter().all(|&count| (1700..2300).contains(&count)));
+    }
+
+    #[test]
+    fn only_the_hash_is_stored() {
+        let stored = challenge("a1b2c3");
+
+        assert!(!stored.code_hash.contains("A1B2C3"));
+        assert_eq!(stored.attempts, 0);
+        assert!(verify_otp_with(&stored, " a1b2c3 ", &FixedClock::from_unix(NOW)).is_ok());
+    }
+
+    #[test]
+    fn verification_respects_expiry() {
+        let stored = challenge("123456");
+        let before = FixedClock::from_unix(NOW + DEFAULT_OTP_TTL_MINUTES * 60 - 1);
+        let after = FixedClock::from_unix(NOW + DEFAULT_OTP_TTL_MINUTES * 60);
+
+        assert!(verify_otp_with(&stored, "123456", &before).is_ok());
+        assert!(matches!(
+            verify_otp_with(&stored, "123456", &after),
+            Err(ServiceError::Unauthorized(msg)) if msg.contains("kedaluwarsa")
+        ));
+        assert!(matches!(
+            verify_otp_with(&stored, "654321", &before),
+            Err(ServiceError::Unauthorized(msg)) if msg == "Kode OTP salah"
+        ));
+    }
+
+    #[test]
+    fn attempts_are_capped_even_for_the_right_code() {
+        let clock = FixedClock::from_unix(NOW);
+        let mut stored = challenge("123456");
+
+        for _ in 0..DEFAULT_OTP_MAX_ATTEMPTS {
+            assert!(
+                verify_otp_with_attempts_at(
+                    &mut stored,
+                    "000000",
+                    DEFAULT_OTP_MAX_ATTEMPTS,
+                    &clock
+                )
+                .is_err()
+            );
+        }
+
+        assert_eq!(stored.attempts, DEFAULT_OTP_MAX_ATTEMPTS);
+        assert!(matches!(
+            verify_otp_with_attempts_at(&mut stored, "123456", DEFAULT_OTP_MAX_ATTEMPTS, &clock),
+            Err(ServiceError::Forbidden(_))
+        ));
+        assert_eq!(stored.attempts, DEFAULT_OTP_MAX_ATTEMPTS);
+    }
+
+    #[test]
+    fn success_within_attempts_counts_the_try() {
+        let clock = FixedClock::from_unix(NOW);
+        let mut stored = challenge("123456");
+
+        assert!(verify_otp_with_attempts_at(&mut stored, "111111", 3, &clock).is_err());
+        assert!(verify_otp_with_attempts_at(&mut stored, "123456", 3, &clock).is_ok());
+        assert_eq!(stored.attempts, 2);
+    }
+}
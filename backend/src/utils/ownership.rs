@@ -0,0 +1,293 @@
+use crate::db::helpers::parse_object_ids;
+use crate::errors::ServiceError;
+use crate::extractors::AuthUser;
+use crate::models::user::ROLE_ADMIN;
+use crate::utils::i18n::{Message, t};
+use crate::utils::{map_mongo_error, string_id_to_obj_id};
+use bson::{Document, doc, oid::ObjectId};
+use mongodb::Collection;
+use std::collections::HashMap;
+
+/// Field pemilik resource, sama dengan `Product.user_id`
+pub const OWNER_FIELD: &str = "user_id";
+
+fn parse_requester_id(requester_id: &str) -> Result<ObjectId, ServiceError> {
+    string_id_to_obj_id(requester_id.trim())
+        .ok_or_else(|| ServiceError::Unauthorized(t(Message::TokenInvalid)))
+}
+
+/// Pastikan `requester_id` (dari `sub` token) adalah pemilik resource.
+/// Dipanggil setelah resource di-load, mismatch menjadi `Forbidden`.
+pub fn assert_owner(resource_owner_id: &ObjectId, requester_id: &str) -> Result<(), ServiceError> {
+    let requester_id = parse_requester_id(requester_id)?;
+
+    if &requester_id != resource_owner_id {
+        return Err(ServiceError::Forbidden(
+            "Anda tidak memiliki akses ke data ini".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Seperti `assert_owner`, tapi admin boleh mengakses resource milik siapa pun
+pub fn assert_owner_or_admin(
+    resource_owner_id: &ObjectId,
+    requester: &AuthUser,
+) -> Result<(), ServiceError> {
+    if requester.role == ROLE_ADMIN {
+        return Ok(());
+    }
+    assert_owner(resource_owner_id, &requester.user_id)
+}
+
+/// Hanya admin, untuk endpoint yang tidak terikat ke satu pemilik seperti daftar semua user
+pub fn assert_admin(requester: &AuthUser) -> Result<(), ServiceError> {
+    if requester.role != ROLE_ADMIN {
+        return Err(ServiceError::Forbidden("Akses hanya untuk admin".into()));
+    }
+    Ok(())
+}
+
+/// Versi bulk `assert_owner` untuk update banyak resource sekaligus. Pemilik semua `ids`
+/// di-load dengan satu query `$in`. Id yang rusak menjadi `BadRequest`, id yang tidak ada
+/// `NotFound`, dan id milik user lain `Forbidden`; masing-masing menyebut semua id terkait.
+pub async fn assert_owner_all<T, S>(
+    collection: &Collection<T>,
+    ids: &[S],
+    requester_id: &str,
+) -> Result<(), ServiceError>
+where
+    T: Send + Sync,
+    S: AsRef<str>,
+{
+    let (object_ids, malformed) = parse_object_ids(ids);
+    if !malformed.is_empty() {
+        return Err(ServiceError::BadRequest(format!(
+            "id tidak valid: {}",
+            malformed.join(", ")
+        )));
+    }
+    let requester_id = parse_requester_id(requester_id)?;
+    if object_ids.is_empty() {
+        return Ok(());
+    }
+
+    let mut cursor = collection
+        .clone_with_type::<Document>()
+        .find(doc! { "_id": { "$in": &object_ids } })
+        .projection(doc! { "_id": 1, OWNER_FIELD: 1 })
+        .await
+        .map_err(map_mongo_error)?;
+
+    let mut owners = HashMap::new();
+    while cursor.advance().await.map_err(map_mongo_error)? {
+        let document = cursor.deserialize_current().map_err(map_mongo_error)?;
+        if let Ok(id) = document.get_object_id("_id") {
+            owners.insert(id, document.get_object_id(OWNER_FIELD).ok());
+        }
+    }
+
+    ensure_all_owned(&object_ids, &owners, &requester_id)
+}
+
+/// Bagian `assert_owner_all` setelah pemilik di-load. `owners` berisi `_id -> pemilik`,
+/// dokumen tanpa field pemilik dianggap bukan milik siapa pun.
+pub fn ensure_all_owned(
+    ids: &[ObjectId],
+    owners: &HashMap<ObjectId, Option<ObjectId>>,
+    requester_id: &ObjectId,
+) -> Result<(), ServiceError> {
+    let mut missing = Vec::new();
+    let mut not_owned = Vec::new();
+    for id in ids {
+        let bucket = match owners.get(id) {
+            None => &mut missing,
+            Some(owner) if owner.as_ref() != Some(requester_id) => &mut not_owned,
+            Some(_) => continue,
+        };
+        let hex = id.to_hex();
+        if !bucket.contains(&hex) {
+            bucket.push(hex);
+        }
+    }
+
+    if !missing.is_empty() {
+        return Err(ServiceError::NotFound(format!(
+            "Data tidak ditemukan: {}",
+            missing.join(", ")
+        )));
+    }
+    if !not_owned.is_empty() {
+        return Err(ServiceError::Forbidden(format!(
+            "Anda tidak memiliki akses ke data: {}",
+            not_owned.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{init_test_config, make_test_claims};
+    use chrono::Duration;
+
+    fn requester(user_id: &ObjectId, role: &str) -> AuthUser {
+        init_test_config();
+        AuthUser::from(make_test_claims(
+            &user_id.to_hex(),
+            role,
+            Duration::minutes(5),
+        ))
+    }
+
+    #[test]
+    fn owner_passes() {
+        let owner = ObjectId::new();
+
+        assert!(assert_owner(&owner, &owner.to_hex()).is_ok());
+        assert!(assert_owner(&owner, &format!(" {} ", owner.to_hex())).is_ok());
+    }
+
+    #[test]
+    fn other_user_is_forbidden() {
+        let owner = ObjectId::new();
+
+        assert!(matches!(
+            assert_owner(&owner, &ObjectId::new().to_hex()),
+            Err(ServiceError::Forbidden(_))
+        ));
+    }
+
+    #[test]
+    fn malformed_requester_id_is_unauthorized() {
+        for requester_id in ["", "user-1", "zzzzzzzzzzzzzzzzzzzzzzzz"] {
+            assert!(matches!(
+                assert_owner(&ObjectId::new(), requester_id),
+                Err(ServiceError::Unauthorized(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn admin_bypasses_ownership() {
+        let owner = ObjectId::new();
+        let stranger = ObjectId::new();
+
+        assert!(assert_owner_or_admin(&owner, &requester(&stranger, ROLE_ADMIN)).is_ok());
+        assert!(assert_owner_or_admin(&owner, &requester(&owner, "user")).is_ok());
+        assert!(matches!(
+            assert_owner_or_admin(&owner, &requester(&stranger, "user")),
+            Err(ServiceError::Forbidden(_))
+        ));
+        assert!(assert_admin(&requester(&stranger, ROLE_ADMIN)).is_ok());
+        assert!(assert_admin(&requester(&stranger, "user")).is_err());
+    }
+
+    fn owned_by(owner: &ObjectId, ids: &[ObjectId]) -> HashMap<ObjectId, Option<ObjectId>> {
+        ids.iter().map(|id| (*id, Some(*owner))).collect()
+    }
+
+    #[test]
+    fn all_owned_passes() {
+        let owner = ObjectId::new();
+        let ids = [ObjectId::new(), ObjectId::new()];
+
+        assert!(ensure_all_owned(&ids, &owned_by(&owner, &ids), &owner).is_ok());
+    }
+
+    #[test]
+    fn some_not_owned_lists_those_ids() {
+        let owner = ObjectId::new();
+        let ids = [ObjectId::new(), ObjectId::new(), ObjectId::new()];
+        let mut owners = owned_by(&owner, &ids);
+        owners.insert(ids[1], Some(ObjectId::new()));
+        owners.insert(ids[2], None);
+
+        match ensure_all_owned(&ids, &owners, &owner) {
+            Err(ServiceError::Forbidden(msg)) => {
+                assert!(!msg.contains(&ids[0].to_hex()));
+                assert!(msg.contains(&ids[1].to_hex()) && msg.contains(&ids[2].to_hex()));
+            }
+            other => panic!("hasil tidak terduga: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_id_is_not_found_before_forbidden() {
+        let owner = ObjectId::new();
+        let ids = [ObjectId::new(), ObjectId::new()];
+        let mut owners = owned_by(&owner, &ids[..1]);
+        owners.insert(ids[0], Some(ObjectId::new()));
+
+        match ensure_all_owned(&ids, &owners, &owner) {
+            Err(ServiceError::NotFound(msg)) => assert!(msg.contains(&ids[1].to_hex())),
+            other => panic!("hasil tidak terduga: {:?}", other),
+        }
+    }
+
+    async fn offline_collection() -> Collection<Document> {
+        mongodb::Client::with_uri_str("mongodb://127.0.0.1:1/")
+            .await
+            .unwrap()
+            .database("qtoky_test")
+            .collection("products")
+    }
+
+    #[actix_web::test]
+    async fn malformed_ids_are_rejected_before_query() {
+        let collection = offline_collection().await;
+        let owner = ObjectId::new().to_hex();
+
+        match assert_owner_all(&collection, &["bukan-id", &owner], &owner).await {
+            Err(ServiceError::BadRequest(msg)) => assert!(msg.contains("bukan-id")),
+            other => panic!("hasil tidak terduga: {:?}", other),
+        }
+        assert!(matches!(
+            assert_owner_all(&collection, &[owner.as_str()], "user-1").await,
+            Err(ServiceError::Unauthorized(_))
+        ));
+        assert!(
+            assert_owner_all(&collection, &[] as &[&str], &owner)
+                .await
+                .is_ok()
+        );
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn bulk_check_uses_stored_owners() {
+        let collection = crate::testing::test_database()
+            .await
+            .collection::<Document>("products");
+        let owner = ObjectId::new();
+        let (mine, theirs) = (ObjectId::new(), ObjectId::new());
+        collection
+            .insert_many([
+                doc! { "_id": mine, OWNER_FIELD: owner },
+                doc! { "_id": theirs, OWNER_FIELD: ObjectId::new() },
+            ])
+            .await
+            .unwrap();
+        let owner = owner.to_hex();
+
+        assert!(
+            assert_owner_all(&collection, &[mine.to_hex()], &owner)
+                .await
+                .is_ok()
+        );
+        assert!(matches!(
+            assert_owner_all(&collection, &[mine.to_hex(), theirs.to_hex()], &owner).await,
+            Err(ServiceError::Forbidden(msg)) if msg.contains(&theirs.to_hex())
+        ));
+        assert!(matches!(
+            assert_owner_all(
+                &collection,
+                &[mine.to_hex(), ObjectId::new().to_hex()],
+                &owner
+            )
+            .await,
+            Err(ServiceError::NotFound(_))
+        ));
+    }
+}
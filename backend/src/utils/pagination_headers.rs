@@ -0,0 +1,141 @@
+use crate::db::pagination::Paginated;
+use actix_web::{HttpRequest, HttpResponseBuilder, http::header::LINK};
+
+pub const TOTAL_COUNT_HEADER: &str = "X-Total-Count";
+pub const PAGE_HEADER: &str = "X-Page";
+pub const PER_PAGE_HEADER: &str = "X-Per-Page";
+
+/// URL request sekarang dengan `page` dan `per_page` diganti, query param lain dipertahankan
+fn page_url(req: &HttpRequest, page: u64, per_page: u64) -> String {
+    let mut url = req.full_url();
+    let kept: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| key != "page" && key != "per_page")
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(kept)
+        .append_pair("page", &page.to_string())
+        .append_pair("per_page", &per_page.to_string());
+    url.to_string()
+}
+
+/// Isi header `Link` (RFC 5988) untuk first/prev/next/last. `prev` tidak ada di halaman
+/// pertama dan `next` tidak ada di halaman terakhir.
+pub fn pagination_links<T>(req: &HttpRequest, paginated: &Paginated<T>) -> String {
+    let last = paginated.total_pages.max(1);
+    let page = paginated.page;
+
+    let mut links = vec![(1, "first")];
+    if page > 1 {
+        links.push((page.saturating_sub(1).min(last), "prev"));
+    }
+    if page < last {
+        links.push((page + 1, "next"));
+    }
+    links.push((last, "last"));
+
+    links
+        .into_iter()
+        .map(|(target, rel)| {
+            format!(
+                "<{}>; rel=\"{}\"",
+                page_url(req, target, paginated.per_page),
+                rel
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Tambahkan `X-Total-Count`, `X-Page`, `X-Per-Page` dan `Link` ke response, untuk client
+/// yang membaca metadata halaman dari header. Envelope JSON tetap dikirim seperti biasa.
+///
+/// ```ignore
+/// let mut resp = HttpResponse::Ok();
+/// with_pagination_headers(&mut resp, &req, &page).json(json!({ ... }))
+/// ```
+pub fn with_pagination_headers<'a, T>(
+    resp: &'a mut HttpResponseBuilder,
+    req: &HttpRequest,
+    paginated: &Paginated<T>,
+) -> &'a mut HttpResponseBuilder {
+    resp.insert_header((TOTAL_COUNT_HEADER, paginated.total.to_string()))
+        .insert_header((PAGE_HEADER, paginated.page.to_string()))
+        .insert_header((PER_PAGE_HEADER, paginated.per_page.to_string()))
+        .insert_header((LINK, pagination_links(req, paginated)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::HttpResponse;
+    use actix_web::test::TestRequest;
+
+    const BASE: &str = "http://api.qtoky.test/api/products?q=kopi";
+
+    fn request(query: &str) -> HttpRequest {
+        TestRequest::get()
+            .uri(&format!("/api/products?{}", query))
+            .insert_header(("host", "api.qtoky.test"))
+            .to_http_request()
+    }
+
+    fn link(page: u64, rel: &str) -> String {
+        format!("<{}&page={}&per_page=10>; rel=\"{}\"", BASE, page, rel)
+    }
+
+    fn page(number: u64) -> Paginated<()> {
+        Paginated::new(Vec::new(), 45, number, 10)
+    }
+
+    #[test]
+    fn middle_page_sets_all_headers() {
+        let req = request("page=3&q=kopi&per_page=10");
+        let mut builder = HttpResponse::Ok();
+
+        let resp = with_pagination_headers(&mut builder, &req, &page(3)).finish();
+
+        let header = |name: &str| resp.headers().get(name).unwrap().to_str().unwrap();
+        assert_eq!(header(TOTAL_COUNT_HEADER), "45");
+        assert_eq!(header(PAGE_HEADER), "3");
+        assert_eq!(header(PER_PAGE_HEADER), "10");
+        assert_eq!(
+            header("link"),
+            [
+                link(1, "first"),
+                link(2, "prev"),
+                link(4, "next"),
+                link(5, "last"),
+            ]
+            .join(", ")
+        );
+    }
+
+    #[test]
+    fn edge_pages_omit_prev_or_next() {
+        let req = request("q=kopi");
+
+        assert_eq!(
+            pagination_links(&req, &page(1)),
+            [link(1, "first"), link(2, "next"), link(5, "last")].join(", ")
+        );
+        assert_eq!(
+            pagination_links(&req, &page(5)),
+            [link(1, "first"), link(4, "prev"), link(5, "last")].join(", ")
+        );
+    }
+
+    #[test]
+    fn empty_result_links_to_single_page() {
+        let req = request("q=kopi");
+        let empty = Paginated::<()>::new(Vec::new(), 0, 1, 10);
+
+        assert_eq!(
+            pagination_links(&req, &empty),
+            [link(1, "first"), link(1, "last")].join(", ")
+        );
+    }
+}
@@ -0,0 +1,618 @@
+use crate::config::{Config, config_error, parse_env};
+use crate::errors::ServiceError;
+use crate::utils::i18n::{Message, t};
+use actix_web::web;
+use argon2::{
+    Algorithm, Argon2, Params, Version,
+    password_hash::{
+        Error as PasswordHashError, PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
+        rand_core::OsRng,
+    },
+};
+use futures::future::join_all;
+use once_cell::sync::Lazy;
+use std::{env, fmt};
+
+/// Parameter biaya Argon2id yang dipakai saat membuat hash baru
+#[derive(Clone, PartialEq, Eq)]
+pub struct Argon2Config {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+    // Secret di sisi server, tidak ikut tersimpan di database bersama hash
+    pub pepper: Option<String>,
+}
+
+impl Default for Argon2Config {
+    fn default() -> Self {
+        Argon2Config {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+            pepper: None,
+        }
+    }
+}
+
+impl fmt::Debug for Argon2Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Argon2Config")
+            .field("memory_kib", &self.memory_kib)
+            .field("iterations", &self.iterations)
+            .field("parallelism", &self.parallelism)
+            .field("pepper", &self.pepper.as_ref().map(|_| "***"))
+            .finish()
+    }
+}
+
+impl Argon2Config {
+    /// Baca parameter dari `ARGON2_MEMORY_KIB`, `ARGON2_ITERATIONS` dan `ARGON2_PARALLELISM`,
+    /// pakai nilai default untuk variabel yang tidak di-set. Pepper dibaca dari
+    /// `PASSWORD_PEPPER` dan boleh kosong.
+    pub fn from_env() -> Result<Self, ServiceError> {
+        let default = Argon2Config::default();
+        let config = Argon2Config {
+            memory_kib: parse_env("ARGON2_MEMORY_KIB", default.memory_kib)?,
+            iterations: parse_env("ARGON2_ITERATIONS", default.iterations)?,
+            parallelism: parse_env("ARGON2_PARALLELISM", default.parallelism)?,
+            pepper: env::var("PASSWORD_PEPPER").ok().filter(|p| !p.is_empty()),
+        };
+
+        config
+            .hasher()
+            .map_err(|e| config_error(format!("parameter Argon2 tidak valid: {}", e)))?;
+        Ok(config)
+    }
+
+    fn hasher(&self) -> Result<Argon2<'_>, PasswordHashError> {
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, None)?;
+        match &self.pepper {
+            Some(pepper) => Ok(Argon2::new_with_secret(
+                pepper.as_bytes(),
+                Algorithm::Argon2id,
+                Version::V0x13,
+                params,
+            )?),
+            None => Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params)),
+        }
+    }
+
+    /// Cek password dengan pepper aktif. Hash lama yang dibuat sebelum pepper diaktifkan
+    /// masih diterima lewat verifikasi tanpa pepper, dan ditandai `legacy` agar di-rehash.
+    /// Hash yang dibuat dengan pepper tidak akan pernah lolos tanpa pepper yang sama.
+    fn verify(&self, password: &str, parsed_hash: &PasswordHash) -> Option<PepperMatch> {
+        // Parameter m/t/p diambil dari hash, yang dipakai dari sini hanya pepper-nya
+        let verifier = self.hasher().ok()?;
+        if verifier
+            .verify_password(password.as_bytes(), parsed_hash)
+            .is_ok()
+        {
+            return Some(PepperMatch::Current);
+        }
+
+        if self.pepper.is_some()
+            && Argon2::default()
+                .verify_password(password.as_bytes(), parsed_hash)
+                .is_ok()
+        {
+            return Some(PepperMatch::Legacy);
+        }
+
+        None
+    }
+}
+
+enum PepperMatch {
+    Current,
+    Legacy,
+}
+
+/// Konfigurasi Argon2 aktif, diambil dari `Config` saat startup
+pub static ARGON2_CONFIG: Lazy<Argon2Config> = Lazy::new(|| Config::global().argon2.clone());
+
+pub fn hash_password(password: &str) -> Result<String, PasswordHashError> {
+    hash_password_with(password, &ARGON2_CONFIG)
+}
+
+pub fn hash_password_with(
+    password: &str,
+    config: &Argon2Config,
+) -> Result<String, PasswordHashError> {
+    let salt = SaltString::generate(&mut OsRng);
+    let argon2 = config.hasher()?;
+
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string();
+    Ok(hash)
+}
+
+/// Hash password di thread pool blocking agar Argon2 tidak menahan worker async
+pub async fn hash_password_async(password: String) -> Result<String, ServiceError> {
+    web::block(move || hash_password(&password))
+        .await
+        .map_err(|e| ServiceError::internal("Thread hashing gagal", e))?
+        .map_err(|e| ServiceError::HashingError(format!("Gagal hashing password: {}", e)))
+}
+
+/// Hash banyak password sekaligus, kegagalan satu item tidak menggagalkan item lain
+pub async fn hash_passwords_bulk(passwords: Vec<String>) -> Vec<Result<String, ServiceError>> {
+    join_all(passwords.into_iter().map(hash_password_async)).await
+}
+
+/// Versi async dari `verify_password`, dijalankan di thread pool blocking
+pub async fn verify_password_async(password: String, password_hash: String) -> bool {
+    web::block(move || verify_password(&password, &password_hash))
+        .await
+        .unwrap_or(false)
+}
+
+/// Parameter Argon2 tersimpan di PHC string, jadi hash lama tetap bisa diverifikasi
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    verify_password_checked(password, password_hash).unwrap_or(false)
+}
+
+/// Sama seperti `verify_password`, tapi hash tersimpan yang rusak dikembalikan sebagai `Err`
+/// alih-alih dianggap password salah
+pub fn verify_password_checked(password: &str, password_hash: &str) -> Result<bool, ServiceError> {
+    let parsed_hash = PasswordHash::new(password_hash)
+        .map_err(|e| ServiceError::HashingError(format!("Hash password tidak valid: {}", e)))?;
+
+    Ok(ARGON2_CONFIG.verify(password, &parsed_hash).is_some())
+}
+
+// Hash dengan konfigurasi aktif agar biaya verifikasinya sama dengan hash user sungguhan
+static DUMMY_HASH: Lazy<String> =
+    Lazy::new(|| hash_password("qtoky-dummy-password").expect("Gagal membuat dummy hash password"));
+
+/// Verifikasi password dengan waktu yang setara walaupun user tidak ditemukan.
+/// Jika `maybe_hash` bernilai `None`, Argon2 tetap dijalankan terhadap dummy hash
+/// dan hasilnya selalu `false`, sehingga waktu respons tidak membocorkan akun yang terdaftar.
+pub fn verify_password_timing_safe(password: &str, maybe_hash: Option<&str>) -> bool {
+    match maybe_hash {
+        Some(hash) => verify_password(password, hash),
+        None => {
+            let _ = verify_password(password, &DUMMY_HASH);
+            false
+        }
+    }
+}
+
+fn matches_config(parsed_hash: &PasswordHash, config: &Argon2Config) -> bool {
+    if parsed_hash.algorithm != Algorithm::Argon2id.ident() {
+        return false;
+    }
+
+    match Params::try_from(parsed_hash) {
+        Ok(params) => {
+            params.m_cost() == config.memory_kib
+                && params.t_cost() == config.iterations
+                && params.p_cost() == config.parallelism
+        }
+        Err(_) => false,
+    }
+}
+
+/// Verifikasi password lalu cek apakah parameter (m, t, p) di hash tersimpan masih sama
+/// dengan konfigurasi aktif. Mengembalikan `Some(hash_baru)` jika hash perlu diperbarui,
+/// termasuk hash lama yang belum memakai pepper.
+pub fn verify_and_maybe_rehash(
+    password: &str,
+    stored_hash: &str,
+    config: &Argon2Config,
+) -> Result<Option<String>, ServiceError> {
+    let parsed_hash = PasswordHash::new(stored_hash)
+        .map_err(|e| ServiceError::HashingError(format!("Hash password tidak valid: {}", e)))?;
+
+    let matched = config
+        .verify(password, &parsed_hash)
+        .ok_or_else(|| ServiceError::Unauthorized(t(Message::InvalidCredentials)))?;
+
+    if matches!(matched, PepperMatch::Current) && matches_config(&parsed_hash, config) {
+        return Ok(None);
+    }
+
+    let new_hash = hash_password_with(password, config)
+        .map_err(|e| ServiceError::HashingError(format!("Gagal hashing password: {}", e)))?;
+    Ok(Some(new_hash))
+}
+
+/// Aturan kekuatan password yang dicek sebelum hashing
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    // Batas atas mencegah input berukuran besar membebani Argon2
+    pub max_length: usize,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        PasswordPolicy {
+            min_length: 8,
+            max_length: 128,
+            require_uppercase: false,
+            require_lowercase: true,
+            require_digit: true,
+            require_symbol: false,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Policy ketat untuk akun dengan hak akses tinggi
+    pub fn strict() -> Self {
+        PasswordPolicy {
+            min_length: 12,
+            max_length: 128,
+            require_uppercase: true,
+            require_lowercase: true,
+            require_digit: true,
+            require_symbol: true,
+        }
+    }
+}
+
+pub fn validate_password_strength(password: &str) -> Result<(), ServiceError> {
+    validate_password_strength_with(password, &PasswordPolicy::default())
+}
+
+/// Cek password terhadap policy, semua aturan yang tidak terpenuhi dikembalikan sekaligus
+pub fn validate_password_strength_with(
+    password: &str,
+    policy: &PasswordPolicy,
+) -> Result<(), ServiceError> {
+    let length = password.chars().count();
+
+    if length > policy.max_length {
+        return Err(ServiceError::BadRequest(format!(
+            "Password maksimal {} karakter",
+            policy.max_length
+        )));
+    }
+
+    if password.trim().is_empty() {
+        return Err(ServiceError::BadRequest(
+            "Password tidak boleh hanya berisi spasi".into(),
+        ));
+    }
+
+    let mut unmet: Vec<String> = Vec::new();
+
+    if length < policy.min_length {
+        unmet.push(format!("minimal {} karakter", policy.min_length));
+    }
+    if policy.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+        unmet.push("mengandung huruf besar".into());
+    }
+    if policy.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+        unmet.push("mengandung huruf kecil".into());
+    }
+    if policy.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+        unmet.push("mengandung angka".into());
+    }
+    if policy.require_symbol
+        && !password
+            .chars()
+            .any(|c| !c.is_alphanumeric() && !c.is_whitespace())
+    {
+        unmet.push("mengandung simbol".into());
+    }
+
+    if !unmet.is_empty() {
+        return Err(ServiceError::BadRequest(format!(
+            "Password harus {}",
+            unmet.join(", ")
+        )));
+    }
+
+    Ok(())
+}
+
+/// Ganti password: `current` harus cocok dengan hash tersimpan dan `new` tidak boleh
+/// sama dengan password lama. Mengembalikan hash baru yang siap disimpan.
+pub fn change_password(
+    stored_hash: &str,
+    current: &str,
+    new: &str,
+) -> Result<String, ServiceError> {
+    if !verify_password_checked(current, stored_hash)? {
+        return Err(ServiceError::Unauthorized("Password saat ini salah".into()));
+    }
+
+    validate_password_strength(new)?;
+
+    if verify_password(new, stored_hash) {
+        return Err(ServiceError::BadRequest(
+            "password baru tidak boleh sama".into(),
+        ));
+    }
+
+    hash_password(new)
+        .map_err(|e| ServiceError::HashingError(format!("Gagal hashing password: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::init_test_config;
+
+    // Parameter kecil agar test cepat, berbeda dari `Argon2Config::default()`
+    fn light_config() -> Argon2Config {
+        Argon2Config {
+            memory_kib: 16,
+            iterations: 2,
+            parallelism: 1,
+            pepper: None,
+        }
+    }
+
+    #[test]
+    fn hash_with_custom_params_verifies_and_embeds_them() {
+        init_test_config();
+        let config = light_config();
+        assert_ne!(config, Argon2Config::default());
+
+        let hash = hash_password_with("rahasia123", &config).unwrap();
+
+        assert!(hash.contains("m=16,t=2,p=1"));
+        assert!(verify_password("rahasia123", &hash));
+        assert!(!verify_password("salah123", &hash));
+    }
+
+    #[test]
+    fn outdated_params_need_rehash() {
+        init_test_config();
+        let old = light_config();
+        let current = Argon2Config {
+            memory_kib: 32,
+            ..light_config()
+        };
+        let stored = hash_password_with("rahasia123", &old).unwrap();
+
+        let new_hash = verify_and_maybe_rehash("rahasia123", &stored, &current)
+            .unwrap()
+            .expect("hash dengan parameter lama harus di-rehash");
+
+        assert!(new_hash.contains("m=32,t=2,p=1"));
+        assert_eq!(
+            verify_and_maybe_rehash("rahasia123", &new_hash, &current).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn up_to_date_params_do_not_rehash() {
+        init_test_config();
+        let config = light_config();
+        let stored = hash_password_with("rahasia123", &config).unwrap();
+
+        assert_eq!(
+            verify_and_maybe_rehash("rahasia123", &stored, &config).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn rehash_check_rejects_wrong_password() {
+        init_test_config();
+        let config = light_config();
+        let stored = hash_password_with("rahasia123", &config).unwrap();
+
+        assert!(matches!(
+            verify_and_maybe_rehash("salah123", &stored, &config),
+            Err(ServiceError::Unauthorized(_))
+        ));
+    }
+
+    #[test]
+    fn checked_verify_reports_malformed_hash() {
+        init_test_config();
+
+        assert!(matches!(
+            verify_password_checked("rahasia123", "hash-rusak"),
+            Err(ServiceError::HashingError(_))
+        ));
+        assert!(!verify_password("rahasia123", "hash-rusak"));
+    }
+
+    #[test]
+    fn checked_verify_distinguishes_correct_and_wrong_password() {
+        init_test_config();
+        let hash = hash_password_with("rahasia123", &light_config()).unwrap();
+
+        assert!(verify_password_checked("rahasia123", &hash).unwrap());
+        assert!(!verify_password_checked("salah123", &hash).unwrap());
+    }
+
+    #[test]
+    fn policy_rejects_too_long_password() {
+        let policy = PasswordPolicy::default();
+        let password = "a1".repeat(policy.max_length);
+
+        assert!(matches!(
+            validate_password_strength_with(&password, &policy),
+            Err(ServiceError::BadRequest(msg)) if msg.contains("maksimal")
+        ));
+    }
+
+    #[test]
+    fn policy_rejects_whitespace_only_password() {
+        assert!(matches!(
+            validate_password_strength_with("            ", &PasswordPolicy::default()),
+            Err(ServiceError::BadRequest(msg)) if msg.contains("spasi")
+        ));
+    }
+
+    #[test]
+    fn policy_rejects_short_password() {
+        assert!(matches!(
+            validate_password_strength_with("ab1", &PasswordPolicy::default()),
+            Err(ServiceError::BadRequest(msg)) if msg.contains("minimal 8 karakter")
+        ));
+    }
+
+    #[test]
+    fn strict_policy_lists_every_missing_class() {
+        let err =
+            validate_password_strength_with("abcdefghijkl", &PasswordPolicy::strict()).unwrap_err();
+
+        let ServiceError::BadRequest(msg) = err else {
+            panic!("harus BadRequest");
+        };
+        assert!(msg.contains("huruf besar"));
+        assert!(msg.contains("angka"));
+        assert!(msg.contains("simbol"));
+        assert!(!msg.contains("huruf kecil"));
+    }
+
+    #[test]
+    fn lenient_policy_skips_class_requirements() {
+        let lenient = PasswordPolicy {
+            require_lowercase: false,
+            require_digit: false,
+            ..PasswordPolicy::default()
+        };
+
+        assert!(validate_password_strength_with("ABCDEFGH", &lenient).is_ok());
+        assert!(validate_password_strength_with("ABCDEFGH", &PasswordPolicy::default()).is_err());
+    }
+
+    #[test]
+    fn strong_password_is_accepted() {
+        assert!(
+            validate_password_strength_with("Rahasia#2024x", &PasswordPolicy::strict()).is_ok()
+        );
+        assert!(validate_password_strength("rahasia123").is_ok());
+    }
+
+    fn peppered(pepper: &str) -> Argon2Config {
+        Argon2Config {
+            pepper: Some(pepper.into()),
+            ..light_config()
+        }
+    }
+
+    #[test]
+    fn peppered_hash_needs_the_same_pepper() {
+        let stored = hash_password_with("rahasia123", &peppered("lada")).unwrap();
+
+        assert_eq!(
+            verify_and_maybe_rehash("rahasia123", &stored, &peppered("lada")).unwrap(),
+            None
+        );
+        for config in [light_config(), peppered("garam")] {
+            assert!(matches!(
+                verify_and_maybe_rehash("rahasia123", &stored, &config),
+                Err(ServiceError::Unauthorized(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn legacy_hash_without_pepper_is_accepted_and_rehashed() {
+        let legacy = hash_password_with("rahasia123", &light_config()).unwrap();
+
+        let rehashed = verify_and_maybe_rehash("rahasia123", &legacy, &peppered("lada"))
+            .unwrap()
+            .expect("hash tanpa pepper harus di-rehash");
+
+        assert_eq!(
+            verify_and_maybe_rehash("rahasia123", &rehashed, &peppered("lada")).unwrap(),
+            None
+        );
+        assert!(verify_and_maybe_rehash("rahasia123", &rehashed, &light_config()).is_err());
+    }
+
+    #[test]
+    fn missing_user_runs_argon2_against_dummy_hash() {
+        init_test_config();
+
+        // Password dummy pun tidak pernah lolos saat user tidak ada
+        assert!(!verify_password_timing_safe("qtoky-dummy-password", None));
+
+        let dummy = Lazy::get(&DUMMY_HASH).expect("dummy hash harus sudah dibuat");
+        assert!(PasswordHash::new(dummy).is_ok());
+        assert!(verify_password("qtoky-dummy-password", dummy));
+    }
+
+    #[test]
+    fn existing_user_is_verified_normally() {
+        init_test_config();
+        let hash = hash_password("rahasia123").unwrap();
+
+        assert!(verify_password_timing_safe("rahasia123", Some(&hash)));
+        assert!(!verify_password_timing_safe("salah123", Some(&hash)));
+    }
+
+    #[actix_web::test]
+    async fn async_hash_is_verifiable_by_sync_verify() {
+        init_test_config();
+
+        let hash = hash_password_async("rahasia123".into()).await.unwrap();
+
+        assert!(verify_password("rahasia123", &hash));
+        assert!(verify_password_async("rahasia123".into(), hash.clone()).await);
+        assert!(!verify_password_async("salah123".into(), hash).await);
+    }
+
+    #[actix_web::test]
+    async fn bulk_hash_returns_one_result_per_password() {
+        init_test_config();
+        let passwords = vec!["satu1111".to_string(), "dua22222".into(), "tiga3333".into()];
+
+        let hashes = hash_passwords_bulk(passwords.clone()).await;
+
+        assert_eq!(hashes.len(), passwords.len());
+        for (password, hash) in passwords.iter().zip(hashes) {
+            assert!(verify_password(password, &hash.unwrap()));
+        }
+    }
+
+    #[test]
+    fn change_password_rejects_wrong_current() {
+        init_test_config();
+        let stored = hash_password_with("rahasia123", &light_config()).unwrap();
+
+        assert!(matches!(
+            change_password(&stored, "salah123", "passwordBaru9"),
+            Err(ServiceError::Unauthorized(_))
+        ));
+    }
+
+    #[test]
+    fn change_password_rejects_weak_new_password() {
+        init_test_config();
+        let stored = hash_password_with("rahasia123", &light_config()).unwrap();
+
+        assert!(matches!(
+            change_password(&stored, "rahasia123", "ab1"),
+            Err(ServiceError::BadRequest(msg)) if msg.contains("minimal 8 karakter")
+        ));
+    }
+
+    #[test]
+    fn change_password_rejects_same_password() {
+        init_test_config();
+        let stored = hash_password_with("rahasia123", &light_config()).unwrap();
+
+        assert!(matches!(
+            change_password(&stored, "rahasia123", "rahasia123"),
+            Err(ServiceError::BadRequest(msg)) if msg == "password baru tidak boleh sama"
+        ));
+    }
+
+    #[test]
+    fn change_password_returns_new_hash() {
+        init_test_config();
+        let stored = hash_password_with("rahasia123", &light_config()).unwrap();
+
+        let new_hash = change_password(&stored, "rahasia123", "passwordBaru9").unwrap();
+
+        assert!(verify_password("passwordBaru9", &new_hash));
+        assert!(!verify_password("rahasia123", &new_hash));
+    }
+}
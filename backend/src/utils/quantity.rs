@@ -0,0 +1,275 @@
+use crate::errors::ServiceError;
+use crate::string_enum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+string_enum! {
+    /// Satuan jual produk yang diizinkan
+    pub enum Unit {
+        Pcs => "pcs",
+        Kg => "kg",
+        Liter => "liter",
+    }
+}
+
+/// Jumlah digit desimal yang didukung, contoh 0.125 kg
+pub const QUANTITY_SCALE: u32 = 3;
+const MILLI_PER_UNIT: i64 = 10_i64.pow(QUANTITY_SCALE);
+
+/// Jumlah beserta satuannya, contoh `{ "value": 2.5, "unit": "kg" }`. Nilai disimpan sebagai
+/// integer seperseribu satuan agar penjumlahan stok tidak terkena pembulatan float.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "QuantityRepr", into = "QuantityRepr")]
+pub struct Quantity {
+    milli: i64,
+    unit: Unit,
+}
+
+#[derive(Serialize, Deserialize)]
+struct QuantityRepr {
+    value: f64,
+    unit: Unit,
+}
+
+impl TryFrom<QuantityRepr> for Quantity {
+    type Error = String;
+
+    fn try_from(repr: QuantityRepr) -> Result<Self, Self::Error> {
+        let scaled = repr.value * MILLI_PER_UNIT as f64;
+        let milli = scaled.round();
+        if !scaled.is_finite() || milli < 0.0 || milli > i64::MAX as f64 {
+            return Err(format!("Jumlah '{}' tidak valid", repr.value));
+        }
+        if (scaled - milli).abs() > 1e-6 {
+            return Err(format!(
+                "Jumlah maksimal {} digit di belakang koma",
+                QUANTITY_SCALE
+            ));
+        }
+        Ok(Quantity::from_milli(milli as i64, repr.unit))
+    }
+}
+
+impl From<Quantity> for QuantityRepr {
+    fn from(quantity: Quantity) -> Self {
+        QuantityRepr {
+            value: quantity.value(),
+            unit: quantity.unit,
+        }
+    }
+}
+
+impl Quantity {
+    /// `milli` dalam seperseribu satuan, contoh `from_milli(2500, Unit::Kg)` adalah 2.5 kg
+    pub fn from_milli(milli: i64, unit: Unit) -> Self {
+        Quantity { milli, unit }
+    }
+
+    pub fn zero(unit: Unit) -> Self {
+        Quantity::from_milli(0, unit)
+    }
+
+    pub fn milli(&self) -> i64 {
+        self.milli
+    }
+
+    pub fn value(&self) -> f64 {
+        self.milli as f64 / MILLI_PER_UNIT as f64
+    }
+
+    pub fn unit(&self) -> Unit {
+        self.unit
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.milli == 0
+    }
+
+    /// Jumlahkan dua quantity, `BadRequest` jika satuannya berbeda
+    pub fn checked_add(self, other: Quantity) -> Result<Quantity, ServiceError> {
+        self.ensure_same_unit(&other)?;
+        self.milli
+            .checked_add(other.milli)
+            .map(|milli| Quantity::from_milli(milli, self.unit))
+            .ok_or_else(too_large)
+    }
+
+    /// Kurangi quantity, `BadRequest` jika satuannya berbeda atau hasilnya negatif
+    pub fn checked_sub(self, other: Quantity) -> Result<Quantity, ServiceError> {
+        self.ensure_same_unit(&other)?;
+        if other.milli > self.milli {
+            return Err(ServiceError::BadRequest(format!(
+                "Jumlah tidak cukup: {} dikurangi {}",
+                self, other
+            )));
+        }
+        Ok(Quantity::from_milli(self.milli - other.milli, self.unit))
+    }
+
+    fn ensure_same_unit(&self, other: &Quantity) -> Result<(), ServiceError> {
+        if self.unit != other.unit {
+            return Err(ServiceError::BadRequest(format!(
+                "Satuan tidak cocok: {} dan {}",
+                self.unit, other.unit
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn too_large() -> ServiceError {
+    ServiceError::BadRequest("Jumlah terlalu besar".into())
+}
+
+/// Contoh: 2.5 kg, 3 pcs
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let whole = self.milli / MILLI_PER_UNIT;
+        let fraction = self.milli % MILLI_PER_UNIT;
+        if fraction == 0 {
+            return write!(f, "{} {}", whole, self.unit);
+        }
+        let fraction = format!("{:0width$}", fraction, width = QUANTITY_SCALE as usize);
+        write!(
+            f,
+            "{}.{} {}",
+            whole,
+            fraction.trim_end_matches('0'),
+            self.unit
+        )
+    }
+}
+
+/// Parse input seperti `"2.5 kg"`, `"2,5kg"` atau `"3 pcs"`. Titik dan koma sama-sama
+/// pemisah desimal. Satuan di luar `Unit` dan jumlah negatif ditolak dengan `BadRequest`.
+pub fn parse_quantity(raw: &str) -> Result<Quantity, ServiceError> {
+    let raw = raw.trim();
+    let invalid = || ServiceError::BadRequest(format!("Jumlah '{}' tidak valid", raw));
+
+    let split = raw.find(|c: char| c.is_alphabetic()).ok_or_else(invalid)?;
+    let (number, unit) = raw.split_at(split);
+    let unit: Unit = unit
+        .trim()
+        .to_lowercase()
+        .parse()
+        .map_err(ServiceError::BadRequest)?;
+
+    let number = number.trim();
+    let (whole, fraction) = number.split_once(['.', ',']).unwrap_or((number, ""));
+
+    let is_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if whole.is_empty() || !is_digits(whole) || !is_digits(fraction) {
+        return Err(invalid());
+    }
+    if fraction.len() > QUANTITY_SCALE as usize {
+        return Err(ServiceError::BadRequest(format!(
+            "Jumlah maksimal {} digit di belakang koma",
+            QUANTITY_SCALE
+        )));
+    }
+
+    let whole: i64 = whole.parse().map_err(|_| too_large())?;
+    let fraction: i64 = if fraction.is_empty() {
+        0
+    } else {
+        // "5" berarti 500 per seribu, bukan 5
+        let padded = format!("{:0<width$}", fraction, width = QUANTITY_SCALE as usize);
+        padded.parse().map_err(|_| too_large())?
+    };
+
+    let milli = whole
+        .checked_mul(MILLI_PER_UNIT)
+        .and_then(|m| m.checked_add(fraction))
+        .ok_or_else(too_large)?;
+    Ok(Quantity::from_milli(milli, unit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_bad_request(result: Result<Quantity, ServiceError>) -> bool {
+        matches!(result, Err(ServiceError::BadRequest(_)))
+    }
+
+    #[test]
+    fn parses_value_and_unit() {
+        assert_eq!(
+            parse_quantity("2.5 kg").unwrap(),
+            Quantity::from_milli(2500, Unit::Kg)
+        );
+        assert_eq!(
+            parse_quantity(" 2,5KG ").unwrap(),
+            Quantity::from_milli(2500, Unit::Kg)
+        );
+        assert_eq!(
+            parse_quantity("3pcs").unwrap(),
+            Quantity::from_milli(3000, Unit::Pcs)
+        );
+        assert_eq!(
+            parse_quantity("0.125 liter").unwrap(),
+            Quantity::from_milli(125, Unit::Liter)
+        );
+        assert_eq!(parse_quantity("2.50 kg").unwrap().to_string(), "2.5 kg");
+    }
+
+    #[test]
+    fn rejects_unknown_unit_and_bad_number() {
+        for raw in [
+            "2 ton",
+            "2 gram",
+            "kg",
+            "-1 kg",
+            "1.2.3 kg",
+            "1.2345 kg",
+            "",
+            "2",
+        ] {
+            assert!(
+                is_bad_request(parse_quantity(raw)),
+                "{:?} harus ditolak",
+                raw
+            );
+        }
+    }
+
+    #[test]
+    fn refuses_to_add_incompatible_units() {
+        let kg = parse_quantity("2.5 kg").unwrap();
+        let pcs = parse_quantity("3 pcs").unwrap();
+
+        assert!(is_bad_request(kg.checked_add(pcs)));
+        assert!(is_bad_request(kg.checked_sub(pcs)));
+        assert_eq!(
+            kg.checked_add(parse_quantity("0.5 kg").unwrap()).unwrap(),
+            Quantity::from_milli(3000, Unit::Kg)
+        );
+    }
+
+    #[test]
+    fn subtraction_does_not_go_negative() {
+        let stock = parse_quantity("1 kg").unwrap();
+
+        assert!(is_bad_request(
+            stock.checked_sub(parse_quantity("1.5 kg").unwrap())
+        ));
+        assert!(stock.checked_sub(stock).unwrap().is_zero());
+    }
+
+    #[test]
+    fn serde_uses_value_and_unit() {
+        let quantity = Quantity::from_milli(2500, Unit::Kg);
+
+        let json = serde_json::to_value(quantity).unwrap();
+        assert_eq!(json, serde_json::json!({ "value": 2.5, "unit": "kg" }));
+        assert_eq!(serde_json::from_value::<Quantity>(json).unwrap(), quantity);
+
+        for bad in [
+            serde_json::json!({ "value": 1, "unit": "ton" }),
+            serde_json::json!({ "value": -1, "unit": "kg" }),
+            serde_json::json!({ "value": 0.0001, "unit": "kg" }),
+        ] {
+            assert!(serde_json::from_value::<Quantity>(bad).is_err());
+        }
+    }
+}
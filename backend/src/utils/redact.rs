@@ -0,0 +1,134 @@
+use bson::{Bson, Document};
+
+pub const REDACTED: &str = "[redacted]";
+
+/// Field yang selalu disamarkan oleh `redact_default`
+pub const DEFAULT_SENSITIVE_FIELDS: &[&str] = &["password", "password_hash", "token", "auth_token"];
+
+/// Salinan `doc` dengan field di `fields` diganti `"[redacted]"`, dipakai sebelum dokumen
+/// ditulis ke log. Field nested ditulis dengan dotted path, contoh `"user.password"`.
+/// Array of document di tengah path ikut diproses per elemen.
+pub fn redact_sensitive(doc: &Document, fields: &[&str]) -> Document {
+    let mut redacted = doc.clone();
+    for field in fields {
+        let path: Vec<&str> = field.split('.').collect();
+        redact_path(&mut redacted, &path);
+    }
+    redacted
+}
+
+/// `redact_sensitive` dengan `DEFAULT_SENSITIVE_FIELDS`
+pub fn redact_default(doc: &Document) -> Document {
+    redact_sensitive(doc, DEFAULT_SENSITIVE_FIELDS)
+}
+
+fn redact_path(doc: &mut Document, path: &[&str]) {
+    let Some((head, rest)) = path.split_first() else {
+        return;
+    };
+
+    let Some(value) = doc.get_mut(*head) else {
+        return;
+    };
+
+    if rest.is_empty() {
+        *value = Bson::String(REDACTED.to_string());
+        return;
+    }
+
+    redact_value(value, rest);
+}
+
+fn redact_value(value: &mut Bson, path: &[&str]) {
+    match value {
+        Bson::Document(nested) => redact_path(nested, path),
+        Bson::Array(items) => {
+            for item in items {
+                redact_value(item, path);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Query string dengan nilai param di `params` diganti `[redacted]`, contoh
+/// `a=1&token=abc` menjadi `a=1&token=[redacted]`. Urutan param lain tidak diubah.
+pub fn redact_query_params(query: &str, params: &[&str]) -> String {
+    query
+        .split('&')
+        .map(|pair| {
+            let key = pair.split_once('=').map_or(pair, |(key, _)| key);
+            if params.contains(&key) {
+                format!("{}={}", key, REDACTED)
+            } else {
+                pair.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::doc;
+
+    #[test]
+    fn default_fields_are_redacted_at_top_level() {
+        let user = doc! {
+            "username": "budi",
+            "password_hash": "$argon2id$v=19$...",
+            "token": "eyJhbGci",
+            "age": 30,
+        };
+
+        assert_eq!(
+            redact_default(&user),
+            doc! {
+                "username": "budi",
+                "password_hash": REDACTED,
+                "token": REDACTED,
+                "age": 30,
+            }
+        );
+        // Dokumen asli tidak ikut berubah
+        assert_eq!(user.get_str("token").unwrap(), "eyJhbGci");
+    }
+
+    #[test]
+    fn dotted_paths_redact_nested_fields() {
+        let event = doc! {
+            "user": { "email": "budi@mail.com", "password": "rahasia123" },
+            "sessions": [{ "jti": "a", "token": "t1" }, { "jti": "b", "token": "t2" }],
+        };
+
+        let redacted = redact_sensitive(&event, &["user.password", "sessions.token"]);
+
+        assert_eq!(
+            redacted,
+            doc! {
+                "user": { "email": "budi@mail.com", "password": REDACTED },
+                "sessions": [{ "jti": "a", "token": REDACTED }, { "jti": "b", "token": REDACTED }],
+            }
+        );
+    }
+
+    #[test]
+    fn missing_or_non_document_paths_are_untouched() {
+        let product = doc! { "name": "Kopi", "price": 10000, "tags": ["promo"] };
+
+        let redacted = redact_sensitive(&product, &["token", "price.amount", "tags.secret", ""]);
+
+        assert_eq!(redacted, product);
+    }
+
+    #[test]
+    fn handshake_token_is_redacted_from_query_string() {
+        assert_eq!(
+            redact_query_params("room=gudang&token=eyJhbGciOi.abc&v=2", &["token"]),
+            "room=gudang&token=[redacted]&v=2"
+        );
+        assert_eq!(redact_query_params("token", &["token"]), "token=[redacted]");
+        assert_eq!(redact_query_params("page=1", &["token"]), "page=1");
+    }
+}
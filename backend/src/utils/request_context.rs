@@ -0,0 +1,26 @@
+use log::Level;
+
+/// Header yang membawa request id di response
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Request id dari request yang sedang diproses, di-set oleh `RequestIdMiddleware`
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Jalankan future dengan request id tertentu, dipakai `RequestIdMiddleware`
+pub async fn with_request_id<F: Future>(request_id: String, future: F) -> F::Output {
+    REQUEST_ID.scope(request_id, future).await
+}
+
+/// Tulis log dengan prefix request id agar log satu request bisa dikorelasikan
+pub fn log_with_context(level: Level, message: &str) {
+    match current_request_id() {
+        Some(id) => log::log!(level, "[request_id={}] {}", id, message),
+        None => log::log!(level, "{}", message),
+    }
+}
@@ -0,0 +1,137 @@
+/// Pilihan untuk `sanitize_text_with`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SanitizeOptions {
+    // Spasi/tab berturut-turut menjadi satu spasi, lebih dari satu baris kosong menjadi satu
+    pub collapse_whitespace: bool,
+    // Newline dan tab diganti spasi, untuk nama dan judul
+    pub single_line: bool,
+}
+
+// Karakter arah teks (bidi override) bisa membalik tampilan teks di sekitarnya
+fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+}
+
+/// Potong `s` menjadi maksimal `max_chars` karakter, selalu di batas karakter UTF-8
+pub fn truncate_chars(s: &str, max_chars: usize) -> &str {
+    match s.char_indices().nth(max_chars) {
+        Some((idx, _)) => &s[..idx],
+        None => s,
+    }
+}
+
+/// Bersihkan teks bebas (deskripsi, catatan) sebelum disimpan: buang karakter kontrol
+/// kecuali newline dan tab (termasuk null byte dan `\r`), trim, lalu potong
+/// maksimal `max_len` karakter.
+pub fn sanitize_text(raw: &str, max_len: usize) -> String {
+    sanitize_text_with(raw, max_len, SanitizeOptions::default())
+}
+
+/// Versi ketat untuk nama: satu baris dan whitespace dirapatkan
+pub fn sanitize_single_line(raw: &str, max_len: usize) -> String {
+    sanitize_text_with(
+        raw,
+        max_len,
+        SanitizeOptions {
+            collapse_whitespace: true,
+            single_line: true,
+        },
+    )
+}
+
+pub fn sanitize_text_with(raw: &str, max_len: usize, options: SanitizeOptions) -> String {
+    let mut cleaned = String::with_capacity(raw.len());
+    let mut pending_space = false;
+    let mut newlines = 0;
+
+    for c in raw.chars() {
+        let c = match c {
+            '\n' | '\t' if options.single_line => ' ',
+            '\n' | '\t' => c,
+            c if c.is_control() || is_bidi_control(c) => continue,
+            c => c,
+        };
+
+        if !options.collapse_whitespace {
+            cleaned.push(c);
+            continue;
+        }
+
+        match c {
+            '\n' => {
+                pending_space = false;
+                newlines += 1;
+            }
+            c if c.is_whitespace() => pending_space = true,
+            c => {
+                if newlines > 0 {
+                    // Spasi di akhir baris dibuang, maksimal satu baris kosong
+                    cleaned.push_str(if newlines > 1 { "\n\n" } else { "\n" });
+                } else if pending_space && !cleaned.is_empty() {
+                    cleaned.push(' ');
+                }
+                pending_space = false;
+                newlines = 0;
+                cleaned.push(c);
+            }
+        }
+    }
+
+    let trimmed = cleaned.trim();
+    truncate_chars(trimmed, max_len).trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn control_characters_are_removed() {
+        assert_eq!(
+            sanitize_text("Kopi\0 Arabika\u{7}\r\nSangrai\tmedium", 100),
+            "Kopi Arabika\nSangrai\tmedium"
+        );
+        assert_eq!(sanitize_text("harga\u{202E}0001", 100), "harga0001");
+        assert_eq!(sanitize_text("\u{1b}[31m", 100), "[31m");
+    }
+
+    #[test]
+    fn truncation_respects_multibyte_boundaries() {
+        assert_eq!(truncate_chars("Kopé☕🍵", 5), "Kopé☕");
+        assert_eq!(truncate_chars("☕☕☕", 10), "☕☕☕");
+        assert_eq!(truncate_chars("☕", 0), "");
+        // Panjang dihitung per karakter, bukan per byte
+        assert_eq!(sanitize_text("🍵🍵🍵🍵", 2), "🍵🍵");
+        assert_eq!(sanitize_text("Teh tarik", 4), "Teh");
+    }
+
+    #[test]
+    fn free_text_keeps_whitespace_by_default() {
+        assert_eq!(
+            sanitize_text("  baris 1  \n\n\n  baris 2  ", 100),
+            "baris 1  \n\n\n  baris 2"
+        );
+    }
+
+    #[test]
+    fn collapsed_text_keeps_at_most_one_blank_line() {
+        let options = SanitizeOptions {
+            collapse_whitespace: true,
+            single_line: false,
+        };
+
+        assert_eq!(
+            sanitize_text_with("\n baris   1  \n\n\n\n  baris \t 2 ", 100, options),
+            "baris 1\n\nbaris 2"
+        );
+    }
+
+    #[test]
+    fn single_line_strips_newlines_and_collapses() {
+        assert_eq!(
+            sanitize_single_line("  Kopi\n\tSusu   Gula  Aren\r\n", 100),
+            "Kopi Susu Gula Aren"
+        );
+        assert_eq!(sanitize_single_line(" \n\t ", 100), "");
+    }
+}
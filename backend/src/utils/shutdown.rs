@@ -0,0 +1,99 @@
+use actix_web::dev::Server;
+use futures::future::LocalBoxFuture;
+use std::future::Future;
+use std::sync::Mutex;
+
+type HookFn = Box<dyn FnOnce() -> LocalBoxFuture<'static, ()>>;
+
+/// Pembersihan terakhir setelah server berhenti (contoh menutup client MongoDB).
+/// Hook hanya dijalankan sekali walaupun `run` dipanggil berkali-kali.
+pub struct ShutdownHook {
+    hook: Mutex<Option<HookFn>>,
+}
+
+impl ShutdownHook {
+    pub fn new<F, Fut>(hook: F) -> Self
+    where
+        F: FnOnce() -> Fut + 'static,
+        Fut: Future<Output = ()> + 'static,
+    {
+        ShutdownHook {
+            hook: Mutex::new(Some(Box::new(move || Box::pin(hook())))),
+        }
+    }
+
+    /// Jalankan hook, `false` jika hook sudah pernah dijalankan
+    pub async fn run(&self) -> bool {
+        // Lock dilepas sebelum await
+        let hook = self
+            .hook
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take();
+
+        match hook {
+            Some(hook) => {
+                hook().await;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Tunggu server berhenti lalu jalankan `hook`. Actix sudah menangani SIGTERM/SIGINT:
+/// listener ditutup, request yang sedang berjalan ditunggu sampai `shutdown_timeout`,
+/// baru kemudian future server selesai. Hook tetap dijalankan walaupun server berhenti
+/// karena error.
+pub async fn run_until_stopped(server: Server, hook: &ShutdownHook) -> std::io::Result<()> {
+    let result = server.await;
+    log::info!("Server berhenti, menjalankan pembersihan");
+    hook.run().await;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{App, HttpResponse, HttpServer, web};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn counting_hook() -> (ShutdownHook, Rc<Cell<u32>>) {
+        let runs = Rc::new(Cell::new(0));
+        let counter = runs.clone();
+        let hook = ShutdownHook::new(move || async move {
+            counter.set(counter.get() + 1);
+        });
+        (hook, runs)
+    }
+
+    #[actix_web::test]
+    async fn hook_runs_exactly_once() {
+        let (hook, runs) = counting_hook();
+
+        assert!(hook.run().await);
+        assert!(!hook.run().await);
+        assert_eq!(runs.get(), 1);
+    }
+
+    #[actix_web::test]
+    async fn hook_runs_once_after_server_stops() {
+        let (hook, runs) = counting_hook();
+        let server = HttpServer::new(|| App::new().route("/", web::get().to(HttpResponse::Ok)))
+            .workers(1)
+            .shutdown_timeout(1)
+            .bind(("127.0.0.1", 0))
+            .unwrap()
+            .run();
+        let handle = server.handle();
+
+        // Sama seperti sinyal SIGTERM: berhenti tanpa menerima koneksi baru
+        actix_web::rt::spawn(async move { handle.stop(true).await });
+        run_until_stopped(server, &hook).await.unwrap();
+
+        assert_eq!(runs.get(), 1);
+        assert!(!hook.run().await);
+        assert_eq!(runs.get(), 1);
+    }
+}
@@ -0,0 +1,190 @@
+use crate::errors::ServiceError;
+use crate::utils::clock::{Clock, SystemClock};
+use crate::utils::jwt::SECRET;
+use crate::utils::token_hash::constant_time_eq;
+use chrono::Duration;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const SIGNED_URL_EXPIRES_PARAM: &str = "expires";
+pub const SIGNED_URL_SIGNATURE_PARAM: &str = "signature";
+
+// Signature mencakup `expires` dan `path`, mengubah salah satunya membuat signature tidak cocok
+fn signed_url_mac(secret: &[u8], path: &str, expires: i64) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(secret).expect("HMAC menerima key dengan panjang apapun");
+    mac.update(b"signed-url:");
+    mac.update(expires.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(path.as_bytes());
+    mac
+}
+
+/// URL gambar/file yang hanya berlaku selama `ttl`, contoh:
+/// `sign_url("/files/products/abc.jpg", Duration::minutes(15))` menjadi
+/// `/files/products/abc.jpg?expires=1700000900&signature=<hex>`
+pub fn sign_url(path: &str, ttl: Duration) -> String {
+    sign_url_with(
+        SECRET.as_bytes(),
+        path,
+        SystemClock.unix_timestamp() + ttl.num_seconds(),
+    )
+}
+
+/// Sama seperti `sign_url` dengan secret dan waktu kedaluwarsa (UNIX detik) eksplisit
+pub fn sign_url_with(secret: &[u8], path: &str, expires: i64) -> String {
+    let signature = hex::encode(
+        signed_url_mac(secret, path, expires)
+            .finalize()
+            .into_bytes(),
+    );
+    let separator = if path.contains('?') { '&' } else { '?' };
+    format!(
+        "{}{}{}={}&{}={}",
+        path, separator, SIGNED_URL_EXPIRES_PARAM, expires, SIGNED_URL_SIGNATURE_PARAM, signature
+    )
+}
+
+/// Cek parameter `expires` dan `signature` dari URL buatan `sign_url`.
+/// `Forbidden` jika signature tidak cocok, `Gone` jika sudah kedaluwarsa.
+pub fn verify_signed_url(path: &str, expires: i64, signature: &str) -> Result<(), ServiceError> {
+    verify_signed_url_at(SECRET.as_bytes(), path, expires, signature, &SystemClock)
+}
+
+pub fn verify_signed_url_at(
+    secret: &[u8],
+    path: &str,
+    expires: i64,
+    signature: &str,
+    clock: &impl Clock,
+) -> Result<(), ServiceError> {
+    // Signature dicek dulu agar `expires` yang diubah tidak dilaporkan sebagai kedaluwarsa
+    let valid = hex::decode(signature.trim()).is_ok_and(|provided| {
+        let expected = signed_url_mac(secret, path, expires)
+            .finalize()
+            .into_bytes();
+        constant_time_eq(&expected, &provided)
+    });
+    if !valid {
+        return Err(ServiceError::Forbidden("Signature URL tidak valid".into()));
+    }
+
+    if clock.unix_timestamp() >= expires {
+        return Err(ServiceError::Gone("Link sudah kedaluwarsa".into()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::init_test_config;
+    use crate::utils::clock::FixedClock;
+
+    const SECRET_KEY: &[u8] = b"rahasia-test";
+    const NOW: i64 = 1_700_000_000;
+
+    // Ambil kembali `expires` dan `signature` dari query URL hasil `sign_url`
+    fn signed_params(url: &str) -> (i64, String) {
+        let (_, query) = url.rsplit_once('?').unwrap();
+        let mut expires = None;
+        let mut signature = None;
+        for pair in query.split('&') {
+            match pair.split_once('=') {
+                Some((SIGNED_URL_EXPIRES_PARAM, value)) => expires = value.parse().ok(),
+                Some((SIGNED_URL_SIGNATURE_PARAM, value)) => signature = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        (expires.unwrap(), signature.unwrap())
+    }
+
+    #[test]
+    fn valid_url_is_accepted() {
+        let path = "/files/products/abc.jpg";
+        let url = sign_url_with(SECRET_KEY, path, NOW + 900);
+        let (expires, signature) = signed_params(&url);
+
+        assert!(url.starts_with("/files/products/abc.jpg?expires=1700000900&signature="));
+        assert!(
+            verify_signed_url_at(
+                SECRET_KEY,
+                path,
+                expires,
+                &signature,
+                &FixedClock::from_unix(NOW)
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn tampered_path_or_expiry_is_forbidden() {
+        let url = sign_url_with(SECRET_KEY, "/files/products/abc.jpg", NOW + 900);
+        let (expires, signature) = signed_params(&url);
+        let clock = FixedClock::from_unix(NOW);
+
+        for (path, expires, signature) in [
+            ("/files/products/xyz.jpg", expires, signature.as_str()),
+            (
+                "/files/products/abc.jpg",
+                expires + 3600,
+                signature.as_str(),
+            ),
+            ("/files/products/abc.jpg", expires, "bukan-hex"),
+        ] {
+            assert!(matches!(
+                verify_signed_url_at(SECRET_KEY, path, expires, signature, &clock),
+                Err(ServiceError::Forbidden(_))
+            ));
+        }
+        assert!(matches!(
+            verify_signed_url_at(
+                b"secret-lain",
+                "/files/products/abc.jpg",
+                expires,
+                &signature,
+                &clock
+            ),
+            Err(ServiceError::Forbidden(_))
+        ));
+    }
+
+    #[test]
+    fn expired_url_is_gone() {
+        let path = "/files/products/abc.jpg";
+        let (expires, signature) = signed_params(&sign_url_with(SECRET_KEY, path, NOW));
+
+        for now in [NOW, NOW + 1] {
+            assert!(matches!(
+                verify_signed_url_at(
+                    SECRET_KEY,
+                    path,
+                    expires,
+                    &signature,
+                    &FixedClock::from_unix(now)
+                ),
+                Err(ServiceError::Gone(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn existing_query_is_kept() {
+        let url = sign_url_with(SECRET_KEY, "/files/a.jpg?w=200", NOW);
+
+        assert!(url.starts_with("/files/a.jpg?w=200&expires="));
+    }
+
+    #[test]
+    fn sign_url_round_trips_with_app_secret() {
+        init_test_config();
+        let path = "/files/products/abc.jpg";
+        let (expires, signature) = signed_params(&sign_url(path, Duration::minutes(15)));
+
+        assert!(verify_signed_url(path, expires, &signature).is_ok());
+        assert!(verify_signed_url("/files/lain.jpg", expires, &signature).is_err());
+    }
+}
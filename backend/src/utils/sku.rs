@@ -0,0 +1,399 @@
+use crate::errors::ServiceError;
+use crate::models::product::Product;
+use crate::utils::map_mongo_error;
+use mongodb::{Collection, bson::doc};
+use nanoid::nanoid;
+
+/// Panjang bagian acak SKU
+pub const SKU_BODY_LEN: usize = 5;
+/// Batas percobaan generate SKU sebelum menyerah
+pub const SKU_MAX_ATTEMPTS: usize = 10;
+/// Prefix bawaan untuk produk tanpa kategori khusus
+pub const DEFAULT_SKU_PREFIX: &str = "SKU";
+/// Panjang maksimal prefix SKU, contoh: "FOOD", "ELEC"
+pub const SKU_PREFIX_MAX_LEN: usize = 8;
+
+/// Batas panjang SKU yang diketik user, termasuk prefix dan tanda `-`
+pub const SKU_MIN_LEN: usize = 3;
+pub const SKU_MAX_LEN: usize = 32;
+
+/// Alfabet bagian acak SKU, sama dengan hasil `normalize_sku` sehingga SKU hasil generate
+/// tidak pernah berisi `_` atau diakhiri `-`
+const BODY_ALPHABET: [char; 36] = [
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I',
+    'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z',
+];
+
+/// Alfabet base32 Crockford, tanpa I, L, O, U yang mudah tertukar saat diketik
+const CHECKED_ALPHABET: [char; 32] = [
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'J',
+    'K', 'M', 'N', 'P', 'Q', 'R', 'S', 'T', 'V', 'W', 'X', 'Y', 'Z',
+];
+
+/// Generate SKU otomatis, contoh: "SKU-X7D2F"
+pub fn generate_random_sku() -> String {
+    generate_random_sku_with_prefix(DEFAULT_SKU_PREFIX).expect("prefix SKU bawaan harus valid")
+}
+
+pub fn generate_random_sku_with_len(body_len: usize) -> String {
+    format_sku(DEFAULT_SKU_PREFIX, body_len)
+}
+
+/// Generate SKU dengan prefix per kategori, contoh: "FOOD-X7D2F"
+pub fn generate_random_sku_with_prefix(prefix: &str) -> Result<String, ServiceError> {
+    validate_sku_prefix(prefix)?;
+    Ok(format_sku(prefix, SKU_BODY_LEN))
+}
+
+/// Prefix hanya boleh huruf besar dan angka, maksimal `SKU_PREFIX_MAX_LEN` karakter
+pub fn validate_sku_prefix(prefix: &str) -> Result<(), ServiceError> {
+    if prefix.is_empty() || prefix.len() > SKU_PREFIX_MAX_LEN {
+        return Err(ServiceError::BadRequest(format!(
+            "Prefix SKU harus 1-{} karakter",
+            SKU_PREFIX_MAX_LEN
+        )));
+    }
+
+    if !prefix
+        .chars()
+        .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+    {
+        return Err(ServiceError::BadRequest(
+            "Prefix SKU hanya boleh huruf besar dan angka".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn format_sku(prefix: &str, body_len: usize) -> String {
+    format!("{}-{}", prefix, nanoid!(body_len, &BODY_ALPHABET))
+}
+
+/// Bentuk kanonik SKU yang diketik user agar sama dengan SKU hasil generate di unique
+/// index: spasi di awal/akhir dan `-` di akhir dibuang lalu diubah ke huruf besar,
+/// contoh " sku-x7d2f- " menjadi "SKU-X7D2F". Hanya huruf, angka, `-` dan `_` (ada di
+/// SKU lama) yang diterima, panjang `SKU_MIN_LEN`-`SKU_MAX_LEN`.
+pub fn normalize_sku(raw: &str) -> Result<String, ServiceError> {
+    let sku = raw.trim().trim_end_matches('-').to_uppercase();
+
+    if sku.chars().any(char::is_whitespace) {
+        return Err(ServiceError::BadRequest(
+            "SKU tidak boleh berisi spasi".into(),
+        ));
+    }
+    if let Some(c) = sku
+        .chars()
+        .find(|&c| !(c.is_ascii_uppercase() || c.is_ascii_digit() || c == '-' || c == '_'))
+    {
+        return Err(ServiceError::BadRequest(format!(
+            "SKU berisi karakter yang tidak diizinkan: '{}'",
+            c
+        )));
+    }
+    if sku.starts_with('-') {
+        return Err(ServiceError::BadRequest(
+            "SKU tidak boleh diawali tanda -".into(),
+        ));
+    }
+    // Semua karakter sudah ASCII, jumlah byte sama dengan jumlah karakter
+    if !(SKU_MIN_LEN..=SKU_MAX_LEN).contains(&sku.len()) {
+        return Err(ServiceError::BadRequest(format!(
+            "SKU harus {}-{} karakter",
+            SKU_MIN_LEN, SKU_MAX_LEN
+        )));
+    }
+
+    Ok(sku)
+}
+
+/// `normalize_sku` untuk SKU berformat `generate_random_sku_checked`, karakter cek yang
+/// tidak cocok (salah ketik) ditolak dengan `BadRequest`
+pub fn normalize_sku_checked(raw: &str) -> Result<String, ServiceError> {
+    let sku = normalize_sku(raw)?;
+    if !validate_sku(&sku) {
+        return Err(ServiceError::BadRequest(
+            "Karakter cek SKU tidak cocok, periksa kembali SKU".into(),
+        ));
+    }
+    Ok(sku)
+}
+
+/// Generate SKU dengan karakter cek di akhir, contoh: "SKU-7D2FKQ".
+/// Dipisah dari `generate_random_sku` agar SKU lama yang tersimpan tetap valid.
+pub fn generate_random_sku_checked() -> String {
+    let body = nanoid!(SKU_BODY_LEN, &CHECKED_ALPHABET);
+    let check = check_char(&body).unwrap_or('0');
+    format!("SKU-{}{}", body, check)
+}
+
+/// Hitung ulang karakter cek SKU hasil `generate_random_sku_checked`, prefix tidak ikut dihitung
+pub fn validate_sku(sku: &str) -> bool {
+    let Some((_, body)) = sku.split_once('-') else {
+        return false;
+    };
+
+    let mut chars = body.chars();
+    let Some(check) = chars.next_back() else {
+        return false;
+    };
+    let payload = chars.as_str();
+
+    !payload.is_empty() && check_char(payload) == Some(check)
+}
+
+// Jumlah berbobot posisi mod 32, bobot berbeda per posisi membuat transposisi
+// dua karakter bersebelahan menghasilkan karakter cek yang berbeda
+fn check_char(payload: &str) -> Option<char> {
+    let mut sum = 0usize;
+    for (i, c) in payload.chars().enumerate() {
+        let value = CHECKED_ALPHABET.iter().position(|&a| a == c)?;
+        sum += value * (i + 1);
+    }
+    Some(CHECKED_ALPHABET[sum % CHECKED_ALPHABET.len()])
+}
+
+/// Generate SKU yang belum dipakai di collection products
+pub async fn generate_unique_sku(collection: &Collection<Product>) -> Result<String, ServiceError> {
+    generate_unique_sku_with(collection, SKU_MAX_ATTEMPTS, SKU_BODY_LEN).await
+}
+
+pub async fn generate_unique_sku_with(
+    collection: &Collection<Product>,
+    max_attempts: usize,
+    body_len: usize,
+) -> Result<String, ServiceError> {
+    generate_unique_candidate(
+        |candidate| async move {
+            let count = collection
+                .count_documents(doc! { "sku": &candidate })
+                .limit(1)
+                .await
+                .map_err(map_mongo_error)?;
+            Ok(count > 0)
+        },
+        max_attempts,
+        body_len,
+    )
+    .await
+}
+
+/// Ulangi generate kandidat SKU sampai `exists` mengembalikan false atau percobaan habis
+pub async fn generate_unique_candidate<F, Fut>(
+    mut exists: F,
+    max_attempts: usize,
+    body_len: usize,
+) -> Result<String, ServiceError>
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = Result<bool, ServiceError>>,
+{
+    for _ in 0..max_attempts {
+        let candidate = generate_random_sku_with_len(body_len);
+
+        if !exists(candidate.clone()).await? {
+            return Ok(candidate);
+        }
+    }
+
+    Err(ServiceError::Unexpected(format!(
+        "Gagal membuat SKU unik setelah {} percobaan",
+        max_attempts
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[actix_web::test]
+    async fn unique_candidate_retries_until_free() {
+        let calls = Cell::new(0);
+
+        let sku = generate_unique_candidate(
+            |_| {
+                calls.set(calls.get() + 1);
+                let taken = calls.get() < 3;
+                async move { Ok(taken) }
+            },
+            SKU_MAX_ATTEMPTS,
+            7,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(calls.get(), 3);
+        assert!(sku.starts_with("SKU-"));
+        assert_eq!(sku.len(), "SKU-".len() + 7);
+    }
+
+    #[actix_web::test]
+    async fn unique_candidate_gives_up_after_max_attempts() {
+        let calls = Cell::new(0);
+
+        let result = generate_unique_candidate(
+            |_| {
+                calls.set(calls.get() + 1);
+                async { Ok(true) }
+            },
+            4,
+            SKU_BODY_LEN,
+        )
+        .await;
+
+        assert_eq!(calls.get(), 4);
+        assert!(matches!(result, Err(ServiceError::Unexpected(_))));
+    }
+
+    #[actix_web::test]
+    async fn unique_candidate_propagates_lookup_error() {
+        let result = generate_unique_candidate(
+            |_| async { Err(ServiceError::DatabaseError("down".into())) },
+            SKU_MAX_ATTEMPTS,
+            SKU_BODY_LEN,
+        )
+        .await;
+
+        assert!(matches!(result, Err(ServiceError::DatabaseError(_))));
+    }
+
+    fn swap_adjacent(sku: &str, index: usize) -> String {
+        let mut chars: Vec<char> = sku.chars().collect();
+        chars.swap(index, index + 1);
+        chars.into_iter().collect()
+    }
+
+    #[test]
+    fn checked_sku_is_valid() {
+        for _ in 0..100 {
+            let sku = generate_random_sku_checked();
+
+            assert!(sku.starts_with("SKU-"));
+            assert_eq!(sku.len(), "SKU-".len() + SKU_BODY_LEN + 1);
+            assert!(validate_sku(&sku), "{} harus valid", sku);
+        }
+    }
+
+    #[test]
+    fn single_transposition_is_detected() {
+        for _ in 0..100 {
+            let sku = generate_random_sku_checked();
+            let body_start = "SKU-".len();
+
+            // Semua pasangan bersebelahan di bagian acak, karakter cek tidak ikut ditukar
+            for index in body_start..body_start + SKU_BODY_LEN - 1 {
+                let typo = swap_adjacent(&sku, index);
+                if typo != sku {
+                    assert!(!validate_sku(&typo), "{} -> {} lolos", sku, typo);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn prefix_is_not_part_of_checksum() {
+        let sku = generate_random_sku_checked();
+        let (_, body) = sku.split_once('-').unwrap();
+
+        assert!(validate_sku(&format!("FOOD-{}", body)));
+    }
+
+    #[test]
+    fn malformed_checked_sku_is_invalid() {
+        for sku in ["SKU7D2FK", "SKU-", "SKU-A", "SKU-7D2FI"] {
+            assert!(!validate_sku(sku), "{} harus ditolak", sku);
+        }
+    }
+
+    #[test]
+    fn normalize_checked_rejects_typo() {
+        let sku = std::iter::repeat_with(generate_random_sku_checked)
+            .find(|sku| sku.as_bytes()[4] != sku.as_bytes()[5])
+            .unwrap();
+
+        assert_eq!(normalize_sku_checked(&sku.to_lowercase()).unwrap(), sku);
+        assert!(matches!(
+            normalize_sku_checked(&swap_adjacent(&sku, 4)),
+            Err(ServiceError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn custom_prefix_is_used() {
+        let sku = generate_random_sku_with_prefix("FOOD").unwrap();
+
+        let (prefix, body) = sku.split_once('-').unwrap();
+        assert_eq!(prefix, "FOOD");
+        assert_eq!(body.len(), SKU_BODY_LEN);
+        assert!(generate_random_sku().starts_with("SKU-"));
+    }
+
+    #[test]
+    fn prefix_with_space_or_lowercase_is_rejected() {
+        for prefix in ["FO OD", "food", "Elec", "", "ELEKTRONIK"] {
+            assert!(
+                matches!(
+                    generate_random_sku_with_prefix(prefix),
+                    Err(ServiceError::BadRequest(_))
+                ),
+                "prefix {:?} harus ditolak",
+                prefix
+            );
+        }
+    }
+
+    fn rejection(raw: &str) -> String {
+        match normalize_sku(raw) {
+            Err(ServiceError::BadRequest(msg)) => msg,
+            other => panic!("{:?} harus ditolak, hasil: {:?}", raw, other),
+        }
+    }
+
+    #[test]
+    fn user_sku_is_normalized() {
+        for (raw, expected) in [
+            (" sku-x7d2f- ", "SKU-X7D2F"),
+            ("food-123--", "FOOD-123"),
+            ("OLD_SKU_01", "OLD_SKU_01"),
+            ("abc", "ABC"),
+        ] {
+            assert_eq!(normalize_sku(raw).unwrap(), expected, "{:?}", raw);
+        }
+    }
+
+    #[test]
+    fn generated_sku_is_already_canonical() {
+        for sku in [
+            generate_random_sku(),
+            generate_random_sku_with_prefix("FOOD").unwrap(),
+            generate_random_sku_checked(),
+        ] {
+            assert_eq!(normalize_sku(&sku).unwrap(), sku);
+            assert_eq!(normalize_sku(&sku.to_lowercase()).unwrap(), sku);
+        }
+    }
+
+    #[test]
+    fn each_rejection_has_its_own_message() {
+        assert_eq!(rejection("SKU X7D2F"), "SKU tidak boleh berisi spasi");
+        assert_eq!(
+            rejection("SKU/X7D2F"),
+            "SKU berisi karakter yang tidak diizinkan: '/'"
+        );
+        assert_eq!(
+            rejection("SKU-É7D2F"),
+            "SKU berisi karakter yang tidak diizinkan: 'É'"
+        );
+        assert_eq!(rejection("-X7D2F"), "SKU tidak boleh diawali tanda -");
+        for raw in ["AB", "   ", "A--", &"A".repeat(SKU_MAX_LEN + 1)] {
+            assert_eq!(
+                rejection(raw),
+                format!("SKU harus {}-{} karakter", SKU_MIN_LEN, SKU_MAX_LEN),
+                "{:?}",
+                raw
+            );
+        }
+        assert!(normalize_sku(&"A".repeat(SKU_MAX_LEN)).is_ok());
+    }
+}
@@ -0,0 +1,133 @@
+use crate::errors::ServiceError;
+use crate::models::product::Product;
+use crate::utils::map_mongo_error;
+use mongodb::{Collection, bson::doc};
+use std::collections::HashSet;
+use unicode_normalization::{UnicodeNormalization, char::is_combining_mark};
+
+/// Slug pengganti jika nama tidak menghasilkan karakter apa pun, contoh nama "!!!"
+pub const DEFAULT_SLUG: &str = "produk";
+
+/// Ubah nama menjadi slug URL, contoh: "Kopi Susu" -> "kopi-susu", "Café Olé" -> "cafe-ole".
+/// Huruf diubah ke kecil, aksen dibuang, karakter selain huruf/angka ASCII menjadi `-`,
+/// `-` berurutan digabung dan `-` di awal/akhir dibuang.
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    let mut pending_hyphen = false;
+
+    // NFD memisahkan huruf dasar dari tanda aksennya
+    for c in name.nfd().filter(|c| !is_combining_mark(*c)) {
+        if c.is_ascii_alphanumeric() {
+            if pending_hyphen && !slug.is_empty() {
+                slug.push('-');
+            }
+            pending_hyphen = false;
+            slug.push(c.to_ascii_lowercase());
+        } else {
+            pending_hyphen = true;
+        }
+    }
+
+    slug
+}
+
+/// Slug `base` yang belum dipakai di collection products, ditambah `-2`, `-3`, dst.
+/// jika sudah ada. Semua slug dengan pola yang sama diambil dalam satu query.
+pub async fn generate_unique_slug(
+    collection: &Collection<Product>,
+    base: &str,
+) -> Result<String, ServiceError> {
+    let base = match slugify(base) {
+        slug if slug.is_empty() => DEFAULT_SLUG.to_string(),
+        slug => slug,
+    };
+
+    let pattern = format!("^{}(-[0-9]+)?$", regex::escape(&base));
+    let taken: HashSet<String> = collection
+        .distinct("slug", doc! { "slug": { "$regex": pattern } })
+        .await
+        .map_err(map_mongo_error)?
+        .into_iter()
+        .filter_map(|slug| slug.as_str().map(str::to_string))
+        .collect();
+
+    Ok(next_free_slug(&base, &taken))
+}
+
+/// `base` jika belum dipakai, atau `base-N` dengan N terkecil mulai dari 2
+pub fn next_free_slug(base: &str, taken: &HashSet<String>) -> String {
+    if !taken.contains(base) {
+        return base.to_string();
+    }
+
+    (2..)
+        .map(|n| format!("{}-{}", base, n))
+        .find(|candidate| !taken.contains(candidate))
+        .expect("iterator suffix tidak terbatas")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_database;
+    use bson::Document;
+
+    #[test]
+    fn slugify_applies_every_rule() {
+        let cases = [
+            ("Kopi Susu", "kopi-susu"),
+            ("Café Olé", "cafe-ole"),
+            ("  Kopi -- Susu!!  ", "kopi-susu"),
+            ("Teh_Tarik/Gula Aren (1L)", "teh-tarik-gula-aren-1l"),
+            ("ÀÉÎÕÜ ñ", "aeiou-n"),
+            ("---", ""),
+        ];
+
+        for (name, expected) in cases {
+            assert_eq!(slugify(name), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn slugify_is_deterministic() {
+        assert_eq!(slugify("Es Kopi Susu"), slugify("Es Kopi Susu"));
+    }
+
+    #[test]
+    fn collision_gets_smallest_free_suffix() {
+        let taken = |slugs: &[&str]| slugs.iter().map(|s| s.to_string()).collect::<HashSet<_>>();
+
+        assert_eq!(next_free_slug("kopi", &taken(&[])), "kopi");
+        assert_eq!(next_free_slug("kopi", &taken(&["kopi"])), "kopi-2");
+        assert_eq!(
+            next_free_slug("kopi", &taken(&["kopi", "kopi-2", "kopi-4"])),
+            "kopi-3"
+        );
+        assert_eq!(next_free_slug("kopi", &taken(&["kopi-2"])), "kopi");
+    }
+
+    #[actix_web::test]
+    #[ignore = "butuh MongoDB"]
+    async fn unique_slug_skips_existing_products() {
+        let db = test_database().await;
+        db.collection::<Document>("products")
+            .insert_many([
+                doc! { "slug": "kopi-susu" },
+                doc! { "slug": "kopi-susu-2" },
+                doc! { "slug": "kopi-susu-gula-aren" },
+            ])
+            .await
+            .unwrap();
+        let products = db.collection::<Product>("products");
+
+        assert_eq!(
+            generate_unique_slug(&products, "Kopi Susu").await.unwrap(),
+            "kopi-susu-3"
+        );
+        assert_eq!(generate_unique_slug(&products, "Teh").await.unwrap(), "teh");
+        assert_eq!(
+            generate_unique_slug(&products, "!!!").await.unwrap(),
+            DEFAULT_SLUG
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use crate::config::{config_error, optional_env, parse_env, required_env};
+use crate::errors::ServiceError;
+use crate::services::mailer::Mailer;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use futures::future::BoxFuture;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_rustls::{
+    TlsConnector,
+    rustls::{ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName},
+};
+
+/// Port SMTP dengan TLS langsung (SMTPS), STARTTLS tidak didukung
+pub const DEFAULT_SMTP_PORT: u16 = 465;
+/// Batas waktu satu sesi pengiriman
+pub const SMTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    pub from: String,
+}
+
+impl fmt::Debug for SmtpConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SmtpConfig")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("username", &self.username)
+            .field("password", &"***")
+            .field("from", &self.from)
+            .finish()
+    }
+}
+
+impl SmtpConfig {
+    /// Baca `SMTP_HOST`, `SMTP_PORT`, `SMTP_USERNAME`, `SMTP_PASSWORD` dan `SMTP_FROM`.
+    /// `None` jika `SMTP_HOST` tidak di-set.
+    pub fn from_env() -> Result<Option<SmtpConfig>, ServiceError> {
+        let Some(host) = optional_env("SMTP_HOST") else {
+            return Ok(None);
+        };
+
+        let username = required_env("SMTP_USERNAME")?;
+        let from = optional_env("SMTP_FROM").unwrap_or_else(|| username.clone());
+        if from.contains(['\r', '\n']) {
+            return Err(config_error("SMTP_FROM tidak boleh berisi baris baru"));
+        }
+
+        Ok(Some(SmtpConfig {
+            host,
+            port: parse_env("SMTP_PORT", DEFAULT_SMTP_PORT)?,
+            username,
+            password: required_env("SMTP_PASSWORD")?,
+            from,
+        }))
+    }
+}
+
+/// Client SMTP minimal: TLS langsung, `AUTH PLAIN`, satu email per koneksi
+#[derive(Clone)]
+pub struct SmtpMailer {
+    config: SmtpConfig,
+    connector: TlsConnector,
+}
+
+impl SmtpMailer {
+    pub fn new(config: SmtpConfig) -> Self {
+        let mut roots = RootCertStore::empty();
+        roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+            OwnedTrustAnchor::from_subject_spki_name_constraints(
+                ta.subject,
+                ta.spki,
+                ta.name_constraints,
+            )
+        }));
+        let tls = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+
+        SmtpMailer {
+            config,
+            connector: TlsConnector::from(Arc::new(tls)),
+        }
+    }
+
+    async fn deliver(&self, to: &str, subject: &str, body: &str) -> Result<(), String> {
+        let server_name =
+            ServerName::try_from(self.config.host.as_str()).map_err(|e| e.to_string())?;
+        let tcp = TcpStream::connect((self.config.host.as_str(), self.config.port))
+            .await
+            .map_err(|e| e.to_string())?;
+        let tls = self
+            .connector
+            .connect(server_name, tcp)
+            .await
+            .map_err(|e| e.to_string())?;
+        let mut stream = BufReader::new(tls);
+
+        let credentials = STANDARD.encode(format!(
+            "\0{}\0{}",
+            self.config.username, self.config.password
+        ));
+
+        expect_reply(&mut stream, 220).await?;
+        command(&mut stream, "EHLO qtoky", 250).await?;
+        command(&mut stream, &format!("AUTH PLAIN {}", credentials), 235).await?;
+        command(
+            &mut stream,
+            &format!("MAIL FROM:<{}>", self.config.from),
+            250,
+        )
+        .await?;
+        command(&mut stream, &format!("RCPT TO:<{}>", to), 250).await?;
+        command(&mut stream, "DATA", 354).await?;
+
+        let message = build_message(&self.config.from, to, subject, body);
+        stream
+            .write_all(message.as_bytes())
+            .await
+            .map_err(|e| e.to_string())?;
+        command(&mut stream, ".", 250).await?;
+
+        // Email sudah diterima server, kegagalan QUIT tidak perlu dilaporkan
+        let _ = command(&mut stream, "QUIT", 221).await;
+        Ok(())
+    }
+}
+
+async fn command<S>(stream: &mut BufReader<S>, line: &str, expected: u16) -> Result<(), String>
+where
+    S: tokio::io::AsyncRead + AsyncWrite + Unpin,
+{
+    stream
+        .write_all(format!("{}\r\n", line).as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+    stream.flush().await.map_err(|e| e.to_string())?;
+    expect_reply(stream, expected).await
+}
+
+/// Baca balasan (bisa multi-baris `250-...`) dan cocokkan kode statusnya
+async fn expect_reply<S>(stream: &mut BufReader<S>, expected: u16) -> Result<(), String>
+where
+    S: tokio::io::AsyncRead + AsyncWrite + Unpin,
+{
+    loop {
+        let mut line = String::new();
+        let read = stream
+            .read_line(&mut line)
+            .await
+            .map_err(|e| e.to_string())?;
+        if read == 0 {
+            return Err("Koneksi SMTP terputus".into());
+        }
+
+        let code: u16 = line
+            .get(..3)
+            .and_then(|c| c.parse().ok())
+            .ok_or_else(|| format!("Balasan SMTP tidak valid: {}", line.trim()))?;
+        // `250-` berarti masih ada baris lanjutan
+        if line.as_bytes().get(3) == Some(&b'-') {
+            continue;
+        }
+        if code != expected {
+            return Err(format!("SMTP membalas {}: {}", code, line.trim()));
+        }
+        return Ok(());
+    }
+}
+
+/// Header dan body email teks biasa. Baris yang diawali `.` digandakan (dot-stuffing)
+/// agar tidak dibaca sebagai akhir DATA.
+fn build_message(from: &str, to: &str, subject: &str, body: &str) -> String {
+    let body: String = body
+        .lines()
+        .map(|line| match line.strip_prefix('.') {
+            Some(_) => format!(".{}\r\n", line),
+            None => format!("{}\r\n", line),
+        })
+        .collect();
+
+    format!(
+        "From: <{}>\r\nTo: <{}>\r\nSubject: {}\r\nMIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}",
+        from, to, subject, body
+    )
+}
+
+fn has_line_break(value: &str) -> bool {
+    value.contains(['\r', '\n'])
+}
+
+impl Mailer for SmtpMailer {
+    fn send<'a>(
+        &'a self,
+        to: &'a str,
+        subject: &'a str,
+        body: &'a str,
+    ) -> BoxFuture<'a, Result<(), ServiceError>> {
+        Box::pin(async move {
+            // Cegah header injection lewat alamat atau subjek
+            if has_line_break(to) || has_line_break(subject) {
+                return Err(ServiceError::BadRequest(
+                    "Alamat email atau subjek tidak valid".into(),
+                ));
+            }
+
+            match actix_web::rt::time::timeout(SMTP_TIMEOUT, self.deliver(to, subject, body)).await
+            {
+                Ok(Ok(())) => Ok(()),
+                Ok(Err(e)) => Err(ServiceError::ServiceUnavailable(format!(
+                    "Gagal mengirim email: {}",
+                    e
+                ))),
+                Err(_) => Err(ServiceError::ServiceUnavailable(
+                    "Pengiriman email timeout".into(),
+                )),
+            }
+        })
+    }
+}
@@ -0,0 +1,97 @@
+/// Akses generik ke enum buatan `string_enum!`, contoh untuk allow-list field sort
+pub trait StringEnum: Copy + 'static {
+    const ALL: &'static [Self];
+
+    fn as_str(&self) -> &'static str;
+
+    /// Cari varian dengan nilai string `value`, `None` jika tidak dikenal
+    fn from_value(value: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|v| v.as_str() == value)
+    }
+
+    /// Semua nilai string, dipakai di pesan error
+    fn values() -> Vec<&'static str> {
+        Self::ALL.iter().map(|v| v.as_str()).collect()
+    }
+}
+
+/// Enum tertutup yang disimpan sebagai string lowercase, contoh field status.
+/// Nilai yang tidak dikenal ditolak saat deserialize sehingga typo di dokumen
+/// hasil import langsung gagal, bukan lolos sebagai string bebas.
+///
+/// ```ignore
+/// string_enum! {
+///     pub enum PaymentStatus {
+///         Paid => "paid",
+///         Unpaid => "unpaid",
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! string_enum {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident => $value:literal),+ $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        $vis enum $name {
+            $($(#[$variant_meta])* $variant),+
+        }
+
+        impl $name {
+            pub const ALL: &'static [$name] = &[$($name::$variant),+];
+
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $($name::$variant => $value),+
+                }
+            }
+        }
+
+        impl $crate::utils::string_enum::StringEnum for $name {
+            const ALL: &'static [Self] = $name::ALL;
+
+            fn as_str(&self) -> &'static str {
+                $name::as_str(self)
+            }
+        }
+
+        impl ::std::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl ::std::str::FromStr for $name {
+            type Err = String;
+
+            fn from_str(value: &str) -> Result<Self, Self::Err> {
+                match value {
+                    $($value => Ok($name::$variant),)+
+                    _ => Err(format!(
+                        "Nilai '{}' tidak dikenal untuk {}, pilihan: {}",
+                        value,
+                        stringify!($name),
+                        [$($value),+].join(", ")
+                    )),
+                }
+            }
+        }
+
+        impl ::serde::Serialize for $name {
+            fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> ::serde::Deserialize<'de> for $name {
+            fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let raw = String::deserialize(deserializer)?;
+                raw.parse().map_err(<D::Error as ::serde::de::Error>::custom)
+            }
+        }
+    };
+}
@@ -0,0 +1,111 @@
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const SALT_LEN: usize = 16;
+
+// Password buatan manusia low-entropy, jadi butuh Argon2 yang sengaja lambat agar
+// brute-force mahal. Token acak (API key, refresh token, link unlock) sudah
+// high-entropy sehingga tidak bisa di-brute-force, cukup HMAC-SHA256 yang cepat.
+// Jangan pakai modul ini untuk password, pakai `utils::password::hash_password`.
+
+fn mac_for(salt: &[u8], token: &str) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(salt).expect("HMAC menerima key dengan panjang apapun");
+    mac.update(token.as_bytes());
+    mac
+}
+
+/// Bandingkan dua secret (CSRF token, API key, hash) tanpa membocorkan posisi byte yang
+/// berbeda lewat waktu eksekusi. Panjang yang berbeda selalu `false`, dan perbandingan
+/// byte tetap dijalankan sehingga tidak ada jalur cepat untuk kasus itu.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let same_len = (a.len() as u64).ct_eq(&(b.len() as u64));
+    let other = if bool::from(same_len) { b } else { a };
+    bool::from(same_len & a.ct_eq(other))
+}
+
+/// Hash token high-entropy dalam format `<salt_hex>$<digest_hex>`
+pub fn hash_token(token: &str) -> String {
+    let mut salt = [0u8; SALT_LEN];
+    rand::rng().fill_bytes(&mut salt);
+    let digest = mac_for(&salt, token).finalize().into_bytes();
+    format!("{}${}", hex::encode(salt), hex::encode(digest))
+}
+
+/// Bandingkan token dengan hash dari `hash_token` secara constant-time
+pub fn verify_token(token: &str, stored_hash: &str) -> bool {
+    let Some((salt_hex, digest_hex)) = stored_hash.split_once('$') else {
+        return false;
+    };
+    let (Ok(salt), Ok(digest)) = (hex::decode(salt_hex), hex::decode(digest_hex)) else {
+        return false;
+    };
+
+    let expected = mac_for(&salt, token).finalize().into_bytes();
+    constant_time_eq(&expected, &digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "qtk_V1StGXR8Z5jd_Hj2kQ9wXcT4mLp7sN3vB8rYf6aZe1uD0";
+
+    #[test]
+    fn hashed_token_verifies() {
+        let stored = hash_token(TOKEN);
+
+        assert!(verify_token(TOKEN, &stored));
+        assert!(!stored.contains(TOKEN));
+    }
+
+    #[test]
+    fn same_token_gets_a_fresh_salt() {
+        let (first, second) = (hash_token(TOKEN), hash_token(TOKEN));
+
+        assert_ne!(first, second);
+        assert!(verify_token(TOKEN, &first) && verify_token(TOKEN, &second));
+    }
+
+    #[test]
+    fn tampered_token_or_hash_is_rejected() {
+        let stored = hash_token(TOKEN);
+        let mut tampered = TOKEN.to_string();
+        tampered.replace_range(4..5, "X");
+        let (salt, digest) = stored.split_once('$').unwrap();
+        let last = if digest.ends_with('0') { '1' } else { '0' };
+        let flipped = format!("{}${}{}", salt, &digest[..digest.len() - 1], last);
+
+        assert!(!verify_token(&tampered, &stored));
+        assert!(!verify_token(TOKEN, &flipped));
+        for malformed in ["", "tanpa-pemisah", "zz$zz", &format!("{}$", salt)] {
+            assert!(!verify_token(TOKEN, malformed), "{}", malformed);
+        }
+    }
+
+    #[test]
+    fn constant_time_eq_compares_length_and_content() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"abcd"));
+        assert!(constant_time_eq(b"", b""));
+    }
+
+    #[test]
+    fn constant_time_eq_handles_each_length_case() {
+        let secret = [7u8; 32];
+        let mut flipped_last = secret;
+        flipped_last[31] ^= 1;
+
+        assert!(constant_time_eq(&secret, &secret.clone()));
+        assert!(!constant_time_eq(&secret, &flipped_last));
+        // Prefix tidak boleh dianggap cocok, dari arah mana pun
+        assert!(!constant_time_eq(&secret, &secret[..31]));
+        assert!(!constant_time_eq(&secret[..31], &secret));
+        assert!(!constant_time_eq(b"", &secret));
+    }
+}
@@ -0,0 +1,234 @@
+//! TOTP (RFC 6238) untuk 2FA admin: HMAC-SHA1, 6 digit, periode 30 detik, sesuai default
+//! Google Authenticator dan aplikasi sejenis. Secret disimpan terpisah dari dokumen user
+//! (bukan di samping `password_hash`) agar bocornya satu data tidak cukup untuk login.
+
+use crate::utils::token_hash::constant_time_eq;
+use hmac::{Hmac, Mac};
+use rand::{TryRngCore, rngs::OsRng};
+use sha1::Sha1;
+
+type HmacSha1 = Hmac<Sha1>;
+
+pub const TOTP_DIGITS: u32 = 6;
+pub const TOTP_PERIOD_SECS: i64 = 30;
+/// Jumlah langkah yang masih diterima sebelum/sesudah langkah saat ini (toleransi jam HP)
+pub const TOTP_DRIFT_STEPS: i64 = 1;
+/// 160 bit, panjang yang disarankan RFC 4226 untuk HMAC-SHA1
+pub const TOTP_SECRET_BYTES: usize = 20;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Base32 RFC 4648 tanpa padding, format secret yang dibaca aplikasi authenticator
+pub fn base32_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    encoded
+}
+
+/// Decode base32, spasi, `-`, padding `=` dan huruf kecil diterima. `None` jika ada
+/// karakter di luar alfabet base32.
+pub fn base32_decode(raw: &str) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(raw.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in raw.chars() {
+        if matches!(c, ' ' | '-' | '=') {
+            continue;
+        }
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&a| a as char == c.to_ascii_uppercase())? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+        }
+    }
+    Some(decoded)
+}
+
+/// Secret TOTP acak dari OsRng dalam base32, ditampilkan sekali saat setup 2FA
+pub fn generate_totp_secret() -> String {
+    let mut bytes = [0u8; TOTP_SECRET_BYTES];
+    OsRng
+        .try_fill_bytes(&mut bytes)
+        .expect("OsRng gagal menghasilkan secret TOTP");
+    base32_encode(&bytes)
+}
+
+// Karakter selain unreserved (RFC 3986) di-percent-encode
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// URI `otpauth://totp/...` untuk QR code setup, contoh untuk issuer `qtoky` dan akun
+/// `admin@toko.id`: `otpauth://totp/qtoky:admin%40toko.id?secret=...&issuer=qtoky&...`
+pub fn totp_provisioning_uri(issuer: &str, account: &str, secret: &str) -> String {
+    format!(
+        "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={digits}&period={period}",
+        issuer = percent_encode(issuer),
+        account = percent_encode(account),
+        secret = secret,
+        digits = TOTP_DIGITS,
+        period = TOTP_PERIOD_SECS,
+    )
+}
+
+/// Kode HOTP (RFC 4226) untuk `counter` dengan dynamic truncation
+fn hotp(key: &[u8], counter: u64) -> String {
+    let mut mac = HmacSha1::new_from_slice(key).expect("HMAC menerima key dengan panjang apapun");
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+    format!(
+        "{:0width$}",
+        binary % 10u32.pow(TOTP_DIGITS),
+        width = TOTP_DIGITS as usize
+    )
+}
+
+/// Kode TOTP untuk waktu `now` (UNIX detik), `None` jika secret bukan base32 valid
+pub fn totp_code(secret: &str, now: i64) -> Option<String> {
+    let key = base32_decode(secret)?;
+    Some(hotp(&key, now.div_euclid(TOTP_PERIOD_SECS) as u64))
+}
+
+/// Langkah waktu (`now / 30`) yang cocok dengan `code`, toleransi `TOTP_DRIFT_STEPS`
+/// langkah. Simpan langkah terakhir yang dipakai dan tolak langkah yang sama atau lebih
+/// lama agar kode yang sama tidak bisa dipakai dua kali.
+pub fn verify_totp_step(secret: &str, code: &str, now: i64) -> Option<i64> {
+    let key = base32_decode(secret)?;
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    if code.len() != TOTP_DIGITS as usize {
+        return None;
+    }
+
+    let current = now.div_euclid(TOTP_PERIOD_SECS);
+    // Semua langkah dicek tanpa berhenti di kecocokan pertama
+    let mut matched = None;
+    for step in current - TOTP_DRIFT_STEPS..=current + TOTP_DRIFT_STEPS {
+        if step >= 0 && constant_time_eq(hotp(&key, step as u64).as_bytes(), code.as_bytes()) {
+            matched.get_or_insert(step);
+        }
+    }
+    matched
+}
+
+/// Cek kode dari aplikasi authenticator untuk waktu `now` (UNIX detik, biasanya
+/// `SystemClock.unix_timestamp()`)
+pub fn verify_totp(secret: &str, code: &str, now: i64) -> bool {
+    verify_totp_step(secret, code, now).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Secret ASCII "12345678901234567890" dari RFC 6238 Appendix B
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn base32_round_trips() {
+        assert_eq!(base32_encode(b"12345678901234567890"), RFC_SECRET);
+        assert_eq!(base32_encode(b"f"), "MY");
+        assert_eq!(base32_decode("my======").unwrap(), b"f");
+        assert_eq!(
+            base32_decode("GEZD GNBV-GY3T").unwrap(),
+            base32_decode("GEZDGNBVGY3T").unwrap()
+        );
+        assert!(base32_decode("GEZ1").is_none());
+
+        let secret = generate_totp_secret();
+        assert_eq!(base32_decode(&secret).unwrap().len(), TOTP_SECRET_BYTES);
+        assert_ne!(secret, generate_totp_secret());
+    }
+
+    #[test]
+    fn codes_match_rfc_vectors() {
+        // 6 digit terakhir dari vektor 8 digit RFC 6238 (SHA1)
+        for (now, expected) in [
+            (59, "287082"),
+            (1_111_111_109, "081804"),
+            (1_234_567_890, "005924"),
+            (2_000_000_000, "279037"),
+        ] {
+            assert_eq!(
+                totp_code(RFC_SECRET, now).as_deref(),
+                Some(expected),
+                "{}",
+                now
+            );
+        }
+    }
+
+    #[test]
+    fn code_is_valid_within_drift_window() {
+        let now = 1_700_000_010;
+        let code = totp_code(RFC_SECRET, now).unwrap();
+        let step = now / TOTP_PERIOD_SECS;
+
+        for offset in [-TOTP_PERIOD_SECS, 0, 19, TOTP_PERIOD_SECS] {
+            assert!(verify_totp(RFC_SECRET, &code, now + offset), "{}", offset);
+        }
+        assert_eq!(verify_totp_step(RFC_SECRET, &code, now + 20), Some(step));
+        let spaced = format!("{} {}", &code[..3], &code[3..]);
+        assert!(verify_totp(RFC_SECRET, &spaced, now));
+    }
+
+    #[test]
+    fn code_outside_window_is_rejected() {
+        let now = 1_700_000_010;
+        let code = totp_code(RFC_SECRET, now).unwrap();
+
+        for offset in [-2 * TOTP_PERIOD_SECS, 2 * TOTP_PERIOD_SECS, 3600] {
+            assert!(!verify_totp(RFC_SECRET, &code, now + offset), "{}", offset);
+        }
+        assert!(!verify_totp(RFC_SECRET, "000000x", now));
+        assert!(!verify_totp(RFC_SECRET, &code[..5], now));
+        assert!(!verify_totp("bukan base32!", &code, now));
+        assert!(!verify_totp(&generate_totp_secret(), &code, now));
+    }
+
+    #[test]
+    fn provisioning_uri_escapes_labels() {
+        let uri = totp_provisioning_uri("qtoky", "admin@toko.id", RFC_SECRET);
+
+        assert_eq!(
+            uri,
+            format!(
+                "otpauth://totp/qtoky:admin%40toko.id?secret={}&issuer=qtoky&algorithm=SHA1&digits=6&period=30",
+                RFC_SECRET
+            )
+        );
+        assert!(totp_provisioning_uri("Toko Q", "a", RFC_SECRET).contains("Toko%20Q:a"));
+    }
+}
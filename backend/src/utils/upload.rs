@@ -0,0 +1,168 @@
+use crate::errors::ServiceError;
+use crate::utils::body_limit::DEFAULT_UPLOAD_LIMIT;
+
+/// Batas ukuran gambar produk, sama dengan batas body upload default
+pub const MAX_IMAGE_BYTES: usize = DEFAULT_UPLOAD_LIMIT;
+
+/// Jenis gambar yang boleh di-upload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageType {
+    Jpeg,
+    Png,
+    Webp,
+}
+
+impl ImageType {
+    pub const ALL: &'static [ImageType] = &[ImageType::Jpeg, ImageType::Png, ImageType::Webp];
+
+    /// Content-Type tanpa parameter (`; charset=...`) dan tidak membedakan huruf besar/kecil
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type.split(';').next().unwrap_or_default().trim();
+        match mime.to_ascii_lowercase().as_str() {
+            "image/jpeg" | "image/jpg" => Some(ImageType::Jpeg),
+            "image/png" => Some(ImageType::Png),
+            "image/webp" => Some(ImageType::Webp),
+            _ => None,
+        }
+    }
+
+    pub fn mime(&self) -> &'static str {
+        match self {
+            ImageType::Jpeg => "image/jpeg",
+            ImageType::Png => "image/png",
+            ImageType::Webp => "image/webp",
+        }
+    }
+
+    /// Cek magic bytes di awal file sesuai jenisnya
+    pub fn matches_magic(&self, bytes: &[u8]) -> bool {
+        match self {
+            ImageType::Jpeg => bytes.starts_with(&[0xFF, 0xD8, 0xFF]),
+            ImageType::Png => bytes.starts_with(b"\x89PNG\r\n\x1a\n"),
+            ImageType::Webp => {
+                bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WEBP"
+            }
+        }
+    }
+}
+
+/// Cek Content-Type terhadap allow-list jpeg/png/webp dan ukuran terhadap `MAX_IMAGE_BYTES`
+pub fn validate_image_upload(content_type: &str, len: usize) -> Result<ImageType, ServiceError> {
+    validate_image_upload_with(content_type, len, MAX_IMAGE_BYTES)
+}
+
+pub fn validate_image_upload_with(
+    content_type: &str,
+    len: usize,
+    max_bytes: usize,
+) -> Result<ImageType, ServiceError> {
+    let image_type = ImageType::from_content_type(content_type).ok_or_else(|| {
+        let allowed: Vec<&str> = ImageType::ALL.iter().map(|t| t.mime()).collect();
+        ServiceError::BadRequest(format!(
+            "Tipe file '{}' tidak didukung, gunakan salah satu dari: {}",
+            content_type.trim(),
+            allowed.join(", ")
+        ))
+    })?;
+
+    if len == 0 {
+        return Err(ServiceError::BadRequest("File gambar kosong".into()));
+    }
+    if len > max_bytes {
+        return Err(ServiceError::BadRequest(format!(
+            "Ukuran gambar {} byte melebihi batas {} byte",
+            len, max_bytes
+        )));
+    }
+
+    Ok(image_type)
+}
+
+/// `validate_image_upload` ditambah pengecekan magic bytes, sehingga file lain yang diberi
+/// Content-Type gambar tetap ditolak
+pub fn validate_image_bytes(content_type: &str, bytes: &[u8]) -> Result<ImageType, ServiceError> {
+    let image_type = validate_image_upload(content_type, bytes.len())?;
+
+    if !image_type.matches_magic(bytes) {
+        return Err(ServiceError::BadRequest(format!(
+            "Isi file tidak sesuai dengan tipe {}",
+            image_type.mime()
+        )));
+    }
+
+    Ok(image_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JPEG: &[u8] = &[0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10];
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR";
+    const WEBP: &[u8] = b"RIFF\x24\x00\x00\x00WEBPVP8 ";
+
+    fn bad_request(result: Result<ImageType, ServiceError>) -> String {
+        match result {
+            Err(ServiceError::BadRequest(msg)) => msg,
+            other => panic!("hasil tidak terduga: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn allowed_types_are_accepted() {
+        assert_eq!(
+            validate_image_bytes("image/jpeg", JPEG).unwrap(),
+            ImageType::Jpeg
+        );
+        assert_eq!(
+            validate_image_bytes("IMAGE/PNG; charset=binary", PNG).unwrap(),
+            ImageType::Png
+        );
+        assert_eq!(
+            validate_image_bytes("image/webp", WEBP).unwrap(),
+            ImageType::Webp
+        );
+        assert_eq!(
+            validate_image_upload("image/jpg", 1024).unwrap(),
+            ImageType::Jpeg
+        );
+    }
+
+    #[test]
+    fn other_types_are_rejected_with_allow_list() {
+        for content_type in ["image/gif", "application/pdf", "image/svg+xml", ""] {
+            let message = bad_request(validate_image_upload(content_type, 10));
+
+            assert!(
+                message.contains("image/jpeg, image/png, image/webp"),
+                "{}",
+                message
+            );
+        }
+    }
+
+    #[test]
+    fn oversized_or_empty_files_are_rejected() {
+        assert_eq!(
+            bad_request(validate_image_upload_with("image/png", 2049, 2048)),
+            "Ukuran gambar 2049 byte melebihi batas 2048 byte"
+        );
+        assert!(validate_image_upload_with("image/png", 2048, 2048).is_ok());
+        assert!(validate_image_upload("image/png", MAX_IMAGE_BYTES + 1).is_err());
+        assert_eq!(
+            bad_request(validate_image_upload("image/png", 0)),
+            "File gambar kosong"
+        );
+    }
+
+    #[test]
+    fn declared_type_must_match_magic_bytes() {
+        assert_eq!(
+            bad_request(validate_image_bytes("image/png", JPEG)),
+            "Isi file tidak sesuai dengan tipe image/png"
+        );
+        assert!(validate_image_bytes("image/jpeg", b"<?php echo 1; ?>").is_err());
+        // RIFF tanpa penanda WEBP, contoh file WAV
+        assert!(validate_image_bytes("image/webp", b"RIFF\x24\x00\x00\x00WAVEfmt ").is_err());
+    }
+}
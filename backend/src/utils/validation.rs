@@ -0,0 +1,209 @@
+use crate::errors::ServiceError;
+use std::collections::HashMap;
+
+fn invalid(message: String) -> Result<(), ServiceError> {
+    Err(ServiceError::BadRequest(message))
+}
+
+/// String tidak boleh kosong atau hanya spasi
+pub fn require_non_empty(field: &str, value: &str) -> Result<(), ServiceError> {
+    if value.trim().is_empty() {
+        return invalid(format!("{} tidak boleh kosong", field));
+    }
+    Ok(())
+}
+
+/// List minimal berisi satu item
+pub fn require_non_empty_list<T>(field: &str, value: &[T]) -> Result<(), ServiceError> {
+    if value.is_empty() {
+        return invalid(format!("{} tidak boleh kosong", field));
+    }
+    Ok(())
+}
+
+/// Panjang string (dalam karakter) harus di antara `min` dan `max`
+pub fn require_len_range(
+    field: &str,
+    value: &str,
+    min: usize,
+    max: usize,
+) -> Result<(), ServiceError> {
+    let length = value.chars().count();
+    if length < min || length > max {
+        return invalid(format!("{} harus {}-{} karakter", field, min, max));
+    }
+    Ok(())
+}
+
+pub fn require_positive(field: &str, value: f64) -> Result<(), ServiceError> {
+    if value.is_nan() || value <= 0.0 {
+        return invalid(format!("{} harus lebih dari 0", field));
+    }
+    Ok(())
+}
+
+pub fn require_non_negative(field: &str, value: f64) -> Result<(), ServiceError> {
+    if value.is_nan() || value < 0.0 {
+        return invalid(format!("{} tidak boleh negatif", field));
+    }
+    Ok(())
+}
+
+/// Gabungkan hasil beberapa validasi menjadi satu `BadRequest` yang memuat semua masalah
+pub fn validate_all(results: &[Result<(), ServiceError>]) -> Result<(), ServiceError> {
+    let problems: Vec<String> = results
+        .iter()
+        .filter_map(|result| result.as_ref().err())
+        .map(|err| match err {
+            ServiceError::BadRequest(msg) => msg.clone(),
+            other => other.to_string(),
+        })
+        .collect();
+
+    if problems.is_empty() {
+        return Ok(());
+    }
+
+    invalid(problems.join("; "))
+}
+
+/// Kumpulan pesan error per field, dikembalikan sebagai `ServiceError::Validation` (422).
+/// Contoh: `errors.add("email", "format tidak valid")`
+#[derive(Debug, Default, Clone)]
+pub struct ValidationErrors {
+    errors: HashMap<String, Vec<String>>,
+}
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        ValidationErrors::default()
+    }
+
+    pub fn add(&mut self, field: &str, message: impl Into<String>) -> &mut Self {
+        self.errors
+            .entry(field.to_string())
+            .or_default()
+            .push(message.into());
+        self
+    }
+
+    /// Catat hasil helper `require_*` ke `field` jika gagal
+    pub fn check(&mut self, field: &str, result: Result<(), ServiceError>) -> &mut Self {
+        if let Err(err) = result {
+            let message = match err {
+                ServiceError::BadRequest(msg) => msg,
+                other => other.to_string(),
+            };
+            self.add(field, message);
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// `Ok(())` jika tidak ada error, selain itu `ServiceError::Validation`
+    pub fn into_result(self) -> Result<(), ServiceError> {
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+        Err(ServiceError::Validation(self.errors))
+    }
+}
+
+/// Ringkasan satu baris dari error per field, urut berdasarkan nama field
+pub fn join_field_errors(errors: &HashMap<String, Vec<String>>) -> String {
+    let mut fields: Vec<&String> = errors.keys().collect();
+    fields.sort();
+
+    fields
+        .into_iter()
+        .map(|field| errors[field].join(", "))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(result: Result<(), ServiceError>) -> String {
+        match result {
+            Err(ServiceError::BadRequest(msg)) => msg,
+            other => panic!("hasil tidak terduga: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn single_failures_name_the_field() {
+        assert_eq!(
+            message(require_non_empty("Nama", "   ")),
+            "Nama tidak boleh kosong"
+        );
+        assert_eq!(
+            message(require_len_range("SKU", "ab", 3, 32)),
+            "SKU harus 3-32 karakter"
+        );
+        assert_eq!(
+            message(require_positive("Harga", 0.0)),
+            "Harga harus lebih dari 0"
+        );
+        assert_eq!(
+            message(require_positive("Harga", f64::NAN)),
+            "Harga harus lebih dari 0"
+        );
+        assert_eq!(
+            message(require_non_negative("Stok", -1.0)),
+            "Stok tidak boleh negatif"
+        );
+        assert_eq!(
+            message(require_non_empty_list::<u8>("Items", &[])),
+            "Items tidak boleh kosong"
+        );
+    }
+
+    #[test]
+    fn valid_values_pass() {
+        assert!(require_non_empty("Nama", "Kopi").is_ok());
+        assert!(require_len_range("Nama", "Kopé", 4, 4).is_ok());
+        assert!(require_positive("Harga", 0.5).is_ok());
+        assert!(require_non_negative("Stok", 0.0).is_ok());
+    }
+
+    #[test]
+    fn validate_all_lists_every_problem() {
+        let result = validate_all(&[
+            require_non_empty("Nama", ""),
+            require_positive("Harga", 10.0),
+            require_positive("Stok", -2.0),
+        ]);
+
+        assert_eq!(
+            message(result),
+            "Nama tidak boleh kosong; Stok harus lebih dari 0"
+        );
+        assert!(validate_all(&[require_non_empty("Nama", "Kopi")]).is_ok());
+    }
+
+    #[test]
+    fn field_errors_become_validation_error() {
+        let mut errors = ValidationErrors::new();
+        errors
+            .check("name", require_non_empty("Nama", ""))
+            .check("price", require_positive("Harga", 1.0))
+            .add("name", "sudah digunakan");
+
+        match errors.into_result() {
+            Err(ServiceError::Validation(fields)) => {
+                assert_eq!(fields.len(), 1);
+                assert_eq!(
+                    fields["name"],
+                    vec!["Nama tidak boleh kosong", "sudah digunakan"]
+                );
+            }
+            other => panic!("hasil tidak terduga: {:?}", other),
+        }
+        assert!(ValidationErrors::new().into_result().is_ok());
+    }
+}
@@ -0,0 +1,161 @@
+use crate::errors::ServiceError;
+use crate::utils::clock::{Clock, SystemClock};
+use crate::utils::token_hash::constant_time_eq;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Signature";
+pub const WEBHOOK_TIMESTAMP_HEADER: &str = "X-Signature-Timestamp";
+/// Selisih maksimal timestamp webhook dengan jam penerima, mencegah replay
+pub const DEFAULT_WEBHOOK_TOLERANCE_SECS: i64 = 5 * 60;
+
+fn webhook_mac(secret: &[u8], timestamp: Option<i64>, body: &[u8]) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(secret).expect("HMAC menerima key dengan panjang apapun");
+    if let Some(timestamp) = timestamp {
+        mac.update(timestamp.to_string().as_bytes());
+        mac.update(b".");
+    }
+    mac.update(body);
+    mac
+}
+
+fn matches(mac: HmacSha256, provided_sig: &str) -> bool {
+    let Ok(provided) = hex::decode(provided_sig.trim()) else {
+        return false;
+    };
+    constant_time_eq(&mac.finalize().into_bytes(), &provided)
+}
+
+/// HMAC-SHA256(secret, body) dalam hex untuk header `X-Signature`
+pub fn sign_webhook(secret: &[u8], body: &[u8]) -> String {
+    hex::encode(webhook_mac(secret, None, body).finalize().into_bytes())
+}
+
+pub fn verify_webhook(secret: &[u8], body: &[u8], provided_sig: &str) -> bool {
+    matches(webhook_mac(secret, None, body), provided_sig)
+}
+
+/// Signature atas `"{timestamp}.{body}"`, kirim `timestamp` (UNIX detik) di header
+/// `X-Signature-Timestamp` agar penerima bisa menolak request lama
+pub fn sign_webhook_with_timestamp(secret: &[u8], timestamp: i64, body: &[u8]) -> String {
+    hex::encode(
+        webhook_mac(secret, Some(timestamp), body)
+            .finalize()
+            .into_bytes(),
+    )
+}
+
+/// Verifikasi signature dari `sign_webhook_with_timestamp` dengan toleransi
+/// `DEFAULT_WEBHOOK_TOLERANCE_SECS`
+pub fn verify_webhook_with_timestamp(
+    secret: &[u8],
+    timestamp: i64,
+    body: &[u8],
+    provided_sig: &str,
+) -> Result<(), ServiceError> {
+    verify_webhook_with_timestamp_at(
+        secret,
+        timestamp,
+        body,
+        provided_sig,
+        DEFAULT_WEBHOOK_TOLERANCE_SECS,
+        &SystemClock,
+    )
+}
+
+pub fn verify_webhook_with_timestamp_at(
+    secret: &[u8],
+    timestamp: i64,
+    body: &[u8],
+    provided_sig: &str,
+    tolerance_secs: i64,
+    clock: &impl Clock,
+) -> Result<(), ServiceError> {
+    if clock.unix_timestamp().abs_diff(timestamp) > tolerance_secs.max(0) as u64 {
+        return Err(ServiceError::Unauthorized(
+            "Timestamp webhook sudah kedaluwarsa".into(),
+        ));
+    }
+    if !matches(webhook_mac(secret, Some(timestamp), body), provided_sig) {
+        return Err(ServiceError::Unauthorized(
+            "Signature webhook tidak valid".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::clock::FixedClock;
+
+    const SECRET: &[u8] = b"rahasia-partner";
+    const BODY: &[u8] = br#"{"event":"order.paid","order_id":"A1"}"#;
+    const NOW: i64 = 1_700_000_000;
+
+    fn verify_at(timestamp: i64, body: &[u8], sig: &str) -> Result<(), ServiceError> {
+        verify_webhook_with_timestamp_at(
+            SECRET,
+            timestamp,
+            body,
+            sig,
+            DEFAULT_WEBHOOK_TOLERANCE_SECS,
+            &FixedClock::from_unix(NOW),
+        )
+    }
+
+    #[test]
+    fn signature_matches_hmac_sha256() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            sign_webhook(b"Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn valid_signature_is_accepted() {
+        let sig = sign_webhook(SECRET, BODY);
+
+        assert!(verify_webhook(SECRET, BODY, &sig));
+        assert!(verify_webhook(SECRET, BODY, &format!(" {} ", sig)));
+        assert!(verify_at(NOW, BODY, &sign_webhook_with_timestamp(SECRET, NOW, BODY)).is_ok());
+    }
+
+    #[test]
+    fn tampered_body_or_wrong_secret_is_rejected() {
+        let sig = sign_webhook(SECRET, BODY);
+        let tampered = br#"{"event":"order.paid","order_id":"A2"}"#;
+        let timed = sign_webhook_with_timestamp(SECRET, NOW, BODY);
+
+        assert!(!verify_webhook(SECRET, tampered, &sig));
+        assert!(!verify_webhook(b"secret-lain", BODY, &sig));
+        assert!(!verify_webhook(SECRET, BODY, "bukan-hex"));
+        assert!(matches!(
+            verify_at(NOW, tampered, &timed),
+            Err(ServiceError::Unauthorized(msg)) if msg.contains("tidak valid")
+        ));
+        // Timestamp ikut ditandatangani, tidak bisa diganti untuk memperpanjang umur
+        assert!(verify_at(NOW - 1, BODY, &timed).is_err());
+    }
+
+    #[test]
+    fn expired_timestamp_is_rejected() {
+        let old = NOW - DEFAULT_WEBHOOK_TOLERANCE_SECS - 1;
+        let future = NOW + DEFAULT_WEBHOOK_TOLERANCE_SECS + 1;
+        let edge = NOW - DEFAULT_WEBHOOK_TOLERANCE_SECS;
+
+        for timestamp in [old, future] {
+            let sig = sign_webhook_with_timestamp(SECRET, timestamp, BODY);
+            assert!(matches!(
+                verify_at(timestamp, BODY, &sig),
+                Err(ServiceError::Unauthorized(msg)) if msg.contains("kedaluwarsa")
+            ));
+        }
+        assert!(verify_at(edge, BODY, &sign_webhook_with_timestamp(SECRET, edge, BODY)).is_ok());
+    }
+}